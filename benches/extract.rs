@@ -0,0 +1,81 @@
+// Compares the JsonApi extractor, which buffers the body and decodes it straight into a Request,
+// with the path it replaced: actix's JsonBody decoding the Request, then FromRequest. Run with
+// `cargo bench --features actixweb`.
+use actix_web::{dev::Payload, test::TestRequest, web::JsonBody, FromRequest as _, HttpRequest};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use jsonapi::{FromRequest, JsonApi, Request};
+use serde_derive::Deserialize;
+
+#[derive(Deserialize)]
+#[allow(dead_code)]
+struct Attributes {
+    title: String,
+    body: String,
+    tags: Vec<String>,
+}
+
+// document is a request body whose attributes hold tags tags
+fn document(tags: usize) -> Vec<u8> {
+    let tags: Vec<String> = (0..tags).map(|n| format!("tag-{}", n)).collect();
+    serde_json::to_vec(&serde_json::json!({
+        "data": {
+            "type": "articles",
+            "attributes": {
+                "title": "Benchmarks",
+                "body": "x".repeat(512),
+                "tags": tags,
+            },
+            "relationships": {
+                "author": {"data": {"type": "people", "id": "9"}}
+            }
+        }
+    }))
+    .unwrap()
+}
+
+fn request(body: &[u8]) -> (HttpRequest, Payload) {
+    TestRequest::post()
+        .insert_header(("content-type", "application/vnd.api+json"))
+        .set_payload(body.to_vec())
+        .to_http_parts()
+}
+
+fn extract(c: &mut Criterion) {
+    let rt = actix_web::rt::Runtime::new().unwrap();
+    for (name, tags) in [("small", 1), ("large", 10_000)] {
+        let body = document(tags);
+        let mut group = c.benchmark_group(format!("extract_{}", name));
+        group.bench_function("json_body", |b| {
+            b.iter_batched(
+                || request(&body),
+                |(req, mut payload)| {
+                    rt.block_on(async {
+                        let req =
+                            JsonBody::<Request<Attributes>>::new(&req, &mut payload, None, true)
+                                .await
+                                .unwrap();
+                        <Request<Attributes> as FromRequest>::from_request(req).unwrap()
+                    })
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.bench_function("jsonapi", |b| {
+            b.iter_batched(
+                || request(&body),
+                |(req, mut payload)| {
+                    rt.block_on(JsonApi::<Request<Attributes>>::from_request(
+                        &req,
+                        &mut payload,
+                    ))
+                    .unwrap()
+                },
+                BatchSize::SmallInput,
+            )
+        });
+        group.finish();
+    }
+}
+
+criterion_group!(benches, extract);
+criterion_main!(benches);
@@ -1,14 +1,33 @@
-use jsonapi_resource_derive::{FromRequest, IntoRelationships, IntoResponse};
-use serde_derive::Serialize;
+// the resources below are only exercised by the tests at the bottom of this file
+#![allow(dead_code)]
+
+use jsonapi::ids::Slug;
+use jsonapi::linkage::{ToManyUpdate, ToOneUpdate};
+use jsonapi::{Links, Maybe, Meta};
+use jsonapi_resource_derive::{
+    FromID, FromRelationshipRequest, FromRelationships, FromRequest, IntoID, IntoRelationships, IntoResponse, JsonApiFilter,
+    JsonApiPatch, JsonApiSort,
+};
+use serde_derive::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
 use uuid::Uuid;
 
 #[derive(FromRequest)]
+#[jsonapi(name = "simple")]
 struct SimpleRequest {
     id: Uuid,
     attributes: SimpleAttributes,
 }
 
-#[derive(Clone, Serialize)]
+// accepts a resource of any type
+#[derive(FromRequest)]
+#[jsonapi(skip_type_check)]
+struct AnyTypeRequest {
+    id: Uuid,
+    attributes: SimpleAttributes,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 struct SimpleAttributes {
     foo: String,
     bar: Option<isize>,
@@ -33,6 +52,328 @@ struct FakeRelations {
     simple: Option<Uuid>,
 }
 
+#[derive(IntoResponse)]
+#[jsonapi(name = "counters")]
+struct CounterResponse {
+    id: usize,
+    attributes: SimpleAttributes,
+    // picked up by name; any other field name can be used with #[jsonapi(meta)]
+    meta: Option<Meta>,
+}
+
+#[derive(IntoResponse)]
+#[jsonapi(name = "articles", self_link = "/articles/{id}")]
+struct ArticleResponse {
+    id: usize,
+    attributes: SimpleAttributes,
+    #[jsonapi(links)]
+    extra_links: Option<Links>,
+}
+
+#[derive(FromRequest, IntoResponse)]
+#[jsonapi(name = "trackeds")]
+struct TrackedResource {
+    id: Uuid,
+    attributes: SimpleAttributes,
+    relations: TrackedRelations,
+    // internal bookkeeping, never read from or written to a document
+    #[jsonapi(skip)]
+    revision: usize,
+}
+
+#[derive(FromRelationships, IntoRelationships)]
+struct TrackedRelations {
+    simple: Option<Uuid>,
+    #[jsonapi(skip)]
+    loaded: bool,
+}
+
+#[derive(FromRequest)]
+#[jsonapi(name = "drafts")]
+struct DraftRequest {
+    // an absent attributes object falls back to DraftAttributes::default()
+    #[jsonapi(default)]
+    attributes: DraftAttributes,
+    relations: DraftRelations,
+}
+
+#[derive(Default, Deserialize)]
+struct DraftAttributes {
+    title: String,
+}
+
+#[derive(FromRelationships)]
+struct DraftRelations {
+    #[jsonapi(default)]
+    tags: Vec<usize>,
+    #[jsonapi(default = "default_owner")]
+    owner: usize,
+}
+
+fn default_owner() -> usize {
+    1
+}
+
+// the relationships of an update, which can leave each one alone, clear it or replace it
+#[derive(FromRelationships)]
+struct EditRelations {
+    editor: Maybe<usize>,
+    reviewer: Maybe<usize>,
+    tags: Maybe<Vec<usize>>,
+}
+
+#[derive(FromRequest)]
+#[jsonapi(name = "events", validate = "validate_event")]
+struct EventRequest {
+    #[jsonapi(validate = "validate_event_attributes")]
+    attributes: EventAttributes,
+}
+
+#[derive(Deserialize)]
+struct EventAttributes {
+    name: String,
+    starts: usize,
+    ends: usize,
+}
+
+fn validate_event_attributes(attrs: &EventAttributes) -> Result<(), String> {
+    if attrs.name.is_empty() {
+        return Err("event name must not be empty".into());
+    }
+    Ok(())
+}
+
+fn validate_event(event: &EventRequest) -> Result<(), &'static str> {
+    if event.attributes.ends < event.attributes.starts {
+        return Err("an event must not end before it starts");
+    }
+    Ok(())
+}
+
+// manuscripts can only be edited by their author, which takes the current user to check
+#[derive(FromRequest)]
+#[jsonapi(context = "CurrentUser", authorize = "authorize_manuscript")]
+struct Manuscript {
+    id: usize,
+    relations: ManuscriptRelations,
+}
+
+#[derive(FromRelationships)]
+struct ManuscriptRelations {
+    author: usize,
+}
+
+struct CurrentUser {
+    id: usize,
+}
+
+#[allow(clippy::result_large_err)]
+fn authorize_manuscript(manuscript: &Manuscript, user: &CurrentUser) -> Result<(), jsonapi::Error> {
+    if manuscript.relations.author != user.id {
+        return Err(jsonapi::Error::new_forbidden("manuscripts can only be edited by their author")
+            .with_pointer("/data/relationships/author"));
+    }
+    Ok(())
+}
+
+#[derive(FromID, IntoID, Clone, Copy, PartialEq, Debug)]
+struct AuthorId(Uuid);
+
+#[derive(FromRequest, IntoResponse)]
+#[jsonapi(name = "books")]
+struct Book {
+    id: AuthorId,
+    attributes: SimpleAttributes,
+    relations: BookRelations,
+}
+
+#[derive(FromRelationships, IntoRelationships)]
+struct BookRelations {
+    #[jsonapi(resource_type = "authors")]
+    author: AuthorId,
+}
+
+// Ipv4Addr implements FromStr and Display, but not FromID
+#[derive(FromRequest, IntoResponse)]
+#[jsonapi(name = "hosts")]
+struct Host {
+    #[jsonapi(id_from_str)]
+    id: Ipv4Addr,
+    attributes: SimpleAttributes,
+    relations: HostRelations,
+}
+
+#[derive(FromRelationships, IntoRelationships)]
+struct HostRelations {
+    #[jsonapi(id_from_str, resource_type = "hosts")]
+    gateway: Option<Ipv4Addr>,
+    #[jsonapi(id_from_str, resource_type = "hosts")]
+    peers: Vec<Ipv4Addr>,
+    #[jsonapi(resource_type = "hosts")]
+    alias: Option<HostAddr>,
+}
+
+#[derive(FromID, IntoID, Debug, PartialEq)]
+#[jsonapi(id_from_str)]
+struct HostAddr(Ipv4Addr);
+
+// slugs are ids like any other, refused if they aren't normalized
+#[derive(FromRequest, IntoResponse)]
+#[jsonapi(name = "pages")]
+struct Page {
+    id: Slug,
+    attributes: SimpleAttributes,
+    relations: PageRelations,
+}
+
+#[derive(FromRelationships, IntoRelationships)]
+struct PageRelations {
+    #[jsonapi(resource_type = "pages")]
+    parent: Option<Slug>,
+}
+
+type MaybeSimple = Option<Uuid>;
+
+#[derive(FromRelationships, IntoRelationships)]
+struct SpelledRelations {
+    #[jsonapi(resource_type = "simples")]
+    qualified: std::option::Option<Uuid>,
+    // optional-ness of an alias is resolved through the type, not its name
+    #[jsonapi(resource_type = "simples")]
+    aliased: MaybeSimple,
+    #[jsonapi(resource_type = "simples")]
+    many: ::std::vec::Vec<Uuid>,
+    #[jsonapi(resource_type = "simples", default, skip_if_empty)]
+    sparse: Vec<Uuid>,
+}
+
+#[derive(IntoResponse)]
+#[jsonapi(name = "blobs")]
+struct BlobResponse {
+    id: usize,
+    attributes: BlobAttributes,
+    relations: BlobRelations,
+}
+
+// neither of these implement Clone, the derives move them into the response
+#[derive(Serialize)]
+struct BlobAttributes {
+    data: Vec<u8>,
+}
+
+#[derive(IntoRelationships)]
+struct BlobRelations {
+    #[jsonapi(resource_type = "blobs")]
+    chunks: Vec<usize>,
+}
+
+#[derive(IntoResponse)]
+struct Category {
+    id: usize,
+}
+
+#[derive(IntoResponse)]
+#[jsonapi(case = "kebab")]
+struct BlogPost {
+    id: usize,
+    relations: BlogPostRelations,
+}
+
+#[derive(IntoResponse)]
+#[jsonapi(name = "BlogPosts", case = "kebab")]
+struct LegacyBlogPost {
+    id: usize,
+}
+
+#[derive(IntoResponse)]
+#[jsonapi(rename = "blogPosts", case = "kebab")]
+struct RenamedBlogPost {
+    id: usize,
+}
+
+#[derive(IntoRelationships)]
+#[jsonapi(case = "kebab")]
+struct BlogPostRelations {
+    person: usize,
+    #[jsonapi(rename = "main-category")]
+    category: Option<usize>,
+}
+
+// generates ArticleFilter and ArticleSort
+#[derive(JsonApiFilter, JsonApiSort)]
+struct Article {
+    title: String,
+    #[jsonapi(rename = "likeCount")]
+    likes: Option<i64>,
+    created_at: u64,
+    #[jsonapi(skip)]
+    body: String,
+}
+
+// generates PostAttributesPatch, the attributes of updates to posts, which can be applied to the
+// attributes or a PostRow
+#[derive(Deserialize, JsonApiPatch)]
+#[jsonapi(name = "posts", apply_to(PostRow))]
+struct PostAttributes {
+    title: String,
+    summary: Option<String>,
+    #[serde(rename = "wordCount")]
+    #[jsonapi(rename = "wordCount")]
+    word_count: usize,
+    #[serde(skip)]
+    #[jsonapi(skip)]
+    revision: u32,
+}
+
+// generates CreatePost and UpdatePost next to Post's own FromRequest
+#[derive(FromRequest)]
+#[jsonapi(requests(create, update))]
+struct Post {
+    #[jsonapi(validate = "validate_post_id")]
+    id: usize,
+    #[jsonapi(validate = "validate_post_attributes")]
+    attributes: PostAttributes,
+    relations: PostRelations,
+}
+
+// generates PostRelationsPatch, the relations of updates to posts
+#[derive(FromRelationships)]
+#[jsonapi(patch)]
+struct PostRelations {
+    editor: Option<usize>,
+    #[jsonapi(default)]
+    tags: Vec<usize>,
+}
+
+fn validate_post_id(id: &usize) -> Result<(), String> {
+    if *id == 0 {
+        return Err("posts are numbered from 1".into());
+    }
+    Ok(())
+}
+
+fn validate_post_attributes(attrs: &PostAttributes) -> Result<(), String> {
+    if attrs.title.is_empty() {
+        return Err("a post needs a title".into());
+    }
+    Ok(())
+}
+
+struct PostRow {
+    id: i64,
+    title: String,
+    summary: Option<String>,
+    word_count: usize,
+}
+
+// the updates of /posts/{id}/relationships/{name}
+#[derive(FromRelationshipRequest, Debug, PartialEq)]
+enum PostRelationship {
+    Author(ToOneUpdate<usize>),
+    #[jsonapi(rename = "tags")]
+    Labels(ToManyUpdate<usize>),
+}
+
 #[derive(IntoResponse)]
 // All the types that can be included in the response of FakeResponse
 enum Included {
@@ -44,12 +385,20 @@ enum Included {
     Simple(SimpleResponse),
 }
 
+// generates Article, ArticleAttributes and ArticleRelations from a sample of a third-party
+// service's articles, and BlogPost and its kin from one of its blog posts
+mod sampled {
+    jsonapi_resource_derive::from_example!("fixtures/article.json");
+    jsonapi_resource_derive::from_example!("fixtures/blog_post.json");
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
 
     use jsonapi::{
-        FromRequest, Identifier, Relationship, RelationshipData, Request, ResourceRequest, Response,
+        ErrorStatus, Extensions, FromRelationships, FromRequest, IntoRelationships, Identifier, Relationship, RelationshipData, Request,
+        ResourceRequest, Response,
     };
 
     use super::*;
@@ -59,26 +408,35 @@ mod tests {
         let id = Uuid::new_v4();
         let mut req = Request {
             data: ResourceRequest {
-                id: Some(id.clone().into()),
+                id: Some(id.into()),
                 typ: "simple".into(),
                 attributes: SimpleAttributes {
                     foo: "test".into(),
                     bar: Some(4),
                 },
                 relationships: None,
+                extensions: Extensions::new(),
             },
+            extensions: Extensions::new(),
         };
         assert!(SimpleRequest::from_request(req.clone()).is_ok());
+        req.data.typ = "bananas".into();
+        let err = SimpleRequest::from_request(req.clone()).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::Conflict));
+        assert_eq!(err.source.unwrap().pointer.unwrap(), "/data/type");
+        assert!(AnyTypeRequest::from_request(req.clone()).is_ok());
+        req.data.typ = "simple".into();
         req.data.id = Some("foobar".into());
         assert!(SimpleRequest::from_request(req.clone()).is_err());
         let mut relations = BTreeMap::new();
         relations.insert(
             "foo".into(),
             RelationshipData {
-                data: Relationship::ToOne(Identifier {
+                data: Some(Relationship::ToOne(Identifier {
                     id: "fake".into(),
                     typ: "fakes".into(),
-                }),
+                })),
+                ..Default::default()
             },
         );
         req.data.relationships = Some(relations);
@@ -93,11 +451,11 @@ mod tests {
         let res = FakeResponse {
             id: 5,
             relations: FakeRelations {
-                simple: Some(id.clone()),
+                simple: Some(id),
             },
         };
         let simple = SimpleResponse {
-            id: id,
+            id,
             attributes: SimpleAttributes {
                 foo: "bar".into(),
                 bar: Some(3),
@@ -108,4 +466,652 @@ mod tests {
             .include(Included::Fake(res));
 		println!("{}", serde_json::to_string(&res).unwrap());
     }
+
+    #[test]
+    fn test_resource_meta() {
+        let mut meta = Meta::new();
+        meta.insert("hits".into(), 3.into());
+        let res = CounterResponse {
+            id: 1,
+            attributes: SimpleAttributes {
+                foo: "bar".into(),
+                bar: None,
+            },
+            meta: Some(meta),
+        };
+        let doc = serde_json::to_value(Response::from(res).finish()).unwrap();
+        assert_eq!(doc["data"][0]["meta"]["hits"], 3);
+
+        let res = CounterResponse {
+            id: 2,
+            attributes: SimpleAttributes {
+                foo: "bar".into(),
+                bar: None,
+            },
+            meta: None,
+        };
+        let doc = serde_json::to_value(Response::from(res).finish()).unwrap();
+        assert!(doc["data"][0].get("meta").is_none());
+    }
+
+    #[test]
+    fn test_resource_links() {
+        let mut extra = Links::new();
+        extra.insert("author".into(), "/people/9".into());
+        let res = ArticleResponse {
+            id: 4,
+            attributes: SimpleAttributes {
+                foo: "bar".into(),
+                bar: None,
+            },
+            extra_links: Some(extra),
+        };
+        let doc = serde_json::to_value(Response::from(res).finish()).unwrap();
+        assert_eq!(doc["data"][0]["links"]["self"], "/articles/4");
+        assert_eq!(doc["data"][0]["links"]["author"], "/people/9");
+    }
+
+    #[test]
+    fn test_skipped_fields() {
+        let id = Uuid::new_v4();
+        let mut relations = BTreeMap::new();
+        relations.insert(
+            "simple".into(),
+            RelationshipData {
+                data: Some(Relationship::ToOne(Identifier {
+                    id: id.into(),
+                    typ: "simples".into(),
+                })),
+                ..Default::default()
+            },
+        );
+        let req = Request {
+            data: ResourceRequest {
+                id: Some(id.into()),
+                typ: "trackeds".into(),
+                attributes: SimpleAttributes {
+                    foo: "test".into(),
+                    bar: None,
+                },
+                relationships: Some(relations),
+                extensions: Extensions::new(),
+            },
+            extensions: Extensions::new(),
+        };
+        let tracked = TrackedResource::from_request(req).unwrap();
+        assert_eq!(tracked.revision, 0);
+        assert!(!tracked.relations.loaded);
+        assert_eq!(tracked.relations.simple, Some(id));
+        let doc = serde_json::to_value(Response::from(tracked).finish()).unwrap();
+        assert!(doc["data"][0]["relationships"].get("loaded").is_none());
+        assert!(doc["data"][0]["attributes"].get("revision").is_none());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let req: Request<<DraftRequest as FromRequest>::Attributes> =
+            serde_json::from_str(r#"{"data": {"type": "drafts"}}"#).unwrap();
+        let draft = DraftRequest::from_request(req).unwrap();
+        assert_eq!(draft.attributes.title, "");
+        assert!(draft.relations.tags.is_empty());
+        assert_eq!(draft.relations.owner, 1);
+
+        let req: Request<<DraftRequest as FromRequest>::Attributes> = serde_json::from_str(
+            r#"{"data": {
+                "type": "drafts",
+                "attributes": {"title": "hello"},
+                "relationships": {"owner": {"data": {"type": "owners", "id": "7"}}}
+            }}"#,
+        )
+        .unwrap();
+        let draft = DraftRequest::from_request(req).unwrap();
+        assert_eq!(draft.attributes.title, "hello");
+        assert_eq!(draft.relations.owner, 7);
+    }
+
+    #[test]
+    fn test_maybe_relationships() {
+        let rels: jsonapi::Relationships = serde_json::from_str(
+            r#"{
+                "editor": {"data": null},
+                "tags": {"data": [{"type": "tags", "id": "3"}]}
+            }"#,
+        )
+        .unwrap();
+        let edit = EditRelations::from_relationships(Some(rels)).unwrap();
+        assert_eq!(edit.editor, Maybe::Null);
+        assert_eq!(edit.reviewer, Maybe::Absent);
+        assert_eq!(edit.tags, Maybe::Value(vec![3]));
+    }
+
+    #[test]
+    fn test_patch_derive() {
+        let req: Request<jsonapi::patch::PatchAttributes> = serde_json::from_str(
+            r#"{"data": {
+                "type": "posts",
+                "id": "1",
+                "attributes": {"summary": null, "wordCount": 120}
+            }}"#,
+        )
+        .unwrap();
+        let patch = PostAttributesPatch::from_request(req).unwrap();
+        assert_eq!(patch.title, Maybe::Absent);
+        assert_eq!(patch.summary, Maybe::Null);
+        assert_eq!(patch.word_count, Maybe::Value(120));
+
+        let req: Request<jsonapi::patch::PatchAttributes> =
+            serde_json::from_str(r#"{"data": {"type": "posts", "id": "1"}}"#).unwrap();
+        let patch = PostAttributesPatch::from_request(req).unwrap();
+        assert!(patch.title.is_absent() && patch.summary.is_absent());
+
+        // title can't be cleared, as it isn't an Option
+        let req: Request<jsonapi::patch::PatchAttributes> = serde_json::from_str(
+            r#"{"data": {"type": "posts", "id": "1", "attributes": {"title": null}}}"#,
+        )
+        .unwrap();
+        let err = PostAttributesPatch::from_request(req).err().unwrap();
+        assert_eq!(
+            err.source.unwrap().pointer.unwrap(),
+            "/data/attributes/title"
+        );
+
+        let req: Request<jsonapi::patch::PatchAttributes> =
+            serde_json::from_str(r#"{"data": {"type": "articles", "id": "1"}}"#).unwrap();
+        let err = PostAttributesPatch::from_request(req).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::Conflict));
+    }
+
+    #[test]
+    fn test_request_variants() {
+        let create = r#"{"data": {"type": "posts", "attributes": {"title": "a", "summary": null, "wordCount": 3}}}"#;
+        let req: Request<<CreatePost as FromRequest>::Attributes> =
+            serde_json::from_str(create).unwrap();
+        let post = CreatePost::from_request(req).unwrap();
+        assert_eq!(post.attributes.title, "a");
+        let with_id = r#"{"data": {"type": "posts", "id": "4", "attributes": {"title": "a", "summary": null, "wordCount": 3}}}"#;
+        let req: Request<<CreatePost as FromRequest>::Attributes> =
+            serde_json::from_str(with_id).unwrap();
+        let err = CreatePost::from_request(req).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::Forbidden));
+
+        let update = r#"{"data": {"type": "posts", "id": "4", "attributes": {"summary": "b"}}}"#;
+        let req: Request<<UpdatePost as FromRequest>::Attributes> =
+            serde_json::from_str(update).unwrap();
+        let post = UpdatePost::from_request(req).unwrap();
+        assert_eq!(post.id, 4);
+        assert!(post.attributes.title.is_absent());
+        assert_eq!(post.attributes.summary, Maybe::Value("b".to_owned()));
+        assert!(post.relations.editor.is_absent());
+        assert!(post.relations.tags.is_absent());
+        let req: Request<<UpdatePost as FromRequest>::Attributes> =
+            serde_json::from_str(r#"{"data": {"type": "posts"}}"#).unwrap();
+        assert!(UpdatePost::from_request(req).is_err());
+
+        // relationships are replaced, or cleared if they can be
+        let update = r#"{"data": {"type": "posts", "id": "4", "relationships": {
+            "editor": {"data": null},
+            "tags": {"data": [{"type": "tags", "id": "1"}, {"type": "tags", "id": "2"}]}
+        }}}"#;
+        let req: Request<<UpdatePost as FromRequest>::Attributes> =
+            serde_json::from_str(update).unwrap();
+        let post = UpdatePost::from_request(req).unwrap();
+        assert!(post.relations.editor.is_null());
+        assert_eq!(post.relations.tags, Maybe::Value(vec![1, 2]));
+        let update = r#"{"data": {"type": "posts", "id": "4", "relationships": {"tags": {"data": null}}}}"#;
+        let req: Request<<UpdatePost as FromRequest>::Attributes> =
+            serde_json::from_str(update).unwrap();
+        let err = UpdatePost::from_request(req).err().unwrap();
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/relationships/tags"));
+
+        // the validators of the fields they carry reject creates and updates as they do posts
+        let create = r#"{"data": {"type": "posts", "attributes": {"title": "", "summary": null, "wordCount": 3}}}"#;
+        let req: Request<<CreatePost as FromRequest>::Attributes> =
+            serde_json::from_str(create).unwrap();
+        let err = CreatePost::from_request(req).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::UnprocessableEntity));
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/attributes"));
+        let update = r#"{"data": {"type": "posts", "id": "0", "attributes": {"summary": "b"}}}"#;
+        let req: Request<<UpdatePost as FromRequest>::Attributes> =
+            serde_json::from_str(update).unwrap();
+        let err = UpdatePost::from_request(req).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::UnprocessableEntity));
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/id"));
+    }
+
+    #[test]
+    fn test_patch_apply() {
+        use jsonapi::patch::Apply;
+
+        let mut attributes = PostAttributes {
+            title: "draft".into(),
+            summary: Some("short".into()),
+            word_count: 10,
+            revision: 3,
+        };
+        let patch = PostAttributesPatch {
+            title: Maybe::Value("final".into()),
+            summary: Maybe::Null,
+            word_count: Maybe::Absent,
+        };
+        assert_eq!(patch.apply(&mut attributes), ["title", "summary"]);
+        assert_eq!(attributes.title, "final");
+        assert_eq!(attributes.summary, None);
+        assert_eq!((attributes.word_count, attributes.revision), (10, 3));
+
+        let mut row = PostRow {
+            id: 1,
+            title: "draft".into(),
+            summary: None,
+            word_count: 10,
+        };
+        let patch = PostAttributesPatch {
+            title: Maybe::Null,
+            summary: Maybe::Value("long".into()),
+            word_count: Maybe::Value(11),
+        };
+        assert_eq!(patch.apply(&mut row), ["summary", "word_count"]);
+        assert_eq!(row.title, "draft");
+        assert_eq!(row.summary.as_deref(), Some("long"));
+    }
+
+    #[test]
+    fn test_validation() {
+        let event = |name: &str, starts, ends| Request {
+            data: ResourceRequest {
+                id: None,
+                typ: "events".into(),
+                attributes: EventAttributes {
+                    name: name.into(),
+                    starts,
+                    ends,
+                },
+                relationships: None,
+                extensions: Extensions::new(),
+            },
+            extensions: Extensions::new(),
+        };
+        assert!(EventRequest::from_request(event("launch", 1, 2)).is_ok());
+
+        let err = EventRequest::from_request(event("", 1, 2)).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::UnprocessableEntity));
+        assert_eq!(err.title, "event name must not be empty");
+        assert_eq!(err.source.unwrap().pointer.unwrap(), "/data/attributes");
+
+        let err = EventRequest::from_request(event("launch", 2, 1)).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::UnprocessableEntity));
+        assert_eq!(err.source.unwrap().pointer.unwrap(), "/data");
+    }
+
+    #[test]
+    fn test_newtype_ids() {
+        let author = AuthorId(Uuid::new_v4());
+        let book = Book {
+            id: AuthorId(Uuid::new_v4()),
+            attributes: SimpleAttributes {
+                foo: "foo".into(),
+                bar: None,
+            },
+            relations: BookRelations { author },
+        };
+        let book_id = book.id;
+        assert_eq!(author.to_string(), author.0.to_string());
+        let doc = serde_json::to_value(Response::from(book).finish()).unwrap();
+        assert_eq!(doc["data"][0]["id"], book_id.to_string());
+        assert_eq!(
+            doc["data"][0]["relationships"]["author"]["data"]["id"],
+            author.to_string()
+        );
+
+        let mut relations = BTreeMap::new();
+        relations.insert(
+            "author".into(),
+            RelationshipData {
+                data: Some(Relationship::ToOne(Identifier {
+                    id: author.into(),
+                    typ: "authors".into(),
+                })),
+                ..Default::default()
+            },
+        );
+        let req = Request {
+            data: ResourceRequest {
+                id: Some(book_id.into()),
+                typ: "books".into(),
+                attributes: SimpleAttributes {
+                    foo: "foo".into(),
+                    bar: None,
+                },
+                relationships: Some(relations),
+                extensions: Extensions::new(),
+            },
+            extensions: Extensions::new(),
+        };
+        let book = Book::from_request(req).unwrap();
+        assert_eq!(book.id, book_id);
+        assert_eq!(book.relations.author, author);
+    }
+
+    #[test]
+    fn test_ids_from_str() {
+        let host = Host {
+            id: Ipv4Addr::new(10, 0, 0, 2),
+            attributes: SimpleAttributes {
+                foo: "foo".into(),
+                bar: None,
+            },
+            relations: HostRelations {
+                gateway: Some(Ipv4Addr::new(10, 0, 0, 1)),
+                peers: vec![Ipv4Addr::new(10, 0, 0, 3)],
+                alias: Some(HostAddr(Ipv4Addr::new(10, 0, 0, 4))),
+            },
+        };
+        let doc = serde_json::to_value(Response::from(host).finish()).unwrap();
+        let data = &doc["data"][0];
+        assert_eq!(data["id"], "10.0.0.2");
+        assert_eq!(data["relationships"]["gateway"]["data"]["id"], "10.0.0.1");
+        assert_eq!(data["relationships"]["peers"]["data"][0]["id"], "10.0.0.3");
+        assert_eq!(data["relationships"]["alias"]["data"]["id"], "10.0.0.4");
+
+        let mut req: Request<SimpleAttributes> = serde_json::from_value(serde_json::json!({
+            "data": {
+                "id": "10.0.0.2",
+                "type": "hosts",
+                "attributes": {"foo": "foo", "bar": null},
+                "relationships": {
+                    "peers": {"data": [{"type": "hosts", "id": "10.0.0.3"}]},
+                    "alias": {"data": {"type": "hosts", "id": "10.0.0.4"}}
+                }
+            }
+        }))
+        .unwrap();
+        let host = Host::from_request(req.clone()).unwrap();
+        assert_eq!(host.id, Ipv4Addr::new(10, 0, 0, 2));
+        assert_eq!(host.relations.gateway, None);
+        assert_eq!(host.relations.peers, vec![Ipv4Addr::new(10, 0, 0, 3)]);
+        assert_eq!(host.relations.alias, Some(HostAddr(Ipv4Addr::new(10, 0, 0, 4))));
+
+        req.data.id = Some("not-an-address".into());
+        let err = Host::from_request(req).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::BadRequest));
+    }
+
+    #[test]
+    fn test_relationship_type_spellings() {
+        let id = Uuid::new_v4();
+        let mut rels = BTreeMap::new();
+        rels.insert(
+            "many".to_owned(),
+            RelationshipData {
+                data: Some(Relationship::ToMany(vec![Identifier {
+                    id: id.into(),
+                    typ: "simples".into(),
+                }])),
+                ..Default::default()
+            },
+        );
+        let parsed = SpelledRelations::from_relationships(Some(rels)).unwrap();
+        assert_eq!(parsed.qualified, None);
+        assert_eq!(parsed.aliased, None);
+        assert_eq!(parsed.many, vec![id]);
+        assert!(parsed.sparse.is_empty());
+        // many is mandatory
+        assert!(SpelledRelations::from_relationships(None).is_err());
+
+        let rels = SpelledRelations {
+            qualified: None,
+            aliased: Some(id),
+            many: vec![],
+            sparse: vec![],
+        }
+        .into_relationships()
+        .unwrap();
+        assert!(!rels.contains_key("qualified"));
+        assert!(rels.contains_key("aliased"));
+        // an empty to-many is still emitted as `data: []` unless it opts out
+        assert!(rels.contains_key("many"));
+        assert!(!rels.contains_key("sparse"));
+    }
+
+    #[test]
+    fn test_type_name_inflection() {
+        let doc = serde_json::to_value(Response::from(Category { id: 1 }).finish()).unwrap();
+        assert_eq!(doc["data"][0]["type"], "categories");
+
+        let post = BlogPost {
+            id: 1,
+            relations: BlogPostRelations {
+                person: 2,
+                category: Some(3),
+            },
+        };
+        let doc = serde_json::to_value(Response::from(post).finish()).unwrap();
+        let data = &doc["data"][0];
+        assert_eq!(data["type"], "blog-posts");
+        assert_eq!(data["relationships"]["person"]["data"]["type"], "people");
+        assert_eq!(
+            data["relationships"]["main-category"]["data"]["type"],
+            "categories"
+        );
+
+        // case only applies to derived names: name is lowercased, rename is taken verbatim
+        let doc = serde_json::to_value(Response::from(LegacyBlogPost { id: 1 }).finish()).unwrap();
+        assert_eq!(doc["data"][0]["type"], "blogposts");
+        let doc = serde_json::to_value(Response::from(RenamedBlogPost { id: 1 }).finish()).unwrap();
+        assert_eq!(doc["data"][0]["type"], "blogPosts");
+    }
+
+    #[test]
+    fn test_responder_without_clone() {
+        let blob = BlobResponse {
+            id: 1,
+            attributes: BlobAttributes {
+                data: vec![0; 1024],
+            },
+            relations: BlobRelations {
+                chunks: vec![2, 3],
+            },
+        };
+        let doc = serde_json::to_value(Response::from(blob).finish()).unwrap();
+        assert_eq!(doc["data"][0]["attributes"]["data"].as_array().unwrap().len(), 1024);
+        assert_eq!(
+            doc["data"][0]["relationships"]["chunks"]["data"][1]["id"],
+            "3"
+        );
+    }
+
+    #[test]
+    fn test_filter_derive() {
+        use jsonapi::filter::Operator;
+
+        let query = "filter[title]=a&filter[likeCount][gte]=2&filter[likeCount][lt]=9";
+        let filter = ArticleFilter::from_query(query).unwrap();
+        assert_eq!(filter.title[0].values, ["a"]);
+        assert_eq!(filter.likes.len(), 2);
+        assert_eq!(filter.likes[1].op, Operator::Lt);
+        assert_eq!(filter.likes[1].values, [9]);
+
+        let err = ArticleFilter::from_query("filter[body]=x").err().unwrap();
+        assert_eq!(err.source.unwrap().parameter.as_deref(), Some("filter[body]"));
+        assert!(ArticleFilter::from_query("filter[likeCount]=many").is_err());
+    }
+
+    #[test]
+    fn test_sort_derive() {
+        use jsonapi::sort::Direction;
+
+        let sort = ArticleSort::parse("-created_at,likeCount").unwrap();
+        assert_eq!(
+            sort,
+            [
+                ArticleSort::CreatedAt(Direction::Desc),
+                ArticleSort::Likes(Direction::Asc)
+            ]
+        );
+        assert_eq!(sort[1].name(), "likeCount");
+        assert_eq!(sort[0].direction(), Direction::Desc);
+        let err = ArticleSort::from_query("sort=body").err().unwrap();
+        assert_eq!(err.source.unwrap().parameter.as_deref(), Some("sort"));
+    }
+
+    #[test]
+    fn test_relationship_request() {
+        use jsonapi::linkage::{FromRelationshipRequest, RelationshipOp, RelationshipRequest};
+
+        let req = |body: serde_json::Value| -> RelationshipRequest {
+            serde_json::from_value(body).unwrap()
+        };
+        let update = PostRelationship::from_relationship_request(
+            "tags",
+            RelationshipOp::Add,
+            req(serde_json::json!({"data": [{"type": "tags", "id": "4"}]})),
+        );
+        assert_eq!(update.unwrap(), PostRelationship::Labels(ToManyUpdate::Add(vec![4])));
+        let update = PostRelationship::from_relationship_request(
+            "author",
+            RelationshipOp::Replace,
+            req(serde_json::json!({"data": null})),
+        );
+        assert_eq!(update.unwrap(), PostRelationship::Author(ToOneUpdate::Clear));
+        let err = PostRelationship::from_relationship_request(
+            "labels",
+            RelationshipOp::Replace,
+            req(serde_json::json!({"data": []})),
+        )
+        .unwrap_err();
+        assert!(matches!(err.status, ErrorStatus::NotFound));
+    }
+
+    #[test]
+    fn test_request_with_context() {
+        use jsonapi::FromRequestWithContext;
+
+        let req: Request<Option<()>> = serde_json::from_value(serde_json::json!({"data": {
+            "type": "manuscripts",
+            "id": "1",
+            "relationships": {"author": {"data": {"type": "people", "id": "7"}}}
+        }}))
+        .unwrap();
+        let manuscript = Manuscript::from_request_with_context(req, &CurrentUser { id: 7 }).unwrap();
+        assert_eq!(manuscript.id, 1);
+
+        let req: Request<Option<()>> = serde_json::from_value(serde_json::json!({"data": {
+            "type": "manuscripts",
+            "id": "1",
+            "relationships": {"author": {"data": {"type": "people", "id": "7"}}}
+        }}))
+        .unwrap();
+        let err = Manuscript::from_request_with_context(req, &CurrentUser { id: 8 })
+            .err()
+            .unwrap();
+        assert!(matches!(err.status, ErrorStatus::Forbidden));
+    }
+
+    #[test]
+    fn test_from_example() {
+        use sampled::{Article, ArticleAttributes};
+
+        let sample = include_str!("../fixtures/article.json");
+        let req: Request<ArticleAttributes> = serde_json::from_str(sample).unwrap();
+        let article = Article::from_request(req).unwrap();
+        assert_eq!(article.id, "1");
+        assert_eq!(article.attributes.word_count, 1200);
+        assert_eq!(article.attributes.tags, vec!["api", "json"]);
+        assert!(article.attributes.summary.is_none());
+        assert!(!article.attributes._2fa);
+        assert_eq!(article.attributes.self_, "/articles/1");
+        assert_eq!(article.attributes.field, "untitled");
+        assert_eq!(article.relations.author.as_deref(), Some("9"));
+        assert_eq!(article.relations.comments, vec!["5", "12"]);
+
+        let out = serde_json::to_value(Response::<ArticleAttributes, ()>::with_capacity(1, 0).push(article)).unwrap();
+        let sample: serde_json::Value = serde_json::from_str(sample).unwrap();
+        assert_eq!(out["data"][0]["attributes"], sample["data"]["attributes"]);
+        assert_eq!(out["data"][0]["relationships"]["comments"], sample["data"]["relationships"]["comments"]);
+
+        // the sample's type is kept as it is
+        use sampled::{BlogPost, BlogPostAttributes};
+        let sample = include_str!("../fixtures/blog_post.json");
+        let req: Request<BlogPostAttributes> = serde_json::from_str(sample).unwrap();
+        let post = BlogPost::from_request(req).unwrap();
+        assert_eq!(post.relations.main_category.as_deref(), Some("3"));
+        let out = serde_json::to_value(Response::<BlogPostAttributes, ()>::with_capacity(1, 0).push(post)).unwrap();
+        assert_eq!(out["data"][0]["type"], "blogPosts");
+        let req: Request<BlogPostAttributes> = serde_json::from_str(&sample.replace("blogPosts", "blogposts")).unwrap();
+        assert!(BlogPost::from_request(req).is_err());
+    }
+
+    #[test]
+    fn test_slug_ids() {
+        let req = |id: &str, parent: &str| -> Request<SimpleAttributes> {
+            serde_json::from_value(serde_json::json!({"data": {
+                "type": "pages",
+                "id": id,
+                "attributes": {"foo": "a", "bar": null},
+                "relationships": {"parent": {"data": {"type": "pages", "id": parent}}}
+            }}))
+            .unwrap()
+        };
+        let page = Page::from_request(req("getting-started", "docs")).unwrap();
+        assert_eq!(page.id, Slug::slugify("Getting started").unwrap());
+        assert_eq!(page.relations.parent.as_ref().map(Slug::as_str), Some("docs"));
+        assert!(Page::from_request(req("Getting Started", "docs")).is_err());
+        assert!(Page::from_request(req("getting-started", "docs!")).is_err());
+
+        let out = serde_json::to_value(Response::<SimpleAttributes, ()>::with_capacity(1, 0).push(page)).unwrap();
+        assert_eq!(out["data"][0]["id"], "getting-started");
+        assert_eq!(out["data"][0]["relationships"]["parent"]["data"]["id"], "docs");
+    }
+
+    #[test]
+    fn test_relationship_errors() {
+        let linkage = serde_json::json!({
+            "gateway": {"data": {"type": "hosts", "id": "not-an-address"}},
+            "peers": {"data": [{"type": "hosts", "id": "10.0.0.3"}, {"type": "hosts", "id": "x"}]},
+            "alias": {"data": {"type": "hosts", "id": "10.0.0.4"}}
+        });
+        let rels: jsonapi::Relationships = serde_json::from_value(linkage.clone()).unwrap();
+        let errors = HostRelations::from_relationships_all(Some(rels.clone())).err().unwrap();
+        let pointers: Vec<_> = errors
+            .iter()
+            .map(|err| err.source.as_ref().unwrap().pointer.as_deref().unwrap())
+            .collect();
+        assert_eq!(pointers, ["/data/relationships/gateway", "/data/relationships/peers"]);
+        let err = HostRelations::from_relationships(Some(rels)).err().unwrap();
+        assert_eq!(err, errors[0]);
+
+        // the extractor answers with every wrong relationship
+        actix_web::rt::System::new().block_on(async {
+            use actix_web::{test, web, App};
+            use jsonapi::JsonApi;
+
+            async fn create(_: JsonApi<Host>) -> &'static str {
+                "created"
+            }
+
+            let app = test::init_service(App::new().route("/hosts", web::post().to(create))).await;
+            let req = test::TestRequest::post()
+                .uri("/hosts")
+                .insert_header(("content-type", "application/vnd.api+json"))
+                .set_payload(serde_json::json!({"data": {
+                    "type": "hosts",
+                    "id": "10.0.0.1",
+                    "attributes": {"foo": "a", "bar": null},
+                    "relationships": linkage,
+                }}).to_string())
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), 400);
+            let doc: serde_json::Value = test::read_body_json(res).await;
+            let errors = doc["errors"].as_array().unwrap();
+            assert_eq!(errors.len(), 2);
+            assert_eq!(errors[0]["source"]["pointer"], "/data/relationships/gateway");
+            assert_eq!(errors[1]["source"]["pointer"], "/data/relationships/peers");
+        });
+
+        let err = ManuscriptRelations::from_relationships(None).err().unwrap();
+        assert_eq!(
+            err.source.unwrap().pointer.as_deref(),
+            Some("/data/relationships/author")
+        );
+    }
 }
@@ -1,9 +1,10 @@
 extern crate proc_macro;
 
 use darling::{ast, util, FromDeriveInput, FromField, FromMeta, FromVariant};
+use inflector::Inflector;
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TS2;
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::{self, Type};
 
 #[derive(FromDeriveInput)]
@@ -11,28 +12,73 @@ use syn::{self, Type};
 struct ResourceProps {
     ident: syn::Ident,
     data: ast::Data<ResourceVariant, ResourceField>,
+    // name is the resource type as is, only lowercased
     name: Option<String>,
+    // rename is the resource type verbatim, matching the attribute used on relationship fields
+    rename: Option<String>,
+    // case applies to derived names only, an explicit name or rename is used as it's given
+    #[darling(default)]
+    case: Case,
+    self_link: Option<String>,
+    validate: Option<syn::Path>,
+    // accept requests whatever their `type` member says, rather than answering 409
+    #[darling(default)]
+    skip_type_check: bool,
+    vis: syn::Visibility,
+    #[darling(default)]
+    requests: RequestVariants,
+    // context is the type FromRequestWithContext is implemented for, and authorize the check
+    // (`fn(&Self, &Context) -> Result<(), Error>`) it runs once FromRequest has built the resource
+    context: Option<syn::Type>,
+    authorize: Option<syn::Path>,
+}
+
+// RequestVariants are the request types the FromRequest derive generates next to the struct's own
+// implementation, e.g. `#[jsonapi(requests(create, update))]`
+#[derive(FromMeta, Default, Clone, Copy)]
+struct RequestVariants {
+    #[darling(default)]
+    create: bool,
+    #[darling(default)]
+    update: bool,
 }
 
 #[derive(FromVariant, Clone)]
 #[darling(attributes(jsonapi))]
 struct ResourceVariant {
     ident: syn::Ident,
-    fields: ast::Fields<()>,
     attr_name: syn::Type,
 }
 
 #[derive(FromField, Clone)]
+#[darling(attributes(jsonapi))]
 struct ResourceField {
     ident: Option<syn::Ident>,
     ty: syn::Type,
+    #[darling(default)]
+    meta: bool,
+    #[darling(default)]
+    links: bool,
+    #[darling(default)]
+    skip: bool,
+    default: Option<util::Override<syn::Path>>,
+    validate: Option<syn::Path>,
+    #[darling(default)]
+    id_from_str: bool,
 }
 
 #[derive(FromDeriveInput)]
 #[darling(attributes(jsonapi), supports(struct_named))]
 struct RelationsProps {
     ident: syn::Ident,
+    vis: syn::Visibility,
     data: ast::Data<util::Ignored, RelationsField>,
+    #[darling(default)]
+    case: Case,
+    // patch has FromRelationships generate the update type of the relations too, see
+    // relations_patch
+    #[darling(default)]
+    patch: bool,
 }
 
 #[derive(FromField, Clone)]
@@ -40,7 +86,115 @@ struct RelationsProps {
 struct RelationsField {
     ident: Option<syn::Ident>,
     resource_type: Option<String>,
+    rename: Option<String>,
+    ty: syn::Type,
+    #[darling(default)]
+    skip: bool,
+    default: Option<util::Override<syn::Path>>,
+    #[darling(default)]
+    id_from_str: bool,
+    #[darling(default)]
+    skip_if_empty: bool,
+}
+
+#[derive(FromDeriveInput)]
+#[darling(attributes(jsonapi), supports(struct_newtype))]
+struct NewtypeProps {
+    ident: syn::Ident,
+    generics: syn::Generics,
+    #[darling(default)]
+    id_from_str: bool,
+}
+
+// QueryProps is the input of the derives generating filter sets and sort enums from the fields
+// of a struct
+#[derive(FromDeriveInput)]
+#[darling(attributes(jsonapi), supports(struct_named))]
+struct QueryProps {
+    ident: syn::Ident,
+    vis: syn::Visibility,
+    data: ast::Data<util::Ignored, QueryField>,
+}
+
+// PatchProps is the input of the derive generating the update type of an attributes struct. name
+// is the resource type updates are checked against, if any, and apply_to lists the structs
+// besides the attributes that updates can be applied to, e.g. database models
+#[derive(FromDeriveInput)]
+#[darling(attributes(jsonapi), supports(struct_named))]
+struct PatchProps {
+    ident: syn::Ident,
+    vis: syn::Visibility,
+    data: ast::Data<util::Ignored, QueryField>,
+    name: Option<String>,
+    #[darling(default)]
+    apply_to: util::PathList,
+}
+
+// RelationshipUpdateProps is the input of the derive dispatching the updates of relationship
+// endpoints to an enum's variants, one per relationship
+#[derive(FromDeriveInput)]
+#[darling(attributes(jsonapi), supports(enum_newtype))]
+struct RelationshipUpdateProps {
+    ident: syn::Ident,
+    data: ast::Data<RelationshipUpdateVariant, util::Ignored>,
+}
+
+#[derive(FromVariant)]
+#[darling(attributes(jsonapi))]
+struct RelationshipUpdateVariant {
+    ident: syn::Ident,
+    rename: Option<String>,
+}
+
+#[derive(FromField)]
+#[darling(attributes(jsonapi))]
+struct QueryField {
+    ident: Option<syn::Ident>,
     ty: syn::Type,
+    rename: Option<String>,
+    #[darling(default)]
+    skip: bool,
+}
+
+// Case is how the words of a derived resource type name are joined, e.g. for `BlogPost`
+#[derive(FromMeta, Default, Clone, Copy)]
+#[darling(rename_all = "lowercase")]
+enum Case {
+    #[default]
+    Lower, // blogposts
+    Kebab,  // blog-posts
+    Snake,  // blog_posts
+    Camel,  // blogPosts
+    Pascal, // BlogPosts
+}
+
+// type_name_for derives a resource type name from a struct or relationship field name by
+// pluralizing its last word and joining the words according to `case`. Struct names and the
+// fields referring to them end up with the same type name.
+fn type_name_for(name: &str, case: Case) -> String {
+    let words = name.to_snake_case();
+    let (head, last) = match words.rsplit_once('_') {
+        Some((head, last)) => (Some(head), last),
+        None => (None, words.as_str()),
+    };
+    // Inflector's rules for these irregulars append to the whole word (e.g. "personople")
+    let plural = match last {
+        "person" => "people".to_owned(),
+        "mouse" => "mice".to_owned(),
+        "louse" => "lice".to_owned(),
+        _ => last.to_plural(),
+    };
+    let snake = match head {
+        Some(head) => format!("{}_{}", head, plural),
+        None => plural,
+    };
+    match case {
+        Case::Lower => snake.replace('_', ""),
+        Case::Kebab => snake.to_kebab_case(),
+        Case::Snake => snake,
+        Case::Camel => snake.to_camel_case(),
+        Case::Pascal => snake.to_pascal_case(),
+    }
 }
 
 struct RelationNames {
@@ -48,6 +202,90 @@ struct RelationNames {
     field_name: syn::Ident,
     relation_name: String,
     is_option: bool,
+    is_vec: bool,
+    default: Option<TS2>,
+    id_from_str: bool,
+    // leave empty to-many relationships out of the document, rather than emitting `data: []`
+    skip_if_empty: bool,
+}
+
+impl RelationNames {
+    // parse_fn is the function turning the relationship linkage into the field's (inner) value
+    fn parse_fn(&self) -> TS2 {
+        match (self.id_from_str, self.is_vec) {
+            (false, _) => quote! { ::jsonapi::FromRelationship::from_relationship },
+            (true, false) => quote! { ::jsonapi::to_one_from_str },
+            (true, true) => quote! { ::jsonapi::to_many_from_str },
+        }
+    }
+
+    // ids_expr converts the field's (inner) value into something IntoRelationship accepts
+    fn ids_expr(&self, value: TS2) -> TS2 {
+        match (self.id_from_str, self.is_vec) {
+            (false, _) => value,
+            (true, false) => quote! { ::jsonapi::ID::from(#value.to_string()) },
+            (true, true) => quote! {
+                #value.iter().map(|id| ::jsonapi::ID::from(id.to_string())).collect::<Vec<_>>()
+            },
+        }
+    }
+}
+
+// last_segment returns the final path segment of a type, so that `Option<T>`,
+// `std::option::Option<T>` and `::core::option::Option<T>` are all recognized alike.
+// Aliases can't be seen through here; the generated code relies on traits for those.
+fn last_segment(ty: &syn::Type) -> Option<&syn::PathSegment> {
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path.path.segments.last(),
+        syn::Type::Group(group) => last_segment(&group.elem),
+        syn::Type::Paren(paren) => last_segment(&paren.elem),
+        _ => None,
+    }
+}
+
+// wraps_option reports whether a relation field is declared as an Option<T>
+fn wraps_option(ty: &syn::Type) -> bool {
+    matches!(last_segment(ty), Some(segment) if segment.ident == "Option")
+}
+
+// option_inner returns T for a field declared as Option<T>
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    let segment = last_segment(ty).filter(|segment| segment.ident == "Option")?;
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(syn::GenericArgument::Type(inner)) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// wraps_vec reports whether a relation field holds to-many linkage, i.e. Vec<T> or Option<Vec<T>>
+fn wraps_vec(ty: &syn::Type) -> bool {
+    let segment = match last_segment(ty) {
+        Some(segment) => segment,
+        None => return false,
+    };
+    if segment.ident == "Vec" {
+        return true;
+    }
+    if segment.ident == "Option" {
+        if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+            if let Some(syn::GenericArgument::Type(inner)) = args.args.first() {
+                return wraps_vec(inner);
+            }
+        }
+    }
+    false
+}
+
+// default_fn produces a path to the function called when a member with
+// #[jsonapi(default)] or #[jsonapi(default = "path::to::fn")] is absent
+fn default_fn(default: &util::Override<syn::Path>) -> TS2 {
+    match default {
+        util::Override::Inherit => quote! { ::std::default::Default::default },
+        util::Override::Explicit(path) => quote! { #path },
+    }
 }
 
 #[proc_macro_derive(IntoResponse, attributes(jsonapi))]
@@ -71,8 +309,446 @@ pub fn from_request_macro_derive(input: TokenStream) -> TokenStream {
     impl_from_request_macro(&syn::parse(input).unwrap())
 }
 
+#[proc_macro_derive(JsonApiFilter, attributes(jsonapi))]
+pub fn filter_macro_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse(input).unwrap();
+    impl_filter_macro(&ast)
+}
+
+#[proc_macro_derive(JsonApiSort, attributes(jsonapi))]
+pub fn sort_macro_derive(input: TokenStream) -> TokenStream {
+    let ast = syn::parse(input).unwrap();
+    impl_sort_macro(&ast)
+}
+
+#[proc_macro_derive(JsonApiPatch, attributes(jsonapi))]
+pub fn patch_macro_derive(input: TokenStream) -> TokenStream {
+    impl_patch_macro(&syn::parse(input).unwrap())
+}
+
+#[proc_macro_derive(FromRelationshipRequest, attributes(jsonapi))]
+pub fn relationship_request_macro_derive(input: TokenStream) -> TokenStream {
+    impl_relationship_request_macro(&syn::parse(input).unwrap())
+}
+
+#[proc_macro_derive(FromID, attributes(jsonapi))]
+pub fn from_id_macro_derive(input: TokenStream) -> TokenStream {
+    impl_from_id_macro(&syn::parse(input).unwrap())
+}
+
+#[proc_macro_derive(IntoID, attributes(jsonapi))]
+pub fn into_id_macro_derive(input: TokenStream) -> TokenStream {
+    impl_into_id_macro(&syn::parse(input).unwrap())
+}
+
+// from_example!("fixtures/article.json") generates a resource from a sample document, e.g. one
+// served by a third-party API. See impl_from_example_macro for what it emits
+#[proc_macro]
+pub fn from_example(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as ExampleInput);
+    impl_from_example_macro(&input).unwrap_or_else(|err| err.to_compile_error().into())
+}
+
+// the filter set of `Article` is a generated `ArticleFilter`, holding the conditions on each field
+// (parsed into the field's type, so it must implement FromStr) in the order they were given
+fn impl_filter_macro(ast: &syn::DeriveInput) -> TokenStream {
+    let props = QueryProps::from_derive_input(ast).unwrap();
+    let fields = match props.data {
+        ast::Data::Struct(data) => data.fields,
+        _ => panic!("unreachable"),
+    };
+    let vis = props.vis;
+    let filter_name = format_ident!("{}Filter", props.ident);
+    let fields: Vec<_> = fields.into_iter().filter(|field| !field.skip).collect();
+    let declarations = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let ty = option_inner(&field.ty).unwrap_or(&field.ty);
+        quote! {
+            pub #ident: Vec<::jsonapi::filter::FieldFilter<#ty>>,
+        }
+    });
+    let arms = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().unwrap();
+        let name = field.rename.clone().unwrap_or_else(|| ident.to_string());
+        quote! {
+            #name => filter.#ident.push(::jsonapi::filter::FieldFilter::parse(condition)?),
+        }
+    });
+    let gen = quote! {
+        #[derive(Default)]
+        #vis struct #filter_name {
+            #(#declarations)*
+        }
+
+        impl #filter_name {
+            pub fn from_spec(spec: &::jsonapi::filter::FilterSpec) -> Result<Self, ::jsonapi::Error> {
+                let mut filter = Self::default();
+                for condition in &spec.0 {
+                    match condition.field.as_str() {
+                        #(#arms)*
+                        _ => return Err(::jsonapi::Error::new_bad_request(
+                            &format!("can't filter by '{}'", condition.field)
+                        ).with_parameter(&condition.parameter())),
+                    }
+                }
+                Ok(filter)
+            }
+
+            pub fn from_query(query: &str) -> Result<Self, ::jsonapi::Error> {
+                Self::from_spec(&::jsonapi::filter::FilterSpec::from_query(query)?)
+            }
+        }
+    };
+    gen.into()
+}
+
+// the sort enum of `Article` is a generated `ArticleSort`, with a variant for each field that can
+// be sorted by, e.g. `ArticleSort::Created(Direction::Desc)` for "-created"
+fn impl_sort_macro(ast: &syn::DeriveInput) -> TokenStream {
+    let props = QueryProps::from_derive_input(ast).unwrap();
+    let fields = match props.data {
+        ast::Data::Struct(data) => data.fields,
+        _ => panic!("unreachable"),
+    };
+    let vis = props.vis;
+    let sort_name = format_ident!("{}Sort", props.ident);
+    let fields: Vec<_> = fields
+        .into_iter()
+        .filter(|field| !field.skip)
+        .map(|field| {
+            let ident = field.ident.unwrap();
+            let variant = format_ident!("{}", ident.to_string().to_pascal_case());
+            let name = field.rename.unwrap_or_else(|| ident.to_string());
+            (variant, name)
+        })
+        .collect();
+    let variants = fields.iter().map(|(variant, _)| {
+        quote! { #variant(::jsonapi::sort::Direction), }
+    });
+    let parse_arms = fields.iter().map(|(variant, name)| {
+        quote! { #name => #sort_name::#variant(field.direction), }
+    });
+    let name_arms = fields.iter().map(|(variant, name)| {
+        quote! { #sort_name::#variant(_) => #name, }
+    });
+    let direction_arms = fields.iter().map(|(variant, _)| {
+        quote! { #sort_name::#variant(direction) => *direction, }
+    });
+    let gen = quote! {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #vis enum #sort_name {
+            #(#variants)*
+        }
+
+        impl #sort_name {
+            pub fn from_spec(spec: &::jsonapi::sort::SortSpec) -> Result<Vec<Self>, ::jsonapi::Error> {
+                let mut sort = Vec::with_capacity(spec.0.len());
+                for field in &spec.0 {
+                    sort.push(match field.name.as_str() {
+                        #(#parse_arms)*
+                        _ => return Err(::jsonapi::Error::new_bad_request(
+                            &format!("can't sort by '{}'", field.name)
+                        ).with_parameter("sort")),
+                    });
+                }
+                Ok(sort)
+            }
+
+            pub fn parse(value: &str) -> Result<Vec<Self>, ::jsonapi::Error> {
+                Self::from_spec(&::jsonapi::sort::SortSpec::parse(value)?)
+            }
+
+            pub fn from_query(query: &str) -> Result<Vec<Self>, ::jsonapi::Error> {
+                Self::from_spec(&::jsonapi::sort::SortSpec::from_query(query)?)
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    #(#name_arms)*
+                }
+            }
+
+            pub fn direction(&self) -> ::jsonapi::sort::Direction {
+                match self {
+                    #(#direction_arms)*
+                }
+            }
+        }
+    };
+    gen.into()
+}
+
+// the update type of `ArticleAttributes` is a generated `ArticleAttributesPatch`, with each field
+// a Maybe of the field's type (of T for an Option<T>, which is the only kind of field that can
+// be cleared with null), read from the attributes of a PATCH request by its FromRequest
+fn impl_patch_macro(ast: &syn::DeriveInput) -> TokenStream {
+    let props = PatchProps::from_derive_input(ast).unwrap();
+    let fields = match props.data {
+        ast::Data::Struct(data) => data.fields,
+        _ => panic!("unreachable"),
+    };
+    let vis = props.vis;
+    let patch_name = format_ident!("{}Patch", props.ident);
+    let fields: Vec<_> = fields.into_iter().filter(|field| !field.skip).collect();
+    let declarations = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let ty = option_inner(&field.ty).unwrap_or(&field.ty);
+        quote! {
+            pub #ident: ::jsonapi::Maybe<#ty>,
+        }
+    });
+    let takes = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().unwrap();
+        let name = field.rename.clone().unwrap_or_else(|| ident.to_string());
+        let nullable = wraps_option(&field.ty);
+        quote! {
+            #ident: ::jsonapi::patch::take_attribute(&mut attributes, #name, #nullable)?,
+        }
+    });
+    let check_type = props.name.map(|name| {
+        quote! {
+            req.data.check_type(#name)?;
+        }
+    });
+    // a field which isn't an Option can't be cleared, so null leaves it alone
+    let assignments: Vec<TS2> = fields
+        .iter()
+        .map(|field| {
+            let ident = field.ident.as_ref().unwrap();
+            let name = ident.to_string();
+            let (value, clear) = if wraps_option(&field.ty) {
+                (
+                    quote! { Some(value) },
+                    quote! { target.#ident = None; changed.push(#name); },
+                )
+            } else {
+                (quote! { value }, quote! {})
+            };
+            quote! {
+                match self.#ident {
+                    ::jsonapi::Maybe::Value(value) => {
+                        target.#ident = #value;
+                        changed.push(#name);
+                    }
+                    ::jsonapi::Maybe::Null => { #clear }
+                    ::jsonapi::Maybe::Absent => {}
+                }
+            }
+        })
+        .collect();
+    let ident = props.ident;
+    let targets = std::iter::once(syn::Path::from(ident)).chain(props.apply_to.iter().cloned());
+    let applies = targets.map(|target| {
+        quote! {
+            impl ::jsonapi::patch::Apply<#target> for #patch_name {
+                fn apply(self, target: &mut #target) -> Vec<&'static str> {
+                    let mut changed = Vec::new();
+                    #(#assignments)*
+                    changed
+                }
+            }
+        }
+    });
+    let gen = quote! {
+        #[derive(Default)]
+        #vis struct #patch_name {
+            #(#declarations)*
+        }
+
+        impl ::jsonapi::FromRequest for #patch_name {
+            type Attributes = ::jsonapi::patch::PatchAttributes;
+
+            fn from_request(req: ::jsonapi::Request<Self::Attributes>) -> Result<Self, ::jsonapi::Error> {
+                #check_type
+                let mut attributes = req.data.attributes.unwrap_or_default();
+                Ok(#patch_name {
+                    #(#takes)*
+                })
+            }
+        }
+
+        #(#applies)*
+    };
+    gen.into()
+}
+
+// each variant of the enum handles the relationship named after it in snake case (e.g. `CoAuthors`
+// the "co_authors" relationship, as a field of that name would), unless it's renamed. Requests to
+// any other relationship are answered with 404
+fn impl_relationship_request_macro(ast: &syn::DeriveInput) -> TokenStream {
+    let props = RelationshipUpdateProps::from_derive_input(ast).unwrap();
+    let variants = match props.data {
+        ast::Data::Enum(variants) => variants,
+        _ => panic!("unreachable"),
+    };
+    let name = props.ident;
+    let arms = variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        let relationship = variant
+            .rename
+            .clone()
+            .unwrap_or_else(|| ident.to_string().to_snake_case());
+        quote! {
+            #relationship => Ok(#name::#ident(
+                ::jsonapi::linkage::FromRelationshipRequest::from_relationship_request(name, op, req)?,
+            )),
+        }
+    });
+    let gen = quote! {
+        impl ::jsonapi::linkage::FromRelationshipRequest for #name {
+            fn from_relationship_request(
+                name: &str,
+                op: ::jsonapi::linkage::RelationshipOp,
+                req: ::jsonapi::linkage::RelationshipRequest,
+            ) -> Result<Self, ::jsonapi::Error> {
+                match name {
+                    #(#arms)*
+                    _ => Err(::jsonapi::Error::new_not_found(&format!(
+                        "relationship '{}' doesn't exist",
+                        name
+                    ))),
+                }
+            }
+        }
+    };
+    gen.into()
+}
+
+// newtype ids (e.g. `struct ArticleId(Uuid)`) are parsed with the wrapped type's FromID
+fn impl_from_id_macro(ast: &syn::DeriveInput) -> TokenStream {
+    let props = NewtypeProps::from_derive_input(ast).unwrap();
+    let name = props.ident;
+    let (impl_generics, ty_generics, where_clause) = props.generics.split_for_impl();
+    let parse = if props.id_from_str {
+        quote! { ::jsonapi::id_from_str }
+    } else {
+        quote! { ::jsonapi::FromID::from_id }
+    };
+    (quote! {
+        impl #impl_generics ::jsonapi::FromID for #name #ty_generics #where_clause {
+            fn from_id(id: ::jsonapi::ID) -> Result<Self, ::jsonapi::Error> {
+                Ok(#name(#parse(id)?))
+            }
+        }
+    })
+    .into()
+}
+
+// newtype ids are converted into an ID, and displayed, through the wrapped type
+fn impl_into_id_macro(ast: &syn::DeriveInput) -> TokenStream {
+    let props = NewtypeProps::from_derive_input(ast).unwrap();
+    let name = props.ident;
+    let (impl_generics, ty_generics, where_clause) = props.generics.split_for_impl();
+    let convert = if props.id_from_str {
+        quote! { id.0.to_string().into() }
+    } else {
+        quote! { id.0.into() }
+    };
+    (quote! {
+        impl #impl_generics From<#name #ty_generics> for ::jsonapi::ID #where_clause {
+            fn from(id: #name #ty_generics) -> ::jsonapi::ID {
+                #convert
+            }
+        }
+
+        impl #impl_generics ::std::fmt::Display for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                ::std::fmt::Display::fmt(&self.0, f)
+            }
+        }
+    })
+    .into()
+}
+
 fn impl_from_request_macro(ast: &syn::DeriveInput) -> TokenStream {
     let desc = ResourceFieldDescription::from(ResourceProps::from_derive_input(ast).unwrap());
+    if desc.requests.update && desc.id_field.is_none() {
+        return syn::Error::new_spanned(&desc.name, "update requests need an id field")
+            .to_compile_error()
+            .into();
+    }
+    let with_context = match (&desc.context, &desc.authorize) {
+        (Some(context), Some(authorize)) => {
+            let name = &desc.name;
+            quote! {
+                impl ::jsonapi::FromRequestWithContext<#context> for #name {
+                    type Attributes = <#name as ::jsonapi::FromRequest>::Attributes;
+                    fn from_request_with_context(
+                        req: ::jsonapi::Request<Self::Attributes>,
+                        ctx: &#context,
+                    ) -> Result<Self, ::jsonapi::Error> {
+                        let result = <#name as ::jsonapi::FromRequest>::from_request(req)?;
+                        #authorize(&result, ctx)?;
+                        Ok(result)
+                    }
+                }
+            }
+        }
+        (None, None) => TS2::new(),
+        _ => {
+            return syn::Error::new_spanned(&desc.name, "context and authorize go together")
+                .to_compile_error()
+                .into()
+        }
+    };
+    let vis = desc.vis.clone();
+    let requests = desc.requests;
+    let attr_ty = desc.attr_field.as_ref().map(|field| field.ty.clone());
+    let relations_ty = desc.relations_field.as_ref().map(|field| field.ty.clone());
+    let id_ty = desc.id_field.as_ref().map(|field| field.ty.clone());
+    // validators run once the resource is constructed, field validators first, then the struct's.
+    // CreateX and UpdateX run the validators of the fields they carry as X has them: the
+    // attributes and relations of a create, the id of an update. The struct's takes X itself
+    let validation = |validate: &syn::Path, value: TS2, pointer: &str| {
+        quote! {
+            if let Err(err) = #validate(&#value) {
+                return Err(::jsonapi::Error::new_unprocessable_entity(&err.to_string()).with_pointer(#pointer).into());
+            }
+        }
+    };
+    let validator = |field: &Option<ResourceField>| {
+        let field = field.as_ref()?;
+        Some((field.ident.clone()?, field.validate.clone()?))
+    };
+    let (id_validator, attr_validator, relations_validator) = (
+        validator(&desc.id_field),
+        validator(&desc.attr_field),
+        validator(&desc.relations_field),
+    );
+    let mut validations = Vec::new();
+    for (field, pointer) in [
+        (&id_validator, "/data/id"),
+        (&attr_validator, "/data/attributes"),
+        (&relations_validator, "/data/relationships"),
+    ] {
+        if let Some((ident, validate)) = field {
+            validations.push(validation(validate, quote! { result.#ident }, pointer));
+        }
+    }
+    if let Some(validate) = &desc.validate {
+        validations.push(validation(validate, quote! { result }, "/data"));
+    }
+    let create_validations = [
+        (
+            &attr_validator,
+            quote! { result.attributes },
+            "/data/attributes",
+        ),
+        (
+            &relations_validator,
+            quote! { result.relations },
+            "/data/relationships",
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(field, value, pointer)| {
+        let (_, validate) = field.as_ref()?;
+        Some(validation(validate, value, pointer))
+    })
+    .collect::<Vec<_>>();
+    let update_validations = id_validator
+        .as_ref()
+        .map(|(_, validate)| validation(validate, quote! { result.id }, "/data/id"));
     let missing_id_err = format!(
         "missing required id field in request for resource {}",
         desc.type_name
@@ -92,29 +768,43 @@ fn impl_from_request_macro(ast: &syn::DeriveInput) -> TokenStream {
             // if there is no id field, don't allow the request to have an ID
             quote! {
                 if req.data.id.is_some() {
-                    return Err(::jsonapi::Error::new_bad_request(#id_not_allowed_err));
+                    return Err(::jsonapi::Error::new_bad_request(#id_not_allowed_err).into());
                 }
             }
         }
     };
+    let type_check_statement = if desc.skip_type_check {
+        TS2::new()
+    } else {
+        let type_name = &desc.type_name;
+        quote! {
+            req.data.check_type(#type_name)?;
+        }
+    };
     let relations_let_statement = match &desc.relations_field {
         Some(field) => {
             let ty = &field.ty;
             quote! {
-                let rels: #ty = ::jsonapi::FromRelationships::from_relationships(req.data.relationships)?;
+                let rels: #ty = ::jsonapi::FromRelationships::from_relationships_all(req.data.relationships)
+                    .map_err(::jsonapi::Errors)?;
             }
         }
         None => {
             quote! {
-                let _: () = ::jsonapi::FromRelationships::from_relationships(req.data.relationships)?;
+                let _: () = ::jsonapi::FromRelationships::from_relationships_all(req.data.relationships)
+                    .map_err(::jsonapi::Errors)?;
             }
         }
     };
+    let id_value = match &desc.id_field {
+        Some(field) if field.id_from_str => quote! { ::jsonapi::id_from_str(id)? },
+        _ => quote! { ::jsonapi::FromID::from_id(id)? },
+    };
     let id_statement = match desc.id_field {
         Some(field) => {
             let name = field.ident.unwrap();
             quote! {
-                #name: ::jsonapi::FromID::from_id(id)?,
+                #name: #id_value,
             }
         }
         None => TS2::new(),
@@ -128,6 +818,11 @@ fn impl_from_request_macro(ast: &syn::DeriveInput) -> TokenStream {
         }
         None => TS2::new(),
     };
+    let skipped_statements = desc.skipped_fields.iter().map(|field| {
+        quote! {
+            #field: ::std::default::Default::default(),
+        }
+    });
     let name = desc.name;
     let attr_type;
     let attributes_statement;
@@ -136,69 +831,311 @@ fn impl_from_request_macro(ast: &syn::DeriveInput) -> TokenStream {
             // TODO using Option<()> seems unnecessary. We should be able to just use ()
             // or a wrapper type and implement some custom serde rules for that type
             // to make it not require the `attributes` object in the request/response
-            attr_type = Type::from_string("Option<()>".into()).unwrap();
+            attr_type = Type::from_string("Option<()>").unwrap();
             attributes_statement = TS2::new();
         }
         Some(field) => {
-            attr_type = field.ty;
             let attr_name = Some(field.ident);
-            attributes_statement = quote! {
-                #attr_name: req.data.attributes
+            match field.default.as_ref() {
+                None => {
+                    attr_type = field.ty;
+                    attributes_statement = quote! {
+                        #attr_name: req.data.attributes
+                    }
+                }
+                Some(default) => {
+                    // an absent attributes member deserializes to None and falls back to the default
+                    let ty = field.ty;
+                    let default = default_fn(default);
+                    attr_type = syn::parse_quote! { Option<#ty> };
+                    attributes_statement = quote! {
+                        #attr_name: req.data.attributes.unwrap_or_else(#default)
+                    }
+                }
             }
         }
     }
 
+    // CreateX has the attributes and relationships of X, and no id: creation requests bringing one
+    // are refused as ClientIdPolicy::Forbid does
+    let attr_declaration = attr_ty.as_ref().map(|ty| quote! { pub attributes: #ty, });
+    let relations_declaration = relations_ty
+        .as_ref()
+        .map(|ty| quote! { pub relations: #ty, });
+    let create = requests.create.then(|| {
+        let create_name = format_ident!("Create{}", name);
+        quote! {
+            #vis struct #create_name {
+                #attr_declaration
+                #relations_declaration
+            }
+
+            impl ::jsonapi::FromRequest for #create_name {
+                type Attributes = #attr_type;
+                fn from_request(req: ::jsonapi::Request<#attr_type>) -> Result<Self, ::jsonapi::Error> {
+                    Self::from_request_all(req).map_err(|mut errors| errors.0.remove(0))
+                }
+
+                fn from_request_all(req: ::jsonapi::Request<#attr_type>) -> Result<Self, ::jsonapi::Errors> {
+                    #type_check_statement
+                    ::jsonapi::ClientIdPolicy::Forbid.check(req.data.id.as_ref())?;
+                    #relations_let_statement
+                    let result = #create_name {
+                        #relations_statement
+                        #attributes_statement
+                    };
+                    #(#create_validations)*
+                    Ok(result)
+                }
+            }
+        }
+    });
+    // UpdateX has the id of X, its attributes as the update type of the JsonApiPatch derive, and
+    // its relations as the update type FromRelationships generates with `#[jsonapi(patch)]`
+    let update = requests.update.then(|| {
+        let update_name = format_ident!("Update{}", name);
+        let patch_ty = attr_ty.as_ref().map(patch_type);
+        let (patch_attr_type, attr_declaration, attributes_statement) = match &patch_ty {
+            Some(ty) => (
+                quote! { ::jsonapi::patch::PatchAttributes },
+                quote! { pub attributes: #ty, },
+                quote! {
+                    attributes: <#ty as ::jsonapi::FromRequest>::from_request(::jsonapi::Request {
+                        data: ::jsonapi::ResourceRequest {
+                            id: None,
+                            typ: req.data.typ,
+                            attributes: req.data.attributes,
+                            relationships: None,
+                            extensions: ::jsonapi::Extensions::new(),
+                        },
+                        extensions: ::jsonapi::Extensions::new(),
+                    })?,
+                },
+            ),
+            None => (quote! { #attr_type }, TS2::new(), TS2::new()),
+        };
+        let (relations_declaration, relations_let_statement, relations_statement) =
+            match relations_ty.as_ref().map(patch_type) {
+                Some(ty) => (
+                    quote! { pub relations: #ty, },
+                    quote! {
+                        let rels: #ty = ::jsonapi::FromRelationships::from_relationships_all(req.data.relationships)
+                            .map_err(::jsonapi::Errors)?;
+                    },
+                    quote! { relations: rels, },
+                ),
+                None => (TS2::new(), relations_let_statement.clone(), TS2::new()),
+            };
+        quote! {
+            #vis struct #update_name {
+                pub id: #id_ty,
+                #attr_declaration
+                #relations_declaration
+            }
+
+            impl ::jsonapi::FromRequest for #update_name {
+                type Attributes = #patch_attr_type;
+                fn from_request(req: ::jsonapi::Request<Self::Attributes>) -> Result<Self, ::jsonapi::Error> {
+                    Self::from_request_all(req).map_err(|mut errors| errors.0.remove(0))
+                }
+
+                fn from_request_all(req: ::jsonapi::Request<Self::Attributes>) -> Result<Self, ::jsonapi::Errors> {
+                    #type_check_statement
+                    #id_let_statement
+                    #relations_let_statement
+                    let result = #update_name {
+                        id: #id_value,
+                        #relations_statement
+                        #attributes_statement
+                    };
+                    #update_validations
+                    Ok(result)
+                }
+            }
+        }
+    });
+
     let gen = quote! {
         impl ::jsonapi::FromRequest for #name {
             type Attributes = #attr_type;
             fn from_request(req: ::jsonapi::Request<#attr_type>) -> Result<Self, ::jsonapi::Error> {
+                Self::from_request_all(req).map_err(|mut errors| errors.0.remove(0))
+            }
+
+            fn from_request_all(req: ::jsonapi::Request<#attr_type>) -> Result<Self, ::jsonapi::Errors> {
+                #type_check_statement
                 #id_let_statement
                 #relations_let_statement
                 let result = #name {
                     #id_statement
                     #relations_statement
+                    #(#skipped_statements)*
                     #attributes_statement
                 };
+                #(#validations)*
                 Ok(result)
             }
         }
+
+        #create
+        #update
+        #with_context
     };
     gen.into()
 }
 
+// patch_type is the update type the JsonApiPatch derive generates for an attributes type, i.e.
+// `ArticleAttributesPatch` for `ArticleAttributes`, in the same module
+fn patch_type(ty: &syn::Type) -> syn::Type {
+    let mut ty = ty.clone();
+    if let syn::Type::Path(path) = &mut ty {
+        if let Some(segment) = path.path.segments.last_mut() {
+            segment.ident = format_ident!("{}Patch", segment.ident);
+            segment.arguments = syn::PathArguments::None;
+        }
+    }
+    ty
+}
+
+// the update type of `ArticleRelations` is a generated `ArticleRelationsPatch`, as JsonApiPatch's
+// is of attributes: each relationship is a Maybe of the field's type (of T for an Option<T>, the
+// only kind of relationship that can be cleared with `data: null`), Absent if the request leaves
+// it out
+fn relations_patch(props: &RelationsProps) -> TS2 {
+    let ast::Data::Struct(data) = &props.data else {
+        return TS2::new();
+    };
+    let vis = &props.vis;
+    let patch_name = format_ident!("{}Patch", props.ident);
+    let fields: Vec<_> = data.fields.iter().filter(|field| !field.skip).collect();
+    let declarations = fields.iter().map(|field| {
+        let ident = &field.ident;
+        let ty = option_inner(&field.ty).unwrap_or(&field.ty);
+        quote! {
+            pub #ident: ::jsonapi::Maybe<#ty>,
+        }
+    });
+    let takes = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().unwrap();
+        let name = field.rename.clone().unwrap_or_else(|| ident.to_string());
+        let nullable = wraps_option(&field.ty);
+        let parse = match (field.id_from_str, wraps_vec(&field.ty)) {
+            (false, _) => quote! { ::jsonapi::FromRelationship::from_relationship },
+            (true, false) => quote! { ::jsonapi::to_one_from_str },
+            (true, true) => quote! { ::jsonapi::to_many_from_str },
+        };
+        quote! {
+            let #ident = match ::jsonapi::take_relationship(&mut rels, #name) {
+                None => Ok(::jsonapi::Maybe::Absent),
+                Some(t) if #nullable && t.data.is_none() => Ok(::jsonapi::Maybe::Null),
+                Some(t) => t.into_data().and_then(#parse).map(::jsonapi::Maybe::Value),
+            };
+            let #ident = match #ident {
+                Ok(value) => value,
+                Err(err) => {
+                    errors.push(::jsonapi::at_relationship(err, #name));
+                    ::jsonapi::Maybe::Absent
+                }
+            };
+        }
+    });
+    let idents = fields.iter().map(|field| &field.ident);
+    quote! {
+        #[derive(Default)]
+        #vis struct #patch_name {
+            #(#declarations)*
+        }
+
+        impl ::jsonapi::FromRelationships for #patch_name {
+            fn from_relationships(rels: Option<::jsonapi::Relationships>) -> Result<Self, ::jsonapi::Error> {
+                Self::from_relationships_all(rels).map_err(|mut errors| errors.remove(0))
+            }
+
+            fn from_relationships_all(rels: Option<::jsonapi::Relationships>) -> Result<Self, Vec<::jsonapi::Error>> {
+                let mut rels = rels.unwrap_or_default();
+                let mut errors: Vec<::jsonapi::Error> = Vec::new();
+                #(#takes)*
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
+                Ok(#patch_name {
+                    #(#idents,)*
+                })
+            }
+        }
+    }
+}
+
+// check_relation_names rejects relationships the spec doesn't allow, as resource objects can't
+// have fields named `type` or `id`
+fn check_relation_names(props: &RelationsProps) -> Result<(), syn::Error> {
+    let ast::Data::Struct(data) = &props.data else {
+        return Ok(());
+    };
+    for field in data.fields.iter().filter(|field| !field.skip) {
+        let ident = field.ident.as_ref().unwrap();
+        let name = field.rename.clone().unwrap_or_else(|| ident.to_string());
+        if name == "type" || name == "id" {
+            return Err(syn::Error::new_spanned(
+                ident,
+                format!("'{}' can't be used as a relationship name", name),
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn impl_from_relations_macro(ast: &syn::DeriveInput) -> TokenStream {
-    let desc = RelationFieldDescription::from(RelationsProps::from_derive_input(ast).unwrap());
-    let mut all_options = true;
+    let props = RelationsProps::from_derive_input(ast).unwrap();
+    if let Err(err) = check_relation_names(&props) {
+        return err.to_compile_error().into();
+    }
+    let patch = props.patch.then(|| relations_patch(&props));
+    let desc = RelationFieldDescription::from(props);
     let var_statements: Vec<TS2> = desc
         .fields
         .iter()
         .map(|names| {
-            if !names.is_option {
-                all_options = false;
-            }
             let name = &names.relation_name;
             let field = &names.field_name;
-            let ts = if names.is_option {
-                quote! {
-                    let #field;
-                    if let Some(t) = rels.remove(#name) {
-                        #field = Some(::jsonapi::FromRelationship::from_relationship(t.data)?);
-                    } else {
-                        #field = None;
-                    };
+            let err_msg = format!("missing mandatory relationship '{}'", name);
+            // id_from_str fields bypass FromID, so their shape has to come from the declared type.
+            // Everything else lets the field's type decide whether it is optional.
+            let value = if names.id_from_str {
+                let parse = names.parse_fn();
+                if names.is_option {
+                    quote! { t.into_data().and_then(#parse).map(Some) }
+                } else {
+                    quote! { t.into_data().and_then(#parse) }
                 }
             } else {
-                let err_msg = format!("missing mandatory relationship '{}'", name);
-                quote! {
-                    let #field;
-                    if let Some(t) = rels.remove(#name) {
-                        #field = ::jsonapi::FromRelationship::from_relationship(t.data)?;
-                    } else {
-                        return Err(::jsonapi::Error::new_bad_request(#err_msg));
-                    };
-                }
+                quote! { ::jsonapi::FromRelationshipField::from_relationship_data(t) }
+            };
+            let missing = match &names.default {
+                Some(default) => quote! { Ok(#default()) },
+                None if names.id_from_str && names.is_option => quote! { Ok(None) },
+                None if names.id_from_str => quote! {
+                    Err(::jsonapi::Error::new_bad_request(#err_msg))
+                },
+                None => quote! {
+                    ::jsonapi::FromRelationshipField::from_missing()
+                        .ok_or_else(|| ::jsonapi::Error::new_bad_request(#err_msg))
+                },
             };
-            ts
+            // each relationship is checked on its own, so every one that's wrong is reported
+            quote! {
+                let #field = match ::jsonapi::take_relationship(&mut rels, #name) {
+                    Some(t) => #value,
+                    None => #missing,
+                };
+                let #field = match #field {
+                    Ok(value) => Some(value),
+                    Err(err) => {
+                        errors.push(::jsonapi::at_relationship(err, #name));
+                        None
+                    }
+                };
+            }
         })
         .collect();
     let struct_statements: Vec<TS2> = desc
@@ -206,68 +1143,96 @@ fn impl_from_relations_macro(ast: &syn::DeriveInput) -> TokenStream {
         .into_iter()
         .map(|names| {
             let field = names.field_name;
-            let ts = quote! {
-                #field,
-            };
-            ts
+            quote! {
+                #field: #field.expect("relationships without errors have values"),
+            }
         })
         .collect();
-    let none_handler = if all_options {
-        // TODO this isn't the most efficient approach in the world
-        quote! {
-            let mut rels: ::std::collections::BTreeMap<String, ::jsonapi::RelationshipData> = match rels {
-                None => ::std::collections::BTreeMap::new(),
-                Some(b) => b
-            };
-        }
-    } else {
+    let skipped_statements = desc.skipped_fields.iter().map(|field| {
         quote! {
-            let mut rels = rels.ok_or_else(|| ::jsonapi::Error::new_bad_request("missing mandatory relationships object"))?;
+            #field: ::std::default::Default::default(),
         }
-    };
+    });
     let struct_name = desc.name;
     let gen = quote! {
         impl ::jsonapi::FromRelationships for #struct_name {
-            fn from_relationships(rels: Option<::std::collections::BTreeMap<String, ::jsonapi::RelationshipData>>) -> Result<Self, ::jsonapi::Error> {
-                #none_handler
+            fn from_relationships(rels: Option<::jsonapi::Relationships>) -> Result<Self, ::jsonapi::Error> {
+                Self::from_relationships_all(rels).map_err(|mut errors| errors.remove(0))
+            }
+
+            fn from_relationships_all(rels: Option<::jsonapi::Relationships>) -> Result<Self, Vec<::jsonapi::Error>> {
+                // an absent relationships object is the same as an empty one, mandatory
+                // relationships are reported individually below
+                let mut rels = rels.unwrap_or_default();
+                let mut errors: Vec<::jsonapi::Error> = Vec::new();
                 #(#var_statements)*
+                if !errors.is_empty() {
+                    return Err(errors);
+                }
                 Ok(#struct_name {
                     #(#struct_statements)*
+                    #(#skipped_statements)*
                 })
             }
         }
+
+        #patch
     };
     gen.into()
 }
 
 fn impl_relations_macro(ast: &syn::DeriveInput) -> TokenStream {
     let props: RelationsProps = RelationsProps::from_derive_input(ast).unwrap();
+    if let Err(err) = check_relation_names(&props) {
+        return err.to_compile_error().into();
+    }
     let desc = RelationFieldDescription::from(props);
-    let statements: Vec<TS2> = desc.fields
+    let statements: Vec<TS2> = desc
+        .fields
         .into_iter()
         .map(|names| {
-            let name = names.relation_name;
-            let resource = names.resource_name;
-            let field = names.field_name;
-            let ts = if names.is_option {
-				quote! {
-				if let Some(field) = self.#field {
-					rels.insert(#name.to_string(), ::jsonapi::IntoRelationship::into_relationship(field, #resource).into());
-				}
-				}
-			} else {
-				 quote! {
-                rels.insert(#name.to_string(), ::jsonapi::IntoRelationship::into_relationship(self.#field, #resource).into());
-				 }
-			};
-            ts
+            let name = &names.relation_name;
+            let resource = &names.resource_name;
+            let field = &names.field_name;
+            let rel = if !names.id_from_str {
+                quote! {
+                    ::jsonapi::IntoRelationshipField::into_relationship_data(self.#field, #resource)
+                }
+            } else if names.is_option {
+                let ids = names.ids_expr(quote! { field });
+                quote! {
+                    self.#field.map(|field| ::jsonapi::RelationshipData::from(::jsonapi::IntoRelationship::into_relationship(#ids, #resource)))
+                }
+            } else {
+                let ids = names.ids_expr(quote! { self.#field });
+                quote! {
+                    Some(::jsonapi::RelationshipData::from(::jsonapi::IntoRelationship::into_relationship(#ids, #resource)))
+                }
+            };
+            let insert = quote! {
+                rels.insert(#name.to_string(), rel);
+            };
+            let insert = if names.skip_if_empty {
+                quote! {
+                    if !rel.is_empty() {
+                        #insert
+                    }
+                }
+            } else {
+                insert
+            };
+            quote! {
+                if let Some(rel) = #rel {
+                    #insert
+                }
+            }
         })
         .collect();
     let struct_name = desc.name;
     (quote! {
         impl ::jsonapi::IntoRelationships for #struct_name {
-            fn into_relationships(self) -> Option<::std::collections::BTreeMap<String, ::jsonapi::RelationshipData>> {
-                let mut rels = ::std::collections::BTreeMap::new();
+            fn into_relationships(self) -> Option<::jsonapi::Relationships> {
+                let mut rels = ::jsonapi::Relationships::new();
                 #(#statements)*
                 Some(rels)
             }
@@ -303,7 +1268,6 @@ fn impl_responder_macro(ast: &syn::DeriveInput) -> TokenStream {
             .into_iter()
             .map(|variant| {
                 let name = variant.ident;
-                let attr = variant.attr_name;
                 quote! {
                     Self::#name (res) => {
                         let inner = ::jsonapi::IntoResponse::into_response(res);
@@ -311,6 +1275,9 @@ fn impl_responder_macro(ast: &syn::DeriveInput) -> TokenStream {
                             id: inner.id,
                             attributes: #attr_enum_name :: # name (inner.attributes),
                             relationships: inner.relationships,
+                            meta: inner.meta,
+                            links: inner.links,
+                            extensions: inner.extensions,
                         }
                     }
                 }
@@ -336,22 +1303,50 @@ fn impl_responder_macro(ast: &syn::DeriveInput) -> TokenStream {
         gen.into()
     } else {
         let desc = ResourceFieldDescription::from(props);
-        let (relations_fn, relations_type) = match desc.relations_field.as_ref() {
-            None => (quote! { None }, quote! {()}),
+        let relations_fn = match desc.relations_field.as_ref() {
+            None => quote! { None },
             Some(field) => {
                 let relations_name = field.ident.as_ref().unwrap();
-                let field_type = &field.ty;
-                (
-                    quote! {
-                        ::jsonapi::IntoRelationships::into_relationships(self.#relations_name)
-
-                    },
-                    quote! {
-                        #field_type
-                    },
-                )
+                quote! {
+                    ::jsonapi::IntoRelationships::into_relationships(self.#relations_name)
+                }
+            }
+        };
+        let meta_fn = match desc.meta_field.as_ref() {
+            None => quote! { None },
+            Some(field) => {
+                let meta_name = field.ident.as_ref().unwrap();
+                quote! {
+                    ::jsonapi::IntoMeta::into_meta(self.#meta_name)
+                }
+            }
+        };
+        let links_fn = match desc.links_field.as_ref() {
+            None => quote! { None },
+            Some(field) => {
+                let links_name = field.ident.as_ref().unwrap();
+                quote! {
+                    ::jsonapi::IntoLinks::into_links(self.#links_name)
+                }
             }
         };
+        // a self link is an RFC 6570 template, expanded with the resource's id and type and added
+        // to any other links
+        let self_link_stmt = match desc.self_link.as_ref() {
+            None => TS2::new(),
+            Some(template) => quote! {
+                links.get_or_insert_with(::jsonapi::Links::new).insert(
+                    "self".to_owned(),
+                    ::jsonapi::Link::Url(::jsonapi::links::UriTemplate::new(#template).expand(
+                        |name| match name {
+                            "id" => Some(id.id.to_string()),
+                            "type" => Some(id.typ.clone()),
+                            _ => None,
+                        },
+                    )),
+                );
+            },
+        };
         let (attr_fn, attr_type) = match desc.attr_field {
             None => (quote! { None }, quote! { Option<()> }),
             Some(field) => {
@@ -367,7 +1362,13 @@ fn impl_responder_macro(ast: &syn::DeriveInput) -> TokenStream {
                 )
             }
         };
-        let id_name = desc.id_field.unwrap().ident.unwrap();
+        let id_field = desc.id_field.unwrap();
+        let id_name = id_field.ident.unwrap();
+        let id_value = if id_field.id_from_str {
+            quote! { ::jsonapi::ID::from(self.#id_name.to_string()) }
+        } else {
+            quote! { self.#id_name.into() }
+        };
         let name = desc.name;
         let type_name = desc.type_name;
         let gen = quote! {
@@ -376,13 +1377,19 @@ fn impl_responder_macro(ast: &syn::DeriveInput) -> TokenStream {
 
                 fn into_response(self) -> ::jsonapi::ResourceResponse<Self::Attributes> {
                     let id = ::jsonapi::Identifier{
-                        id: self.id.into(),
-                        typ: #type_name.to_owned().to_lowercase()
+                        id: #id_value,
+                        typ: #type_name.to_owned()
                     };
+                    #[allow(unused_mut)]
+                    let mut links: Option<::jsonapi::Links> = #links_fn;
+                    #self_link_stmt
                     ::jsonapi::ResourceResponse{
                         id,
                         attributes: #attr_fn,
-                        relationships: #relations_fn
+                        relationships: #relations_fn,
+                        meta: #meta_fn,
+                        links,
+                        extensions: ::jsonapi::Extensions::new(),
                     }
                 }
             }
@@ -398,48 +1405,60 @@ struct ResourceFieldDescription {
     id_field: Option<ResourceField>,
     attr_field: Option<ResourceField>,
     relations_field: Option<ResourceField>,
+    meta_field: Option<ResourceField>,
+    links_field: Option<ResourceField>,
+    self_link: Option<String>,
+    validate: Option<syn::Path>,
+    skip_type_check: bool,
+    // fields marked #[jsonapi(skip)], filled with their Default when constructing from a request
+    skipped_fields: Vec<syn::Ident>,
+    vis: syn::Visibility,
+    requests: RequestVariants,
+    context: Option<syn::Type>,
+    authorize: Option<syn::Path>,
 }
 
 struct RelationFieldDescription {
     name: syn::Ident,
     fields: Vec<RelationNames>,
+    skipped_fields: Vec<syn::Ident>,
 }
 
 impl From<RelationsProps> for RelationFieldDescription {
     fn from(props: RelationsProps) -> RelationFieldDescription {
+        let case = props.case;
+        let (skipped, fields): (Vec<RelationsField>, Vec<RelationsField>) = match props.data {
+            ast::Data::Struct(data) => data.fields.into_iter().partition(|field| field.skip),
+            _ => panic!("unreachable"),
+        };
         RelationFieldDescription {
-            fields: match props.data {
-                ast::Data::Struct(data) => data
-                    .fields
-                    .into_iter()
-                    .map(|field| {
-                        let resource_name = match field.resource_type {
-                            Some(name) => name,
-                            None => format!("{}s", field.ident.clone().unwrap()),
-                        };
-                        let is_option = match field.ty {
-				syn::Type::Path(path) => {
-					if path.path.leading_colon.is_none() && path.path.segments.len() == 1 {
-						match path.path.segments.into_iter().next().unwrap().ident {
-							i if i == "Option" => true,
-							_ => false,
-						}
-					} else {
-						panic!("unsupported type name for deriving Relations, Option<T> or T where T: Into<ID> supported")
-					}
-				},
-				_ => panic!("unsupported type for deriving Relations, Option<T> or T where T:Into<ID supported")
-			};
-                        RelationNames {
-                            resource_name,
-                            field_name: field.ident.clone().unwrap(),
-                            relation_name: field.ident.clone().unwrap().to_string(),
-                            is_option,
-                        }
-                    })
-                    .collect(),
-                _ => panic!("unreachable"),
-            },
+            skipped_fields: skipped
+                .into_iter()
+                .map(|field| field.ident.unwrap())
+                .collect(),
+            fields: fields
+                .into_iter()
+                .map(|field| {
+                    let resource_name = match field.resource_type {
+                        Some(name) => name,
+                        None => type_name_for(&field.ident.clone().unwrap().to_string(), case),
+                    };
+                    let is_vec = wraps_vec(&field.ty);
+                    let is_option = wraps_option(&field.ty);
+                    RelationNames {
+                        resource_name,
+                        field_name: field.ident.clone().unwrap(),
+                        relation_name: field
+                            .rename
+                            .unwrap_or_else(|| field.ident.clone().unwrap().to_string()),
+                        is_option,
+                        is_vec,
+                        default: field.default.as_ref().map(default_fn),
+                        id_from_str: field.id_from_str,
+                        skip_if_empty: field.skip_if_empty,
+                    }
+                })
+                .collect(),
             name: props.ident,
         }
     }
@@ -448,24 +1467,38 @@ impl From<RelationsProps> for RelationFieldDescription {
 impl From<ResourceProps> for ResourceFieldDescription {
     fn from(props: ResourceProps) -> Self {
         let name = props.ident;
-        let mut type_name = format!("{}s", name);
-        if let Some(custom_name) = props.name {
-            type_name = custom_name;
-        }
+        let type_name = match (props.rename, props.name) {
+            (Some(rename), _) => rename,
+            (None, Some(custom_name)) => custom_name.to_lowercase(),
+            (None, None) => type_name_for(&name.to_string(), props.case),
+        };
         // try to identify the id, attributes fields.
         let mut id_field: Option<ResourceField> = None;
         let mut attr_field: Option<ResourceField> = None;
         let mut relations_field: Option<ResourceField> = None;
+        let mut meta_field: Option<ResourceField> = None;
+        let mut links_field: Option<ResourceField> = None;
+        let mut skipped_fields = Vec::new();
         match props.data {
             ast::Data::Struct(data) => {
                 for field in &data.fields {
-                    if let Some(i) = &field.ident {
+                    if field.skip {
+                        skipped_fields.push(field.ident.clone().unwrap());
+                    } else if field.meta {
+                        meta_field = Some(field.clone());
+                    } else if field.links {
+                        links_field = Some(field.clone());
+                    } else if let Some(i) = &field.ident {
                         if i == "id" {
                             id_field = Some(field.clone())
                         } else if i == "attributes" {
                             attr_field = Some(field.clone())
                         } else if i == "relations" {
                             relations_field = Some(field.clone())
+                        } else if i == "meta" && meta_field.is_none() {
+                            meta_field = Some(field.clone())
+                        } else if i == "links" && links_field.is_none() {
+                            links_field = Some(field.clone())
                         }
                     }
                 }
@@ -478,6 +1511,200 @@ impl From<ResourceProps> for ResourceFieldDescription {
             id_field,
             attr_field,
             relations_field,
+            meta_field,
+            links_field,
+            self_link: props.self_link,
+            validate: props.validate,
+            skip_type_check: props.skip_type_check,
+            skipped_fields,
+            vis: props.vis,
+            requests: props.requests,
+            context: props.context,
+            authorize: props.authorize,
+        }
+    }
+}
+
+// ExampleInput is the path of the sample document, relative to the crate's manifest, optionally
+// followed by the name of the resource struct: `"fixtures/article.json", Post`
+struct ExampleInput {
+    path: syn::LitStr,
+    name: Option<syn::Ident>,
+}
+
+impl syn::parse::Parse for ExampleInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let name = if input.parse::<Option<syn::Token![,]>>()?.is_some() {
+            input.parse()?
+        } else {
+            None
+        };
+        Ok(ExampleInput { path, name })
+    }
+}
+
+// the resource of a sample document is its primary data, or the first resource of it if it's a
+// list. With a type of `blog-posts` it generates
+//   - `BlogPostAttributes`, a field per attribute typed after the sample's value
+//   - `BlogPostRelations`, a field per relationship with linkage: an Option<String> for to-one,
+//     a Vec<String> for to-many
+//   - `BlogPost`, the resource of them deriving IntoResponse and FromRequest, with a String id
+// Attributes which are null in the sample, objects or lists of mixed values are serde_json
+// Values. The crate using it needs serde_json and serde_derive, and jsonapi_resource_derive
+// under that name
+fn impl_from_example_macro(input: &ExampleInput) -> syn::Result<TokenStream> {
+    let span = input.path.span();
+    let err = |msg: String| syn::Error::new(span, msg);
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = std::path::Path::new(&dir).join(input.path.value());
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| err(format!("{} couldn't be read: {}", path.display(), e)))?;
+    let doc: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| err(format!("{} isn't JSON: {}", path.display(), e)))?;
+    let resource = match &doc["data"] {
+        serde_json::Value::Array(data) => data.first(),
+        data => Some(data),
+    }
+    .and_then(|data| data.as_object())
+    .ok_or_else(|| err("the sample has no resource as primary data".to_owned()))?;
+    let typ = resource
+        .get("type")
+        .and_then(|typ| typ.as_str())
+        .ok_or_else(|| err("the sample resource has no type".to_owned()))?;
+    let name = match &input.name {
+        Some(name) => name.clone(),
+        None => format_ident!("{}", typ.to_snake_case().to_singular().to_pascal_case()),
+    };
+    let attrs_name = format_ident!("{}Attributes", name);
+    let rels_name = format_ident!("{}Relations", name);
+
+    // sanitized and snake cased members can collide, e.g. "wordCount" and "word_count"
+    let mut fields_seen = std::collections::HashSet::new();
+    let mut attributes = Vec::new();
+    if let Some(fields) = resource.get("attributes").and_then(|a| a.as_object()) {
+        for (key, value) in fields {
+            let (ident, rename) = example_field(key);
+            if !fields_seen.insert(ident.to_string()) {
+                return Err(err(format!(
+                    "the sample's attributes have two `{}` fields",
+                    ident
+                )));
+            }
+            let ty = example_type(value);
+            let rename = rename.map(|key| quote! { #[serde(rename = #key)] });
+            attributes.push(quote! {
+                #rename
+                pub #ident: #ty
+            });
+        }
+    }
+    let mut relations = Vec::new();
+    if let Some(rels) = resource.get("relationships").and_then(|r| r.as_object()) {
+        for (key, rel) in rels {
+            let (ident, rename) = example_field(key);
+            if !fields_seen.insert(format!("rel {}", ident)) {
+                return Err(err(format!(
+                    "the sample's relationships have two `{}` fields",
+                    ident
+                )));
+            }
+            let rename = rename.map(|key| quote! { rename = #key, });
+            // relationships sampled without linkage (e.g. only links) can't be typed
+            let (ty, related) = match rel.get("data") {
+                Some(serde_json::Value::Array(data)) => (quote! { Vec<String> }, data.first()),
+                Some(data @ serde_json::Value::Object(_)) => {
+                    (quote! { Option<String> }, Some(data))
+                }
+                _ => continue,
+            };
+            let related = related
+                .and_then(|data| data.get("type"))
+                .and_then(|typ| typ.as_str())
+                .map_or_else(|| type_name_for(key, Case::Lower), str::to_owned);
+            relations.push(quote! {
+                #[jsonapi(#rename resource_type = #related)]
+                pub #ident: #ty
+            });
+        }
+    }
+    let path = path.display().to_string();
+    Ok(quote! {
+        // the sample is included so that changing it rebuilds what's generated from it
+        const _: &str = include_str!(#path);
+
+        #[derive(::serde_derive::Serialize, ::serde_derive::Deserialize, Clone, Debug)]
+        pub struct #attrs_name {
+            #(#attributes,)*
+        }
+
+        #[derive(
+            ::jsonapi_resource_derive::IntoRelationships,
+            ::jsonapi_resource_derive::FromRelationships,
+            Clone,
+            Debug
+        )]
+        pub struct #rels_name {
+            #(#relations,)*
+        }
+
+        #[derive(
+            ::jsonapi_resource_derive::IntoResponse,
+            ::jsonapi_resource_derive::FromRequest,
+            Clone,
+            Debug
+        )]
+        #[jsonapi(rename = #typ)]
+        pub struct #name {
+            pub id: String,
+            pub attributes: #attrs_name,
+            pub relations: #rels_name,
+        }
+    }
+    .into())
+}
+
+// example_field is the field a member of the sample is read into, with the member's name if the
+// field's differs. Members which aren't identifiers once snake cased ("", "2fa", "self") get a
+// sanitized field: `field`, `_2fa`, `self_`. Other keywords are raw identifiers (`r#type`)
+fn example_field(key: &str) -> (syn::Ident, Option<String>) {
+    let mut snake: String = key
+        .to_snake_case()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    if snake.is_empty() {
+        snake.push_str("field");
+    } else if snake.starts_with(|c: char| c.is_ascii_digit()) {
+        snake.insert(0, '_');
+    }
+    if matches!(snake.as_str(), "self" | "super" | "crate" | "Self" | "_") {
+        snake.push('_');
+    }
+    let ident = syn::parse_str::<syn::Ident>(&snake)
+        .unwrap_or_else(|_| syn::Ident::new_raw(&snake, proc_macro2::Span::call_site()));
+    (ident, (snake != key).then(|| key.to_owned()))
+}
+
+fn example_type(value: &serde_json::Value) -> TS2 {
+    use serde_json::Value;
+
+    match value {
+        Value::Bool(_) => quote! { bool },
+        Value::Number(n) if n.is_f64() => quote! { f64 },
+        Value::Number(_) => quote! { i64 },
+        Value::String(_) => quote! { String },
+        Value::Array(values) => {
+            let first = values.first().map(example_type);
+            let same = values.iter().all(|value| {
+                Some(example_type(value).to_string()) == first.as_ref().map(TS2::to_string)
+            });
+            match first {
+                Some(ty) if same && !values[0].is_null() => quote! { Vec<#ty> },
+                _ => quote! { Vec<::serde_json::Value> },
+            }
         }
+        Value::Null => quote! { Option<::serde_json::Value> },
+        Value::Object(_) => quote! { ::serde_json::Value },
     }
 }
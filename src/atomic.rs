@@ -0,0 +1,383 @@
+// The Atomic Operations extension (https://jsonapi.org/ext/atomic), which lets a client send a
+// list of add/update/remove operations to be carried out together in one request.
+#[cfg(feature = "actixweb")]
+use actix_web::{
+    http::header::CONTENT_TYPE, web::JsonBody, FromRequest as FromWebRequest, HttpResponse,
+};
+#[cfg(feature = "actixweb")]
+use futures_core::ready;
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, future::Future};
+#[cfg(feature = "actixweb")]
+use std::{pin::Pin, task::Poll};
+
+#[cfg(feature = "actixweb")]
+use crate::{media::MediaType, SpecVersion};
+
+use crate::{Error, FromRequest, Meta, Request, ID};
+
+// EXT_URI identifies the extension in the `ext` media type parameter
+pub const EXT_URI: &str = "https://jsonapi.org/ext/atomic";
+
+// MEDIA_TYPE is the content type of requests and responses using the extension
+pub const MEDIA_TYPE: &str = "application/vnd.api+json; ext=\"https://jsonapi.org/ext/atomic\"";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AtomicRequest {
+    #[serde(rename = "atomic:operations")]
+    pub operations: Vec<Operation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AtomicResponse {
+    #[serde(rename = "atomic:results")]
+    pub results: Vec<OperationResult>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OpCode {
+    Add,
+    Update,
+    Remove,
+}
+
+// Operation is a single entry of atomic:operations. Its target is either `ref` or `href`, or
+// (when adding a resource) implied by `data`. `data` is left as JSON: it can be a resource
+// object, a resource identifier, a list of identifiers or null depending on the operation
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Operation {
+    pub op: OpCode,
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<Ref>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+// Ref points an operation at a resource, or at one of its relationships
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Ref {
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ID>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relationship: Option<String>,
+}
+
+// OperationResult is an entry of atomic:results. An operation without data to return (e.g. a
+// remove) produces an empty result
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct OperationResult {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl Operation {
+    // into_request parses the operation's resource object into a FromRequest type, so that the
+    // types used by the regular endpoints can be reused for add and update operations
+    pub fn into_request<T>(self) -> Result<T, Error>
+    where
+        T: FromRequest,
+        T::Attributes: DeserializeOwned,
+    {
+        let data = self
+            .data
+            .ok_or_else(|| Error::new_bad_request("operation has no data"))?;
+        let req: Request<T::Attributes> =
+            serde_json::from_value(serde_json::json!({ "data": data })).map_err(|err| {
+                Error::new_bad_request(&format!("invalid resource object in operation: {}", err))
+            })?;
+        T::from_request(req)
+    }
+}
+
+// LocalIds maps the local ids (lid) of resources added by earlier operations to the ids they
+// were given, so later operations can refer to them
+#[derive(Default, Debug)]
+pub struct LocalIds(HashMap<(String, String), ID>);
+
+impl LocalIds {
+    pub fn assign(&mut self, typ: &str, lid: &str, id: ID) {
+        self.0.insert((typ.to_owned(), lid.to_owned()), id);
+    }
+
+    pub fn get(&self, typ: &str, lid: &str) -> Option<&ID> {
+        self.0.get(&(typ.to_owned(), lid.to_owned()))
+    }
+
+    // resolve replaces the local ids an operation refers to with the real ids. The lid of the
+    // resource an add operation creates is left alone, as it has no id yet
+    pub fn resolve(&self, op: &mut Operation) -> Result<(), Error> {
+        if let Some(target) = op.target.as_mut() {
+            if let Some(lid) = target.lid.take() {
+                target.id = Some(self.lookup(&target.typ, &lid)?);
+            }
+        }
+        let Some(data) = op.data.as_mut() else {
+            return Ok(());
+        };
+        match data {
+            Value::Array(identifiers) => {
+                for identifier in identifiers {
+                    self.resolve_identifier(identifier)?;
+                }
+            }
+            Value::Object(resource) => {
+                if let Some(Value::Object(relationships)) = resource.get_mut("relationships") {
+                    for relationship in relationships.values_mut() {
+                        match relationship.get_mut("data") {
+                            Some(Value::Array(identifiers)) => {
+                                for identifier in identifiers {
+                                    self.resolve_identifier(identifier)?;
+                                }
+                            }
+                            Some(identifier) => self.resolve_identifier(identifier)?,
+                            None => {}
+                        }
+                    }
+                }
+                if op.op != OpCode::Add {
+                    self.resolve_identifier(data)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn resolve_identifier(&self, identifier: &mut Value) -> Result<(), Error> {
+        let Some(identifier) = identifier.as_object_mut() else {
+            return Ok(());
+        };
+        let (Some(Value::String(typ)), Some(Value::String(lid))) =
+            (identifier.get("type"), identifier.get("lid"))
+        else {
+            return Ok(());
+        };
+        let id = self.lookup(typ, lid)?;
+        identifier.remove("lid");
+        identifier.insert("id".into(), Value::String(id.to_string()));
+        Ok(())
+    }
+
+    fn lookup(&self, typ: &str, lid: &str) -> Result<ID, Error> {
+        self.get(typ, lid).cloned().ok_or_else(|| {
+            Error::new_bad_request(&format!(
+                "lid '{}' doesn't refer to a {} added by an earlier operation",
+                lid, typ
+            ))
+        })
+    }
+}
+
+// AtomicHandler carries out the operations of an atomic request, one at a time and in order.
+// The extension requires all of them to succeed or none to take effect, so the handler should
+// do its work in a transaction, committed only once execute has succeeded
+pub trait AtomicHandler {
+    fn perform(&mut self, op: Operation) -> impl Future<Output = Result<OperationResult, Error>>;
+}
+
+// execute runs each operation through the handler, resolving local ids along the way, and
+// stops at the first error, which points at the failing operation
+pub async fn execute<H: AtomicHandler>(
+    handler: &mut H,
+    req: AtomicRequest,
+) -> Result<AtomicResponse, Error> {
+    let mut lids = LocalIds::default();
+    let mut results = Vec::with_capacity(req.operations.len());
+    for (i, mut op) in req.operations.into_iter().enumerate() {
+        let pointer = format!("/atomic:operations/{}", i);
+        lids.resolve(&mut op)
+            .map_err(|err| err.with_pointer(&pointer))?;
+        // the lid of an added resource is recorded once the handler has given it an id
+        let added_lid = match (&op.op, &op.data) {
+            (OpCode::Add, Some(data)) => match (data.get("type"), data.get("lid")) {
+                (Some(Value::String(typ)), Some(Value::String(lid))) => {
+                    Some((typ.clone(), lid.clone()))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        let result = match handler.perform(op).await {
+            Ok(result) => result,
+            Err(err) if err.source.is_some() => return Err(err),
+            Err(err) => return Err(err.with_pointer(&pointer)),
+        };
+        if let Some((typ, lid)) = added_lid {
+            if let Some(Value::String(id)) = result.data.as_ref().and_then(|data| data.get("id")) {
+                lids.assign(&typ, &lid, id.clone().into());
+            }
+        }
+        results.push(result);
+    }
+    Ok(AtomicResponse { results })
+}
+
+// accepts_atomic reports whether a content type is the JSON:API media type with the atomic
+// extension among its `ext` URIs
+#[cfg(feature = "actixweb")]
+fn accepts_atomic(content_type: &str) -> bool {
+    MediaType::parse(content_type, SpecVersion::V1_1).is_some_and(|media| media.has_ext(EXT_URI))
+}
+
+#[cfg(feature = "actixweb")]
+impl AtomicResponse {
+    // into_http_response sends the results with the extension's media type
+    pub fn into_http_response(self) -> HttpResponse {
+        HttpResponse::Ok().content_type(MEDIA_TYPE).json(self)
+    }
+}
+
+// JsonApiAtomic extracts the operations of an atomic request. Requests not declaring the
+// extension in their content type are rejected with 415
+#[cfg(feature = "actixweb")]
+pub struct JsonApiAtomic(pub AtomicRequest);
+
+#[cfg(feature = "actixweb")]
+impl JsonApiAtomic {
+    pub fn into_inner(self) -> AtomicRequest {
+        self.0
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl FromWebRequest for JsonApiAtomic {
+    type Error = Error;
+
+    type Future = AtomicExtractFut;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let content_type = req.headers().get(CONTENT_TYPE);
+        match content_type.and_then(|value| value.to_str().ok()) {
+            Some(content_type) if accepts_atomic(content_type) => AtomicExtractFut(Ok(
+                JsonBody::new(req, payload, None, true).limit(crate::BODY_LIMIT),
+            )),
+            _ => AtomicExtractFut(Err(Some(Error::new_unsupported_media_type(&format!(
+                "atomic operations must be sent as {}",
+                MEDIA_TYPE
+            ))))),
+        }
+    }
+}
+
+#[cfg(feature = "actixweb")]
+pub struct AtomicExtractFut(Result<JsonBody<AtomicRequest>, Option<Error>>);
+
+#[cfg(feature = "actixweb")]
+impl Future for AtomicExtractFut {
+    type Output = Result<JsonApiAtomic, Error>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        match &mut self.get_mut().0 {
+            Ok(body) => {
+                let req = ready!(Pin::new(body).poll(cx))?;
+                Poll::Ready(Ok(JsonApiAtomic(req)))
+            }
+            Err(err) => Poll::Ready(Err(err
+                .take()
+                .expect("AtomicExtractFut polled after completion"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use std::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use super::{execute, AtomicHandler, AtomicRequest, OpCode, Operation, OperationResult};
+    use crate::Error;
+
+    // Store hands out sequential ids and remembers the operations it was given
+    #[derive(Default)]
+    struct Store {
+        next_id: usize,
+        seen: Vec<Operation>,
+    }
+
+    impl AtomicHandler for Store {
+        async fn perform(&mut self, op: Operation) -> Result<OperationResult, Error> {
+            self.seen.push(op.clone());
+            if op.op != OpCode::Add {
+                return Ok(OperationResult::default());
+            }
+            self.next_id += 1;
+            let mut data = op.data.unwrap();
+            data["id"] = json!(self.next_id.to_string());
+            Ok(OperationResult {
+                data: Some(data),
+                meta: None,
+            })
+        }
+    }
+
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let mut fut = pin!(fut);
+        match fut.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("the test handler never waits"),
+        }
+    }
+
+    #[test]
+    fn test_execute() {
+        let req: AtomicRequest = serde_json::from_value(json!({
+            "atomic:operations": [
+                {"op": "add", "data": {"type": "authors", "lid": "a", "attributes": {}}},
+                {"op": "add", "data": {"type": "articles", "lid": "b", "attributes": {},
+                    "relationships": {"author": {"data": {"type": "authors", "lid": "a"}}}}},
+                {"op": "remove", "ref": {"type": "articles", "lid": "b"}}
+            ]
+        }))
+        .unwrap();
+        let mut store = Store::default();
+        let res = block_on(execute(&mut store, req)).unwrap();
+        assert_eq!(res.results.len(), 3);
+        assert_eq!(res.results[1].data.as_ref().unwrap()["id"], "2");
+        let author = &store.seen[1].data.as_ref().unwrap()["relationships"]["author"]["data"];
+        assert_eq!(author["id"], "1");
+        assert_eq!(author.get("lid"), None);
+        assert_eq!(
+            store.seen[2]
+                .target
+                .as_ref()
+                .unwrap()
+                .id
+                .as_ref()
+                .unwrap()
+                .as_str(),
+            "2"
+        );
+        let out = serde_json::to_value(&res).unwrap();
+        assert_eq!(out["atomic:results"][2], Value::Object(Default::default()));
+
+        let req: AtomicRequest = serde_json::from_value(json!({
+            "atomic:operations": [{"op": "remove", "ref": {"type": "articles", "lid": "x"}}]
+        }))
+        .unwrap();
+        let err = block_on(execute(&mut Store::default(), req)).unwrap_err();
+        assert_eq!(err.source.unwrap().pointer.unwrap(), "/atomic:operations/0");
+    }
+}
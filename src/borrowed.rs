@@ -0,0 +1,158 @@
+// Borrowed counterparts of the request types, for services parsing large payloads. Types, ids
+// and relationship names borrow from the input wherever possible (strings containing escapes
+// are the exception) instead of allocating a String each.
+#[cfg(feature = "indexmap")]
+use indexmap::IndexMap as Map;
+use serde::{Deserialize as _, Deserializer};
+use serde_derive::Deserialize;
+use std::borrow::Cow;
+#[cfg(not(feature = "indexmap"))]
+use std::collections::BTreeMap as Map;
+
+#[derive(Deserialize, Clone)]
+pub struct Request<'a, D> {
+    #[serde(borrow)]
+    pub data: ResourceRequest<'a, D>,
+}
+
+// RawRequest leaves the attributes unparsed, to be deserialized (or forwarded) later
+pub type RawRequest<'a> = Request<'a, &'a serde_json::value::RawValue>;
+
+#[derive(Deserialize, Clone)]
+pub struct ResourceRequest<'a, D> {
+    #[serde(default, borrow, deserialize_with = "borrow_optional")]
+    pub id: Option<Cow<'a, str>>,
+    #[serde(rename = "type", borrow)]
+    pub typ: Cow<'a, str>,
+    pub attributes: D,
+    #[serde(default, borrow, deserialize_with = "borrow_relationships")]
+    pub relationships: Option<Map<Cow<'a, str>, RelationshipData<'a>>>,
+}
+
+// serde only borrows a Cow<str> which is itself the field, not one nested in an Option or
+// used as a map key, so those go through this wrapper
+#[derive(Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct BorrowedStr<'a>(#[serde(borrow)] Cow<'a, str>);
+
+fn borrow_optional<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Cow<'a, str>>, D::Error> {
+    Ok(Option::<BorrowedStr>::deserialize(deserializer)?.map(|s| s.0))
+}
+
+type BorrowedRelationships<'a> = Map<Cow<'a, str>, RelationshipData<'a>>;
+
+fn borrow_relationships<'de: 'a, 'a, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<BorrowedRelationships<'a>>, D::Error> {
+    let rels = Option::<Map<BorrowedStr, RelationshipData>>::deserialize(deserializer)?;
+    Ok(rels.map(|rels| rels.into_iter().map(|(name, rel)| (name.0, rel)).collect()))
+}
+
+#[derive(Deserialize, Clone)]
+pub struct RelationshipData<'a> {
+    #[serde(borrow)]
+    pub data: Relationship<'a>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Relationship<'a> {
+    #[serde(borrow)]
+    ToOne(Identifier<'a>),
+    #[serde(borrow)]
+    ToMany(Vec<Identifier<'a>>),
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Identifier<'a> {
+    #[serde(borrow)]
+    pub id: Cow<'a, str>,
+    #[serde(rename = "type", borrow)]
+    pub typ: Cow<'a, str>,
+}
+
+impl<D> Request<'_, D> {
+    // into_owned converts into the owned request, e.g. to hand it to a FromRequest implementation
+    pub fn into_owned(self) -> crate::Request<D> {
+        crate::Request {
+            data: crate::ResourceRequest {
+                id: self.data.id.map(|id| id.into_owned().into()),
+                typ: self.data.typ.into_owned(),
+                attributes: self.data.attributes,
+                relationships: self.data.relationships.map(|rels| {
+                    rels.into_iter()
+                        .map(|(name, rel)| (name.into_owned(), rel.into_owned()))
+                        .collect()
+                }),
+                extensions: crate::Extensions::new(),
+            },
+            extensions: crate::Extensions::new(),
+        }
+    }
+}
+
+impl RelationshipData<'_> {
+    pub fn into_owned(self) -> crate::RelationshipData {
+        crate::RelationshipData {
+            data: Some(self.data.into_owned()),
+            ..Default::default()
+        }
+    }
+}
+
+impl Relationship<'_> {
+    pub fn into_owned(self) -> crate::Relationship {
+        match self {
+            Relationship::ToOne(one) => crate::Relationship::ToOne(one.into_owned()),
+            Relationship::ToMany(many) => {
+                crate::Relationship::ToMany(many.into_iter().map(Identifier::into_owned).collect())
+            }
+        }
+    }
+}
+
+impl Identifier<'_> {
+    pub fn into_owned(self) -> crate::Identifier {
+        crate::Identifier {
+            id: self.id.into_owned().into(),
+            typ: self.typ.into_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{RawRequest, Relationship, Request};
+
+    #[test]
+    fn test_borrowed_request() {
+        let body = r#"{"data": {
+            "id": "1",
+            "type": "articles",
+            "attributes": {"title": "a \"quoted\" title"},
+            "relationships": {"author": {"data": {"id": "9", "type": "people"}}}
+        }}"#;
+        let req: Request<serde_json::Value> = serde_json::from_str(body).unwrap();
+        assert!(matches!(req.data.typ, Cow::Borrowed("articles")));
+        assert!(matches!(req.data.id, Some(Cow::Borrowed("1"))));
+        let rels = req.data.relationships.as_ref().unwrap();
+        assert!(matches!(rels.keys().next(), Some(Cow::Borrowed("author"))));
+        match &rels["author"].data {
+            Relationship::ToOne(one) => assert!(matches!(one.id, Cow::Borrowed("9"))),
+            Relationship::ToMany(_) => panic!("expected a to-one relationship"),
+        }
+
+        let owned = req.into_owned();
+        assert_eq!(owned.data.id.unwrap().0, "1");
+        assert_eq!(owned.data.attributes["title"], "a \"quoted\" title");
+
+        let raw: RawRequest = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            raw.data.attributes.get(),
+            r#"{"title": "a \"quoted\" title"}"#
+        );
+    }
+}
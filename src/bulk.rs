@@ -0,0 +1,235 @@
+// Bulk requests: documents whose primary data is an array of resource objects, creating or
+// updating several resources at once (as the old bulk extension had it). The base spec only
+// allows a single resource in requests, so they're opt-in: JsonApi still refuses arrays, handlers
+// taking bulk requests use JsonApiBulk instead.
+#[cfg(feature = "actixweb")]
+use actix_web::{web::JsonBody, FromRequest as FromWebRequest};
+#[cfg(feature = "actixweb")]
+use futures_core::ready;
+use serde_derive::{Deserialize, Serialize};
+#[cfg(feature = "actixweb")]
+use std::{future::Future, pin::Pin, task::Poll};
+
+#[cfg(feature = "actixweb")]
+use serde::de::DeserializeOwned;
+
+use crate::{Error, Extensions, FromRequest, Request, ResourceRequest};
+
+#[derive(Serialize, Deserialize)]
+pub struct BulkRequest<D> {
+    pub data: Vec<ResourceRequest<D>>,
+}
+
+impl<D> BulkRequest<D> {
+    // into_requests splits the document into a request per resource
+    pub fn into_requests(self) -> impl Iterator<Item = Request<D>> {
+        self.data.into_iter().map(|data| Request {
+            data,
+            extensions: Extensions::new(),
+        })
+    }
+
+    // parse turns every resource into a FromRequest type, stopping at the first one which fails.
+    // Its error points into that resource, e.g. /data/3/attributes/title
+    pub fn parse<T: FromRequest<Attributes = D>>(self) -> Result<Vec<T>, Error> {
+        self.into_requests()
+            .enumerate()
+            .map(|(i, req)| T::from_request(req).map_err(|err| at_index(err, i)))
+            .collect()
+    }
+}
+
+// at_index moves the pointer of an error about a single resource (/data/...) under the resource's
+// index in the bulk document. Errors without a pointer are pointed at the resource
+pub fn at_index(err: Error, index: usize) -> Error {
+    let pointer = match err
+        .source
+        .as_ref()
+        .and_then(|source| source.pointer.as_deref())
+    {
+        None => format!("/data/{}", index),
+        Some(pointer) => match pointer.strip_prefix("/data") {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                format!("/data/{}{}", index, rest)
+            }
+            _ => return err,
+        },
+    };
+    err.with_pointer(&pointer)
+}
+
+// JsonApiBulk extracts the resources of a bulk request. The ids of those a POST creates are
+// subject to the client id policy of JsonApiConfig, like those of JsonApi
+#[cfg(feature = "actixweb")]
+pub struct JsonApiBulk<R>(pub Vec<R>);
+
+#[cfg(feature = "actixweb")]
+impl<R> JsonApiBulk<R> {
+    pub fn into_inner(self) -> Vec<R> {
+        self.0
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl<R: FromRequest> FromWebRequest for JsonApiBulk<R>
+where
+    R::Attributes: DeserializeOwned,
+{
+    type Error = Error;
+
+    type Future = BulkExtractFut<R>;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let config = req
+            .app_data::<crate::JsonApiConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let checked = crate::check_content_type(req, &config);
+        let body =
+            checked.map(|_| JsonBody::new(req, payload, None, true).limit(crate::BODY_LIMIT));
+        BulkExtractFut {
+            body: body.map_err(Some),
+            config,
+            creating: req.method() == actix_web::http::Method::POST,
+        }
+    }
+}
+
+#[cfg(feature = "actixweb")]
+pub struct BulkExtractFut<R: FromRequest> {
+    body: Result<JsonBody<BulkRequest<R::Attributes>>, Option<Error>>,
+    config: crate::JsonApiConfig,
+    creating: bool,
+}
+
+#[cfg(feature = "actixweb")]
+impl<R: FromRequest> Future for BulkExtractFut<R>
+where
+    R::Attributes: DeserializeOwned,
+{
+    type Output = Result<JsonApiBulk<R>, Error>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let this = self.get_mut();
+        let body = match &mut this.body {
+            Ok(body) => body,
+            Err(err) => {
+                return Poll::Ready(Err(err
+                    .take()
+                    .expect("BulkExtractFut polled after completion")))
+            }
+        };
+        let req = ready!(Pin::new(body).poll(cx))?;
+        if this.creating {
+            for (i, data) in req.data.iter().enumerate() {
+                this.config
+                    .client_ids
+                    .check(data.id.as_ref())
+                    .map_err(|err| at_index(err, i))?;
+            }
+        }
+        Poll::Ready(req.parse().map(JsonApiBulk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_derive::Deserialize;
+    use serde_json::json;
+
+    use super::{at_index, BulkRequest};
+    use crate::{Error, FromRequest, Request};
+
+    #[derive(Deserialize)]
+    struct Attributes {
+        title: String,
+    }
+
+    #[derive(Debug)]
+    struct Article {
+        title: String,
+    }
+
+    impl FromRequest for Article {
+        type Attributes = Attributes;
+
+        fn from_request(req: Request<Attributes>) -> Result<Self, Error> {
+            if req.data.attributes.title.is_empty() {
+                return Err(Error::new_unprocessable_entity("title can't be empty")
+                    .with_pointer("/data/attributes/title"));
+            }
+            Ok(Article {
+                title: req.data.attributes.title,
+            })
+        }
+    }
+
+    #[test]
+    fn test_bulk_request() {
+        let req: BulkRequest<Attributes> = serde_json::from_value(json!({"data": [
+            {"type": "articles", "attributes": {"title": "a"}},
+            {"type": "articles", "attributes": {"title": "b"}}
+        ]}))
+        .unwrap();
+        let articles: Vec<Article> = req.parse().unwrap();
+        assert_eq!(articles.len(), 2);
+        assert_eq!(articles[1].title, "b");
+
+        let req: BulkRequest<Attributes> = serde_json::from_value(json!({"data": [
+            {"type": "articles", "attributes": {"title": "a"}},
+            {"type": "articles", "attributes": {"title": ""}}
+        ]}))
+        .unwrap();
+        let err = req.parse::<Article>().unwrap_err();
+        assert_eq!(
+            err.source.unwrap().pointer.as_deref(),
+            Some("/data/1/attributes/title")
+        );
+
+        let pointer = |err: Error| err.source.and_then(|source| source.pointer);
+        let err = at_index(Error::new_bad_request("bad"), 3);
+        assert_eq!(pointer(err).as_deref(), Some("/data/3"));
+        let err = at_index(Error::new_bad_request("bad").with_pointer("/included"), 3);
+        assert_eq!(pointer(err).as_deref(), Some("/included"));
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor() {
+        use actix_web::{test, FromRequest};
+
+        use super::JsonApiBulk;
+
+        let body = json!({"data": [
+            {"type": "articles", "attributes": {"title": "a"}},
+            {"type": "articles", "id": "2", "attributes": {"title": "b"}}
+        ]});
+        let config = crate::JsonApiConfig::default().client_ids(crate::ClientIdPolicy::Forbid);
+        let (req, mut payload) = test::TestRequest::post()
+            .app_data(config)
+            .insert_header(("content-type", crate::media::JSONAPI_MEDIA_TYPE))
+            .set_payload(body.to_string())
+            .to_http_parts();
+        let err = actix_web::rt::System::new()
+            .block_on(JsonApiBulk::<Article>::from_request(&req, &mut payload))
+            .err()
+            .unwrap();
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/1/id"));
+
+        let (req, mut payload) = test::TestRequest::patch()
+            .insert_header(("content-type", crate::media::JSONAPI_MEDIA_TYPE))
+            .set_payload(body.to_string())
+            .to_http_parts();
+        let articles = actix_web::rt::System::new()
+            .block_on(JsonApiBulk::<Article>::from_request(&req, &mut payload))
+            .ok()
+            .unwrap();
+        assert_eq!(articles.into_inner().len(), 2);
+    }
+}
@@ -0,0 +1,236 @@
+// A catalog of the resource types an API serves: their attributes, relationships and the sort
+// and filter parameters they accept. It's registered at runtime, served as a JSON document (see
+// Catalog::service) and resources point at their entry in it with a describedby link.
+use serde_derive::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{filter::Operator, Link, Links, ResourceResponse, Response, ResponseType};
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResourceDescription {
+    pub attributes: Vec<String>,
+    pub relationships: BTreeMap<String, RelationshipDescription>,
+    pub sort: Vec<String>,
+    // filter maps filterable fields to the operators they can be filtered with
+    pub filter: BTreeMap<String, Vec<&'static str>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelationshipDescription {
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub to_many: bool,
+}
+
+impl ResourceDescription {
+    pub fn new() -> ResourceDescription {
+        ResourceDescription::default()
+    }
+
+    pub fn attribute(mut self, name: &str) -> Self {
+        self.attributes.push(name.to_owned());
+        self
+    }
+
+    pub fn to_one(self, name: &str, typ: &str) -> Self {
+        self.relationship(name, typ, false)
+    }
+
+    pub fn to_many(self, name: &str, typ: &str) -> Self {
+        self.relationship(name, typ, true)
+    }
+
+    fn relationship(mut self, name: &str, typ: &str, to_many: bool) -> Self {
+        let description = RelationshipDescription {
+            typ: typ.to_owned(),
+            to_many,
+        };
+        self.relationships.insert(name.to_owned(), description);
+        self
+    }
+
+    pub fn sort(mut self, field: &str) -> Self {
+        self.sort.push(field.to_owned());
+        self
+    }
+
+    pub fn filter(mut self, field: &str, operators: &[Operator]) -> Self {
+        let operators = operators.iter().map(Operator::as_str).collect();
+        self.filter.insert(field.to_owned(), operators);
+        self
+    }
+}
+
+// Catalog is the registry of resource types, and the document describing them that is served at
+// its path
+#[derive(Debug, Clone, Serialize)]
+pub struct Catalog {
+    #[serde(skip)]
+    path: String,
+    resources: BTreeMap<String, ResourceDescription>,
+}
+
+impl Catalog {
+    pub fn new(path: &str) -> Catalog {
+        Catalog {
+            path: path.to_owned(),
+            resources: BTreeMap::new(),
+        }
+    }
+
+    pub fn resource(mut self, typ: &str, description: ResourceDescription) -> Self {
+        self.resources.insert(typ.to_owned(), description);
+        self
+    }
+
+    pub fn get(&self, typ: &str) -> Option<&ResourceDescription> {
+        self.resources.get(typ)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // describedby is the link to a type's entry, a JSON pointer into the catalog as fragment.
+    // None for types which aren't registered
+    pub fn describedby(&self, typ: &str) -> Option<Link> {
+        self.resources.contains_key(typ).then(|| {
+            let pointer = typ.replace('~', "~0").replace('/', "~1");
+            Link::Url(format!("{}#/resources/{}", self.path, pointer))
+        })
+    }
+
+    // describe adds the describedby link of a resource, unless it has one already
+    pub fn describe<D>(&self, resource: &mut ResourceResponse<D>) {
+        let Some(link) = self.describedby(&resource.id.typ) else {
+            return;
+        };
+        resource
+            .links
+            .get_or_insert_with(Links::new)
+            .entry("describedby".into())
+            .or_insert(link);
+    }
+
+    // service serves the catalog at its path, e.g. `App::new().service(catalog.service())`
+    #[cfg(feature = "actixweb")]
+    pub fn service(&self) -> actix_web::Resource {
+        let body = serde_json::to_vec(self).expect("the catalog is always valid JSON");
+        actix_web::web::resource(self.path.as_str()).get(move || {
+            let body = body.clone();
+            async move {
+                actix_web::HttpResponse::Ok()
+                    .content_type("application/json")
+                    .body(body)
+            }
+        })
+    }
+}
+
+impl<P, I> Response<P, I> {
+    // described adds describedby links to every resource of a type the catalog has
+    pub fn described(mut self, catalog: &Catalog) -> Self {
+        if let ResponseType::Ok(data) = &mut self.primary {
+            data.iter_mut().for_each(|res| catalog.describe(res));
+        }
+        if let Some(included) = &mut self.included {
+            included.iter_mut().for_each(|res| catalog.describe(res));
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::smallvec;
+
+    use super::{Catalog, ResourceDescription};
+    use crate::{filter::Operator, Identifier, ResourceResponse, Response, ResponseType};
+
+    fn catalog() -> Catalog {
+        Catalog::new("/catalog").resource(
+            "articles",
+            ResourceDescription::new()
+                .attribute("title")
+                .to_one("author", "people")
+                .to_many("comments", "comments")
+                .sort("title")
+                .filter("title", &[Operator::Eq, Operator::Contains]),
+        )
+    }
+
+    fn resource(typ: &str) -> ResourceResponse<()> {
+        ResourceResponse {
+            id: Identifier {
+                id: "1".into(),
+                typ: typ.into(),
+            },
+            attributes: (),
+            relationships: None,
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_catalog() {
+        let catalog = catalog();
+        let out = serde_json::to_value(&catalog).unwrap();
+        let articles = &out["resources"]["articles"];
+        assert_eq!(articles["attributes"], serde_json::json!(["title"]));
+        assert_eq!(articles["relationships"]["comments"]["toMany"], true);
+        assert_eq!(articles["relationships"]["author"]["type"], "people");
+        assert_eq!(
+            articles["filter"]["title"],
+            serde_json::json!(["eq", "contains"])
+        );
+
+        let response: Response<(), ()> = Response {
+            primary: ResponseType::Ok(smallvec![resource("articles")]),
+            included: Some(vec![resource("people")]),
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Default::default(),
+        };
+        let out = serde_json::to_value(response.described(&catalog)).unwrap();
+        assert_eq!(
+            out["data"][0]["links"]["describedby"],
+            "/catalog#/resources/articles"
+        );
+        assert!(out["included"][0].get("links").is_none());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_service() {
+        use actix_web::{test, App};
+
+        let out: serde_json::Value = actix_web::rt::System::new().block_on(async {
+            let app = test::init_service(App::new().service(catalog().service())).await;
+            let req = test::TestRequest::get().uri("/catalog").to_request();
+            test::call_and_read_body_json(&app, req).await
+        });
+        assert_eq!(out["resources"]["articles"]["sort"][0], "title");
+
+        let config = crate::JsonApiConfig::default().catalog(std::sync::Arc::new(catalog()));
+        let req = test::TestRequest::get().app_data(config).to_http_request();
+        let response: Response<(), ()> = Response {
+            primary: ResponseType::Ok(smallvec![resource("articles")]),
+            included: None,
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Default::default(),
+        };
+        let res = actix_web::Responder::respond_to(response, &req);
+        let body = actix_web::body::MessageBody::try_into_bytes(res.into_body()).unwrap();
+        let out: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            out["data"][0]["links"]["describedby"],
+            "/catalog#/resources/articles"
+        );
+    }
+}
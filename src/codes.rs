@@ -0,0 +1,133 @@
+// A registry of the error codes an API answers with, so a code means the same status and wording
+// in every handler. Titles and details are templates whose {placeholders} are filled in when an
+// error is made (see ErrorCatalog::err), and the registry can be served as a JSON document
+// describing the codes to API consumers.
+use serde_derive::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{Error, ErrorStatus};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorTemplate {
+    pub status: ErrorStatus,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ErrorTemplate {
+    pub fn new(status: ErrorStatus, title: &str) -> ErrorTemplate {
+        ErrorTemplate {
+            status,
+            title: title.to_owned(),
+            detail: None,
+        }
+    }
+
+    pub fn detail(mut self, detail: &str) -> Self {
+        self.detail = Some(detail.to_owned());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ErrorCatalog {
+    codes: BTreeMap<String, ErrorTemplate>,
+}
+
+impl ErrorCatalog {
+    pub fn new() -> ErrorCatalog {
+        ErrorCatalog::default()
+    }
+
+    pub fn code(mut self, code: &str, template: ErrorTemplate) -> Self {
+        self.codes.insert(code.to_owned(), template);
+        self
+    }
+
+    pub fn get(&self, code: &str) -> Option<&ErrorTemplate> {
+        self.codes.get(code)
+    }
+
+    // err makes the error of a code, filling in the {placeholders} of its templates with args.
+    // Placeholders without an arg are left as they are. A code that isn't registered is a bug of
+    // the app, answered with 500
+    pub fn err(&self, code: &str, args: &[(&str, &str)]) -> Error {
+        let Some(template) = self.codes.get(code) else {
+            return Error::new_internal_error(&format!("unregistered error code {}", code));
+        };
+        let mut err = Error::new_internal_error(&fill(&template.title, args));
+        err.status = template.status.clone();
+        err.code = Some(code.to_owned());
+        err.detail = template.detail.as_deref().map(|detail| fill(detail, args));
+        err
+    }
+
+    // service serves the codes at path, e.g. `App::new().service(codes.service("/errors"))`
+    #[cfg(feature = "actixweb")]
+    pub fn service(&self, path: &str) -> actix_web::Resource {
+        let body = serde_json::to_vec(self).expect("the catalog is always valid JSON");
+        actix_web::web::resource(path).get(move || {
+            let body = body.clone();
+            async move {
+                actix_web::HttpResponse::Ok()
+                    .content_type("application/json")
+                    .body(body)
+            }
+        })
+    }
+}
+
+// fill replaces the {name} placeholders of a template
+fn fill(template: &str, args: &[(&str, &str)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let arg = rest.find('}').and_then(|end| {
+            let (_, value) = args.iter().find(|(name, _)| *name == &rest[1..end])?;
+            Some((value, end))
+        });
+        match arg {
+            Some((value, end)) => {
+                filled.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                filled.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorCatalog, ErrorTemplate};
+    use crate::ErrorStatus;
+
+    #[test]
+    fn test_error_catalog() {
+        let codes = ErrorCatalog::new().code(
+            "article_locked",
+            ErrorTemplate::new(ErrorStatus::Conflict, "article {id} is locked")
+                .detail("{user} is editing it, {id} stays {locked}"),
+        );
+        let err = codes.err("article_locked", &[("id", "7"), ("user", "ann")]);
+        assert_eq!(err.status, ErrorStatus::Conflict);
+        assert_eq!(err.code.as_deref(), Some("article_locked"));
+        assert_eq!(err.title, "article 7 is locked");
+        assert_eq!(
+            err.detail.as_deref(),
+            Some("ann is editing it, 7 stays {locked}")
+        );
+        assert_eq!(codes.err("gone", &[]).status, ErrorStatus::InternalError);
+        assert_eq!(
+            serde_json::to_value(&codes).unwrap()["codes"]["article_locked"]["status"],
+            "409"
+        );
+    }
+}
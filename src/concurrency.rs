@@ -0,0 +1,142 @@
+// Optimistic concurrency. Resources carry their version as a `version` member of their meta (and
+// over HTTP as the ETag of single resource responses, see etag); clients send the version they
+// read back in an If-Match header with their update, which then fails with 412 Precondition
+// Failed if the resource has changed in the meantime.
+#[cfg(feature = "actixweb")]
+use actix_web::{http::header::IF_MATCH, FromRequest as FromWebRequest};
+
+use crate::{Error, Meta, ResourceResponse};
+
+// VERSION is the meta member holding a resource's version
+pub const VERSION: &str = "version";
+
+// etag is the (strong) entity tag of a version, as it goes in the ETag header
+pub fn etag(version: &str) -> String {
+    format!("\"{}\"", version)
+}
+
+impl<D> ResourceResponse<D> {
+    // with_version adds the version to the resource's meta
+    pub fn with_version(&mut self, version: &str) {
+        self.meta
+            .get_or_insert_with(Meta::new)
+            .insert(VERSION.into(), version.into());
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.meta.as_ref()?.get(VERSION)?.as_str()
+    }
+}
+
+// IfMatch is the precondition of a request. Weak entity tags never match (If-Match compares
+// strongly), so they are left out of Versions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum IfMatch {
+    // Absent means there is no header, and the client doesn't care about concurrent changes
+    #[default]
+    Absent,
+    // Any is `*`, which matches whenever the resource exists
+    Any,
+    Versions(Vec<String>),
+}
+
+impl IfMatch {
+    // parse parses the value of an If-Match header, e.g. `"3", "4"`
+    pub fn parse(header: &str) -> IfMatch {
+        if header.trim() == "*" {
+            return IfMatch::Any;
+        }
+        let versions = header
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.starts_with("W/"))
+            .filter_map(|tag| tag.strip_prefix('"')?.strip_suffix('"'))
+            .map(str::to_owned)
+            .collect();
+        IfMatch::Versions(versions)
+    }
+
+    // check compares the precondition with the current version of the resource, failing with
+    // 412 if it doesn't match
+    pub fn check(&self, current: &str) -> Result<(), Error> {
+        match self {
+            IfMatch::Absent | IfMatch::Any => Ok(()),
+            IfMatch::Versions(versions) if versions.iter().any(|v| v == current) => Ok(()),
+            IfMatch::Versions(_) => Err(Error {
+                detail: Some(format!("its current version is {}", etag(current))),
+                ..Error::new_precondition_failed("the resource has been modified since it was read")
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl FromWebRequest for IfMatch {
+    type Error = Error;
+
+    type Future = std::future::Ready<Result<IfMatch, Error>>;
+
+    fn from_request(req: &actix_web::HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        let if_match = match req.headers().get(IF_MATCH) {
+            None => Ok(IfMatch::Absent),
+            Some(value) => value
+                .to_str()
+                .map(IfMatch::parse)
+                .map_err(|_| Error::new_bad_request("invalid If-Match header")),
+        };
+        std::future::ready(if_match)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{etag, IfMatch};
+    use crate::{ErrorStatus, Identifier, ResourceResponse};
+
+    #[test]
+    fn test_if_match() {
+        assert_eq!(IfMatch::parse("*"), IfMatch::Any);
+        assert_eq!(
+            IfMatch::parse(r#""3", W/"4" ,"5""#),
+            IfMatch::Versions(vec!["3".into(), "5".into()])
+        );
+        assert!(IfMatch::Absent.check("1").is_ok());
+        assert!(IfMatch::Any.check("1").is_ok());
+        assert!(IfMatch::parse(&etag("1")).check("1").is_ok());
+        let err = IfMatch::parse(r#"W/"1""#).check("1").unwrap_err();
+        assert_eq!(err.status, ErrorStatus::PreconditionFailed);
+
+        let mut resource = ResourceResponse {
+            id: Identifier {
+                id: "1".into(),
+                typ: "articles".into(),
+            },
+            attributes: (),
+            relationships: None,
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        };
+        resource.with_version("7");
+        assert_eq!(resource.version(), Some("7"));
+        let out = serde_json::to_value(&resource).unwrap();
+        assert_eq!(out["meta"]["version"], "7");
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor() {
+        use actix_web::{test, FromRequest};
+
+        let extract = |req: test::TestRequest| {
+            let req = req.to_http_request();
+            actix_web::rt::System::new().block_on(IfMatch::extract(&req))
+        };
+        assert_eq!(
+            extract(test::TestRequest::patch()).unwrap(),
+            IfMatch::Absent
+        );
+        let if_match = extract(test::TestRequest::patch().insert_header(("If-Match", "\"2\"")));
+        assert_eq!(if_match.unwrap(), IfMatch::Versions(vec!["2".into()]));
+    }
+}
@@ -0,0 +1,172 @@
+// Opaque pagination cursors. Whatever state a handler needs to resume listing (the sort key of the
+// last item, say) is serialized to JSON and encoded with URL-safe base64, so clients can't rely
+// on its shape. With a key the cursor is signed with HMAC-SHA256, so it can't be forged or
+// altered either, and it can be given an expiry. Cursors which don't decode are refused with a 400.
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde_derive::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::Error;
+
+// Envelope is what's encoded: the state and, if the cursor expires, when (in unix seconds)
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    state: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires: Option<u64>,
+}
+
+// CursorCodec encodes and decodes cursors. Without a key they're only opaque
+#[derive(Debug, Clone, Default)]
+pub struct CursorCodec {
+    key: Option<Vec<u8>>,
+    ttl: Option<Duration>,
+}
+
+impl CursorCodec {
+    pub fn new() -> CursorCodec {
+        CursorCodec::default()
+    }
+
+    // signed makes the codec sign cursors with the key, and refuse cursors that aren't signed with it
+    pub fn signed(key: &[u8]) -> CursorCodec {
+        CursorCodec {
+            key: Some(key.to_vec()),
+            ttl: None,
+        }
+    }
+
+    // ttl makes cursors expire some time after they're encoded
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn encode<T: serde::Serialize>(&self, state: &T) -> String {
+        self.encode_at(state, now())
+    }
+
+    // decode gives back the state of a cursor. Callers can point the error at the parameter the
+    // cursor came from with `with_parameter`
+    pub fn decode<T: DeserializeOwned>(&self, cursor: &str) -> Result<T, Error> {
+        self.decode_at(cursor, now())
+    }
+
+    fn encode_at<T: serde::Serialize>(&self, state: &T, now: u64) -> String {
+        let envelope = Envelope {
+            state,
+            expires: self.ttl.map(|ttl| now.saturating_add(ttl.as_secs())),
+        };
+        // serializing to a Vec only fails for maps with non-string keys, which a cursor has no use for
+        let json = serde_json::to_vec(&envelope).expect("cursor state should serialize to JSON");
+        let payload = URL_SAFE_NO_PAD.encode(json);
+        match self.mac(&payload) {
+            Some(mac) => {
+                let signature = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+                format!("{}.{}", payload, signature)
+            }
+            None => payload,
+        }
+    }
+
+    fn decode_at<T: DeserializeOwned>(&self, cursor: &str, now: u64) -> Result<T, Error> {
+        let invalid = || Error::new_bad_request("the cursor is invalid");
+        let payload = match cursor.split_once('.') {
+            Some((payload, signature)) => {
+                let signature = URL_SAFE_NO_PAD.decode(signature).map_err(|_| invalid())?;
+                // verify_slice compares in constant time, so the signature can't be guessed byte
+                // by byte
+                let mac = self.mac(payload).ok_or_else(invalid)?;
+                mac.verify_slice(&signature).map_err(|_| invalid())?;
+                payload
+            }
+            None if self.key.is_some() => return Err(invalid()),
+            None => cursor,
+        };
+        let json = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+        let envelope: Envelope<T> = serde_json::from_slice(&json).map_err(|_| invalid())?;
+        match envelope.expires {
+            Some(expires) if expires < now => Err(Error::new_bad_request("the cursor has expired")),
+            _ => Ok(envelope.state),
+        }
+    }
+
+    fn mac(&self, payload: &str) -> Option<Hmac<Sha256>> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.key.as_ref()?)
+            .expect("HMAC should take keys of any length");
+        mac.update(payload.as_bytes());
+        Some(mac)
+    }
+}
+
+// encode makes an unsigned cursor which doesn't expire
+pub fn encode<T: serde::Serialize>(state: &T) -> String {
+    CursorCodec::new().encode(state)
+}
+
+// decode reads a cursor made by `encode`
+pub fn decode<T: DeserializeOwned>(cursor: &str) -> Result<T, Error> {
+    CursorCodec::new().decode(cursor)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_derive::{Deserialize, Serialize};
+    use std::time::Duration;
+
+    use super::{decode, encode, CursorCodec};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct After {
+        created: u64,
+        id: String,
+    }
+
+    #[test]
+    fn test_cursor() {
+        let state = After {
+            created: 1700000000,
+            id: "42".into(),
+        };
+        let cursor = encode(&state);
+        assert!(cursor
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+        assert_eq!(decode::<After>(&cursor).unwrap(), state);
+        assert!(decode::<After>("not a cursor").is_err());
+
+        let codec = CursorCodec::signed(b"secret");
+        let cursor = codec.encode(&state);
+        assert_eq!(codec.decode::<After>(&cursor).unwrap(), state);
+        // a payload re-encoded by the client no longer matches the signature
+        let (_, signature) = cursor.split_once('.').unwrap();
+        let forged = format!(
+            "{}.{}",
+            encode(&After {
+                created: 0,
+                ..state
+            }),
+            signature
+        );
+        assert!(codec.decode::<After>(&forged).is_err());
+        assert!(CursorCodec::signed(b"other")
+            .decode::<After>(&cursor)
+            .is_err());
+        assert!(codec.decode::<After>(&encode(&1)).is_err());
+
+        let codec = codec.ttl(Duration::from_secs(60));
+        let cursor = codec.encode_at(&1, 1000);
+        assert_eq!(codec.decode_at::<u32>(&cursor, 1060).unwrap(), 1);
+        let err = codec.decode_at::<u32>(&cursor, 1061).unwrap_err();
+        assert_eq!(err.title, "the cursor has expired");
+    }
+}
@@ -0,0 +1,216 @@
+// Deserialize implementations for the response and request types. They read a resource object or document
+// member by member rather than through serde's flatten, which buffers the input (RawValue
+// attributes can't be deserialized from that) and can't hand back the members it doesn't know.
+// Those are kept as extensions instead, so proxied documents round-trip without losing anything,
+// as are the top-level meta, links and jsonapi members if they don't have the spec's shape.
+use serde::de::{
+    self, value::UnitDeserializer, Deserialize, DeserializeOwned, Deserializer, MapAccess, Visitor,
+};
+use std::{fmt, marker::PhantomData};
+
+use crate::{
+    Error, Extensions, Identifier, PrimaryData, Request, ResourceRequest, ResourceResponse,
+    Response, ResponseType,
+};
+
+// missing deserializes an absent member, which gives None for Option types (as derived
+// implementations do) and a missing field error otherwise
+fn missing<'de, T: Deserialize<'de>, E: de::Error>(name: &'static str) -> Result<T, E> {
+    T::deserialize(UnitDeserializer::<E>::new()).map_err(|_| E::missing_field(name))
+}
+
+// take_member takes a member out of the extensions if it parses as T, leaving it there otherwise
+pub(crate) fn take_member<T: DeserializeOwned>(
+    extensions: &mut Extensions,
+    name: &str,
+) -> Option<T> {
+    let parsed = T::deserialize(extensions.get(name)?).ok()?;
+    extensions.remove(name);
+    Some(parsed)
+}
+
+impl<'de, D: Deserialize<'de>> Deserialize<'de> for ResourceResponse<D> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        deserializer.deserialize_map(ResourceVisitor(PhantomData))
+    }
+}
+
+struct ResourceVisitor<D>(PhantomData<D>);
+
+impl<'de, D: Deserialize<'de>> Visitor<'de> for ResourceVisitor<D> {
+    type Value = ResourceResponse<D>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a resource object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut id = None;
+        let mut typ = None;
+        let mut attributes = None;
+        let mut relationships = None;
+        let mut meta = None;
+        let mut links = None;
+        let mut extensions = Extensions::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "id" => id = Some(map.next_value()?),
+                "type" => typ = Some(map.next_value()?),
+                "attributes" => attributes = Some(map.next_value()?),
+                "relationships" => relationships = map.next_value()?,
+                "meta" => meta = map.next_value()?,
+                "links" => links = map.next_value()?,
+                _ => {
+                    extensions.insert(key, map.next_value()?);
+                }
+            }
+        }
+        Ok(ResourceResponse {
+            id: Identifier {
+                id: id.ok_or_else(|| de::Error::missing_field("id"))?,
+                typ: typ.ok_or_else(|| de::Error::missing_field("type"))?,
+            },
+            attributes: match attributes {
+                Some(attributes) => attributes,
+                None => missing("attributes")?,
+            },
+            relationships,
+            meta,
+            links,
+            extensions,
+        })
+    }
+}
+
+impl<'de, P: Deserialize<'de>, I: Deserialize<'de>> Deserialize<'de> for Response<P, I> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        deserializer.deserialize_map(DocumentVisitor(PhantomData))
+    }
+}
+
+struct DocumentVisitor<P, I>(PhantomData<(P, I)>);
+
+impl<'de, P: Deserialize<'de>, I: Deserialize<'de>> Visitor<'de> for DocumentVisitor<P, I> {
+    type Value = Response<P, I>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON:API document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut data: Option<PrimaryData<P>> = None;
+        let mut errors: Option<Vec<Error>> = None;
+        let mut included = None;
+        let mut extensions = Extensions::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "data" => data = map.next_value()?,
+                "errors" => errors = map.next_value()?,
+                "included" => included = map.next_value()?,
+                _ => {
+                    extensions.insert(key, map.next_value()?);
+                }
+            }
+        }
+        let primary = match (data, errors) {
+            (Some(data), None) => ResponseType::Ok(data),
+            (None, Some(errors)) => ResponseType::Error(errors),
+            (Some(_), Some(_)) => {
+                return Err(de::Error::custom(
+                    "a document can't contain both data and errors",
+                ))
+            }
+            (None, None) => {
+                return Err(de::Error::custom(
+                    "a document must contain either data or errors",
+                ))
+            }
+        };
+        Ok(Response {
+            primary,
+            included,
+            meta: take_member(&mut extensions, "meta"),
+            links: take_member(&mut extensions, "links"),
+            jsonapi: take_member(&mut extensions, "jsonapi"),
+            extensions,
+        })
+    }
+}
+
+impl<'de, D: Deserialize<'de>> Deserialize<'de> for ResourceRequest<D> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        deserializer.deserialize_map(ResourceRequestVisitor(PhantomData))
+    }
+}
+
+struct ResourceRequestVisitor<D>(PhantomData<D>);
+
+impl<'de, D: Deserialize<'de>> Visitor<'de> for ResourceRequestVisitor<D> {
+    type Value = ResourceRequest<D>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a resource object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut id = None;
+        let mut typ = None;
+        let mut attributes = None;
+        let mut relationships = None;
+        let mut extensions = Extensions::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "id" => id = map.next_value()?,
+                "type" => typ = Some(map.next_value()?),
+                "attributes" => attributes = Some(map.next_value()?),
+                "relationships" => relationships = map.next_value()?,
+                _ => {
+                    extensions.insert(key, map.next_value()?);
+                }
+            }
+        }
+        Ok(ResourceRequest {
+            id,
+            typ: typ.ok_or_else(|| de::Error::missing_field("type"))?,
+            attributes: match attributes {
+                Some(attributes) => attributes,
+                None => missing("attributes")?,
+            },
+            relationships,
+            extensions,
+        })
+    }
+}
+
+impl<'de, D: Deserialize<'de>> Deserialize<'de> for Request<D> {
+    fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+        deserializer.deserialize_map(RequestVisitor(PhantomData))
+    }
+}
+
+struct RequestVisitor<D>(PhantomData<D>);
+
+impl<'de, D: Deserialize<'de>> Visitor<'de> for RequestVisitor<D> {
+    type Value = Request<D>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON:API document")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut data = None;
+        let mut extensions = Extensions::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "data" => data = Some(map.next_value()?),
+                _ => {
+                    extensions.insert(key, map.next_value()?);
+                }
+            }
+        }
+        Ok(Request {
+            data: data.ok_or_else(|| de::Error::missing_field("data"))?,
+            extensions,
+        })
+    }
+}
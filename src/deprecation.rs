@@ -0,0 +1,173 @@
+// Deprecation of resources and endpoints. In documents a deprecation shows as `deprecated` and
+// `sunset` members of meta and a describedby link to its documentation; over HTTP as the
+// Deprecation (RFC 9745) and Sunset (RFC 8594) headers, see Deprecation::headers.
+use crate::{Link, Links, Meta, ResourceResponse, Response};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deprecation {
+    // since is when it was (or will be) deprecated, in seconds since the unix epoch
+    pub since: Option<u64>,
+    // sunset is the HTTP date after which it may stop working, e.g. "Wed, 11 Nov 2026 23:59:59 GMT"
+    pub sunset: Option<String>,
+    // describedby links to documentation of the deprecation, e.g. a migration guide
+    pub describedby: Option<String>,
+}
+
+impl Deprecation {
+    pub fn new() -> Deprecation {
+        Deprecation::default()
+    }
+
+    pub fn since(mut self, since: u64) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn sunset(mut self, date: &str) -> Self {
+        self.sunset = Some(date.to_owned());
+        self
+    }
+
+    pub fn describedby(mut self, url: &str) -> Self {
+        self.describedby = Some(url.to_owned());
+        self
+    }
+
+    pub fn to_meta(&self) -> Meta {
+        let mut meta = Meta::new();
+        meta.insert("deprecated".into(), true.into());
+        if let Some(sunset) = &self.sunset {
+            meta.insert("sunset".into(), sunset.clone().into());
+        }
+        meta
+    }
+
+    fn to_links(&self) -> Links {
+        self.describedby
+            .iter()
+            .map(|url| ("describedby".to_owned(), Link::from(url.as_str())))
+            .collect()
+    }
+
+    // header_value is the Deprecation header: the date as a structured field date, or just
+    // "true" (as earlier drafts had it) if there isn't one
+    pub fn header_value(&self) -> String {
+        match self.since {
+            Some(since) => format!("@{}", since),
+            None => "true".into(),
+        }
+    }
+
+    // headers is a middleware adding the Deprecation and Sunset headers, and a Link to the
+    // documentation, to every response of the scope or resource it wraps, e.g.
+    // `web::scope("/v1").wrap(deprecation.headers())`
+    #[cfg(feature = "actixweb")]
+    pub fn headers(&self) -> actix_web::middleware::DefaultHeaders {
+        let mut headers =
+            actix_web::middleware::DefaultHeaders::new().add(("Deprecation", self.header_value()));
+        if let Some(sunset) = &self.sunset {
+            headers = headers.add(("Sunset", sunset.as_str()));
+        }
+        if let Some(url) = &self.describedby {
+            headers = headers.add(("Link", format!("<{}>; rel=\"deprecation\"", url)));
+        }
+        headers
+    }
+}
+
+impl<D> ResourceResponse<D> {
+    // deprecate marks a single resource as deprecated in its meta and links
+    pub fn deprecate(&mut self, deprecation: &Deprecation) {
+        self.meta
+            .get_or_insert_with(Meta::new)
+            .extend(deprecation.to_meta());
+        let links = deprecation.to_links();
+        if !links.is_empty() {
+            self.links.get_or_insert_with(Links::new).extend(links);
+        }
+    }
+}
+
+impl<P, I> Response<P, I> {
+    // deprecated marks the whole document (the endpoint it came from) as deprecated, in its
+    // top-level meta and links
+    pub fn deprecated(self, deprecation: &Deprecation) -> Self {
+        self.merge_meta(deprecation.to_meta())
+            .merge_links(deprecation.to_links())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Deprecation;
+    use crate::{Identifier, ResourceResponse, Response, ResponseType};
+
+    #[test]
+    fn test_deprecation() {
+        let deprecation = Deprecation::new()
+            .since(1767225600)
+            .sunset("Wed, 01 Jul 2026 00:00:00 GMT")
+            .describedby("https://example.com/migrate");
+        assert_eq!(deprecation.header_value(), "@1767225600");
+        assert_eq!(Deprecation::new().header_value(), "true");
+
+        let mut resource = ResourceResponse {
+            id: Identifier {
+                id: "1".into(),
+                typ: "articles".into(),
+            },
+            attributes: (),
+            relationships: None,
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        };
+        resource.deprecate(&deprecation);
+        let out = serde_json::to_value(&resource).unwrap();
+        assert_eq!(out["meta"]["deprecated"], true);
+        assert_eq!(out["links"]["describedby"], "https://example.com/migrate");
+
+        let response: Response<(), ()> = Response {
+            primary: ResponseType::Ok(Default::default()),
+            included: None,
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Default::default(),
+        };
+        let out = serde_json::to_value(response.deprecated(&Deprecation::new())).unwrap();
+        assert_eq!(out["meta"], serde_json::json!({"deprecated": true}));
+        assert!(out.get("links").is_none());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_headers() {
+        use actix_web::{test, web, App, HttpResponse};
+
+        let deprecation = Deprecation::new()
+            .sunset("Wed, 01 Jul 2026 00:00:00 GMT")
+            .describedby("https://example.com/migrate");
+        let res = actix_web::rt::System::new().block_on(async {
+            let app = test::init_service(
+                App::new().service(
+                    web::resource("/old")
+                        .wrap(deprecation.headers())
+                        .to(HttpResponse::Ok),
+                ),
+            )
+            .await;
+            test::call_service(&app, test::TestRequest::get().uri("/old").to_request()).await
+        });
+        let headers = res.headers();
+        assert_eq!(headers.get("deprecation").unwrap(), "true");
+        assert_eq!(
+            headers.get("sunset").unwrap(),
+            "Wed, 01 Jul 2026 00:00:00 GMT"
+        );
+        assert_eq!(
+            headers.get("link").unwrap(),
+            "<https://example.com/migrate>; rel=\"deprecation\""
+        );
+    }
+}
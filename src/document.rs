@@ -0,0 +1,1372 @@
+// Documents as a whole, and a dynamic model of them for code which handles documents of any type
+// without knowing their attributes at compile time: gateways, admin tooling, generic middleware.
+// Generic resources keep their attributes as plain JSON; everything else is parsed into the
+// crate's own types.
+use serde::{de::DeserializeOwned, Deserializer};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::{
+    Error, Extensions, FromRequest, Identifier, IntoResponse, Link, Links, Meta, Relationship,
+    RelationshipData, Relationships, Request, ResourceRequest, ResourceResponse, Response,
+    ResponseType, ID,
+};
+
+// GenericResource is a resource object of any type. The id is optional, as it is for resources
+// being created, which may carry a local id (lid) instead
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GenericResource {
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<ID>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lid: Option<String>,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub attributes: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relationships: Option<Relationships>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl GenericResource {
+    pub fn new(typ: &str, id: Option<ID>) -> GenericResource {
+        GenericResource {
+            typ: typ.to_owned(),
+            id,
+            lid: None,
+            attributes: Value::Null,
+            relationships: None,
+            meta: None,
+            links: None,
+            extensions: Extensions::new(),
+        }
+    }
+
+    // identifier is the resource's type and id, None while it has no id
+    pub fn identifier(&self) -> Option<Identifier> {
+        Some(Identifier {
+            id: self.id.clone()?,
+            typ: self.typ.clone(),
+        })
+    }
+
+    // attribute looks up a single attribute by name
+    pub fn attribute(&self, name: &str) -> Option<&Value> {
+        self.attributes.get(name)
+    }
+
+    // into_request hands the resource to a FromRequest type, as if it had arrived in a request,
+    // so a gateway can pass typed resources on once it knows what they are. The type check is
+    // the FromRequest implementation's, as usual
+    pub fn into_request<T>(self) -> Result<T, Error>
+    where
+        T: FromRequest,
+        T::Attributes: DeserializeOwned,
+    {
+        T::from_request(Request::try_from(self)?)
+    }
+
+    // from_response turns a typed resource into a generic one
+    pub fn from_response<T>(resource: T) -> Result<GenericResource, Error>
+    where
+        T: IntoResponse,
+        T::Attributes: serde::Serialize,
+    {
+        GenericResource::try_from(resource.into_response())
+    }
+}
+
+impl<D: DeserializeOwned> TryFrom<GenericResource> for Request<D> {
+    type Error = Error;
+
+    fn try_from(res: GenericResource) -> Result<Self, Error> {
+        let attributes = D::deserialize(&res.attributes).map_err(|err| {
+            Error::new_bad_request(&format!("invalid attributes: {}", err))
+                .with_pointer("/data/attributes")
+        })?;
+        Ok(Request {
+            data: ResourceRequest {
+                id: res.id,
+                typ: res.typ,
+                attributes,
+                relationships: res.relationships,
+                extensions: res.extensions,
+            },
+            extensions: Extensions::new(),
+        })
+    }
+}
+
+impl<D: serde::Serialize> TryFrom<ResourceResponse<D>> for GenericResource {
+    type Error = Error;
+
+    fn try_from(res: ResourceResponse<D>) -> Result<Self, Error> {
+        let attributes = serde_json::to_value(&res.attributes).map_err(|err| {
+            Error::new_internal_error(&format!("attributes are not valid JSON: {}", err))
+        })?;
+        Ok(GenericResource {
+            typ: res.id.typ,
+            id: Some(res.id.id),
+            lid: None,
+            attributes,
+            relationships: res.relationships,
+            meta: res.meta,
+            links: res.links,
+            extensions: res.extensions,
+        })
+    }
+}
+
+// Patch is the resource object of a PATCH request built by diffing two versions of a resource
+pub type Patch = ResourceRequest<serde_json::Map<String, Value>>;
+
+impl GenericResource {
+    // diff builds the smallest update turning this resource into `after`: the attributes whose
+    // values changed (attributes `after` lacks are cleared with null) and the relationships whose
+    // linkage changed, replaced in full. Relationships `after` lacks, or has no linkage for, are
+    // left alone. The type and id are `after`'s
+    pub fn diff(&self, after: &GenericResource) -> Patch {
+        ResourceRequest {
+            id: after.id.clone().or_else(|| self.id.clone()),
+            typ: after.typ.clone(),
+            attributes: diff_attributes(&self.attributes, &after.attributes),
+            relationships: diff_relationships(
+                self.relationships.as_ref(),
+                after.relationships.as_ref(),
+            ),
+            extensions: Extensions::new(),
+        }
+    }
+}
+
+impl<D: serde::Serialize> ResourceResponse<D> {
+    // diff is GenericResource::diff for typed resources, comparing attributes as they serialize
+    pub fn diff(&self, after: &ResourceResponse<D>) -> Result<Patch, Error> {
+        let to_value = |attributes: &D| {
+            serde_json::to_value(attributes).map_err(|err| {
+                Error::new_internal_error(&format!("attributes are not valid JSON: {}", err))
+            })
+        };
+        Ok(ResourceRequest {
+            id: Some(after.id.id.clone()),
+            typ: after.id.typ.clone(),
+            attributes: diff_attributes(
+                &to_value(&self.attributes)?,
+                &to_value(&after.attributes)?,
+            ),
+            relationships: diff_relationships(
+                self.relationships.as_ref(),
+                after.relationships.as_ref(),
+            ),
+            extensions: Extensions::new(),
+        })
+    }
+}
+
+fn diff_attributes(before: &Value, after: &Value) -> serde_json::Map<String, Value> {
+    let empty = serde_json::Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+    let changed = after
+        .iter()
+        .filter(|(name, value)| before.get(name.as_str()) != Some(value))
+        .map(|(name, value)| (name.clone(), value.clone()));
+    let removed = before
+        .keys()
+        .filter(|name| !after.contains_key(name.as_str()))
+        .map(|name| (name.clone(), Value::Null));
+    changed.chain(removed).collect()
+}
+
+fn diff_relationships(
+    before: Option<&Relationships>,
+    after: Option<&Relationships>,
+) -> Option<Relationships> {
+    let changed: Relationships = after?
+        .iter()
+        .filter_map(|(name, rel)| Some((name, rel.data.as_ref()?)))
+        .filter(|(name, data)| {
+            before
+                .and_then(|before| before.get(name.as_str()))
+                .is_none_or(|old| old.data.as_ref() != Some(*data))
+        })
+        .map(|(name, data)| (name.clone(), RelationshipData::from(data.clone())))
+        .collect();
+    (!changed.is_empty()).then_some(changed)
+}
+
+// Data is the primary data of a document: a single resource, a list of them, or null (a to-one
+// lookup which found nothing)
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Data<R> {
+    Null,
+    One(Box<R>),
+    Many(Vec<R>),
+}
+
+impl<R> Data<R> {
+    // iter goes over the resources, of which there are none for Null
+    pub fn iter(&self) -> std::slice::Iter<'_, R> {
+        match self {
+            Data::Null => [].iter(),
+            Data::One(one) => std::slice::from_ref(one.as_ref()).iter(),
+            Data::Many(many) => many.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, R> {
+        match self {
+            Data::Null => [].iter_mut(),
+            Data::One(one) => std::slice::from_mut(one.as_mut()).iter_mut(),
+            Data::Many(many) => many.iter_mut(),
+        }
+    }
+
+    pub fn into_vec(self) -> Vec<R> {
+        match self {
+            Data::Null => Vec::new(),
+            Data::One(one) => vec![*one],
+            Data::Many(many) => many,
+        }
+    }
+}
+
+// Document is a whole document, request or response. data is None when the member is missing (as
+// in error documents) and Some(Data::Null) when it's null. Request and Response convert to and
+// from their documents, so middleware can handle both the same way
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(bound(deserialize = "D: serde::Deserialize<'de>, I: serde::Deserialize<'de>"))]
+pub struct Document<D, I = D> {
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "present"
+    )]
+    pub data: Option<Data<D>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<Error>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub included: Option<Vec<I>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<Value>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+// GenericDocument is a document of any resource types
+pub type GenericDocument = Document<GenericResource>;
+
+impl<D, I> Default for Document<D, I> {
+    fn default() -> Self {
+        Document {
+            data: None,
+            errors: None,
+            included: None,
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Extensions::new(),
+        }
+    }
+}
+
+impl<D, I> Document<D, I> {
+    // primary goes over the primary resources
+    pub fn primary(&self) -> std::slice::Iter<'_, D> {
+        match &self.data {
+            Some(data) => data.iter(),
+            None => [].iter(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.errors.is_some()
+    }
+}
+
+impl<R> Document<R, R> {
+    // resources goes over the primary and then the included resources
+    pub fn resources(&self) -> impl Iterator<Item = &R> {
+        self.primary().chain(self.included.iter().flatten())
+    }
+}
+
+impl GenericDocument {
+    // find looks up a primary or included resource by type and id
+    pub fn find(&self, typ: &str, id: &str) -> Option<&GenericResource> {
+        self.resources()
+            .find(|res| res.typ == typ && res.id.as_ref().is_some_and(|rid| rid.as_str() == id))
+    }
+}
+
+impl<D> From<Request<D>> for Document<ResourceRequest<D>> {
+    // from moves the request's meta, links and jsonapi members to the document's, if they have
+    // the spec's shape
+    fn from(req: Request<D>) -> Self {
+        let mut extensions = req.extensions;
+        Document {
+            data: Some(Data::One(Box::new(req.data))),
+            meta: crate::de::take_member(&mut extensions, "meta"),
+            links: crate::de::take_member(&mut extensions, "links"),
+            jsonapi: extensions.remove("jsonapi"),
+            extensions,
+            ..Default::default()
+        }
+    }
+}
+
+impl<D> TryFrom<Document<ResourceRequest<D>>> for Request<D> {
+    type Error = Error;
+
+    fn try_from(doc: Document<ResourceRequest<D>>) -> Result<Self, Error> {
+        match doc.data {
+            Some(Data::One(data)) => {
+                let mut extensions = doc.extensions;
+                let meta = doc.meta.map(serde_json::to_value);
+                let links = doc.links.map(serde_json::to_value);
+                for (name, member) in [("meta", meta), ("links", links)] {
+                    if let Some(Ok(member)) = member {
+                        extensions.insert(name.into(), member);
+                    }
+                }
+                if let Some(jsonapi) = doc.jsonapi {
+                    extensions.insert("jsonapi".into(), jsonapi);
+                }
+                Ok(Request {
+                    data: *data,
+                    extensions,
+                })
+            }
+            _ => Err(
+                Error::new_bad_request("a request document needs a single resource in data")
+                    .with_pointer("/data"),
+            ),
+        }
+    }
+}
+
+impl<P, I> From<Response<P, I>> for Document<ResourceResponse<P>, ResourceResponse<I>> {
+    fn from(res: Response<P, I>) -> Self {
+        let (data, errors) = match res.primary {
+            ResponseType::Ok(data) => (Some(Data::Many(data.into_vec())), None),
+            ResponseType::Error(errors) => (None, Some(errors)),
+        };
+        Document {
+            data,
+            errors,
+            included: res.included,
+            meta: res.meta,
+            links: res.links,
+            jsonapi: res.jsonapi.map(|jsonapi| {
+                serde_json::to_value(jsonapi).expect("jsonapi is always valid JSON")
+            }),
+            extensions: res.extensions,
+        }
+    }
+}
+
+impl<P, I> From<Document<ResourceResponse<P>, ResourceResponse<I>>> for Response<P, I> {
+    fn from(doc: Document<ResourceResponse<P>, ResourceResponse<I>>) -> Self {
+        let primary = match (doc.errors, doc.data) {
+            (Some(errors), _) => ResponseType::Error(errors),
+            (None, data) => ResponseType::Ok(data.map(Data::into_vec).unwrap_or_default().into()),
+        };
+        // a jsonapi object without the spec's shape stays among the extensions
+        let mut extensions = doc.extensions;
+        if let Some(jsonapi) = doc.jsonapi {
+            extensions.insert("jsonapi".into(), jsonapi);
+        }
+        Response {
+            primary,
+            included: doc.included,
+            meta: doc.meta,
+            links: doc.links,
+            jsonapi: crate::de::take_member(&mut extensions, "jsonapi"),
+            extensions,
+        }
+    }
+}
+
+// ResourceObject is what walking and addressing a document need of a resource, whichever form it
+// has. Forms which can't carry meta or links (resources in requests) return None for them, and
+// only generic resources have attributes that can be looked into
+pub trait ResourceObject {
+    fn typ(&self) -> &str;
+    fn id(&self) -> Option<&ID>;
+    fn relationships(&self) -> Option<&Relationships>;
+    fn meta(&self) -> Option<&Meta>;
+    fn links(&self) -> Option<&Links>;
+    fn relationships_mut(&mut self) -> Option<&mut Relationships>;
+    fn meta_mut(&mut self) -> Option<&mut Meta>;
+    fn links_mut(&mut self) -> Option<&mut Links>;
+
+    fn attributes(&self) -> Option<&Value> {
+        None
+    }
+}
+
+impl ResourceObject for GenericResource {
+    fn typ(&self) -> &str {
+        &self.typ
+    }
+
+    fn id(&self) -> Option<&ID> {
+        self.id.as_ref()
+    }
+
+    fn relationships(&self) -> Option<&Relationships> {
+        self.relationships.as_ref()
+    }
+
+    fn meta(&self) -> Option<&Meta> {
+        self.meta.as_ref()
+    }
+
+    fn links(&self) -> Option<&Links> {
+        self.links.as_ref()
+    }
+
+    fn relationships_mut(&mut self) -> Option<&mut Relationships> {
+        self.relationships.as_mut()
+    }
+
+    fn meta_mut(&mut self) -> Option<&mut Meta> {
+        self.meta.as_mut()
+    }
+
+    fn links_mut(&mut self) -> Option<&mut Links> {
+        self.links.as_mut()
+    }
+
+    fn attributes(&self) -> Option<&Value> {
+        Some(&self.attributes)
+    }
+}
+
+impl<D> ResourceObject for ResourceResponse<D> {
+    fn typ(&self) -> &str {
+        &self.id.typ
+    }
+
+    fn id(&self) -> Option<&ID> {
+        Some(&self.id.id)
+    }
+
+    fn relationships(&self) -> Option<&Relationships> {
+        self.relationships.as_ref()
+    }
+
+    fn meta(&self) -> Option<&Meta> {
+        self.meta.as_ref()
+    }
+
+    fn links(&self) -> Option<&Links> {
+        self.links.as_ref()
+    }
+
+    fn relationships_mut(&mut self) -> Option<&mut Relationships> {
+        self.relationships.as_mut()
+    }
+
+    fn meta_mut(&mut self) -> Option<&mut Meta> {
+        self.meta.as_mut()
+    }
+
+    fn links_mut(&mut self) -> Option<&mut Links> {
+        self.links.as_mut()
+    }
+}
+
+impl<D> ResourceObject for ResourceRequest<D> {
+    fn typ(&self) -> &str {
+        &self.typ
+    }
+
+    fn id(&self) -> Option<&ID> {
+        self.id.as_ref()
+    }
+
+    fn relationships(&self) -> Option<&Relationships> {
+        self.relationships.as_ref()
+    }
+
+    fn meta(&self) -> Option<&Meta> {
+        None
+    }
+
+    fn links(&self) -> Option<&Links> {
+        None
+    }
+
+    fn relationships_mut(&mut self) -> Option<&mut Relationships> {
+        self.relationships.as_mut()
+    }
+
+    fn meta_mut(&mut self) -> Option<&mut Meta> {
+        None
+    }
+
+    fn links_mut(&mut self) -> Option<&mut Links> {
+        None
+    }
+}
+
+// Visitor is called by Document::walk for every part of a document it cares about, each with the
+// JSON pointer of the part. All methods do nothing by default
+#[allow(unused_variables)]
+pub trait Visitor<D, I = D> {
+    fn visit_primary(&mut self, pointer: &str, resource: &mut D) {}
+    fn visit_included(&mut self, pointer: &str, resource: &mut I) {}
+    fn visit_relationship(&mut self, pointer: &str, name: &str, rel: &mut RelationshipData) {}
+    fn visit_identifier(&mut self, pointer: &str, identifier: &mut Identifier) {}
+    fn visit_links(&mut self, pointer: &str, links: &mut Links) {}
+    fn visit_meta(&mut self, pointer: &str, meta: &mut Meta) {}
+    fn visit_error(&mut self, pointer: &str, error: &mut Error) {}
+}
+
+impl<D: ResourceObject, I: ResourceObject> Document<D, I> {
+    // walk visits the primary resources, then the included ones, then errors and the top-level
+    // links and meta. A resource is visited before its relationships (and their identifiers,
+    // links and meta), links and meta
+    pub fn walk<V: Visitor<D, I>>(&mut self, visitor: &mut V) {
+        match &mut self.data {
+            Some(Data::One(res)) => {
+                visitor.visit_primary("/data", res);
+                walk_resource("/data", res.as_mut(), visitor);
+            }
+            Some(Data::Many(many)) => {
+                for (i, res) in many.iter_mut().enumerate() {
+                    let pointer = format!("/data/{}", i);
+                    visitor.visit_primary(&pointer, res);
+                    walk_resource(&pointer, res, visitor);
+                }
+            }
+            Some(Data::Null) | None => {}
+        }
+        for (i, res) in self.included.iter_mut().flatten().enumerate() {
+            let pointer = format!("/included/{}", i);
+            visitor.visit_included(&pointer, res);
+            walk_resource(&pointer, res, visitor);
+        }
+        for (i, error) in self.errors.iter_mut().flatten().enumerate() {
+            visitor.visit_error(&format!("/errors/{}", i), error);
+        }
+        if let Some(links) = &mut self.links {
+            visitor.visit_links("/links", links);
+        }
+        if let Some(meta) = &mut self.meta {
+            visitor.visit_meta("/meta", meta);
+        }
+    }
+}
+
+fn walk_resource<D, I, R: ResourceObject>(
+    pointer: &str,
+    res: &mut R,
+    visitor: &mut impl Visitor<D, I>,
+) {
+    for (name, rel) in res.relationships_mut().into_iter().flatten() {
+        let pointer = format!("{}/relationships/{}", pointer, escape(name));
+        visitor.visit_relationship(&pointer, name, rel);
+        match &mut rel.data {
+            Some(Relationship::ToOne(identifier)) => {
+                visitor.visit_identifier(&format!("{}/data", pointer), identifier)
+            }
+            Some(Relationship::ToMany(many)) => {
+                for (i, identifier) in many.iter_mut().enumerate() {
+                    visitor.visit_identifier(&format!("{}/data/{}", pointer, i), identifier);
+                }
+            }
+            None => {}
+        }
+        if let Some(links) = &mut rel.links {
+            visitor.visit_links(&format!("{}/links", pointer), links);
+        }
+        if let Some(meta) = &mut rel.meta {
+            visitor.visit_meta(&format!("{}/meta", pointer), meta);
+        }
+    }
+    if let Some(links) = res.links_mut() {
+        visitor.visit_links(&format!("{}/links", pointer), links);
+    }
+    if let Some(meta) = res.meta_mut() {
+        visitor.visit_meta(&format!("{}/meta", pointer), meta);
+    }
+}
+
+impl<D: ResourceObject, I: ResourceObject> Document<D, I> {
+    // merge combines two documents, e.g. the responses of several backends assembled into one.
+    // Primary data and errors are concatenated, and a resource which both documents have (by
+    // type and id) is kept once, as the first document has it. The other document's meta members
+    // overwrite the first's; its links, jsonapi object and extension members only fill in for
+    // ones the first doesn't have
+    pub fn merge(mut self, other: Document<D, I>) -> Self {
+        self.data = match (self.data, other.data) {
+            (None | Some(Data::Null), data) | (data, None | Some(Data::Null)) => data,
+            (Some(data), Some(more)) => {
+                let mut data = data.into_vec();
+                data.extend(more.into_vec());
+                Some(Data::Many(dedup(data)))
+            }
+        };
+        if let Some(errors) = other.errors {
+            self.errors.get_or_insert_with(Vec::new).extend(errors);
+        }
+        if let Some(included) = other.included {
+            let mut all = self.included.unwrap_or_default();
+            all.extend(included);
+            self.included = Some(dedup(all));
+        }
+        if let Some(meta) = other.meta {
+            self.meta.get_or_insert_with(Meta::new).extend(meta);
+        }
+        if let Some(links) = other.links {
+            let mine = self.links.get_or_insert_with(Links::new);
+            for (name, link) in links {
+                mine.entry(name).or_insert(link);
+            }
+        }
+        self.jsonapi = self.jsonapi.or(other.jsonapi);
+        for (name, value) in other.extensions {
+            self.extensions.entry(name).or_insert(value);
+        }
+        self
+    }
+}
+
+// dedup drops resources whose type and id came up before. Resources without an id are all kept
+fn dedup<R: ResourceObject>(resources: Vec<R>) -> Vec<R> {
+    let mut seen = HashSet::new();
+    resources
+        .into_iter()
+        .filter(|res| match res.id() {
+            Some(id) => seen.insert((res.typ().to_owned(), id.clone())),
+            None => true,
+        })
+        .collect()
+}
+
+// Node is a part of a document found by its JSON pointer, see Document::pointer. Values are
+// anything inside attributes, meta or the jsonapi object
+#[derive(Debug)]
+pub enum Node<'a, D, I = D> {
+    Primary(&'a D),
+    Included(&'a I),
+    Relationship(&'a RelationshipData),
+    Identifier(&'a Identifier),
+    Id(&'a ID),
+    Type(&'a str),
+    Links(&'a Links),
+    Link(&'a Link),
+    Meta(&'a Meta),
+    Value(&'a Value),
+    Error(&'a Error),
+}
+
+impl<D, I> Clone for Node<'_, D, I> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<D, I> Copy for Node<'_, D, I> {}
+
+impl<'a, D: ResourceObject, I: ResourceObject> Node<'a, D, I> {
+    // is tells whether two nodes are the same part of the same document, not just equal
+    fn is(&self, other: &Node<'_, D, I>) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other) && self.addr() == other.addr()
+    }
+
+    fn addr(&self) -> *const () {
+        match *self {
+            Node::Primary(res) => res as *const D as *const (),
+            Node::Included(res) => res as *const I as *const (),
+            Node::Relationship(rel) => rel as *const RelationshipData as *const (),
+            Node::Identifier(identifier) => identifier as *const Identifier as *const (),
+            Node::Id(id) => id as *const ID as *const (),
+            Node::Type(typ) => typ.as_ptr() as *const (),
+            Node::Links(links) => links as *const Links as *const (),
+            Node::Link(link) => link as *const Link as *const (),
+            Node::Meta(meta) => meta as *const Meta as *const (),
+            Node::Value(value) => value as *const Value as *const (),
+            Node::Error(error) => error as *const Error as *const (),
+        }
+    }
+
+    // child steps down one member (or more, for members like relationships which are only a
+    // map of the parts that can be addressed)
+    fn child<'t>(self, token: &str, rest: &mut impl Iterator<Item = &'t str>) -> Option<Self> {
+        let node = match self {
+            Node::Primary(res) => return resource_child(res, token, rest),
+            Node::Included(res) => return resource_child(res, token, rest),
+            Node::Relationship(rel) => match token {
+                "data" => match rel.data.as_ref()? {
+                    Relationship::ToOne(identifier) => Node::Identifier(identifier),
+                    Relationship::ToMany(many) => Node::Identifier(many.get(index(rest.next()?)?)?),
+                },
+                "links" => Node::Links(rel.links.as_ref()?),
+                "meta" => Node::Meta(rel.meta.as_ref()?),
+                _ => return None,
+            },
+            Node::Identifier(identifier) => match token {
+                "id" => Node::Id(&identifier.id),
+                "type" => Node::Type(&identifier.typ),
+                _ => return None,
+            },
+            Node::Links(links) => Node::Link(links.get(token)?),
+            Node::Meta(meta) => Node::Value(meta.get(token)?),
+            Node::Value(Value::Object(map)) => Node::Value(map.get(token)?),
+            Node::Value(Value::Array(values)) => Node::Value(values.get(index(token)?)?),
+            _ => return None,
+        };
+        Some(node)
+    }
+}
+
+fn resource_child<'a, 't, D, I, R: ResourceObject>(
+    res: &'a R,
+    token: &str,
+    rest: &mut impl Iterator<Item = &'t str>,
+) -> Option<Node<'a, D, I>> {
+    let node = match token {
+        "type" => Node::Type(res.typ()),
+        "id" => Node::Id(res.id()?),
+        "attributes" => Node::Value(res.attributes()?),
+        "relationships" => Node::Relationship(res.relationships()?.get(rest.next()?)?),
+        "links" => Node::Links(res.links()?),
+        "meta" => Node::Meta(res.meta()?),
+        _ => return None,
+    };
+    Some(node)
+}
+
+impl<D: ResourceObject, I: ResourceObject> Document<D, I> {
+    // pointer finds the part of the document a JSON pointer refers to, e.g.
+    // "/data/0/relationships/author/data/id". Pointers into errors stop at the error
+    pub fn pointer(&self, pointer: &str) -> Option<Node<'_, D, I>> {
+        let tokens = tokens(pointer)?;
+        let mut tokens = tokens.iter().map(String::as_str);
+        let mut node = match tokens.next()? {
+            "data" => match self.data.as_ref()? {
+                Data::Null => return None,
+                Data::One(res) => Node::Primary(res.as_ref()),
+                Data::Many(many) => Node::Primary(many.get(index(tokens.next()?)?)?),
+            },
+            "included" => Node::Included(self.included.as_ref()?.get(index(tokens.next()?)?)?),
+            "errors" => Node::Error(self.errors.as_ref()?.get(index(tokens.next()?)?)?),
+            "links" => Node::Links(self.links.as_ref()?),
+            "meta" => Node::Meta(self.meta.as_ref()?),
+            "jsonapi" => Node::Value(self.jsonapi.as_ref()?),
+            _ => return None,
+        };
+        while let Some(token) = tokens.next() {
+            node = node.child(token, &mut tokens)?;
+        }
+        Some(node)
+    }
+
+    // pointer_of is the inverse of pointer: the JSON pointer of a part of this document, e.g. to
+    // point an error's source at it. None for nodes of other documents
+    pub fn pointer_of(&self, node: Node<'_, D, I>) -> Option<String> {
+        let mut found = None;
+        self.each_node(&mut |pointer, other| {
+            if node.is(&other) {
+                found = Some(pointer.to_owned());
+            }
+            found.is_none()
+        });
+        found
+    }
+
+    // each_node calls f with every addressable part of the document until it returns false
+    fn each_node<'a>(&'a self, f: &mut NodeFn<'_, 'a, D, I>) -> bool {
+        let primary: Vec<(String, &D)> = match &self.data {
+            Some(Data::One(res)) => vec![("/data".into(), res.as_ref())],
+            Some(Data::Many(many)) => many
+                .iter()
+                .enumerate()
+                .map(|(i, res)| (format!("/data/{}", i), res))
+                .collect(),
+            Some(Data::Null) | None => Vec::new(),
+        };
+        for (pointer, res) in primary {
+            if !f(&pointer, Node::Primary(res)) || !each_resource_node(&pointer, res, f) {
+                return false;
+            }
+        }
+        for (i, res) in self.included.iter().flatten().enumerate() {
+            let pointer = format!("/included/{}", i);
+            if !f(&pointer, Node::Included(res)) || !each_resource_node(&pointer, res, f) {
+                return false;
+            }
+        }
+        for (i, error) in self.errors.iter().flatten().enumerate() {
+            if !f(&format!("/errors/{}", i), Node::Error(error)) {
+                return false;
+            }
+        }
+        self.links
+            .as_ref()
+            .is_none_or(|links| each_links_node("/links", links, f))
+            && self
+                .meta
+                .as_ref()
+                .is_none_or(|meta| each_meta_node("/meta", meta, f))
+            && self
+                .jsonapi
+                .as_ref()
+                .is_none_or(|value| each_value_node("/jsonapi", value, f))
+    }
+}
+
+// NodeFn is called with each node of a document and its pointer, see Document::each_node
+type NodeFn<'f, 'a, D, I> = dyn FnMut(&str, Node<'a, D, I>) -> bool + 'f;
+
+fn each_resource_node<'a, D, I, R: ResourceObject>(
+    pointer: &str,
+    res: &'a R,
+    f: &mut NodeFn<'_, 'a, D, I>,
+) -> bool {
+    if !f(&format!("{}/type", pointer), Node::Type(res.typ())) {
+        return false;
+    }
+    if let Some(id) = res.id() {
+        if !f(&format!("{}/id", pointer), Node::Id(id)) {
+            return false;
+        }
+    }
+    if let Some(attributes) = res.attributes() {
+        if !each_value_node(&format!("{}/attributes", pointer), attributes, f) {
+            return false;
+        }
+    }
+    for (name, rel) in res.relationships().into_iter().flatten() {
+        let pointer = format!("{}/relationships/{}", pointer, escape(name));
+        if !f(&pointer, Node::Relationship(rel)) {
+            return false;
+        }
+        let identifiers: Vec<(String, &Identifier)> = match &rel.data {
+            Some(Relationship::ToOne(identifier)) => {
+                vec![(format!("{}/data", pointer), identifier)]
+            }
+            Some(Relationship::ToMany(many)) => many
+                .iter()
+                .enumerate()
+                .map(|(i, identifier)| (format!("{}/data/{}", pointer, i), identifier))
+                .collect(),
+            None => Vec::new(),
+        };
+        for (pointer, identifier) in identifiers {
+            if !f(&pointer, Node::Identifier(identifier))
+                || !f(&format!("{}/id", pointer), Node::Id(&identifier.id))
+                || !f(&format!("{}/type", pointer), Node::Type(&identifier.typ))
+            {
+                return false;
+            }
+        }
+        if let Some(links) = &rel.links {
+            if !each_links_node(&format!("{}/links", pointer), links, f) {
+                return false;
+            }
+        }
+        if let Some(meta) = &rel.meta {
+            if !each_meta_node(&format!("{}/meta", pointer), meta, f) {
+                return false;
+            }
+        }
+    }
+    res.links()
+        .is_none_or(|links| each_links_node(&format!("{}/links", pointer), links, f))
+        && res
+            .meta()
+            .is_none_or(|meta| each_meta_node(&format!("{}/meta", pointer), meta, f))
+}
+
+fn each_links_node<'a, D, I>(
+    pointer: &str,
+    links: &'a Links,
+    f: &mut NodeFn<'_, 'a, D, I>,
+) -> bool {
+    f(pointer, Node::Links(links))
+        && links
+            .iter()
+            .all(|(name, link)| f(&format!("{}/{}", pointer, escape(name)), Node::Link(link)))
+}
+
+fn each_meta_node<'a, D, I>(pointer: &str, meta: &'a Meta, f: &mut NodeFn<'_, 'a, D, I>) -> bool {
+    f(pointer, Node::Meta(meta))
+        && meta
+            .iter()
+            .all(|(key, value)| each_value_node(&format!("{}/{}", pointer, escape(key)), value, f))
+}
+
+fn each_value_node<'a, D, I>(
+    pointer: &str,
+    value: &'a Value,
+    f: &mut NodeFn<'_, 'a, D, I>,
+) -> bool {
+    if !f(pointer, Node::Value(value)) {
+        return false;
+    }
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .all(|(key, value)| each_value_node(&format!("{}/{}", pointer, escape(key)), value, f)),
+        Value::Array(values) => values
+            .iter()
+            .enumerate()
+            .all(|(i, value)| each_value_node(&format!("{}/{}", pointer, i), value, f)),
+        _ => true,
+    }
+}
+
+// tokens splits a JSON pointer into its unescaped reference tokens
+fn tokens(pointer: &str) -> Option<Vec<String>> {
+    let rest = pointer.strip_prefix('/')?;
+    Some(
+        rest.split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+// index parses an array index, which JSON pointers write without leading zeros
+fn index(token: &str) -> Option<usize> {
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+    token.parse().ok()
+}
+
+// escape makes a member name a JSON pointer reference token
+fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+// present keeps a null member apart from a missing one: Option alone reads both as None
+fn present<'de, D: Deserializer<'de>, T: serde::Deserialize<'de>>(
+    d: D,
+) -> Result<Option<T>, D::Error> {
+    T::deserialize(d).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use smallvec::smallvec;
+
+    use super::{Data, Document, GenericDocument};
+    use crate::{
+        Extensions, Identifier, Relationship, Request, ResourceRequest, ResourceResponse, Response,
+        ResponseType, SpecVersion,
+    };
+
+    #[test]
+    fn test_generic_document() {
+        let body = json!({
+            "data": {
+                "type": "articles",
+                "id": "1",
+                "attributes": {"title": "JSON:API", "tags": ["a"]},
+                "relationships": {"author": {"data": {"type": "people", "id": "9"}}},
+                "links": {"self": "/articles/1"}
+            },
+            "included": [{"type": "people", "id": "9", "attributes": {"name": "Dan"}}],
+            "meta": {"total": 1},
+            "jsonapi": {"version": "1.1"}
+        });
+        let doc: GenericDocument = serde_json::from_value(body.clone()).unwrap();
+        let Some(Data::One(article)) = &doc.data else {
+            panic!("expected a single resource");
+        };
+        assert_eq!(article.attribute("title").unwrap(), "JSON:API");
+        let author = &article.relationships.as_ref().unwrap()["author"];
+        assert!(matches!(&author.data, Some(Relationship::ToOne(id)) if id.id.as_str() == "9"));
+        assert_eq!(doc.find("people", "9").unwrap().attributes["name"], "Dan");
+        assert_eq!(serde_json::to_value(&doc).unwrap(), body);
+
+        let doc: GenericDocument = serde_json::from_value(json!({"data": null})).unwrap();
+        assert_eq!(doc.data, Some(Data::Null));
+        assert_eq!(serde_json::to_value(&doc).unwrap(), json!({"data": null}));
+
+        let doc: GenericDocument =
+            serde_json::from_value(json!({"errors": [{"status": "404", "title": "gone"}]}))
+                .unwrap();
+        assert!(doc.data.is_none());
+        assert_eq!(doc.errors.unwrap()[0].title, "gone");
+
+        let doc: GenericDocument =
+            serde_json::from_value(json!({"data": [{"type": "tags", "lid": "x"}]})).unwrap();
+        assert_eq!(doc.resources().count(), 1);
+        assert!(doc.resources().next().unwrap().identifier().is_none());
+    }
+
+    #[test]
+    fn test_document_views() {
+        let req = Request {
+            data: ResourceRequest {
+                id: None,
+                typ: "articles".into(),
+                attributes: json!({"title": "a"}),
+                relationships: None,
+                extensions: Extensions::new(),
+            },
+            extensions: Extensions::new(),
+        };
+        let expected = serde_json::to_value(&req).unwrap();
+        let doc = Document::from(req);
+        assert_eq!(doc.primary().count(), 1);
+        let req = Request::try_from(doc).unwrap();
+        assert_eq!(serde_json::to_value(&req).unwrap(), expected);
+        let empty: Document<ResourceRequest<()>> = Document::default();
+        let err = Request::try_from(empty).err().unwrap();
+        assert_eq!(err.source.unwrap().pointer.unwrap(), "/data");
+
+        let resource = ResourceResponse {
+            id: Identifier {
+                id: "1".into(),
+                typ: "articles".into(),
+            },
+            attributes: json!({"title": "a"}),
+            relationships: None,
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        };
+        let response: Response<serde_json::Value, ()> = Response {
+            primary: ResponseType::Ok(smallvec![resource]),
+            included: None,
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Default::default(),
+        };
+        let mut response = response.jsonapi(SpecVersion::V1_1);
+        response.meta = Some(crate::Meta::from([("total".into(), json!(1))]));
+        let expected = serde_json::to_value(&response).unwrap();
+        let doc = Document::from(response);
+        assert_eq!(doc.meta.as_ref().unwrap()["total"], 1);
+        assert_eq!(doc.jsonapi.as_ref().unwrap()["version"], "1.1");
+        assert!(doc.extensions.is_empty());
+        assert_eq!(serde_json::to_value(&doc).unwrap(), expected);
+        let response = Response::from(doc);
+        assert_eq!(serde_json::to_value(&response).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_typed_conversion() {
+        use serde_derive::{Deserialize, Serialize};
+
+        use super::GenericResource;
+        use crate::{Error, FromRequest, IntoResponse};
+
+        #[derive(Serialize, Deserialize)]
+        struct Attributes {
+            title: String,
+        }
+
+        struct Article {
+            id: String,
+            title: String,
+        }
+
+        impl FromRequest for Article {
+            type Attributes = Attributes;
+
+            fn from_request(req: Request<Attributes>) -> Result<Self, Error> {
+                req.data.check_type("articles")?;
+                Ok(Article {
+                    id: req.data.id.map(|id| id.to_string()).unwrap_or_default(),
+                    title: req.data.attributes.title,
+                })
+            }
+        }
+
+        impl IntoResponse for Article {
+            type Attributes = Attributes;
+
+            fn into_response(self) -> ResourceResponse<Attributes> {
+                ResourceResponse {
+                    id: Identifier {
+                        id: self.id.into(),
+                        typ: "articles".into(),
+                    },
+                    attributes: Attributes { title: self.title },
+                    relationships: None,
+                    meta: None,
+                    links: None,
+                    extensions: Default::default(),
+                }
+            }
+        }
+
+        let generic: GenericResource = serde_json::from_value(json!({
+            "type": "articles", "id": "1", "attributes": {"title": "a"}
+        }))
+        .unwrap();
+        let article: Article = generic.clone().into_request().unwrap();
+        assert_eq!((article.id.as_str(), article.title.as_str()), ("1", "a"));
+        assert_eq!(GenericResource::from_response(article).unwrap(), generic);
+
+        let mut other = generic.clone();
+        other.typ = "people".into();
+        assert!(other.into_request::<Article>().is_err());
+        let mut invalid = generic;
+        invalid.attributes = json!({"title": 1});
+        let err = invalid.into_request::<Article>().err().unwrap();
+        assert_eq!(err.source.unwrap().pointer.unwrap(), "/data/attributes");
+    }
+
+    #[test]
+    fn test_walk() {
+        use super::Visitor;
+        use crate::{Identifier, Link, Links};
+
+        // rewrites people ids and rebases links, remembering where each identifier was
+        #[derive(Default)]
+        struct Rewrite {
+            identifiers: Vec<String>,
+            resources: Vec<String>,
+        }
+
+        impl Visitor<super::GenericResource> for Rewrite {
+            fn visit_primary(&mut self, pointer: &str, res: &mut super::GenericResource) {
+                self.resources.push(format!("{} {}", pointer, res.typ));
+            }
+
+            fn visit_included(&mut self, pointer: &str, res: &mut super::GenericResource) {
+                self.resources.push(format!("{} {}", pointer, res.typ));
+                res.attributes = json!({});
+            }
+
+            fn visit_identifier(&mut self, pointer: &str, identifier: &mut Identifier) {
+                self.identifiers.push(pointer.to_owned());
+                if identifier.typ == "people" {
+                    identifier.id = format!("p-{}", identifier.id).into();
+                }
+            }
+
+            fn visit_links(&mut self, _: &str, links: &mut Links) {
+                for link in links.values_mut() {
+                    *link = Link::Url(format!("https://api.example.com{}", link.href()));
+                }
+            }
+        }
+
+        let mut doc: GenericDocument = serde_json::from_value(json!({
+            "data": [{
+                "type": "articles",
+                "id": "1",
+                "relationships": {
+                    "author": {"data": {"type": "people", "id": "9"}},
+                    "tags/all": {"data": [{"type": "tags", "id": "t"}]}
+                },
+                "links": {"self": "/articles/1"}
+            }],
+            "included": [{"type": "people", "id": "9", "attributes": {"email": "x"}}],
+            "links": {"self": "/articles"}
+        }))
+        .unwrap();
+        let mut visitor = Rewrite::default();
+        doc.walk(&mut visitor);
+        assert_eq!(
+            visitor.identifiers,
+            [
+                "/data/0/relationships/author/data",
+                "/data/0/relationships/tags~1all/data/0"
+            ]
+        );
+        assert_eq!(
+            visitor.resources,
+            ["/data/0 articles", "/included/0 people"]
+        );
+        let out = serde_json::to_value(&doc).unwrap();
+        assert_eq!(
+            out["data"][0]["relationships"]["author"]["data"]["id"],
+            "p-9"
+        );
+        assert_eq!(
+            out["data"][0]["links"]["self"],
+            "https://api.example.com/articles/1"
+        );
+        assert_eq!(out["links"]["self"], "https://api.example.com/articles");
+        assert_eq!(out["included"][0]["attributes"], json!({}));
+    }
+
+    #[test]
+    fn test_pointer() {
+        use super::Node;
+
+        let doc: GenericDocument = serde_json::from_value(json!({
+            "data": [{
+                "type": "articles",
+                "id": "1",
+                "attributes": {"title": "a", "tags": ["x", "y"], "a/b": 1},
+                "relationships": {
+                    "author": {"data": {"type": "people", "id": "9"}},
+                    "comments": {"data": [{"type": "comments", "id": "5"}]}
+                },
+                "links": {"self": "/articles/1"}
+            }],
+            "meta": {"total": 1}
+        }))
+        .unwrap();
+        let Some(Node::Id(id)) = doc.pointer("/data/0/relationships/author/data/id") else {
+            panic!("expected an id");
+        };
+        assert_eq!(id.as_str(), "9");
+        let Some(Node::Identifier(comment)) = doc.pointer("/data/0/relationships/comments/data/0")
+        else {
+            panic!("expected an identifier");
+        };
+        assert_eq!(comment.typ, "comments");
+        assert!(matches!(
+            doc.pointer("/data/0/attributes/tags/1"),
+            Some(Node::Value(value)) if value == "y"
+        ));
+        assert!(matches!(
+            doc.pointer("/data/0/attributes/a~1b"),
+            Some(Node::Value(_))
+        ));
+        assert!(matches!(
+            doc.pointer("/data/0/links/self"),
+            Some(Node::Link(_))
+        ));
+        assert!(matches!(doc.pointer("/meta/total"), Some(Node::Value(_))));
+        assert!(doc.pointer("/data/01").is_none());
+        assert!(doc.pointer("/data/0/relationships/editor").is_none());
+        assert!(doc.pointer("data").is_none());
+
+        assert_eq!(
+            doc.pointer_of(Node::Identifier(comment)).unwrap(),
+            "/data/0/relationships/comments/data/0"
+        );
+        assert_eq!(
+            doc.pointer_of(Node::Id(id)).unwrap(),
+            "/data/0/relationships/author/data/id"
+        );
+        let title = doc.primary().next().unwrap().attribute("title").unwrap();
+        assert_eq!(
+            doc.pointer_of(Node::Value(title)).unwrap(),
+            "/data/0/attributes/title"
+        );
+        let elsewhere = json!("a");
+        assert!(doc.pointer_of(Node::Value(&elsewhere)).is_none());
+        for pointer in [
+            "/data/0",
+            "/data/0/relationships/author",
+            "/data/0/attributes/a~1b",
+        ] {
+            let node = doc.pointer(pointer).unwrap();
+            assert_eq!(doc.pointer_of(node).unwrap(), pointer);
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let first: GenericDocument = serde_json::from_value(json!({
+            "data": {"type": "articles", "id": "1"},
+            "included": [{"type": "people", "id": "9", "attributes": {"name": "first"}}],
+            "meta": {"total": 1, "source": "a"},
+            "links": {"self": "/a"}
+        }))
+        .unwrap();
+        let second: GenericDocument = serde_json::from_value(json!({
+            "data": [{"type": "articles", "id": "2"}, {"type": "articles", "id": "1"}],
+            "included": [
+                {"type": "people", "id": "9", "attributes": {"name": "second"}},
+                {"type": "people", "id": "10"}
+            ],
+            "meta": {"source": "b"},
+            "links": {"self": "/b", "next": "/b?page=2"}
+        }))
+        .unwrap();
+        let out = serde_json::to_value(first.merge(second)).unwrap();
+        assert_eq!(
+            out["data"],
+            json!([{"type": "articles", "id": "1"}, {"type": "articles", "id": "2"}])
+        );
+        assert_eq!(out["included"].as_array().unwrap().len(), 2);
+        assert_eq!(out["included"][0]["attributes"]["name"], "first");
+        assert_eq!(out["meta"], json!({"total": 1, "source": "b"}));
+        assert_eq!(out["links"], json!({"self": "/a", "next": "/b?page=2"}));
+
+        let empty: GenericDocument = serde_json::from_value(json!({"data": null})).unwrap();
+        let one: GenericDocument =
+            serde_json::from_value(json!({"data": {"type": "articles", "id": "1"}})).unwrap();
+        let merged = empty.merge(one.clone());
+        assert_eq!(merged, one);
+    }
+
+    #[test]
+    fn test_diff() {
+        use super::GenericResource;
+
+        let before: GenericResource = serde_json::from_value(json!({
+            "type": "articles",
+            "id": "1",
+            "attributes": {"title": "a", "body": "text", "draft": true},
+            "relationships": {
+                "author": {"data": {"type": "people", "id": "9"}},
+                "tags": {"data": [{"type": "tags", "id": "x"}]},
+                "comments": {"links": {"related": "/articles/1/comments"}}
+            }
+        }))
+        .unwrap();
+        let after: GenericResource = serde_json::from_value(json!({
+            "type": "articles",
+            "id": "1",
+            "attributes": {"title": "b", "body": "text"},
+            "relationships": {
+                "author": {"data": {"type": "people", "id": "9"}},
+                "tags": {"data": [{"type": "tags", "id": "x"}, {"type": "tags", "id": "y"}]},
+                "comments": {"links": {"related": "/articles/1/comments"}}
+            }
+        }))
+        .unwrap();
+        let patch = serde_json::to_value(before.diff(&after)).unwrap();
+        assert_eq!(
+            patch,
+            json!({
+                "id": "1",
+                "type": "articles",
+                "attributes": {"title": "b", "draft": null},
+                "relationships": {
+                    "tags": {"data": [{"type": "tags", "id": "x"}, {"type": "tags", "id": "y"}]}
+                }
+            })
+        );
+        let unchanged = before.diff(&before);
+        assert!(unchanged.attributes.is_empty() && unchanged.relationships.is_none());
+
+        let typed = |title: &str| ResourceResponse {
+            id: Identifier {
+                id: "1".into(),
+                typ: "articles".into(),
+            },
+            attributes: json!({"title": title, "body": "text"}),
+            relationships: None,
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        };
+        let patch = typed("a").diff(&typed("b")).unwrap();
+        assert_eq!(
+            serde_json::to_value(patch.attributes).unwrap(),
+            json!({"title": "b"})
+        );
+    }
+}
@@ -0,0 +1,244 @@
+// Binary encodings of documents, for service-to-service traffic where JSON's overhead matters. The
+// documents are the same, only written as MessagePack (application/vnd.api+msgpack, with the
+// msgpack feature) or CBOR (application/vnd.api+cbor, with the cbor feature). The JsonApi
+// extractor reads request bodies in either, and Response answers in one if the Accept header
+// prefers it.
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{media::JSONAPI_MEDIA_TYPE, Error};
+
+#[cfg(feature = "msgpack")]
+pub const MSGPACK_MEDIA_TYPE: &str = "application/vnd.api+msgpack";
+#[cfg(feature = "cbor")]
+pub const CBOR_MEDIA_TYPE: &str = "application/vnd.api+cbor";
+
+// BINARY is the binary encodings enabled
+const BINARY: &[Encoding] = &[
+    #[cfg(feature = "msgpack")]
+    Encoding::MessagePack,
+    #[cfg(feature = "cbor")]
+    Encoding::Cbor,
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Json,
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+impl Encoding {
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            Encoding::Json => JSONAPI_MEDIA_TYPE,
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => MSGPACK_MEDIA_TYPE,
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => CBOR_MEDIA_TYPE,
+        }
+    }
+
+    // from_media_type gives the binary encoding a media type stands for, ignoring parameters.
+    // Anything else is None, JSON included
+    pub fn from_media_type(value: &str) -> Option<Encoding> {
+        let essence = value.split(';').next().unwrap_or_default().trim();
+        BINARY
+            .iter()
+            .copied()
+            .find(|encoding| essence.eq_ignore_ascii_case(encoding.media_type()))
+    }
+
+    // from_accept picks the encoding of a response from an Accept header: a binary one only if
+    // it's weighted above everything else the client accepts, so JSON stays the default
+    pub fn from_accept(value: Option<&str>) -> Encoding {
+        let mut best = (0.0, Encoding::Json);
+        for entry in value.into_iter().flat_map(|value| value.split(',')) {
+            let Some(encoding) = Encoding::from_media_type(entry) else {
+                let weight = weight(entry);
+                if weight >= best.0 {
+                    best = (weight, Encoding::Json);
+                }
+                continue;
+            };
+            let weight = weight(entry);
+            if weight > best.0 {
+                best = (weight, encoding);
+            }
+        }
+        best.1
+    }
+
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, Error> {
+        let encoded = match self {
+            Encoding::Json => serde_json::to_vec(value).map_err(|err| err.to_string()),
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => rmp_serde::to_vec_named(value).map_err(|err| err.to_string()),
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => {
+                let mut buf = Vec::new();
+                ciborium::into_writer(value, &mut buf)
+                    .map(|_| buf)
+                    .map_err(|err| err.to_string())
+            }
+        };
+        encoded.map_err(|err| {
+            Error::new_internal_error(&format!(
+                "the document couldn't be encoded as {}: {}",
+                self.media_type(),
+                err
+            ))
+        })
+    }
+
+    // decode reads a document, failing with 400 if it isn't one
+    pub fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T, Error> {
+        let decoded = match self {
+            Encoding::Json => serde_json::from_slice(body).map_err(|err| err.to_string()),
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => rmp_serde::from_slice(body).map_err(|err| err.to_string()),
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => ciborium::from_reader(body).map_err(|err| err.to_string()),
+        };
+        decoded.map_err(|err| {
+            Error::new_bad_request(&format!(
+                "the body isn't a valid {} document: {}",
+                self.media_type(),
+                err
+            ))
+        })
+    }
+}
+
+// weight is the q parameter of an Accept entry, 1 if it has none
+pub(crate) fn weight(entry: &str) -> f32 {
+    entry
+        .split(';')
+        .skip(1)
+        .find_map(|param| param.trim().strip_prefix("q="))
+        .map_or(1.0, |q| q.trim().parse().unwrap_or(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_derive::{Deserialize, Serialize};
+
+    use super::Encoding;
+    use crate::{IntoResponse, Request, ResourceResponse, Response};
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    struct Article {
+        title: String,
+    }
+
+    impl IntoResponse for Article {
+        type Attributes = Article;
+
+        fn into_response(self) -> ResourceResponse<Article> {
+            ResourceResponse {
+                id: crate::Identifier {
+                    id: "1".into(),
+                    typ: "articles".into(),
+                },
+                attributes: self,
+                relationships: None,
+                meta: None,
+                links: None,
+                extensions: Default::default(),
+            }
+        }
+    }
+
+    fn round_trip(encoding: Encoding) {
+        let response =
+            Response::<Article, ()>::with_capacity(1, 0).push(Article { title: "a".into() });
+        let body = encoding.encode(&response).unwrap();
+        let decoded: Response<Article, ()> = encoding.decode(&body).unwrap();
+        assert_eq!(
+            serde_json::to_value(decoded).unwrap(),
+            serde_json::to_value(response).unwrap()
+        );
+        let req = serde_json::json!({"data": {"type": "articles", "attributes": {"title": "b"}}});
+        let body = encoding.encode(&req).unwrap();
+        let req: Request<Article> = encoding.decode(&body).unwrap();
+        assert_eq!(req.data.attributes.title, "b");
+        assert!(encoding.decode::<Request<Article>>(b"\x01").is_err());
+    }
+
+    #[test]
+    fn test_encodings() {
+        round_trip(Encoding::Json);
+        #[cfg(feature = "msgpack")]
+        round_trip(Encoding::MessagePack);
+        #[cfg(feature = "cbor")]
+        round_trip(Encoding::Cbor);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(Encoding::from_accept(None), Encoding::Json);
+        assert_eq!(
+            Encoding::from_accept(Some("application/vnd.api+msgpack")),
+            Encoding::MessagePack
+        );
+        assert_eq!(
+            Encoding::from_accept(Some(
+                "application/vnd.api+json, application/vnd.api+msgpack"
+            )),
+            Encoding::Json
+        );
+        assert_eq!(
+            Encoding::from_accept(Some(
+                "application/vnd.api+json;q=0.5, application/vnd.api+msgpack"
+            )),
+            Encoding::MessagePack
+        );
+        assert_eq!(
+            Encoding::from_media_type("application/vnd.api+msgpack; charset=x"),
+            Some(Encoding::MessagePack)
+        );
+        assert_eq!(
+            Encoding::from_media_type(crate::media::JSONAPI_MEDIA_TYPE),
+            None
+        );
+    }
+
+    #[cfg(all(feature = "msgpack", feature = "actixweb"))]
+    #[test]
+    fn test_actix() {
+        use actix_web::{body::MessageBody, test, FromRequest, Responder};
+
+        use crate::JsonApi;
+
+        let req = serde_json::json!({"data": {"type": "articles", "attributes": {"title": "b"}}});
+        let (http, mut payload) = test::TestRequest::post()
+            .insert_header(("content-type", super::MSGPACK_MEDIA_TYPE))
+            .set_payload(Encoding::MessagePack.encode(&req).unwrap())
+            .to_http_parts();
+        let req = actix_web::rt::System::new()
+            .block_on(JsonApi::<Request<Article>>::from_request(
+                &http,
+                &mut payload,
+            ))
+            .ok()
+            .unwrap();
+        assert_eq!(req.into_inner().data.attributes.title, "b");
+
+        let http = test::TestRequest::get()
+            .insert_header(("accept", super::MSGPACK_MEDIA_TYPE))
+            .to_http_request();
+        let res = Response::<Article, ()>::with_capacity(1, 0)
+            .push(Article { title: "a".into() })
+            .respond_to(&http);
+        assert_eq!(
+            res.headers().get("content-type").unwrap(),
+            super::MSGPACK_MEDIA_TYPE
+        );
+        let body = res.into_body().try_into_bytes().ok().unwrap();
+        let res: Response<Article, ()> = Encoding::MessagePack.decode(&body).unwrap();
+        assert_eq!(serde_json::to_value(res).unwrap()["data"][0]["id"], "1");
+    }
+}
@@ -0,0 +1,384 @@
+// Error documents answered as the app's JsonApiConfig says: translated with its localizer (see
+// locale), redacted if it says so (see redaction) and with the status its status policy decides
+// (see status). The JsonApi extractor
+// answers with a Rejection, which carries the config's policy, and documents handlers respond with
+// are finished by the Responder, both having the request at hand. Errors handlers return are
+// answered by their error_response(), which has neither, so those take the ErrorDocuments
+// middleware, e.g. `App::new().wrap(ErrorDocuments)`. Without it they're answered as they are,
+// which is logged once (with the tracing feature).
+use actix_web::{
+    body::{BoxBody, EitherBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header::RETRY_AFTER, StatusCode},
+    HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder, ResponseError,
+};
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use crate::{locale, redaction, status, Error, Errors, Response};
+
+// Rejection is what the JsonApi extractor answers a request it won't hand over with: its errors,
+// translated into the language of the request and redacted, and the status policy of the config
+pub struct Rejection {
+    pub errors: Vec<Error>,
+    policy: Option<Arc<dyn status::StatusPolicy>>,
+}
+
+impl Rejection {
+    pub(crate) fn new(req: &HttpRequest, mut errors: Vec<Error>) -> Rejection {
+        locale::localize_request(req, errors.iter_mut());
+        let config = crate::JsonApiConfig::of(req);
+        let errors = errors.into_iter();
+        Rejection {
+            errors: errors
+                .map(|err| redaction::redact_internal(&config, err))
+                .collect(),
+            policy: config.status_policy.clone(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rejection")
+            .field("errors", &self.errors)
+            .finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Errors(self.errors.clone()).fmt(f)
+    }
+}
+
+impl std::error::Error for Rejection {}
+
+impl ResponseError for Rejection {
+    fn status_code(&self) -> StatusCode {
+        status::status_of(&self.errors, self.policy.as_deref())
+    }
+
+    fn error_response(&self) -> HttpResponse<BoxBody> {
+        error_document(self.status_code(), &self.errors)
+    }
+}
+
+// error_document answers with errors, telling clients when to come back if the first error does
+pub(crate) fn error_document(status: StatusCode, errors: &[Error]) -> HttpResponse<BoxBody> {
+    let mut res = HttpResponseBuilder::new(status);
+    if let Some(secs) = errors.first().and_then(Error::retry_after) {
+        res.insert_header((RETRY_AFTER, secs));
+    }
+    res.json(Response::<(), ()>::from(errors.to_vec()))
+}
+
+// Wrapped marks the requests ErrorDocuments sees
+#[derive(Clone, Copy)]
+struct Wrapped;
+
+// warn_unwrapped warns (once) that JsonApiConfig has a localizer, status policy or redaction which
+// errors handlers return escape, the request not having gone through ErrorDocuments
+pub(crate) fn warn_unwrapped(req: &HttpRequest) {
+    let configured = req
+        .app_data::<crate::JsonApiConfig>()
+        .is_some_and(|config| {
+            config.localizer.is_some() || config.status_policy.is_some() || config.redacted
+        });
+    if configured && !req.extensions().contains::<Wrapped>() {
+        #[cfg(feature = "tracing")]
+        {
+            static WARNED: std::sync::Once = std::sync::Once::new();
+            WARNED.call_once(|| {
+                tracing::warn!(
+                    "JsonApiConfig has a localizer, status policy or redaction, but the app isn't \
+                     wrapped in errors::ErrorDocuments: errors handlers return are answered as \
+                     they are"
+                )
+            });
+        }
+    }
+}
+
+// ErrorDocuments is the middleware answering the errors handlers return as the JsonApiConfig of
+// the app says
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ErrorDocuments;
+
+impl<S, B> Transform<S, ServiceRequest> for ErrorDocuments
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = ErrorDocumentsService<S>;
+    type InitError = ();
+    type Future = std::future::Ready<Result<ErrorDocumentsService<S>, ()>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(ErrorDocumentsService { service }))
+    }
+}
+
+pub struct ErrorDocumentsService<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for ErrorDocumentsService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = ErrorDocumentsFut<S::Future>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        req.extensions_mut().insert(Wrapped);
+        ErrorDocumentsFut {
+            fut: Box::pin(self.service.call(req)),
+        }
+    }
+}
+
+pub struct ErrorDocumentsFut<F> {
+    fut: Pin<Box<F>>,
+}
+
+impl<F, B> Future for ErrorDocumentsFut<F>
+where
+    F: Future<Output = Result<ServiceResponse<B>, actix_web::Error>>,
+    B: MessageBody,
+{
+    type Output = Result<ServiceResponse<EitherBody<B>>, actix_web::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let res = match self.fut.as_mut().poll(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+            Poll::Ready(Ok(res)) => res,
+        };
+        // rejections of the extractor were answered with the config already
+        let mut errors = match res.response().error() {
+            Some(err) => match (err.as_error::<Error>(), err.as_error::<Errors>()) {
+                (Some(err), _) => vec![err.clone()],
+                (None, Some(errors)) => errors.0.clone(),
+                (None, None) => return Poll::Ready(Ok(res.map_into_left_body())),
+            },
+            None => return Poll::Ready(Ok(res.map_into_left_body())),
+        };
+        let localized = locale::localize_request(res.request(), errors.iter_mut());
+        let config = crate::JsonApiConfig::of(res.request());
+        let redacted = config.redacted
+            && errors
+                .iter()
+                .any(|err| err.status == crate::ErrorStatus::InternalError);
+        let errors: Vec<_> = errors
+            .into_iter()
+            .map(|err| redaction::redact_internal(&config, err))
+            .collect();
+        let status = status::request_status(res.request(), &errors);
+        if !localized && !redacted && status == res.status() {
+            return Poll::Ready(Ok(res.map_into_left_body()));
+        }
+        let (http, _) = res.into_parts();
+        let res = ServiceResponse::new(http, error_document(status, &errors));
+        Poll::Ready(Ok(res.map_into_right_body()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, web, App, HttpResponse};
+
+    use super::ErrorDocuments;
+    use crate::{
+        locale::Translations,
+        status::{MostGeneral, StatusPolicy},
+        Error, ErrorStatus, JsonApi, JsonApiConfig, Request, Response,
+    };
+
+    fn translations() -> Translations {
+        Translations::new().text("Not Found", "de", "nicht gefunden", None)
+    }
+
+    // upstream makes errors with an "upstream" code 502s
+    fn upstream(errors: &[Error]) -> Option<StatusCode> {
+        if errors
+            .iter()
+            .any(|err| err.code.as_deref() == Some("upstream"))
+        {
+            Some(StatusCode::BAD_GATEWAY)
+        } else {
+            MostGeneral.status(errors)
+        }
+    }
+
+    async fn missing() -> Result<HttpResponse, Error> {
+        Err(Error::new_not_found("no such article"))
+    }
+
+    async fn failing() -> Result<HttpResponse, Error> {
+        let mut err = Error::new_internal_error("upstream failed");
+        err.code = Some("upstream".into());
+        Err(err)
+    }
+
+    async fn document() -> Response<(), ()> {
+        Error::new_not_found("no such article").into()
+    }
+
+    async fn mixed() -> Response<(), ()> {
+        vec![Error::new_not_found("a"), Error::new_forbidden("b")].into()
+    }
+
+    async fn create(_: JsonApi<Request<serde_json::Value>>) -> HttpResponse {
+        HttpResponse::Created().finish()
+    }
+
+    // answer is the status and first error title of a response
+    async fn answer<B: actix_web::body::MessageBody>(
+        res: actix_web::dev::ServiceResponse<B>,
+    ) -> (StatusCode, serde_json::Value) {
+        use actix_web::test;
+
+        let status = res.status();
+        let doc: serde_json::Value = test::read_body_json(res).await;
+        (status, doc["errors"][0]["title"].clone())
+    }
+
+    #[test]
+    fn test_error_documents() {
+        use actix_web::test;
+
+        actix_web::rt::System::new().block_on(async {
+            let config = JsonApiConfig::default()
+                .localizer(translations())
+                .status_policy(upstream);
+            let app = test::init_service(
+                App::new()
+                    .app_data(config)
+                    .wrap(ErrorDocuments)
+                    .route("/missing", web::get().to(missing))
+                    .route("/failing", web::get().to(failing))
+                    .route("/document", web::get().to(document))
+                    .route("/mixed", web::get().to(mixed)),
+            )
+            .await;
+            for (uri, language, status, title) in [
+                ("/missing", "de", 404, "nicht gefunden"),
+                ("/missing", "it", 404, "no such article"),
+                ("/document", "de-DE", 404, "nicht gefunden"),
+                ("/failing", "de", 502, "upstream failed"),
+                ("/mixed", "de", 400, "nicht gefunden"),
+            ] {
+                let req = test::TestRequest::get()
+                    .uri(uri)
+                    .insert_header(("accept-language", language))
+                    .to_request();
+                let (got, got_title) = answer(test::call_service(&app, req).await).await;
+                assert_eq!(got.as_u16(), status, "{}", uri);
+                assert_eq!(got_title, title);
+            }
+        });
+    }
+
+    #[test]
+    fn test_redaction() {
+        use actix_web::test;
+        use std::sync::{Arc, Mutex};
+
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let report = {
+            let reported = reported.clone();
+            move |err: &Error| reported.lock().unwrap().push(err.title.clone())
+        };
+        actix_web::rt::System::new().block_on(async {
+            let config = JsonApiConfig::default().redacted(true).reporter(report);
+            let app = test::init_service(
+                App::new()
+                    .app_data(config)
+                    .wrap(ErrorDocuments)
+                    .route("/missing", web::get().to(missing))
+                    .route("/failing", web::get().to(failing))
+                    .route("/articles", web::post().to(create)),
+            )
+            .await;
+            let req = test::TestRequest::get().uri("/failing").to_request();
+            let (status, title) = answer(test::call_service(&app, req).await).await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+            assert_eq!(title, "internal server error");
+            let req = test::TestRequest::post()
+                .uri("/articles")
+                .insert_header(("content-type", "application/vnd.api+json"))
+                .set_payload("{\"data\":")
+                .to_request();
+            let (status, title) = answer(test::call_service(&app, req).await).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(title, "the request body isn't a valid document");
+            // errors meant for clients are answered as they are
+            let req = test::TestRequest::get().uri("/missing").to_request();
+            let (_, title) = answer(test::call_service(&app, req).await).await;
+            assert_eq!(title, "no such article");
+            let reported = reported.lock().unwrap();
+            assert_eq!(reported.len(), 2);
+            assert_eq!(reported[0], "upstream failed");
+        });
+    }
+
+    #[test]
+    fn test_unwrapped() {
+        use actix_web::test;
+
+        // a policy answering bodies of the wrong type with 400
+        let policy = |errors: &[Error]| {
+            let unsupported = errors
+                .iter()
+                .any(|err| err.status == ErrorStatus::UnsupportedMediaType);
+            unsupported.then_some(StatusCode::BAD_REQUEST)
+        };
+        let translations = translations().text("Unsupported Media Type", "de", "kein JSON", None);
+        actix_web::rt::System::new().block_on(async {
+            let config = JsonApiConfig::default()
+                .localizer(translations)
+                .status_policy(policy);
+            let app = test::init_service(
+                App::new()
+                    .app_data(config)
+                    .route("/missing", web::get().to(missing))
+                    .route("/failing", web::get().to(failing))
+                    .route("/articles", web::post().to(create)),
+            )
+            .await;
+            // errors handlers return are answered as they are
+            let req = test::TestRequest::get()
+                .uri("/missing")
+                .insert_header(("accept-language", "de"))
+                .to_request();
+            let (status, title) = answer(test::call_service(&app, req).await).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            assert_eq!(title, "no such article");
+            let req = test::TestRequest::get().uri("/failing").to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+            // rejections of the extractor are answered with the config all the same
+            let req = test::TestRequest::post()
+                .uri("/articles")
+                .insert_header(("content-type", "text/plain"))
+                .insert_header(("accept-language", "de"))
+                .set_payload("{}")
+                .to_request();
+            let (status, title) = answer(test::call_service(&app, req).await).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(title, "kein JSON");
+        });
+    }
+}
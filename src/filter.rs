@@ -0,0 +1,285 @@
+// Filtering through the `filter` family of query parameters. The spec leaves the strategy to the
+// server; here `filter[field]=value` matches fields equal to the value, and
+// `filter[field][op]=value` applies one of the operators below.
+use std::{cmp::Ordering, str::FromStr};
+
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Lte,
+    Gt,
+    Gte,
+    Contains,
+    // In matches any of a comma separated list of values
+    In,
+}
+
+impl Operator {
+    pub fn parse(name: &str) -> Option<Operator> {
+        Some(match name {
+            "eq" => Operator::Eq,
+            "ne" => Operator::Ne,
+            "lt" => Operator::Lt,
+            "lte" => Operator::Lte,
+            "gt" => Operator::Gt,
+            "gte" => Operator::Gte,
+            "contains" => Operator::Contains,
+            "in" => Operator::In,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operator::Eq => "eq",
+            Operator::Ne => "ne",
+            Operator::Lt => "lt",
+            Operator::Lte => "lte",
+            Operator::Gt => "gt",
+            Operator::Gte => "gte",
+            Operator::Contains => "contains",
+            Operator::In => "in",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterCondition {
+    pub field: String,
+    pub op: Operator,
+    pub value: String,
+}
+
+impl FilterCondition {
+    // parameter is the query parameter the condition came from, for errors to point at
+    pub fn parameter(&self) -> String {
+        format!("filter[{}]", self.field)
+    }
+}
+
+// FilterSpec is the parsed filter parameters. An item has to meet every condition
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterSpec(pub Vec<FilterCondition>);
+
+impl FilterSpec {
+    pub fn from_query(query: &str) -> Result<FilterSpec, Error> {
+        let mut conditions = Vec::new();
+        for (name, value) in form_urlencoded::parse(query.as_bytes()) {
+            let Some(key) = name.strip_prefix("filter[") else {
+                continue;
+            };
+            let (field, op) = match key.split_once("][") {
+                Some((field, op)) => (field, op.strip_suffix(']')),
+                None => (key.strip_suffix(']').unwrap_or_default(), Some("eq")),
+            };
+            let op = op.and_then(Operator::parse).ok_or_else(|| {
+                Error::new_bad_request(&format!("unknown filter operator in {}", name))
+                    .with_parameter(&name)
+            })?;
+            if field.is_empty() {
+                return Err(
+                    Error::new_bad_request("filter fields can't be empty").with_parameter(&name)
+                );
+            }
+            conditions.push(FilterCondition {
+                field: field.to_owned(),
+                op,
+                value: value.into_owned(),
+            });
+        }
+        Ok(FilterSpec(conditions))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // apply keeps the items meeting every condition. Fields the type can't be filtered by, and
+    // values which can't be compared with the field, are refused with a 400
+    pub fn apply<T: Filterable>(&self, items: &mut Vec<T>) -> Result<(), Error> {
+        let accessors = self
+            .0
+            .iter()
+            .map(|condition| {
+                T::filter_value(&condition.field).ok_or_else(|| {
+                    Error::new_bad_request(&format!("can't filter by '{}'", condition.field))
+                        .with_parameter(&condition.parameter())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut err = None;
+        items.retain(|item| {
+            if err.is_some() {
+                return true;
+            }
+            for (condition, accessor) in self.0.iter().zip(&accessors) {
+                match accessor(item).matches(condition.op, &condition.value) {
+                    Some(true) => {}
+                    Some(false) => return false,
+                    None => {
+                        err = Some(
+                            Error::new_bad_request(&format!(
+                                "'{}' can't be used to filter {}",
+                                condition.value, condition.field
+                            ))
+                            .with_parameter(&condition.parameter()),
+                        );
+                        return true;
+                    }
+                }
+            }
+            true
+        });
+        match err {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+// FieldFilter is a condition on one field of a typed filter set (see the JsonApiFilter derive),
+// its value parsed into the field's type. `in` gives one value per item of its list
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldFilter<T> {
+    pub op: Operator,
+    pub values: Vec<T>,
+}
+
+impl<T: FromStr> FieldFilter<T> {
+    pub fn parse(condition: &FilterCondition) -> Result<FieldFilter<T>, Error> {
+        let values = match condition.op {
+            Operator::In => condition.value.split(',').collect(),
+            _ => vec![condition.value.as_str()],
+        };
+        let values = values
+            .into_iter()
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    Error::new_bad_request(&format!(
+                        "'{}' can't be used to filter {}",
+                        value, condition.field
+                    ))
+                    .with_parameter(&condition.parameter())
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(FieldFilter {
+            op: condition.op,
+            values,
+        })
+    }
+}
+
+// FilterValue is the value of a field as filters see it. Values from the query are parsed into
+// the same kind before they are compared
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl FilterValue {
+    // matches applies an operator, None if the value can't be compared with the field
+    pub fn matches(&self, op: Operator, value: &str) -> Option<bool> {
+        match (self, op) {
+            (_, Operator::In) => {
+                for value in value.split(',') {
+                    if self.matches(Operator::Eq, value)? {
+                        return Some(true);
+                    }
+                }
+                Some(false)
+            }
+            (FilterValue::Str(s), Operator::Contains) => Some(s.contains(value)),
+            (_, Operator::Contains) => None,
+            // null is only ever equal to null, and has no order
+            (FilterValue::Null, Operator::Eq) => Some(value == "null"),
+            (FilterValue::Null, Operator::Ne) => Some(value != "null"),
+            (FilterValue::Null, _) => Some(false),
+            (_, op) => {
+                let ordering = self.compare(value)?;
+                Some(match op {
+                    Operator::Eq => ordering.is_eq(),
+                    Operator::Ne => ordering.is_ne(),
+                    Operator::Lt => ordering.is_lt(),
+                    Operator::Lte => ordering.is_le(),
+                    Operator::Gt => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                })
+            }
+        }
+    }
+
+    fn compare(&self, value: &str) -> Option<Ordering> {
+        match self {
+            FilterValue::Bool(b) => Some(b.cmp(&value.parse().ok()?)),
+            FilterValue::Int(i) => Some(i.cmp(&value.parse().ok()?)),
+            FilterValue::Float(f) => f.partial_cmp(&value.parse().ok()?),
+            FilterValue::Str(s) => Some(s.as_str().cmp(value)),
+            FilterValue::Null => None,
+        }
+    }
+}
+
+// Filterable maps the filter field names of a type to its values, for filtering collections held
+// in memory where pushing filters down to the database isn't worth it
+pub trait Filterable {
+    // filter_value gives the accessor of a field, or None if it can't be filtered by
+    fn filter_value(field: &str) -> Option<fn(&Self) -> FilterValue>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FilterSpec, FilterValue, Filterable, Operator};
+
+    #[derive(Debug, PartialEq)]
+    struct Article {
+        title: &'static str,
+        likes: i64,
+    }
+
+    impl Filterable for Article {
+        fn filter_value(field: &str) -> Option<fn(&Self) -> FilterValue> {
+            match field {
+                "title" => Some(|a| FilterValue::Str(a.title.into())),
+                "likes" => Some(|a| FilterValue::Int(a.likes)),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let article = |title, likes| Article { title, likes };
+        let all = || vec![article("rust", 5), article("rusty", 1), article("go", 7)];
+
+        let spec =
+            FilterSpec::from_query("filter[title][contains]=rust&filter[likes][gte]=2").unwrap();
+        assert_eq!(spec.0[1].op, Operator::Gte);
+        let mut articles = all();
+        spec.apply(&mut articles).unwrap();
+        assert_eq!(articles, [article("rust", 5)]);
+
+        let mut articles = all();
+        let spec = FilterSpec::from_query("filter[title][in]=go,rusty&sort=title").unwrap();
+        spec.apply(&mut articles).unwrap();
+        assert_eq!(articles.len(), 2);
+
+        let spec = FilterSpec::from_query("filter[likes]=many").unwrap();
+        let err = spec.apply(&mut all()).unwrap_err();
+        assert_eq!(
+            err.source.unwrap().parameter.as_deref(),
+            Some("filter[likes]")
+        );
+        let spec = FilterSpec::from_query("filter[author]=x").unwrap();
+        assert!(spec.apply(&mut all()).is_err());
+        assert!(FilterSpec::from_query("filter[likes][near]=1").is_err());
+    }
+}
@@ -0,0 +1,269 @@
+// Conversion between resources and async-graphql values, for services serving the same types over
+// GraphQL and JSON:API. A resource becomes an object of its id and attributes, with a field per
+// relationship: the related object (just its id and __typename) for a to-one relationship, a
+// connection of them for a to-many. ObjectMapping reads input objects back into requests.
+use async_graphql::Value;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use crate::{
+    Error, Extensions, Identifier, Relationship, RelationshipData, Relationships, Request,
+    ResourceRequest, ResourceResponse, ID,
+};
+
+fn node(identifier: &Identifier) -> serde_json::Value {
+    json!({"id": identifier.id, "__typename": identifier.typ})
+}
+
+// connection lays out identifiers as a connection in the shape of async-graphql's own, their ids
+// serving as cursors
+fn connection(identifiers: &[Identifier]) -> serde_json::Value {
+    let edges: Vec<serde_json::Value> = identifiers
+        .iter()
+        .map(|identifier| json!({"cursor": identifier.id, "node": node(identifier)}))
+        .collect();
+    json!({
+        "edges": edges,
+        "nodes": identifiers.iter().map(node).collect::<Vec<_>>(),
+        "pageInfo": {
+            "hasPreviousPage": false,
+            "hasNextPage": false,
+            "startCursor": identifiers.first().map(|identifier| &identifier.id),
+            "endCursor": identifiers.last().map(|identifier| &identifier.id),
+        },
+    })
+}
+
+// to_object turns a resource into a GraphQL object. Attributes which aren't an object (or map)
+// can't be fields, and are refused
+pub fn to_object<D: Serialize>(resource: ResourceResponse<D>) -> Result<Value, Error> {
+    let mut object = match serde_json::to_value(&resource.attributes) {
+        Ok(serde_json::Value::Object(fields)) => fields,
+        _ => {
+            return Err(Error::new_internal_error(&format!(
+                "the attributes of {} aren't an object",
+                resource.id.typ
+            )))
+        }
+    };
+    object.insert("id".into(), json!(resource.id.id));
+    object.insert("__typename".into(), json!(resource.id.typ));
+    for (name, rel) in resource.relationships.into_iter().flatten() {
+        let field = match rel.data {
+            None => serde_json::Value::Null,
+            Some(Relationship::ToOne(identifier)) => node(&identifier),
+            Some(Relationship::ToMany(identifiers)) => connection(&identifiers),
+        };
+        object.insert(name, field);
+    }
+    Value::from_json(serde_json::Value::Object(object))
+        .map_err(|err| Error::new_internal_error(&err.to_string()))
+}
+
+// ObjectMapping reads the input objects of a resource type: the fields it names as relationships
+// become relationship linkage, the id field the resource's id, and the others its attributes
+#[derive(Debug, Clone)]
+pub struct ObjectMapping {
+    typ: String,
+    relationships: BTreeMap<String, String>,
+}
+
+impl ObjectMapping {
+    pub fn new(typ: &str) -> ObjectMapping {
+        ObjectMapping {
+            typ: typ.to_owned(),
+            relationships: BTreeMap::new(),
+        }
+    }
+
+    // relationship names a field holding a relationship to resources of type `related`. Its value
+    // is an id, an object with an id, a list of either or a connection of such objects
+    pub fn relationship(mut self, name: &str, related: &str) -> Self {
+        self.relationships
+            .insert(name.to_owned(), related.to_owned());
+        self
+    }
+
+    // to_request reads an input object into a request, to be taken apart by FromRequest. Errors
+    // point at the member of the request the offending field would be
+    pub fn to_request<D: DeserializeOwned>(&self, value: Value) -> Result<Request<D>, Error> {
+        let mut fields = match value.into_json() {
+            Ok(serde_json::Value::Object(fields)) => fields,
+            _ => {
+                return Err(Error::new_bad_request(&format!(
+                    "{} input has to be an object",
+                    self.typ
+                ))
+                .with_pointer("/data"))
+            }
+        };
+        let id = match fields.remove("id") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(id) => Some(scalar_id(&id).ok_or_else(|| {
+                Error::new_bad_request("id has to be a string or number").with_pointer("/data/id")
+            })?),
+        };
+        fields.remove("__typename");
+        let mut relationships = Relationships::new();
+        for (name, related) in &self.relationships {
+            if let Some(field) = fields.remove(name) {
+                let data = linkage(related, field).ok_or_else(|| {
+                    Error::new_bad_request(&format!("'{}' isn't resource linkage", name))
+                        .with_pointer(&format!("/data/relationships/{}", name))
+                })?;
+                relationships.insert(
+                    name.clone(),
+                    RelationshipData {
+                        data,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+        let attributes =
+            serde_json::from_value(serde_json::Value::Object(fields)).map_err(|err| {
+                Error::new_unprocessable_entity(&err.to_string()).with_pointer("/data/attributes")
+            })?;
+        Ok(Request {
+            data: ResourceRequest {
+                id,
+                typ: self.typ.clone(),
+                attributes,
+                relationships: (!relationships.is_empty()).then_some(relationships),
+                extensions: Extensions::new(),
+            },
+            extensions: Extensions::new(),
+        })
+    }
+}
+
+fn scalar_id(value: &serde_json::Value) -> Option<ID> {
+    match value {
+        serde_json::Value::String(id) => Some(id.clone().into()),
+        serde_json::Value::Number(id) => Some(id.to_string().into()),
+        _ => None,
+    }
+}
+
+fn object_id(related: &str, value: &serde_json::Value) -> Option<Identifier> {
+    let id = match value {
+        serde_json::Value::Object(object) => scalar_id(object.get("id")?)?,
+        value => scalar_id(value)?,
+    };
+    Some(Identifier {
+        id,
+        typ: related.to_owned(),
+    })
+}
+
+// linkage reads a relationship field, None if it isn't linkage. A null one clears the relationship
+fn linkage(related: &str, value: serde_json::Value) -> Option<Option<Relationship>> {
+    let many = |values: &[serde_json::Value]| {
+        values
+            .iter()
+            .map(|value| object_id(related, value))
+            .collect::<Option<Vec<_>>>()
+            .map(|identifiers| Some(Relationship::ToMany(identifiers)))
+    };
+    match &value {
+        serde_json::Value::Null => Some(None),
+        serde_json::Value::Array(values) => many(values),
+        serde_json::Value::Object(object) => match (object.get("nodes"), object.get("edges")) {
+            (Some(serde_json::Value::Array(nodes)), _) => many(nodes),
+            (_, Some(serde_json::Value::Array(edges))) => {
+                let nodes: Option<Vec<serde_json::Value>> =
+                    edges.iter().map(|edge| edge.get("node").cloned()).collect();
+                many(&nodes?)
+            }
+            _ => object_id(related, &value).map(|id| Some(Relationship::ToOne(id))),
+        },
+        _ => object_id(related, &value).map(|id| Some(Relationship::ToOne(id))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Value;
+    use serde_derive::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::{to_object, ObjectMapping};
+    use crate::{Identifier, Relationship, RelationshipData, Relationships, ResourceResponse};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Article {
+        title: String,
+    }
+
+    #[test]
+    fn test_to_object() {
+        let mut relationships = Relationships::new();
+        relationships.insert(
+            "tags".into(),
+            RelationshipData {
+                data: Some(Relationship::ToMany(vec![Identifier {
+                    id: "3".into(),
+                    typ: "tags".into(),
+                }])),
+                ..Default::default()
+            },
+        );
+        let resource = ResourceResponse {
+            id: Identifier {
+                id: "1".into(),
+                typ: "articles".into(),
+            },
+            attributes: Article { title: "a".into() },
+            relationships: Some(relationships),
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        };
+        let object = to_object(resource).unwrap().into_json().unwrap();
+        assert_eq!(object["title"], "a");
+        assert_eq!(object["__typename"], "articles");
+        assert_eq!(
+            object["tags"]["edges"],
+            json!([{"cursor": "3", "node": {"id": "3", "__typename": "tags"}}])
+        );
+        assert_eq!(object["tags"]["pageInfo"]["endCursor"], "3");
+    }
+
+    #[test]
+    fn test_to_request() {
+        let mapping = ObjectMapping::new("articles")
+            .relationship("author", "people")
+            .relationship("tags", "tags");
+        let input = Value::from_json(json!({
+            "title": "a",
+            "author": "9",
+            "tags": {"nodes": [{"id": "3"}, {"id": 4}]}
+        }))
+        .unwrap();
+        let req = mapping.to_request::<Article>(input).unwrap();
+        assert_eq!(req.data.attributes, Article { title: "a".into() });
+        assert!(req.data.id.is_none());
+        let rels = req.data.relationships.unwrap();
+        assert_eq!(
+            rels["author"].data,
+            Some(Relationship::ToOne(Identifier {
+                id: "9".into(),
+                typ: "people".into()
+            }))
+        );
+        match &rels["tags"].data {
+            Some(Relationship::ToMany(tags)) => assert_eq!(tags[1].id, "4".into()),
+            _ => panic!("tags should be a to-many relationship"),
+        }
+
+        let input = Value::from_json(json!({"title": "a", "author": [true]})).unwrap();
+        let err = mapping.to_request::<Article>(input).err().unwrap();
+        assert_eq!(
+            err.source.unwrap().pointer.as_deref(),
+            Some("/data/relationships/author")
+        );
+        let input = Value::from_json(json!({"title": 1})).unwrap();
+        assert!(mapping.to_request::<Article>(input).is_err());
+    }
+}
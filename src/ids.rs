@@ -0,0 +1,845 @@
+// Id types beyond the plain ones FromID is implemented for, and the rules ids sent by clients are
+// held to. TypedId<R> tags an id with the resource it belongs to, so that an article's id can't be
+// used where an author's is expected, while still converting to and from an ID like the id it
+// wraps. CompositeId puts the parts of a composite key (e.g. of a legacy table) into a single id,
+// and Slug is a human-readable id such as "hello-world". ObfuscatedId hides integer database ids
+// behind short strings.
+#[cfg(feature = "base64")]
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Display},
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    str::FromStr,
+};
+
+use crate::{try_from_id, Error, FromID, Relationship, ResourceRequest, ID};
+
+// TypedId is an id of a resource R, which is only a marker: nothing is required of it, and it
+// needn't implement the traits TypedId does. The id itself is an I, an ID unless given
+pub struct TypedId<R, I = ID> {
+    id: I,
+    resource: PhantomData<fn() -> R>,
+}
+
+impl<R, I> TypedId<R, I> {
+    pub fn new(id: I) -> Self {
+        TypedId {
+            id,
+            resource: PhantomData,
+        }
+    }
+
+    pub fn get(&self) -> &I {
+        &self.id
+    }
+
+    pub fn into_inner(self) -> I {
+        self.id
+    }
+}
+
+impl<R, I: FromID> FromID for TypedId<R, I> {
+    fn from_id(id: ID) -> Result<Self, Error> {
+        I::from_id(id).map(TypedId::new)
+    }
+}
+
+try_from_id!({R, I: FromID} TypedId<R, I>);
+
+// ID being From TypedId makes typed ids relationship linkage too (see IntoRelationship)
+impl<R, I> From<TypedId<R, I>> for ID
+where
+    ID: From<I>,
+{
+    fn from(id: TypedId<R, I>) -> ID {
+        id.id.into()
+    }
+}
+
+impl<R, I: Display> Display for TypedId<R, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.id.fmt(f)
+    }
+}
+
+impl<R, I: Debug> Debug for TypedId<R, I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("TypedId")
+            .field(&std::any::type_name::<R>())
+            .field(&self.id)
+            .finish()
+    }
+}
+
+// the traits below are implemented by hand, since deriving them would require them of R
+
+impl<R, I: Clone> Clone for TypedId<R, I> {
+    fn clone(&self) -> Self {
+        TypedId::new(self.id.clone())
+    }
+}
+
+impl<R, I: Copy> Copy for TypedId<R, I> {}
+
+impl<R, I: PartialEq> PartialEq for TypedId<R, I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<R, I: Eq> Eq for TypedId<R, I> {}
+
+impl<R, I: PartialOrd> PartialOrd for TypedId<R, I> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.id.partial_cmp(&other.id)
+    }
+}
+
+impl<R, I: Ord> Ord for TypedId<R, I> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl<R, I: Hash> Hash for TypedId<R, I> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl<R, I: Serialize> Serialize for TypedId<R, I> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.id.serialize(serializer)
+    }
+}
+
+impl<'de, R, I: Deserialize<'de>> Deserialize<'de> for TypedId<R, I> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        I::deserialize(deserializer).map(TypedId::new)
+    }
+}
+
+// CompositeKey is a key made of several parts, e.g. a tuple of them. It's implemented for tuples
+// of up to four parts which parse from and display as strings; structs implement it by hand
+pub trait CompositeKey: Sized {
+    fn to_parts(&self) -> Vec<String>;
+
+    // from_parts fails with the title of the 400 answering the id
+    fn from_parts(parts: Vec<String>) -> Result<Self, String>;
+}
+
+macro_rules! tuple_keys {
+    ($($len:literal => ($($part:ident),+)),* $(,)?) => {
+        $(
+            impl<$($part),+> CompositeKey for ($($part,)+)
+            where
+                $($part: FromStr + Display, $part::Err: Display,)+
+            {
+                #[allow(non_snake_case)]
+                fn to_parts(&self) -> Vec<String> {
+                    let ($($part,)+) = self;
+                    vec![$($part.to_string()),+]
+                }
+
+                fn from_parts(parts: Vec<String>) -> Result<Self, String> {
+                    if parts.len() != $len {
+                        return Err(format!("the id should have {} parts", $len));
+                    }
+                    let mut parts = parts.into_iter();
+                    Ok(($(
+                        parts
+                            .next()
+                            .unwrap_or_default()
+                            .parse::<$part>()
+                            .map_err(|err| format!("invalid part of the id: {}", err))?,
+                    )+))
+                }
+            }
+        )*
+    };
+}
+
+tuple_keys! {
+    2 => (A, B),
+    3 => (A, B, C),
+    4 => (A, B, C, D),
+}
+
+// CompositeFormat is how the parts of a composite id are put into one string
+pub trait CompositeFormat {
+    fn join(parts: &[String]) -> String;
+
+    // split is None if the id isn't one join would give
+    fn split(id: &str) -> Option<Vec<String>>;
+}
+
+// Separated joins the parts with SEP. A SEP or % in a part is percent-encoded, so any parts can be
+// joined and plain ones stay readable, e.g. "7,2024-01-01"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Separated<const SEP: char>;
+
+impl<const SEP: char> CompositeFormat for Separated<SEP> {
+    fn join(parts: &[String]) -> String {
+        let mut id = String::new();
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                id.push(SEP);
+            }
+            for c in part.chars() {
+                if c == SEP || c == '%' {
+                    let mut buf = [0; 4];
+                    for byte in c.encode_utf8(&mut buf).bytes() {
+                        id.push_str(&format!("%{:02X}", byte));
+                    }
+                } else {
+                    id.push(c);
+                }
+            }
+        }
+        id
+    }
+
+    fn split(id: &str) -> Option<Vec<String>> {
+        id.split(SEP).map(pct_decode).collect()
+    }
+}
+
+fn pct_decode(part: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(part.len());
+    let mut rest = part.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+// Base64 encodes the parts as a JSON array in URL-safe base64, for keys whose parts are anything
+// but plain and ids which are opaque anyway
+#[cfg(feature = "base64")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Base64;
+
+#[cfg(feature = "base64")]
+impl CompositeFormat for Base64 {
+    fn join(parts: &[String]) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(parts).unwrap_or_default())
+    }
+
+    fn split(id: &str) -> Option<Vec<String>> {
+        let json = URL_SAFE_NO_PAD.decode(id).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+// CompositeId is the id of a resource with a composite key K, in the format F, by default its
+// parts separated by commas, e.g. `CompositeId<(u32, String)>` for "7,en"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompositeId<K, F = Separated<','>> {
+    pub key: K,
+    format: PhantomData<F>,
+}
+
+impl<K, F> CompositeId<K, F> {
+    pub fn new(key: K) -> Self {
+        CompositeId {
+            key,
+            format: PhantomData,
+        }
+    }
+
+    pub fn into_inner(self) -> K {
+        self.key
+    }
+}
+
+impl<K: CompositeKey, F: CompositeFormat> FromID for CompositeId<K, F> {
+    fn from_id(id: ID) -> Result<Self, Error> {
+        let parts = F::split(id.as_str())
+            .ok_or_else(|| Error::new_bad_request(&format!("invalid composite id: {}", id)))?;
+        K::from_parts(parts)
+            .map(CompositeId::new)
+            .map_err(|title| Error::new_bad_request(&title))
+    }
+}
+
+try_from_id!({K: CompositeKey, F: CompositeFormat} CompositeId<K, F>);
+
+impl<K: CompositeKey, F: CompositeFormat> From<CompositeId<K, F>> for ID {
+    fn from(id: CompositeId<K, F>) -> ID {
+        F::join(&id.key.to_parts()).into()
+    }
+}
+
+impl<K: CompositeKey, F: CompositeFormat> Display for CompositeId<K, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&F::join(&self.key.to_parts()))
+    }
+}
+
+// Slug is a human-readable id: lowercase letters and digits, in words separated by single hyphens,
+// e.g. "hello-world". FromID refuses ids which aren't slugs with 400 rather than normalizing them,
+// so that each resource has one id; slugify makes slugs of titles and such
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde_derive::Serialize)]
+pub struct Slug(String);
+
+impl Slug {
+    // slugify lowercases text and turns each run of anything but letters and digits into a
+    // hyphen, dropping them at either end. None if nothing's left
+    pub fn slugify(text: &str) -> Option<Slug> {
+        let mut slug = String::with_capacity(text.len());
+        for word in text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            if !slug.is_empty() {
+                slug.push('-');
+            }
+            slug.extend(word.chars().flat_map(char::to_lowercase));
+        }
+        (!slug.is_empty()).then_some(Slug(slug))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    fn is_slug(text: &str) -> bool {
+        Slug::slugify(text).is_some_and(|slug| slug.0 == text)
+    }
+}
+
+impl FromStr for Slug {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Error> {
+        if Slug::is_slug(text) {
+            Ok(Slug(text.to_owned()))
+        } else {
+            Err(Error::new_bad_request(&format!(
+                "invalid value for slug id value: {}",
+                text
+            )))
+        }
+    }
+}
+
+impl FromID for Slug {
+    fn from_id(id: ID) -> Result<Self, Error> {
+        id.as_str().parse()
+    }
+}
+
+try_from_id!(Slug);
+
+impl From<Slug> for ID {
+    fn from(slug: Slug) -> ID {
+        slug.0.into()
+    }
+}
+
+impl Display for Slug {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Slug {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse()
+            .map_err(|err: Error| serde::de::Error::custom(err.title))
+    }
+}
+
+// NanoId is a nanoid: made of URL-safe characters (A-Za-z0-9_-), 21 of them if it's generated
+// with new. FromID refuses ids with other characters
+#[cfg(feature = "nanoid")]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde_derive::Serialize)]
+pub struct NanoId(String);
+
+#[cfg(feature = "nanoid")]
+impl NanoId {
+    pub fn new() -> NanoId {
+        NanoId(nanoid::nanoid!())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "nanoid")]
+impl Default for NanoId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "nanoid")]
+impl FromStr for NanoId {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self, Error> {
+        if !text.is_empty() && text.chars().all(|c| nanoid::alphabet::SAFE.contains(&c)) {
+            Ok(NanoId(text.to_owned()))
+        } else {
+            Err(Error::new_bad_request(&format!(
+                "invalid value for nanoid id value: {}",
+                text
+            )))
+        }
+    }
+}
+
+#[cfg(feature = "nanoid")]
+impl FromID for NanoId {
+    fn from_id(id: ID) -> Result<Self, Error> {
+        id.as_str().parse()
+    }
+}
+
+#[cfg(feature = "nanoid")]
+try_from_id!(NanoId);
+
+#[cfg(feature = "nanoid")]
+impl From<NanoId> for ID {
+    fn from(id: NanoId) -> ID {
+        id.0.into()
+    }
+}
+
+#[cfg(feature = "nanoid")]
+impl Display for NanoId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "nanoid")]
+impl<'de> Deserialize<'de> for NanoId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse()
+            .map_err(|err: Error| serde::de::Error::custom(err.title))
+    }
+}
+
+// ObfuscationSalt is the secret an ObfuscatedId is scrambled with, e.g.
+// `struct ArticleSalt; impl ObfuscationSalt for ArticleSalt { const SALT: &'static str = "..."; }`
+pub trait ObfuscationSalt {
+    const SALT: &'static str;
+}
+
+// ObfuscatedId is an integer id sent as a short string (up to 11 letters and digits), so that
+// clients can't enumerate ids or infer how many there are. The integer is scrambled with a
+// permutation keyed by the salt S, in the manner of hashids but without its dependency. It's
+// obfuscation, not encryption: the salt hides ids from casual guessing, not from a determined
+// attacker with many ids at hand
+pub struct ObfuscatedId<S> {
+    pub id: u64,
+    salt: PhantomData<fn() -> S>,
+}
+
+const BASE62: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+impl<S: ObfuscationSalt> ObfuscatedId<S> {
+    pub fn new(id: u64) -> Self {
+        ObfuscatedId {
+            id,
+            salt: PhantomData,
+        }
+    }
+
+    // keys derives the round keys of the permutation from the salt (FNV-1a, then splitmix64)
+    fn keys() -> [u64; 4] {
+        let mut state = S::SALT.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        [(); 4].map(|_| {
+            state = state.wrapping_add(0x9e3779b97f4a7c15);
+            mix(state)
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        // a Feistel network over the halves of the id, which makes it a permutation
+        let (mut left, mut right) = ((self.id >> 32) as u32, self.id as u32);
+        for key in Self::keys() {
+            (left, right) = (right, left ^ mix(right as u64 ^ key) as u32);
+        }
+        let mut n = (left as u64) << 32 | right as u64;
+        let mut digits = Vec::with_capacity(11);
+        loop {
+            digits.push(BASE62[(n % 62) as usize]);
+            n /= 62;
+            if n == 0 {
+                break;
+            }
+        }
+        digits.reverse();
+        String::from_utf8(digits).unwrap_or_default()
+    }
+
+    // decode reverses encode, None for strings encode doesn't give (e.g. with leading zeros)
+    pub fn decode(text: &str) -> Option<Self> {
+        if text.is_empty() || text.len() > 11 || (text.len() > 1 && text.starts_with('0')) {
+            return None;
+        }
+        let mut n: u64 = 0;
+        for c in text.bytes() {
+            let digit = BASE62.iter().position(|d| *d == c)? as u64;
+            n = n.checked_mul(62)?.checked_add(digit)?;
+        }
+        let (mut left, mut right) = ((n >> 32) as u32, n as u32);
+        for key in Self::keys().into_iter().rev() {
+            (left, right) = (right ^ mix(left as u64 ^ key) as u32, left);
+        }
+        Some(Self::new((left as u64) << 32 | right as u64))
+    }
+}
+
+// mix is splitmix64's finalizer
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+impl<S: ObfuscationSalt> FromID for ObfuscatedId<S> {
+    fn from_id(id: ID) -> Result<Self, Error> {
+        Self::decode(id.as_str()).ok_or_else(|| {
+            Error::new_bad_request(&format!("invalid value for obfuscated id value: {}", id))
+        })
+    }
+}
+
+try_from_id!({S: ObfuscationSalt} ObfuscatedId<S>);
+
+impl<S: ObfuscationSalt> From<ObfuscatedId<S>> for ID {
+    fn from(id: ObfuscatedId<S>) -> ID {
+        id.encode().into()
+    }
+}
+
+impl<S: ObfuscationSalt> Display for ObfuscatedId<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+// Debug shows the integer, which is what's being debugged
+impl<S> Debug for ObfuscatedId<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ObfuscatedId").field(&self.id).finish()
+    }
+}
+
+impl<S> Clone for ObfuscatedId<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for ObfuscatedId<S> {}
+
+impl<S> PartialEq for ObfuscatedId<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<S> Eq for ObfuscatedId<S> {}
+
+impl<S> Hash for ObfuscatedId<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+// IdRules reject ids which would be trouble for storage layers, e.g. megabytes long or with
+// control characters, with 400. By default ids are non-empty, up to 256 bytes and free of control
+// characters. Registered with JsonApiConfig::id_rules, the extractor checks the id and linkage of
+// requests with them
+#[derive(Debug, Clone, Copy)]
+pub struct IdRules {
+    max_length: usize,
+    allow_empty: bool,
+    allowed: fn(char) -> bool,
+}
+
+impl Default for IdRules {
+    fn default() -> Self {
+        IdRules {
+            max_length: 256,
+            allow_empty: false,
+            allowed: |c| !c.is_control(),
+        }
+    }
+}
+
+impl IdRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // max_length caps the length of ids, in bytes
+    pub fn max_length(mut self, length: usize) -> Self {
+        self.max_length = length;
+        self
+    }
+
+    pub fn allow_empty(mut self, allow: bool) -> Self {
+        self.allow_empty = allow;
+        self
+    }
+
+    // allowed_chars sets which characters ids may be made of, e.g. `|c| c.is_ascii_alphanumeric()`
+    pub fn allowed_chars(mut self, allowed: fn(char) -> bool) -> Self {
+        self.allowed = allowed;
+        self
+    }
+
+    // check applies the rules to an id. The errors don't repeat the id, which may be huge
+    pub fn check(&self, id: &ID) -> Result<(), Error> {
+        let id = id.as_str();
+        let title = if id.is_empty() && !self.allow_empty {
+            "id can't be empty".to_owned()
+        } else if id.len() > self.max_length {
+            format!("id is longer than {} bytes", self.max_length)
+        } else if let Some(c) = id.chars().find(|c| !(self.allowed)(*c)) {
+            format!("id can't contain '{}'", c.escape_default())
+        } else {
+            return Ok(());
+        };
+        Err(Error::new_bad_request(&title))
+    }
+
+    // from_id checks an id before converting it, for ids which don't come through the extractor,
+    // e.g. from the path
+    pub fn from_id<T: FromID>(&self, id: ID) -> Result<T, Error> {
+        self.check(&id)?;
+        T::from_id(id)
+    }
+
+    // check_request checks the id of a request's resource and the ids of its relationship linkage,
+    // pointing at the first offending one
+    pub fn check_request<D>(&self, req: &ResourceRequest<D>) -> Result<(), Error> {
+        if let Some(id) = &req.id {
+            self.check(id).map_err(|err| err.with_pointer("/data/id"))?;
+        }
+        for (name, rel) in req.relationships.iter().flatten() {
+            let pointer = format!("/data/relationships/{}/data", name);
+            match &rel.data {
+                Some(Relationship::ToOne(identifier)) => self
+                    .check(&identifier.id)
+                    .map_err(|err| err.with_pointer(&format!("{}/id", pointer)))?,
+                Some(Relationship::ToMany(identifiers)) => {
+                    for (i, identifier) in identifiers.iter().enumerate() {
+                        self.check(&identifier.id)
+                            .map_err(|err| err.with_pointer(&format!("{}/{}/id", pointer, i)))?;
+                    }
+                }
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CompositeId, IdRules, ObfuscatedId, ObfuscationSalt, Separated, Slug, TypedId};
+    use crate::{
+        Extensions, FromID, FromRelationship, Identifier, IntoRelationship, Relationship,
+        RelationshipData, Relationships, ResourceRequest, ID,
+    };
+
+    struct Article;
+    struct Author;
+
+    fn author_of(_article: TypedId<Article, u64>) -> TypedId<Author, u64> {
+        TypedId::new(9)
+    }
+
+    #[test]
+    fn test_typed_id() {
+        let article: TypedId<Article, u64> = FromID::from_id("1".into()).unwrap();
+        assert_eq!(*article.get(), 1);
+        assert!(TypedId::<Article, u64>::from_id("a".into()).is_err());
+        assert_eq!(
+            TypedId::<Article, u64>::try_from(ID::from(1u64)).unwrap(),
+            article
+        );
+        let author = author_of(article);
+        assert_eq!(author.to_string(), "9");
+        assert_eq!(ID::from(author), ID::from("9"));
+
+        assert_eq!(
+            author.into_relationship("people"),
+            Relationship::ToOne(Identifier {
+                id: "9".into(),
+                typ: "people".into(),
+            })
+        );
+        let authors: Vec<TypedId<Author>> = FromRelationship::from_relationship(
+            vec![TypedId::<Author, u64>::new(2)].into_relationship("people"),
+        )
+        .unwrap();
+        assert_eq!(authors[0].get().as_str(), "2");
+
+        let parsed: TypedId<Author, u64> = serde_json::from_str("4").unwrap();
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), "4");
+    }
+
+    #[test]
+    fn test_id_rules() {
+        let rules = IdRules::new();
+        assert!(rules.check(&"a-1".into()).is_ok());
+        assert!(rules.check(&"".into()).is_err());
+        assert!(rules.check(&"a\u{0}".into()).is_err());
+        assert!(rules.check(&"a".repeat(257).into()).is_err());
+        assert!(rules.allow_empty(true).check(&"".into()).is_ok());
+        let alnum = rules
+            .max_length(4)
+            .allowed_chars(|c| c.is_ascii_alphanumeric());
+        assert!(alnum.check(&"a-1".into()).is_err());
+        assert!(alnum.check(&"abcde".into()).is_err());
+        assert_eq!(alnum.from_id::<u64>("12".into()).unwrap(), 12);
+        assert!(alnum.from_id::<String>("1 2".into()).is_err());
+
+        let mut relationships = Relationships::new();
+        relationships.insert(
+            "tags".into(),
+            RelationshipData {
+                data: Some(Relationship::ToMany(vec![
+                    Identifier {
+                        id: "1".into(),
+                        typ: "tags".into(),
+                    },
+                    Identifier {
+                        id: "\n".into(),
+                        typ: "tags".into(),
+                    },
+                ])),
+                ..Default::default()
+            },
+        );
+        let mut req = ResourceRequest {
+            id: Some("1".into()),
+            typ: "articles".into(),
+            attributes: (),
+            relationships: Some(relationships),
+            extensions: Extensions::new(),
+        };
+        let err = rules.check_request(&req).err().unwrap();
+        assert_eq!(err.title, "id can't contain '\\n'");
+        assert_eq!(
+            err.source.unwrap().pointer.as_deref(),
+            Some("/data/relationships/tags/data/1/id")
+        );
+        req.id = Some("".into());
+        let err = rules.check_request(&req).err().unwrap();
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/id"));
+    }
+
+    #[test]
+    fn test_composite_id() {
+        let id = CompositeId::<(u32, String)>::new((7, "en".into()));
+        assert_eq!(ID::from(id.clone()), ID::from("7,en"));
+        let parsed: CompositeId<(u32, String)> = FromID::from_id("7,en".into()).unwrap();
+        assert_eq!(parsed, id);
+        assert!(CompositeId::<(u32, String)>::from_id("7".into()).is_err());
+        assert!(CompositeId::<(u32, String)>::from_id("x,en".into()).is_err());
+        assert!(CompositeId::<(u32, String, String)>::from_id("7,en,%zz".into()).is_err());
+
+        // parts holding the separator survive the round trip
+        let id = CompositeId::<(String, String), Separated<':'>>::new(("a:b".into(), "%".into()));
+        assert_eq!(id.to_string(), "a%3Ab:%25");
+        let parsed: CompositeId<(String, String), Separated<':'>> =
+            FromID::from_id(id.into()).unwrap();
+        assert_eq!(parsed.key, ("a:b".to_owned(), "%".to_owned()));
+
+        #[cfg(feature = "base64")]
+        {
+            use super::Base64;
+
+            let id = CompositeId::<(i64, String, bool), Base64>::new((-1, ",".into(), true));
+            let parsed: CompositeId<(i64, String, bool), Base64> =
+                FromID::from_id(id.clone().into()).unwrap();
+            assert_eq!(parsed, id);
+            assert!(CompositeId::<(i64, String, bool), Base64>::from_id("!".into()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_slug() {
+        let slug = Slug::slugify("  Hello, Wörld! -- 2024_Edition ").unwrap();
+        assert_eq!(slug.as_str(), "hello-wörld-2024-edition");
+        assert!(Slug::slugify("--").is_none());
+        assert_eq!(Slug::from_id(slug.clone().into()).unwrap(), slug);
+        for id in ["", "Hello", "hello--world", "-hello", "hello_world"] {
+            assert!(Slug::from_id(id.into()).is_err(), "{:?} is no slug", id);
+        }
+        assert!(serde_json::from_str::<Slug>(r#""hello world""#).is_err());
+        assert_eq!(
+            serde_json::from_str::<Slug>(r#""hello-world""#).unwrap(),
+            Slug::slugify("Hello World").unwrap()
+        );
+    }
+
+    #[cfg(feature = "nanoid")]
+    #[test]
+    fn test_nanoid() {
+        use super::NanoId;
+
+        let id = NanoId::new();
+        assert_eq!(id.as_str().len(), 21);
+        assert_eq!(NanoId::from_id(id.clone().into()).unwrap(), id);
+        assert!(NanoId::from_id("V1StGXR8_Z5jdHi6B-myT".into()).is_ok());
+        assert!(NanoId::from_id("V1StGXR8 Z5jdHi6B-myT".into()).is_err());
+        assert!(NanoId::from_id("".into()).is_err());
+    }
+
+    struct Salt;
+    impl ObfuscationSalt for Salt {
+        const SALT: &'static str = "pepper";
+    }
+
+    struct OtherSalt;
+    impl ObfuscationSalt for OtherSalt {
+        const SALT: &'static str = "paprika";
+    }
+
+    #[test]
+    fn test_obfuscated_id() {
+        for id in [0, 1, 2, 1000, u32::MAX as u64 + 1, u64::MAX] {
+            let obfuscated = ObfuscatedId::<Salt>::new(id);
+            let encoded = ID::from(obfuscated);
+            assert!(encoded.as_str().len() <= 11);
+            assert_ne!(encoded.as_str(), id.to_string());
+            assert_eq!(ObfuscatedId::<Salt>::from_id(encoded).unwrap(), obfuscated);
+        }
+        // neighbours and salts don't give similar ids
+        let one = ObfuscatedId::<Salt>::new(1).to_string();
+        assert_ne!(one, ObfuscatedId::<Salt>::new(2).to_string());
+        assert_ne!(one, ObfuscatedId::<OtherSalt>::new(1).to_string());
+        assert_ne!(
+            ObfuscatedId::<OtherSalt>::from_id(one.into()).unwrap().id,
+            1
+        );
+
+        for id in ["", "a-b", "0a", "zzzzzzzzzzzz", "zzzzzzzzzzz"] {
+            assert!(ObfuscatedId::<Salt>::from_id(id.into()).is_err(), "{}", id);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+// Long-running operations. A request which can't be carried out right away is answered with 202
+// Accepted and a job resource (type "jobs") whose self link, also sent as Content-Location, is
+// where the client polls it. While the job is pending polling returns it as is; once it's done
+// polling answers with 303 See Other and a Location pointing at the outcome (e.g. the created
+// resource). See Job::accepted and Job::poll.
+#[cfg(feature = "actixweb")]
+use actix_web::{
+    http::header::{CONTENT_LOCATION, LOCATION},
+    http::StatusCode,
+    HttpResponse, HttpResponseBuilder,
+};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{Identifier, IntoResponse, Link, Links, ResourceResponse, Response};
+
+// TYPE is the resource type of jobs
+pub const TYPE: &str = "jobs";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Done,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct JobAttributes {
+    pub status: JobStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    pub id: String,
+    pub status: JobStatus,
+    // url is where the job is polled, its self link
+    pub url: String,
+    // location is where the outcome of a job that is done can be found
+    pub location: Option<String>,
+}
+
+impl Job {
+    pub fn pending(id: &str, url: &str) -> Job {
+        Job {
+            id: id.to_owned(),
+            status: JobStatus::Pending,
+            url: url.to_owned(),
+            location: None,
+        }
+    }
+
+    // done marks the job as done, its outcome being at location
+    pub fn done(mut self, location: &str) -> Self {
+        self.status = JobStatus::Done;
+        self.location = Some(location.to_owned());
+        self
+    }
+
+    pub fn failed(mut self) -> Self {
+        self.status = JobStatus::Failed;
+        self
+    }
+
+    pub fn to_response(&self) -> Response<JobAttributes, ()> {
+        Response::with_capacity(1, 0).push(self.clone())
+    }
+
+    // accepted answers the request which started the job: 202 with the job, and its url as
+    // Content-Location
+    #[cfg(feature = "actixweb")]
+    pub fn accepted(&self) -> HttpResponse {
+        self.respond(StatusCode::ACCEPTED)
+            .insert_header((CONTENT_LOCATION, self.url.as_str()))
+            .body(self.body())
+    }
+
+    // poll answers a request for the job: 303 to its location once it's done, otherwise the job
+    // itself
+    #[cfg(feature = "actixweb")]
+    pub fn poll(&self) -> HttpResponse {
+        match (&self.status, &self.location) {
+            (JobStatus::Done, Some(location)) => HttpResponse::SeeOther()
+                .insert_header((LOCATION, location.as_str()))
+                .finish(),
+            _ => self.respond(StatusCode::OK).body(self.body()),
+        }
+    }
+
+    #[cfg(feature = "actixweb")]
+    fn respond(&self, status: StatusCode) -> HttpResponseBuilder {
+        let mut builder = HttpResponseBuilder::new(status);
+        builder.content_type(crate::media::JSONAPI_MEDIA_TYPE);
+        builder
+    }
+
+    #[cfg(feature = "actixweb")]
+    fn body(&self) -> Vec<u8> {
+        self.to_response()
+            .to_vec()
+            .expect("jobs are always valid JSON")
+    }
+}
+
+impl IntoResponse for Job {
+    type Attributes = JobAttributes;
+
+    fn into_response(self) -> ResourceResponse<JobAttributes> {
+        let mut links = Links::new();
+        links.insert("self".into(), Link::Url(self.url));
+        if let Some(location) = self.location {
+            links.insert("related".into(), Link::Url(location));
+        }
+        ResourceResponse {
+            id: Identifier {
+                id: self.id.into(),
+                typ: TYPE.into(),
+            },
+            attributes: JobAttributes {
+                status: self.status,
+            },
+            relationships: None,
+            meta: None,
+            links: Some(links),
+            extensions: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Job;
+
+    #[test]
+    fn test_job() {
+        let job = Job::pending("1", "/jobs/1");
+        let out = serde_json::to_value(job.to_response()).unwrap();
+        assert_eq!(
+            out["data"][0],
+            serde_json::json!({
+                "type": "jobs",
+                "id": "1",
+                "attributes": {"status": "pending"},
+                "links": {"self": "/jobs/1"}
+            })
+        );
+        let out = serde_json::to_value(job.done("/articles/1").to_response()).unwrap();
+        assert_eq!(out["data"][0]["attributes"]["status"], "done");
+        assert_eq!(out["data"][0]["links"]["related"], "/articles/1");
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_responses() {
+        use actix_web::http::StatusCode;
+
+        let job = Job::pending("1", "/jobs/1");
+        let res = job.accepted();
+        assert_eq!(res.status(), StatusCode::ACCEPTED);
+        assert_eq!(res.headers().get("content-location").unwrap(), "/jobs/1");
+        assert_eq!(job.poll().status(), StatusCode::OK);
+        let res = job.done("/articles/1").poll();
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_eq!(res.headers().get("location").unwrap(), "/articles/1");
+    }
+}
@@ -1,29 +1,332 @@
+// Error mirrors a JSON:API error object, so it is large by nature and returned by value everywhere
+#![allow(clippy::result_large_err)]
+
 #[cfg(feature = "actixweb")]
 use actix_web::{
+    dev::Decompress,
     error::JsonPayloadError,
+    http::header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE},
     http::StatusCode,
-    web::{Json, JsonBody},
-    FromRequest as FromWebRequest, HttpResponse, HttpResponseBuilder, ResponseError,
+    mime,
+    web::BytesMut,
+    FromRequest as FromWebRequest, HttpMessage, HttpResponse, HttpResponseBuilder, ResponseError,
 };
 #[cfg(feature = "actixweb")]
 use core::future::Future;
 #[cfg(feature = "actixweb")]
-use futures_core::ready;
+use futures_core::{ready, Stream};
 #[cfg(feature = "actixweb")]
-use serde::de::DeserializeOwned;
+use serde::de::{DeserializeOwned, IgnoredAny};
 use serde_derive::{Deserialize, Serialize};
-use std::{collections::BTreeMap, fmt::Display, ops};
+use smallvec::{smallvec, SmallVec};
+#[cfg(feature = "actixweb")]
+use std::marker::PhantomData;
+use std::num::{NonZeroU32, NonZeroU64};
+use std::{
+    borrow::Cow, collections::BTreeMap, fmt::Display, io, ops, str::FromStr, time::Duration,
+};
 #[cfg(feature = "actixweb")]
 use std::{pin::Pin, task::Poll};
 #[cfg(feature = "server")]
 use uuid::Uuid;
 
-#[derive(Serialize, Deserialize)]
+pub mod atomic;
+pub mod borrowed;
+pub mod bulk;
+pub mod catalog;
+pub mod codes;
+pub mod concurrency;
+#[cfg(feature = "cursor")]
+pub mod cursor;
+mod de;
+pub mod deprecation;
+pub mod document;
+pub mod encoding;
+#[cfg(feature = "actixweb")]
+pub mod errors;
+pub mod filter;
+#[cfg(feature = "async-graphql")]
+pub mod graphql;
+pub mod ids;
+pub mod jobs;
+pub mod linkage;
+pub mod links;
+pub mod lint;
+pub mod locale;
+pub mod media;
+#[cfg(feature = "utoipa")]
+pub mod openapi;
+#[cfg(feature = "sea-orm")]
+pub mod orm;
+pub mod pagination;
+pub mod patch;
+#[cfg(any(feature = "actixweb", feature = "tower"))]
+pub mod recovery;
+pub mod redaction;
+#[cfg(feature = "sqlx")]
+pub mod rows;
+#[cfg(feature = "schemars")]
+pub mod schema;
+pub mod sort;
+#[cfg(feature = "sea-query")]
+pub mod sql;
+#[cfg(feature = "actixweb")]
+pub mod status;
+pub mod stream;
+pub mod timestamps;
+#[cfg(feature = "tracing")]
+mod trace;
+#[cfg(feature = "jsonschema")]
+pub mod validation;
+pub mod warnings;
+
+pub use lint::{validate, validate_with, Violation};
+pub use patch::Maybe;
+
+// ResourceResponse and Response implement Deserialize by hand, in the de module
+#[derive(Serialize)]
 pub struct ResourceResponse<D> {
     #[serde(flatten)]
     pub id: Identifier,
     pub attributes: D,
-    pub relationships: Option<BTreeMap<String, RelationshipData>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relationships: Option<Relationships>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl<D> ResourceResponse<D> {
+    // link_with adds the links the generator has for this resource, leaving any already set
+    // alone: the resource's self link, and the self (relationship) and related links of each of
+    // its relationships
+    pub fn link_with(&mut self, generator: &dyn links::LinkGenerator) {
+        let Identifier { id, typ } = &self.id;
+        let links = self.links.get_or_insert_with(Links::new);
+        if !links.contains_key("self") {
+            if let Some(link) = generator.self_link(typ, id) {
+                links.insert("self".into(), link);
+            }
+        }
+        if links.is_empty() {
+            self.links = None;
+        }
+        for (name, rel) in self.relationships.iter_mut().flatten() {
+            let links = rel.links.get_or_insert_with(Links::new);
+            if !links.contains_key("self") {
+                if let Some(link) = generator.relationship_link(typ, id, name) {
+                    links.insert("self".into(), link);
+                }
+            }
+            if !links.contains_key("related") {
+                if let Some(link) = generator.related_link(typ, id, name) {
+                    links.insert("related".into(), link);
+                }
+            }
+            if links.is_empty() {
+                rel.links = None;
+            }
+        }
+    }
+}
+
+// RawResourceResponse leaves attributes as the raw JSON they arrived as, for proxies which forward
+// documents and only need to look at ids, types and relationships
+pub type RawResourceResponse = ResourceResponse<Box<serde_json::value::RawValue>>;
+
+// Relationships maps relationship names to their data. It's sorted by name, unless the indexmap
+// feature is enabled, in which case the order they were inserted (or parsed) in is kept
+#[cfg(not(feature = "indexmap"))]
+pub type Relationships = BTreeMap<String, RelationshipData>;
+#[cfg(feature = "indexmap")]
+pub type Relationships = indexmap::IndexMap<String, RelationshipData>;
+
+// at_relationship points an error at the relationship it's about, unless it points somewhere
+// already
+pub fn at_relationship(err: Error, name: &str) -> Error {
+    match err
+        .source
+        .as_ref()
+        .and_then(|source| source.pointer.as_ref())
+    {
+        Some(_) => err,
+        None => err.with_pointer(&format!("/data/relationships/{}", name)),
+    }
+}
+
+// take_relationship removes a relationship by name, without disturbing the order of the rest
+pub fn take_relationship(rels: &mut Relationships, name: &str) -> Option<RelationshipData> {
+    #[cfg(not(feature = "indexmap"))]
+    return rels.remove(name);
+    #[cfg(feature = "indexmap")]
+    return rels.shift_remove(name);
+}
+
+// Extensions holds the members of a resource object or document this crate doesn't model, e.g.
+// extension members like "atomic:operations", so they survive a round trip
+pub type Extensions = serde_json::Map<String, serde_json::Value>;
+
+// Meta is the free-form, non-standard information a resource object (or document) may carry
+pub type Meta = BTreeMap<String, serde_json::Value>;
+
+// Links maps link names (e.g. "self", "related") to their links
+pub type Links = BTreeMap<String, Link>;
+
+// JsonApiObject is the top-level jsonapi member, describing the implementation a document comes
+// from: the spec version it follows and the extensions and profiles it applies
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct JsonApiObject {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ext: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub profile: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+// Link is either of the forms the spec allows: a plain URL, or a link object describing its
+// target. Both are accepted when parsing; which one is produced is up to whoever builds it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum Link {
+    Url(String),
+    Object {
+        href: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rel: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        describedby: Option<Box<Link>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+        typ: Option<String>,
+        // hreflang is a single language tag or an array of them, a single one is written as a string
+        #[serde(
+            default,
+            skip_serializing_if = "Vec::is_empty",
+            serialize_with = "ser_hreflang",
+            deserialize_with = "de_hreflang"
+        )]
+        hreflang: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        meta: Option<Meta>,
+    },
+}
+
+impl Link {
+    // object makes a link object with only an href, for the other members to be filled in
+    pub fn object(href: &str) -> Link {
+        Link::Object {
+            href: href.to_owned(),
+            rel: None,
+            describedby: None,
+            title: None,
+            typ: None,
+            hreflang: Vec::new(),
+            meta: None,
+        }
+    }
+
+    pub fn href(&self) -> &str {
+        match self {
+            Link::Url(href) | Link::Object { href, .. } => href,
+        }
+    }
+}
+
+impl From<String> for Link {
+    fn from(href: String) -> Link {
+        Link::Url(href)
+    }
+}
+
+impl From<&str> for Link {
+    fn from(href: &str) -> Link {
+        Link::Url(href.to_owned())
+    }
+}
+
+// links compare equal to a string with the same href, whichever form they're in
+impl PartialEq<str> for Link {
+    fn eq(&self, other: &str) -> bool {
+        self.href() == other
+    }
+}
+
+impl PartialEq<&str> for Link {
+    fn eq(&self, other: &&str) -> bool {
+        self.href() == *other
+    }
+}
+
+impl Display for Link {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.href())
+    }
+}
+
+fn ser_hreflang<S: serde::Serializer>(tags: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::Serialize as _;
+    match tags {
+        [tag] => serializer.serialize_str(tag),
+        tags => tags.serialize(serializer),
+    }
+}
+
+fn de_hreflang<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(
+        match <OneOrMany as serde::Deserialize>::deserialize(deserializer)? {
+            OneOrMany::One(tag) => vec![tag],
+            OneOrMany::Many(tags) => tags,
+        },
+    )
+}
+
+// SpecVersion is the version of the JSON:API spec documents are produced and checked against.
+// 1.1 added local ids (lid), extensions and profiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum SpecVersion {
+    V1_0,
+    #[default]
+    V1_1,
+}
+
+impl SpecVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpecVersion::V1_0 => "1.0",
+            SpecVersion::V1_1 => "1.1",
+        }
+    }
+
+    // accepts_lid reports whether resource objects may be identified by a local id
+    pub fn accepts_lid(&self) -> bool {
+        *self >= SpecVersion::V1_1
+    }
+
+    // accepts_media_type_param reports whether the JSON:API media type may carry a parameter. 1.0
+    // allows none at all, 1.1 allows ext and profile
+    pub fn accepts_media_type_param(&self, name: &str) -> bool {
+        *self >= SpecVersion::V1_1 && (name == "ext" || name == "profile")
+    }
+}
+
+impl Display for SpecVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 pub trait Resource {
@@ -35,51 +338,146 @@ pub trait Resource {
     fn into_response(self) -> Response<Self::Attributes, Self::Relations>;
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(untagged)]
 pub enum Relationship {
     ToOne(Identifier),
     ToMany(Vec<Identifier>),
 }
 
-impl Into<RelationshipData> for Relationship {
-    fn into(self) -> RelationshipData {
-        RelationshipData { data: self }
+impl Relationship {
+    // is_empty is true for to-many relationships without any identifiers
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Relationship::ToOne(_) => false,
+            Relationship::ToMany(many) => many.is_empty(),
+        }
+    }
+
+    // identifiers is the linkage as a list, of one identifier for a to-one relationship
+    pub fn identifiers(&self) -> &[Identifier] {
+        match self {
+            Relationship::ToOne(one) => std::slice::from_ref(one),
+            Relationship::ToMany(many) => many,
+        }
+    }
+
+    pub fn contains(&self, identifier: &Identifier) -> bool {
+        self.identifiers().contains(identifier)
+    }
+
+    // difference lists the identifiers of this relationship which aren't in other, in order and
+    // each once
+    pub fn difference(&self, other: &Relationship) -> Vec<Identifier> {
+        let other: std::collections::HashSet<&Identifier> = other.identifiers().iter().collect();
+        let mut seen = std::collections::HashSet::new();
+        self.identifiers()
+            .iter()
+            .filter(|identifier| !other.contains(identifier) && seen.insert(*identifier))
+            .cloned()
+            .collect()
+    }
+
+    // dedup drops repeated identifiers of a to-many relationship, keeping the first of each
+    pub fn dedup(&mut self) {
+        if let Relationship::ToMany(many) = self {
+            let mut seen = std::collections::HashSet::new();
+            many.retain(|identifier| seen.insert(identifier.clone()));
+        }
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Hash)]
-pub struct ID(pub String);
+impl From<Relationship> for RelationshipData {
+    fn from(r: Relationship) -> RelationshipData {
+        RelationshipData {
+            data: Some(r),
+            ..Default::default()
+        }
+    }
+}
+
+// ID borrows static strings (fixtures, well-known ids) rather than allocating one per identifier
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ID(pub Cow<'static, str>);
+
+impl ID {
+    pub const fn from_static(id: &'static str) -> ID {
+        ID(Cow::Borrowed(id))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
 
 #[cfg(feature = "server")]
 impl From<Uuid> for ID {
     fn from(id: Uuid) -> ID {
-        ID(id.to_string())
+        ID(Cow::Owned(id.to_string()))
+    }
+}
+
+#[cfg(feature = "ulid")]
+impl From<ulid::Ulid> for ID {
+    fn from(id: ulid::Ulid) -> ID {
+        ID(Cow::Owned(id.to_string()))
     }
 }
 
 impl From<String> for ID {
     fn from(s: String) -> ID {
-        ID(s)
+        ID(Cow::Owned(s))
     }
 }
 
 impl From<&str> for ID {
     fn from(s: &str) -> ID {
-        ID(s.into())
+        ID(Cow::Owned(s.to_owned()))
     }
 }
 
-impl From<usize> for ID {
-    fn from(u: usize) -> ID {
-        ID(u.to_string())
+impl From<Cow<'static, str>> for ID {
+    fn from(s: Cow<'static, str>) -> ID {
+        ID(s)
     }
 }
 
-impl From<isize> for ID {
-    fn from(i: isize) -> ID {
-        ID(i.to_string())
-    }
+// integer_ids implements From<_> for ID and FromID for integer types, the latter failing with 400
+// (saying what the id should have been) if the id doesn't parse as one
+macro_rules! integer_ids {
+    ($($typ:ty => $kind:literal),* $(,)?) => {
+        $(
+            impl From<$typ> for ID {
+                fn from(i: $typ) -> ID {
+                    ID(Cow::Owned(i.to_string()))
+                }
+            }
+
+            impl FromID for $typ {
+                fn from_id(id: ID) -> Result<Self, Error> {
+                    id.0.parse().or(Err(Error::new_bad_request(&format!(
+                        concat!("invalid value for ", $kind, " id value: {}"),
+                        id
+                    ))))
+                }
+            }
+
+            try_from_id!($typ);
+        )*
+    };
+}
+
+integer_ids! {
+    usize => "unsigned",
+    u32 => "unsigned",
+    u64 => "unsigned",
+    u128 => "unsigned",
+    NonZeroU32 => "non-zero unsigned",
+    NonZeroU64 => "non-zero unsigned",
+    isize => "integer",
+    i32 => "integer",
+    i64 => "integer",
+    i128 => "integer",
 }
 
 impl Display for ID {
@@ -94,6 +492,12 @@ impl Ord for ID {
     }
 }
 
+impl PartialOrd for ID {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 pub trait FromID
 where
     Self: Sized,
@@ -103,36 +507,26 @@ where
 
 impl FromID for String {
     fn from_id(id: ID) -> Result<Self, Error> {
-        Ok(id.0)
-    }
-}
-
-impl FromID for usize {
-    fn from_id(id: ID) -> Result<Self, Error> {
-        id.0.parse().or(Err(Error::new_bad_request(&format!(
-            "invalid value for unsigned id value: {}",
-            id
-        ))))
+        Ok(id.0.into_owned())
     }
 }
 
-impl FromID for isize {
+#[cfg(feature = "server")]
+impl FromID for Uuid {
     fn from_id(id: ID) -> Result<Self, Error> {
-        id.0.parse().or(Err(Error::new_bad_request(&format!(
-            "invalid value for integer id value: {}",
-            id
-        ))))
+        Uuid::parse_str(&id.0).map_err(|err| {
+            Error::new_bad_request(&format!("invalid value for UUID id value: {}", err))
+                .with_cause(err)
+        })
     }
 }
 
-#[cfg(feature = "server")]
-impl FromID for Uuid {
+#[cfg(feature = "ulid")]
+impl FromID for ulid::Ulid {
     fn from_id(id: ID) -> Result<Self, Error> {
-        Uuid::parse_str(&id.0).map_err(|err| {
-            Error::new_bad_request(&format!(
-                "invalid value for UUID id value: {}",
-                err.to_string()
-            ))
+        ulid::Ulid::from_string(&id.0).map_err(|err| {
+            Error::new_bad_request(&format!("invalid value for ULID id value: {}", err))
+                .with_cause(err)
         })
     }
 }
@@ -143,25 +537,284 @@ impl FromID for ID {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+// try_from_id implements TryFrom<ID> and TryFrom<&ID> for types implementing FromID, for generic
+// code written against the standard conversions, e.g. `try_from_id!({R, I: FromID} TypedId<R, I>)`
+macro_rules! try_from_id {
+    ($({$($generics:tt)*})? $typ:ty) => {
+        impl<$($($generics)*)?> TryFrom<$crate::ID> for $typ {
+            type Error = $crate::Error;
+
+            fn try_from(id: $crate::ID) -> Result<Self, $crate::Error> {
+                $crate::FromID::from_id(id)
+            }
+        }
+
+        impl<$($($generics)*)?> TryFrom<&$crate::ID> for $typ {
+            type Error = $crate::Error;
+
+            fn try_from(id: &$crate::ID) -> Result<Self, $crate::Error> {
+                $crate::FromID::from_id(id.clone())
+            }
+        }
+    };
+}
+pub(crate) use try_from_id;
+
+#[cfg(feature = "server")]
+try_from_id!(Uuid);
+#[cfg(feature = "ulid")]
+try_from_id!(ulid::Ulid);
+
+// any id is a string, so that conversion can't fail
+impl From<ID> for String {
+    fn from(id: ID) -> String {
+        id.0.into_owned()
+    }
+}
+
+impl From<&ID> for String {
+    fn from(id: &ID) -> String {
+        id.0.clone().into_owned()
+    }
+}
+
+impl AsRef<str> for ID {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+// id_from_str parses an id with the target type's FromStr implementation. It backs the
+// #[jsonapi(id_from_str)] derive attribute, for id types which don't (or can't) implement FromID
+pub fn id_from_str<T>(id: ID) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    id.0.parse()
+        .map_err(|err| Error::new_bad_request(&format!("invalid value for id '{}': {}", id, err)))
+}
+
+// to_one_from_str is the to-one relationship counterpart of id_from_str
+pub fn to_one_from_str<T>(r: Relationship) -> Result<T, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let id: ID = FromRelationship::from_relationship(r)?;
+    id_from_str(id)
+}
+
+// to_many_from_str is the to-many relationship counterpart of id_from_str
+pub fn to_many_from_str<T>(r: Relationship) -> Result<Vec<T>, Error>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let ids: Vec<ID> = FromRelationship::from_relationship(r)?;
+    ids.into_iter().map(id_from_str).collect()
+}
+
+// RelationshipData is a relationship object. Large to-many relationships can leave data out (or
+// hold only part of it) and carry pagination links instead, see RelationshipData::paginated
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct RelationshipData {
-    pub data: Relationship,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Relationship>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+impl RelationshipData {
+    // paginated builds a to-many relationship from a page of it: the pagination links and meta,
+    // plus the identifiers on the page if they should be included as partial linkage
+    pub fn paginated(
+        page: pagination::Paginated,
+        linkage: Option<Vec<Identifier>>,
+    ) -> RelationshipData {
+        RelationshipData {
+            data: linkage.map(Relationship::ToMany),
+            links: Some(page.links),
+            meta: Some(page.meta).filter(|meta| !meta.is_empty()),
+        }
+    }
+
+    // with_links adds links to any already there, e.g. self and related next to pagination links
+    pub fn with_links(mut self, links: Links) -> Self {
+        self.links.get_or_insert_with(Links::new).extend(links);
+        self
+    }
+
+    // is_empty is true for relationships without any identifiers or links to find them by
+    pub fn is_empty(&self) -> bool {
+        self.data.as_ref().is_none_or(Relationship::is_empty)
+            && self.links.as_ref().is_none_or(Links::is_empty)
+    }
+
+    // into_data gives the resource linkage, which relationships in requests can't do without
+    pub fn into_data(self) -> Result<Relationship, Error> {
+        self.data
+            .ok_or_else(|| Error::new_bad_request("relationships need resource linkage in data"))
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Identifier {
     pub id: ID,
     #[serde(rename = "type")]
     pub typ: String,
 }
 
-#[derive(Serialize, Deserialize)]
+// identifiers are ordered by type, then id, so sorted linkage groups each type together
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (&self.typ, &self.id).cmp(&(&other.typ, &other.id))
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Serialize)]
 pub struct ResourceRequest<D> {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<ID>,
     #[serde(rename = "type")]
     pub typ: String,
     pub attributes: D,
-    pub relationships: Option<BTreeMap<String, RelationshipData>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relationships: Option<Relationships>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
+}
+
+impl<D> ResourceRequest<D> {
+    // check_type rejects a request for a different resource type than the endpoint handles,
+    // with the 409 Conflict the spec calls for
+    pub fn check_type(&self, expected: &str) -> Result<(), Error> {
+        if self.typ == expected {
+            return Ok(());
+        }
+        Err(Error::new_conflict(&format!(
+            "resource type '{}' does not match the expected type '{}'",
+            self.typ, expected
+        ))
+        .with_pointer("/data/type"))
+    }
+}
+
+// ClientIdPolicy decides whether a request creating a resource may bring its own id. The spec
+// has servers refuse unsupported client-generated ids with 403 Forbidden
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClientIdPolicy {
+    Forbid,
+    #[default]
+    Accept,
+    // AcceptUuid only accepts ids which are UUIDs, so they can't collide with the server's own
+    #[cfg(feature = "server")]
+    AcceptUuid,
+}
+
+impl ClientIdPolicy {
+    // check applies the policy to the id of a resource being created
+    pub fn check(&self, id: Option<&ID>) -> Result<(), Error> {
+        let title = match (self, id) {
+            (_, None) | (ClientIdPolicy::Accept, _) => return Ok(()),
+            (ClientIdPolicy::Forbid, _) => "client-generated ids are not supported".to_owned(),
+            #[cfg(feature = "server")]
+            (ClientIdPolicy::AcceptUuid, Some(id)) => match Uuid::parse_str(id.as_str()) {
+                Ok(_) => return Ok(()),
+                Err(_) => format!("client-generated id '{}' is not a UUID", id),
+            },
+        };
+        Err(Error::new_forbidden(&title).with_pointer("/data/id"))
+    }
+}
+
+// CreateRequest is the resource of a request creating one. The id is optional, clients may
+// bring their own as far as the endpoint's ClientIdPolicy allows, see check_id
+pub struct CreateRequest<D> {
+    pub id: Option<ID>,
+    pub typ: String,
+    pub attributes: D,
+    pub relationships: Option<Relationships>,
+}
+
+impl<D> CreateRequest<D> {
+    pub fn check_id(&self, policy: ClientIdPolicy) -> Result<(), Error> {
+        policy.check(self.id.as_ref())
+    }
+}
+
+impl<D> FromRequest for CreateRequest<D> {
+    type Attributes = D;
+
+    fn from_request(req: Request<D>) -> Result<Self, Error> {
+        let ResourceRequest {
+            id,
+            typ,
+            attributes,
+            relationships,
+            ..
+        } = req.data;
+        Ok(CreateRequest {
+            id,
+            typ,
+            attributes,
+            relationships,
+        })
+    }
+}
+
+// UpdateRequest is the resource of a request updating one, which has to say which by its id
+pub struct UpdateRequest<D> {
+    pub id: ID,
+    pub typ: String,
+    pub attributes: D,
+    pub relationships: Option<Relationships>,
+}
+
+impl<D> UpdateRequest<D> {
+    // check_id rejects an update of a different resource than the endpoint's (its URL's), with
+    // the 409 Conflict the spec calls for
+    pub fn check_id(&self, expected: &str) -> Result<(), Error> {
+        if self.id.as_str() == expected {
+            return Ok(());
+        }
+        Err(Error::new_conflict(&format!(
+            "resource id '{}' does not match the id '{}' of the endpoint",
+            self.id, expected
+        ))
+        .with_pointer("/data/id"))
+    }
+}
+
+impl<D> FromRequest for UpdateRequest<D> {
+    type Attributes = D;
+
+    fn from_request(req: Request<D>) -> Result<Self, Error> {
+        let ResourceRequest {
+            id,
+            typ,
+            attributes,
+            relationships,
+            ..
+        } = req.data;
+        let id = id.ok_or_else(|| {
+            Error::new_bad_request("updates need the id of the resource").with_pointer("/data")
+        })?;
+        Ok(UpdateRequest {
+            id,
+            typ,
+            attributes,
+            relationships,
+        })
+    }
 }
 
 impl<T: Clone> Clone for Request<T> {
@@ -171,39 +824,175 @@ impl<T: Clone> Clone for Request<T> {
                 id: self.data.id.clone(),
                 typ: self.data.typ.clone(),
                 attributes: self.data.attributes.clone(),
-                relationships: match &self.data.relationships {
-                    Some(x) => Some(x.clone()),
-                    None => None,
-                },
+                relationships: self.data.relationships.clone(),
+                extensions: self.data.extensions.clone(),
             },
+            extensions: self.extensions.clone(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize)]
+// Request and ResourceRequest implement Deserialize by hand, in the de module, keeping the
+// members they don't model (e.g. meta, or lid) as extensions
+#[derive(Serialize)]
 pub struct Request<D> {
     pub data: ResourceRequest<D>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize)]
 pub struct Response<P, I> {
     #[serde(flatten)]
     pub primary: ResponseType<P>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub included: Option<Vec<ResourceResponse<I>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub links: Option<Links>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jsonapi: Option<JsonApiObject>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
+// RawResponse is a whole document with every resource's attributes left as raw JSON
+pub type RawResponse = Response<Box<serde_json::value::RawValue>, Box<serde_json::value::RawValue>>;
+
 impl<P, I> Response<P, I> {
+    // with_capacity starts an empty document with room for the given number of primary and
+    // included resources, to be filled with push and include
+    pub fn with_capacity(primary: usize, included: usize) -> Self {
+        Response {
+            primary: ResponseType::Ok(SmallVec::with_capacity(primary)),
+            included: (included > 0).then(|| Vec::with_capacity(included)),
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Extensions::new(),
+        }
+    }
+
+    // from_parts builds a whole compound document at once, allocating each list exactly once
+    pub fn from_parts<R, Ex>(primary: Vec<R>, included: Vec<Ex>) -> Self
+    where
+        R: IntoResponse<Attributes = P>,
+        Ex: IntoResponse<Attributes = I>,
+    {
+        Response {
+            primary: ResponseType::Ok(primary.into_iter().map(|res| res.into_response()).collect()),
+            included: Some(
+                included
+                    .into_iter()
+                    .map(|res| res.into_response())
+                    .collect(),
+            ),
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Extensions::new(),
+        }
+    }
+
+    // into_result takes a document apart into its primary and included resources, or its errors
+    #[allow(clippy::type_complexity)]
+    pub fn into_result(
+        self,
+    ) -> Result<(Vec<ResourceResponse<P>>, Option<Vec<ResourceResponse<I>>>), Vec<Error>> {
+        match self.primary {
+            ResponseType::Ok(data) => Ok((data.into_vec(), self.included)),
+            ResponseType::Error(errors) => Err(errors),
+        }
+    }
+
+    // jsonapi sets the document's top-level jsonapi object, announcing the spec version it follows
+    pub fn jsonapi(mut self, version: SpecVersion) -> Self {
+        self.jsonapi_object().version = Some(version.as_str().to_owned());
+        self
+    }
+
+    // profile announces a profile the document follows in the jsonapi object
+    pub fn profile(mut self, uri: &str) -> Self {
+        let profiles = &mut self.jsonapi_object().profile;
+        if !profiles.iter().any(|profile| profile == uri) {
+            profiles.push(uri.to_owned());
+        }
+        self
+    }
+
+    // jsonapi_object is the jsonapi object to add to. One the document was read with but which
+    // didn't have the spec's shape (and so was kept among the extensions) is replaced
+    fn jsonapi_object(&mut self) -> &mut JsonApiObject {
+        self.extensions.remove("jsonapi");
+        self.jsonapi.get_or_insert_with(JsonApiObject::default)
+    }
+
+    // paginated adds the links and meta of a page to any already in the document
+    pub fn paginated(self, page: pagination::Paginated) -> Self {
+        self.merge_links(page.links).merge_meta(page.meta)
+    }
+
+    // with_collection_meta adds the counts of a collection to any top-level meta already there,
+    // under the given keys (CollectionMetaKeys::default() for pageSize, total, etc.)
+    pub fn with_collection_meta(
+        self,
+        meta: pagination::CollectionMeta,
+        keys: &pagination::CollectionMetaKeys,
+    ) -> Self {
+        self.merge_meta(meta.to_meta(keys))
+    }
+
+    // merge_links and merge_meta replace a member the document was read with but which wasn't
+    // an object (and so was kept among the extensions), like jsonapi_object
+    fn merge_links(mut self, extra: Links) -> Self {
+        if !extra.is_empty() {
+            self.extensions.remove("links");
+            self.links.get_or_insert_with(Links::new).extend(extra);
+        }
+        self
+    }
+
+    fn merge_meta(mut self, extra: Meta) -> Self {
+        if !extra.is_empty() {
+            self.extensions.remove("meta");
+            self.meta.get_or_insert_with(Meta::new).extend(extra);
+        }
+        self
+    }
+
+    // linked fills in the links the generator knows of and the resources don't have yet, see
+    // ResourceResponse::link_with
+    pub fn linked(mut self, generator: &dyn links::LinkGenerator) -> Self {
+        if let ResponseType::Ok(data) = &mut self.primary {
+            data.iter_mut().for_each(|res| res.link_with(generator));
+        }
+        if let Some(included) = &mut self.included {
+            included.iter_mut().for_each(|res| res.link_with(generator));
+        }
+        self
+    }
+
+    // push adds a primary resource. Error documents have no primary data, so it's a no-op on those
+    pub fn push<R>(mut self, resource: R) -> Self
+    where
+        R: IntoResponse<Attributes = P>,
+    {
+        if let ResponseType::Ok(data) = &mut self.primary {
+            data.push(resource.into_response());
+        }
+        self
+    }
+
     pub fn include<Ex>(mut self, resource: Ex) -> Self
     where
         Ex: IntoResponse<Attributes = I>,
     {
-        if self.included.is_none() {
-            self.included = Some(vec![resource.into_response()])
-        } else {
-            self.included
-                .as_mut()
-                .unwrap()
-                .push(resource.into_response())
+        #[cfg(feature = "tracing")]
+        let _span = trace::include_span(1).entered();
+        match self.included.as_mut() {
+            None => self.included = Some(vec![resource.into_response()]),
+            Some(included) => included.push(resource.into_response()),
         }
         self
     }
@@ -212,40 +1001,52 @@ impl<P, I> Response<P, I> {
     where
         Ex: IntoResponse<Attributes = I>,
     {
-        if self.included.is_none() {
-            self.included = Some(
-                resources
-                    .into_iter()
-                    .map(|res| res.into_response())
-                    .collect(),
-            )
-        } else {
-            self.included.as_mut().unwrap().append(
-                &mut resources
-                    .into_iter()
-                    .map(|res| res.into_response())
-                    .collect(),
-            )
-        }
+        #[cfg(feature = "tracing")]
+        let _span = trace::include_span(resources.len()).entered();
+        let included = self
+            .included
+            .get_or_insert_with(|| Vec::with_capacity(resources.len()));
+        included.reserve(resources.len());
+        included.extend(resources.into_iter().map(|res| res.into_response()));
         self
     }
 }
 
+impl<P: serde::Serialize, I: serde::Serialize> Response<P, I> {
+    // write_to serializes the document straight into a writer (a file, socket or buffer) rather
+    // than building an intermediate String
+    pub fn write_to<W: io::Write>(&self, writer: W) -> serde_json::Result<()> {
+        #[cfg(feature = "tracing")]
+        let _span = trace::serialize_span(self).entered();
+        serde_json::to_writer(writer, self)
+    }
+
+    pub fn to_vec(&self) -> serde_json::Result<Vec<u8>> {
+        #[cfg(feature = "tracing")]
+        let _span = trace::serialize_span(self).entered();
+        serde_json::to_vec(self)
+    }
+}
+
 impl<P> Response<P, Option<()>> {
     pub fn finish(self) -> Self {
         self
     }
 }
 
+// PrimaryData holds a single resource inline, so the common one-resource response doesn't
+// allocate; larger collections spill to the heap
+pub type PrimaryData<D> = SmallVec<[ResourceResponse<D>; 1]>;
+
 #[derive(Serialize, Deserialize)]
 pub enum ResponseType<D> {
     #[serde(rename = "data")]
-    Ok(Vec<ResourceResponse<D>>),
+    Ok(PrimaryData<D>),
     #[serde(rename = "errors")]
     Error(Vec<Error>),
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum ErrorStatus {
     #[serde(rename = "400")]
     BadRequest,
@@ -255,127 +1056,436 @@ pub enum ErrorStatus {
     Forbidden,
     #[serde(rename = "404")]
     NotFound,
+    #[serde(rename = "406")]
+    NotAcceptable,
     #[serde(rename = "409")]
     Conflict,
+    #[serde(rename = "412")]
+    PreconditionFailed,
+    #[serde(rename = "413")]
+    PayloadTooLarge,
+    #[serde(rename = "415")]
+    UnsupportedMediaType,
+    #[serde(rename = "422")]
+    UnprocessableEntity,
+    #[serde(rename = "429")]
+    TooManyRequests,
     #[serde(rename = "500")]
     InternalError,
 }
 
-impl std::fmt::Display for ErrorStatus {
+impl ErrorStatus {
+    // as_str is the status code, e.g. "404"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorStatus::BadRequest => "400",
+            ErrorStatus::Unauthorized => "401",
+            ErrorStatus::Forbidden => "403",
+            ErrorStatus::NotFound => "404",
+            ErrorStatus::NotAcceptable => "406",
+            ErrorStatus::Conflict => "409",
+            ErrorStatus::PreconditionFailed => "412",
+            ErrorStatus::PayloadTooLarge => "413",
+            ErrorStatus::UnsupportedMediaType => "415",
+            ErrorStatus::UnprocessableEntity => "422",
+            ErrorStatus::TooManyRequests => "429",
+            ErrorStatus::InternalError => "500",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", serde_json::to_string::<ErrorStatus>(self).unwrap())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Error {
+    // id identifies this occurrence of the problem, e.g. a correlation id to find it in the logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub status: ErrorStatus,
+    // this is a human readable code, not a numeric code (that is status, above)
+    pub code: Option<String>,
+    pub title: String,
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<ErrorSource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+    // cause is the error this one was made of, for logging. It's never part of the document, nor
+    // compared
+    #[serde(skip)]
+    pub cause: Option<std::sync::Arc<dyn std::error::Error + Send + Sync>>,
+}
+
+// ErrorSource points at the part of the request document (or query parameter) that caused an error
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ErrorSource {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pointer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parameter: Option<String>,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error {}: {}", self.status, self.title)
+    }
+}
+
+impl PartialEq for Error {
+    fn eq(&self, other: &Error) -> bool {
+        self.id == other.id
+            && self.status == other.status
+            && self.code == other.code
+            && self.title == other.title
+            && self.detail == other.detail
+            && self.source == other.source
+            && self.meta == other.meta
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        let cause = self.cause.as_ref()?;
+        Some(cause.as_ref())
+    }
+}
+
+// Errors are the problems of a request reported at once, e.g. every relationship which is wrong.
+// There's always at least one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Errors(pub Vec<Error>);
+
+impl std::fmt::Display for Errors {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            serde_json::to_string::<ErrorStatus>(&self).unwrap()
-        )
+        for (n, err) in self.0.iter().enumerate() {
+            if n > 0 {
+                f.write_str("; ")?;
+            }
+            err.fmt(f)?;
+        }
+        Ok(())
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Error {
-    pub status: ErrorStatus,
-    // this is a human readable code, not a numeric code (that is status, above)
-    pub code: Option<String>,
-    pub title: String,
-    pub detail: Option<String>,
-}
+impl std::error::Error for Errors {}
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "error {}: {}", self.status, self.title)
+impl From<Error> for Errors {
+    fn from(err: Error) -> Errors {
+        Errors(vec![err])
     }
 }
 
 impl Error {
     pub fn new_not_found(title: &str) -> Self {
         Error {
+            id: None,
             status: ErrorStatus::NotFound,
             code: Some("Not Found".to_owned()),
             title: title.to_owned(),
             detail: None,
+            source: None,
+            meta: None,
+            cause: None,
         }
     }
     pub fn new_bad_request(title: &str) -> Self {
         Error {
+            id: None,
             status: ErrorStatus::BadRequest,
             code: Some("Bad Request".to_owned()),
             title: title.to_owned(),
             detail: None,
+            source: None,
+            meta: None,
+            cause: None,
         }
     }
     pub fn new_internal_error(title: &str) -> Self {
         Error {
+            id: None,
             status: ErrorStatus::InternalError,
             code: Some("Internal Server Error".to_owned()),
             title: title.to_owned(),
             detail: None,
+            source: None,
+            meta: None,
+            cause: None,
         }
     }
     pub fn new_forbidden(title: &str) -> Self {
         Error {
+            id: None,
             status: ErrorStatus::Forbidden,
             code: Some("Forbidden".into()),
             title: title.into(),
             detail: None,
+            source: None,
+            meta: None,
+            cause: None,
         }
     }
     pub fn new_unauthorized(title: &str) -> Self {
         Error {
+            id: None,
             status: ErrorStatus::Unauthorized,
             code: Some("Unauthorized".into()),
             title: title.into(),
             detail: None,
+            source: None,
+            meta: None,
+            cause: None,
+        }
+    }
+    pub fn new_not_acceptable(title: &str) -> Self {
+        Error {
+            id: None,
+            status: ErrorStatus::NotAcceptable,
+            code: Some("Not Acceptable".into()),
+            title: title.into(),
+            detail: None,
+            source: None,
+            meta: None,
+            cause: None,
         }
     }
     pub fn new_conflict(title: &str) -> Self {
         Error {
+            id: None,
             status: ErrorStatus::Conflict,
             code: Some("Confict".to_owned()),
             title: title.into(),
             detail: None,
+            source: None,
+            meta: None,
+            cause: None,
+        }
+    }
+    pub fn new_precondition_failed(title: &str) -> Self {
+        Error {
+            id: None,
+            status: ErrorStatus::PreconditionFailed,
+            code: Some("Precondition Failed".into()),
+            title: title.into(),
+            detail: None,
+            source: None,
+            meta: None,
+            cause: None,
+        }
+    }
+    pub fn new_payload_too_large(title: &str) -> Self {
+        Error {
+            id: None,
+            status: ErrorStatus::PayloadTooLarge,
+            code: Some("Payload Too Large".into()),
+            title: title.into(),
+            detail: None,
+            source: None,
+            meta: None,
+            cause: None,
         }
     }
+    pub fn new_unsupported_media_type(title: &str) -> Self {
+        Error {
+            id: None,
+            status: ErrorStatus::UnsupportedMediaType,
+            code: Some("Unsupported Media Type".into()),
+            title: title.into(),
+            detail: None,
+            source: None,
+            meta: None,
+            cause: None,
+        }
+    }
+    pub fn new_unprocessable_entity(title: &str) -> Self {
+        Error {
+            id: None,
+            status: ErrorStatus::UnprocessableEntity,
+            code: Some("Unprocessable Entity".into()),
+            title: title.into(),
+            detail: None,
+            source: None,
+            meta: None,
+            cause: None,
+        }
+    }
+
+    // new_too_many_requests tells a client it's been rate limited, with the number of seconds
+    // (rounded up) to wait before retrying in meta.retryAfter, and in the Retry-After header
+    // when answered by actix
+    pub fn new_too_many_requests(retry_after: Duration) -> Self {
+        let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        Error {
+            id: None,
+            status: ErrorStatus::TooManyRequests,
+            code: Some("Too Many Requests".into()),
+            title: format!("too many requests, retry in {} seconds", secs),
+            detail: None,
+            source: None,
+            meta: Some(Meta::from([("retryAfter".to_owned(), secs.into())])),
+            cause: None,
+        }
+    }
+
+    // retry_after is the number of seconds a rate limited client is told to wait
+    pub fn retry_after(&self) -> Option<u64> {
+        if self.status != ErrorStatus::TooManyRequests {
+            return None;
+        }
+        self.meta.as_ref()?.get("retryAfter")?.as_u64()
+    }
+
+    // with_cause keeps the error this one was made of, see std::error::Error::source
+    pub fn with_cause<E: std::error::Error + Send + Sync + 'static>(mut self, cause: E) -> Self {
+        self.cause = Some(std::sync::Arc::new(cause));
+        self
+    }
+
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    // with_pointer sets a JSON pointer (e.g. "/data/attributes/title") to the offending request member
+    pub fn with_pointer(mut self, pointer: &str) -> Self {
+        self.source.get_or_insert_with(ErrorSource::default).pointer = Some(pointer.into());
+        self
+    }
+
+    // with_parameter names the query parameter (e.g. "page[size]") that caused the error
+    pub fn with_parameter(mut self, parameter: &str) -> Self {
+        self.source
+            .get_or_insert_with(ErrorSource::default)
+            .parameter = Some(parameter.into());
+        self
+    }
 }
 
 // IntoResponse is used to create _successful_ jsonapi responses from a resource struct
 // it is not used to create error responses (return a jsonapi::Error::into() for that)
+// into_response consumes the resource, so attributes and relations are moved into the
+// response rather than cloned, and resources don't need to implement Clone
 pub trait IntoResponse {
     type Attributes;
 
     fn into_response(self) -> ResourceResponse<Self::Attributes>;
 }
 
+// a ResourceResponse is already a response, e.g. one hydrated from a database row
+impl<D> IntoResponse for ResourceResponse<D> {
+    type Attributes = D;
+
+    fn into_response(self) -> ResourceResponse<D> {
+        self
+    }
+}
+
 pub trait FromRequest
 where
     Self: Sized,
 {
     type Attributes;
     fn from_request(req: Request<Self::Attributes>) -> Result<Self, Error>;
+
+    // from_request_all is from_request reporting every problem of the request it can rather than
+    // the first, see FromRelationships::from_relationships_all. It's what the JsonApi extractor
+    // answers with
+    fn from_request_all(req: Request<Self::Attributes>) -> Result<Self, Errors> {
+        Self::from_request(req).map_err(Errors::from)
+    }
+}
+
+// a Request is its own FromRequest, for handlers which build their resource themselves (see
+// JsonApi::with_context)
+impl<D> FromRequest for Request<D> {
+    type Attributes = D;
+
+    fn from_request(req: Request<D>) -> Result<Self, Error> {
+        Ok(req)
+    }
+}
+
+// FromRequestWithContext is FromRequest for types whose construction depends on more than the
+// request, e.g. on the current user or tenant to check ownership against. ctx is whatever the
+// handler has at hand, and errors are passed on as they are, so a check can answer with 403
+pub trait FromRequestWithContext<C>
+where
+    Self: Sized,
+{
+    type Attributes;
+    fn from_request_with_context(req: Request<Self::Attributes>, ctx: &C) -> Result<Self, Error>;
 }
 
 pub trait IntoRelationships {
-    fn into_relationships(self) -> Option<BTreeMap<String, RelationshipData>>;
+    fn into_relationships(self) -> Option<Relationships>;
 }
 
 pub trait FromRelationships
 where
     Self: Sized,
 {
-    fn from_relationships(rels: Option<BTreeMap<String, RelationshipData>>) -> Result<Self, Error>;
+    fn from_relationships(rels: Option<Relationships>) -> Result<Self, Error>;
+
+    // from_relationships_all is from_relationships reporting every relationship which is wrong
+    // rather than the first, e.g. to answer a request with all its problems at once
+    fn from_relationships_all(rels: Option<Relationships>) -> Result<Self, Vec<Error>> {
+        Self::from_relationships(rels).map_err(|err| vec![err])
+    }
+}
+
+pub trait IntoMeta {
+    fn into_meta(self) -> Option<Meta>;
+}
+
+impl IntoMeta for Meta {
+    fn into_meta(self) -> Option<Meta> {
+        Some(self)
+    }
+}
+
+impl IntoMeta for serde_json::Map<String, serde_json::Value> {
+    fn into_meta(self) -> Option<Meta> {
+        Some(self.into_iter().collect())
+    }
+}
+
+impl<M: IntoMeta> IntoMeta for Option<M> {
+    fn into_meta(self) -> Option<Meta> {
+        self.and_then(IntoMeta::into_meta)
+    }
+}
+
+pub trait IntoLinks {
+    fn into_links(self) -> Option<Links>;
+}
+
+impl IntoLinks for Links {
+    fn into_links(self) -> Option<Links> {
+        Some(self)
+    }
+}
+
+impl<L: IntoLinks> IntoLinks for Option<L> {
+    fn into_links(self) -> Option<Links> {
+        self.and_then(IntoLinks::into_links)
+    }
 }
 
 impl IntoRelationships for () {
-    fn into_relationships(self) -> Option<BTreeMap<String, RelationshipData>> {
+    fn into_relationships(self) -> Option<Relationships> {
         None
     }
 }
 
 impl FromRelationships for () {
-    fn from_relationships(rels: Option<BTreeMap<String, RelationshipData>>) -> Result<(), Error> {
+    fn from_relationships(rels: Option<Relationships>) -> Result<(), Error> {
         match rels {
             None => Ok(()),
             Some(map) => {
-                if map.len() == 0 {
+                if map.is_empty() {
                     Ok(())
                 } else {
                     Err(Error::new_bad_request(
@@ -426,6 +1536,99 @@ impl<I: FromID> FromRelationship for Vec<I> {
     }
 }
 
+// FromRelationshipField is how the FromRelationships derive builds each field. Going through a
+// trait lets the field's type decide whether the relationship is optional, however it's spelled
+// (including type aliases), rather than the derive guessing from the type's name.
+pub trait FromRelationshipField
+where
+    Self: Sized,
+{
+    // from_missing provides the value for an absent relationship, None if it is mandatory
+    fn from_missing() -> Option<Self>;
+    fn from_relationship_field(r: Relationship) -> Result<Self, Error>;
+
+    // from_relationship_data is what the derive calls. Only fields which can be cleared accept a
+    // relationship without linkage (`data: null`), see Maybe
+    fn from_relationship_data(rel: RelationshipData) -> Result<Self, Error> {
+        Self::from_relationship_field(rel.into_data()?)
+    }
+}
+
+impl<R: FromRelationship> FromRelationshipField for R {
+    fn from_missing() -> Option<Self> {
+        None
+    }
+
+    fn from_relationship_field(r: Relationship) -> Result<Self, Error> {
+        R::from_relationship(r)
+    }
+}
+
+impl<R: FromRelationship> FromRelationshipField for Maybe<R> {
+    fn from_missing() -> Option<Self> {
+        Some(Maybe::Absent)
+    }
+
+    fn from_relationship_field(r: Relationship) -> Result<Self, Error> {
+        Ok(Maybe::Value(R::from_relationship(r)?))
+    }
+
+    fn from_relationship_data(rel: RelationshipData) -> Result<Self, Error> {
+        match rel.data {
+            Some(r) => Self::from_relationship_field(r),
+            None => Ok(Maybe::Null),
+        }
+    }
+}
+
+impl<R: FromRelationship> FromRelationshipField for Option<R> {
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+
+    fn from_relationship_field(r: Relationship) -> Result<Self, Error> {
+        Ok(Some(R::from_relationship(r)?))
+    }
+}
+
+// IntoRelationshipField is the IntoRelationships derive counterpart of FromRelationshipField,
+// returning None for relationships which should be left out of the document
+pub trait IntoRelationshipField {
+    fn into_relationship_field(self, resource_name: &str) -> Option<Relationship>;
+
+    // into_relationship_data is what the derive inserts. Fields holding a whole relationship
+    // object (e.g. a paginated one) override it to keep their links and meta
+    fn into_relationship_data(self, resource_name: &str) -> Option<RelationshipData>
+    where
+        Self: Sized,
+    {
+        self.into_relationship_field(resource_name)
+            .map(RelationshipData::from)
+    }
+}
+
+impl IntoRelationshipField for RelationshipData {
+    fn into_relationship_field(self, _: &str) -> Option<Relationship> {
+        self.data
+    }
+
+    fn into_relationship_data(self, _: &str) -> Option<RelationshipData> {
+        Some(self)
+    }
+}
+
+impl<R: IntoRelationship> IntoRelationshipField for R {
+    fn into_relationship_field(self, resource_name: &str) -> Option<Relationship> {
+        Some(self.into_relationship(resource_name))
+    }
+}
+
+impl<R: IntoRelationship> IntoRelationshipField for Option<R> {
+    fn into_relationship_field(self, resource_name: &str) -> Option<Relationship> {
+        self.map(|r| r.into_relationship(resource_name))
+    }
+}
+
 impl<I> IntoRelationship for I
 where
     ID: From<I>,
@@ -457,8 +1660,12 @@ where
 impl<R: IntoResponse, I> From<R> for Response<R::Attributes, I> {
     fn from(r: R) -> Self {
         Response {
-            primary: ResponseType::Ok(vec![r.into_response()]),
+            primary: ResponseType::Ok(smallvec![r.into_response()]),
             included: None,
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Extensions::new(),
         }
     }
 }
@@ -469,51 +1676,369 @@ impl<R: IntoResponse, I> From<Vec<R>> for Response<R::Attributes, I> {
         Response {
             primary: ResponseType::Ok(data),
             included: None,
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Extensions::new(),
+        }
+    }
+}
+
+// JsonApiResult is what handlers naturally produce: a resource (or a list of them) or the error
+// explaining why there isn't one. Either way it converts into a document with Response::from
+pub type JsonApiResult<T> = Result<T, Error>;
+
+impl<R: IntoResponse, I> From<JsonApiResult<R>> for Response<R::Attributes, I> {
+    fn from(result: JsonApiResult<R>) -> Self {
+        match result {
+            Ok(r) => Response::from(r),
+            Err(e) => Response::from(e),
+        }
+    }
+}
+
+impl<R: IntoResponse, I> From<JsonApiResult<Vec<R>>> for Response<R::Attributes, I> {
+    fn from(result: JsonApiResult<Vec<R>>) -> Self {
+        match result {
+            Ok(v) => Response::from(v),
+            Err(e) => Response::from(e),
+        }
+    }
+}
+
+// errors don't depend on the resource types, so any document can be an error document
+impl<P, I> From<Error> for Response<P, I> {
+    fn from(e: Error) -> Self {
+        Response {
+            primary: ResponseType::Error(vec![e]),
+            included: None,
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Extensions::new(),
+        }
+    }
+}
+
+impl<P, I> From<Errors> for Response<P, I> {
+    fn from(errors: Errors) -> Self {
+        errors.0.into()
+    }
+}
+
+impl<P, I> From<Vec<Error>> for Response<P, I> {
+    fn from(v: Vec<Error>) -> Self {
+        Response {
+            primary: ResponseType::Error(v),
+            included: None,
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Extensions::new(),
+        }
+    }
+}
+
+// Stuff that should be moved into a jsonapi-actixweb crate at a later date
+pub struct JsonApi<R>(R);
+
+impl<R> JsonApi<R> {
+    pub fn into_inner(self) -> R {
+        self.0
+    }
+}
+
+impl<D> JsonApi<Request<D>> {
+    // with_context builds a FromRequestWithContext type from the extracted request, e.g.
+    // `req.with_context::<Article, _>(&user)?` with `req: JsonApi<Request<ArticleAttributes>>`
+    pub fn with_context<R, C>(self, ctx: &C) -> Result<R, Error>
+    where
+        R: FromRequestWithContext<C, Attributes = D>,
+    {
+        R::from_request_with_context(self.0, ctx)
+    }
+}
+
+impl<R> ops::Deref for JsonApi<R> {
+    type Target = R;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+// the largest request body the extractor accepts, the same as actix's own Json extractor
+#[cfg(feature = "actixweb")]
+pub(crate) const BODY_LIMIT: usize = 2_097_152;
+
+// JsonApiConfig configures the JsonApi extractor. It's registered as app data, e.g.
+// `App::new().app_data(JsonApiConfig::default().strict(true))`
+#[cfg(feature = "actixweb")]
+#[derive(Clone, Default)]
+pub struct JsonApiConfig {
+    strict: bool,
+    version: SpecVersion,
+    extensions: Vec<String>,
+    client_ids: ClientIdPolicy,
+    links: Option<links::SharedLinkGenerator>,
+    host_aware_links: bool,
+    route_names: Option<std::sync::Arc<links::RouteNames>>,
+    catalog: Option<std::sync::Arc<catalog::Catalog>>,
+    #[cfg(feature = "jsonschema")]
+    attribute_schemas: Option<std::sync::Arc<validation::AttributeSchemas>>,
+    id_rules: Option<ids::IdRules>,
+    localizer: Option<std::sync::Arc<dyn locale::ErrorLocalizer>>,
+    status_policy: Option<std::sync::Arc<dyn status::StatusPolicy>>,
+    redacted: bool,
+    reporter: Option<std::sync::Arc<redaction::Reporter>>,
+}
+
+#[cfg(feature = "actixweb")]
+impl JsonApiConfig {
+    // of is the config registered with the app of a request, or the default one
+    pub(crate) fn of(req: &actix_web::HttpRequest) -> Cow<'_, JsonApiConfig> {
+        match req.app_data::<JsonApiConfig>() {
+            Some(config) => Cow::Borrowed(config),
+            None => Cow::Owned(JsonApiConfig::default()),
         }
     }
+
+    // strict rejects requests with members the spec doesn't define, at the top level or in the
+    // resource object. By default (lenient) they're ignored
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    // version selects the spec version requests are held to, by default 1.1
+    pub fn version(mut self, version: SpecVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    // client_ids sets the policy for ids sent with POST requests, which create resources. By
+    // default they're accepted
+    pub fn client_ids(mut self, policy: ClientIdPolicy) -> Self {
+        self.client_ids = policy;
+        self
+    }
+
+    // extension declares an extension (by URI) the server supports. Requests using any other
+    // extension are refused with 415, or 406 if it's only asked for in the response
+    pub fn extension(mut self, uri: &str) -> Self {
+        self.extensions.push(uri.to_owned());
+        self
+    }
+
+    // links registers the generator responses are linked with, see links::from_request
+    pub fn links<G: links::LinkGenerator + Send + Sync + 'static>(mut self, generator: G) -> Self {
+        self.links = Some(std::sync::Arc::new(generator));
+        self
+    }
+
+    pub fn link_generator(&self) -> Option<&links::SharedLinkGenerator> {
+        self.links.as_ref()
+    }
+
+    // host_aware_links puts the origin the request was made to (see links::request_origin) in
+    // front of the generator's base URL, which is then just the path prefix, e.g. "/v1". The
+    // origin comes from headers the client can set, so it should only be enabled behind a proxy
+    // which overwrites them
+    pub fn host_aware_links(mut self, enabled: bool) -> Self {
+        self.host_aware_links = enabled;
+        self
+    }
+
+    // route_names builds the links of the named resource types from the app's own routes,
+    // see links::RouteNames. Other types still get theirs from the registered generator
+    pub fn route_names(mut self, names: links::RouteNames) -> Self {
+        self.route_names = Some(std::sync::Arc::new(names));
+        self
+    }
+
+    // catalog makes responses link their resources to their entry in the catalog with
+    // describedby. It's still up to the app to serve it, see catalog::Catalog::service
+    pub fn catalog(mut self, catalog: std::sync::Arc<catalog::Catalog>) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    // attribute_schemas holds the attributes of requests to the schemas registered for their
+    // type, see validation::AttributeSchemas
+    #[cfg(feature = "jsonschema")]
+    pub fn attribute_schemas(
+        mut self,
+        schemas: std::sync::Arc<validation::AttributeSchemas>,
+    ) -> Self {
+        self.attribute_schemas = Some(schemas);
+        self
+    }
+
+    // id_rules holds the ids of requests, their resource's and their linkage's, to rules. By
+    // default any id is accepted
+    pub fn id_rules(mut self, rules: ids::IdRules) -> Self {
+        self.id_rules = Some(rules);
+        self
+    }
+
+    // localizer translates the errors of responses into the language of the request. The app
+    // has to be wrapped in errors::ErrorDocuments for it to reach errors handlers return
+    pub fn localizer<L: locale::ErrorLocalizer + 'static>(mut self, localizer: L) -> Self {
+        self.localizer = Some(std::sync::Arc::new(localizer));
+        self
+    }
+
+    // status_policy decides the status of error documents, see status::StatusPolicy. As with
+    // localizer, the app has to be wrapped in errors::ErrorDocuments
+    pub fn status_policy<P: status::StatusPolicy + 'static>(mut self, policy: P) -> Self {
+        self.status_policy = Some(std::sync::Arc::new(policy));
+        self
+    }
+
+    // redacted answers internal errors and bodies which can't be parsed with a generic message,
+    // see redaction. As with localizer, the app has to be wrapped in errors::ErrorDocuments
+    pub fn redacted(mut self, redacted: bool) -> Self {
+        self.redacted = redacted;
+        self
+    }
+
+    // reporter is handed the errors redacted, with the correlation id they're answered with
+    pub fn reporter<F: Fn(&Error) + Send + Sync + 'static>(mut self, reporter: F) -> Self {
+        self.reporter = Some(std::sync::Arc::new(reporter));
+        self
+    }
+}
+
+// StrictRequest and StrictResourceRequest mirror Request and ResourceRequest for strict mode.
+// Members the spec allows but the extractor has no use for are accepted and discarded
+#[cfg(feature = "actixweb")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+#[allow(dead_code)]
+struct StrictRequest<D> {
+    data: StrictResourceRequest<D>,
+    meta: Option<serde_json::Value>,
+    jsonapi: Option<serde_json::Value>,
+    links: Option<serde_json::Value>,
+    included: Option<serde_json::Value>,
+}
+
+#[cfg(feature = "actixweb")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+#[allow(dead_code)]
+struct StrictResourceRequest<D> {
+    id: Option<ID>,
+    lid: Option<serde_json::Value>,
+    #[serde(rename = "type")]
+    typ: String,
+    attributes: D,
+    relationships: Option<Relationships>,
+    links: Option<serde_json::Value>,
+    meta: Option<serde_json::Value>,
+}
+
+// FieldNames reads only the attribute and relationship names of a request, which strict mode
+// checks against the names the spec reserves before the request is decoded
+#[cfg(feature = "actixweb")]
+#[derive(Deserialize)]
+struct FieldNames {
+    data: ResourceFieldNames,
 }
 
-impl From<Error> for Response<(), ()> {
-    fn from(e: Error) -> Self {
-        Response {
-            primary: ResponseType::Error(vec![e]),
-            included: None,
-        }
-    }
+#[cfg(feature = "actixweb")]
+#[derive(Deserialize)]
+struct ResourceFieldNames {
+    #[serde(default)]
+    attributes: Option<BTreeMap<String, IgnoredAny>>,
+    #[serde(default)]
+    relationships: Option<BTreeMap<String, IgnoredAny>>,
 }
 
-impl From<Vec<Error>> for Response<(), ()> {
-    fn from(v: Vec<Error>) -> Self {
-        Response {
-            primary: ResponseType::Error(v),
-            included: None,
-        }
-    }
+// LocalId reads only the lid of a request, which 1.0 doesn't have, strict or not
+#[cfg(feature = "actixweb")]
+#[derive(Deserialize)]
+struct LocalId {
+    data: ResourceLocalId,
 }
 
-// Stuff that should be moved into a jsonapi-actixweb crate at a later date
-pub struct JsonApi<R>(R);
+#[cfg(feature = "actixweb")]
+#[derive(Deserialize)]
+struct ResourceLocalId {
+    #[serde(default)]
+    lid: Option<IgnoredAny>,
+}
 
-impl<R> JsonApi<R> {
-    pub fn into_inner(self) -> R {
-        self.0
+#[cfg(feature = "actixweb")]
+impl FieldNames {
+    // check rejects fields named type or id, and attributes named relationships or links, which
+    // would be mistaken for the resource object's own members
+    fn check(&self) -> Result<(), Error> {
+        let attributes = self.data.attributes.iter().flat_map(|attrs| attrs.keys());
+        let relationships = self.data.relationships.iter().flat_map(|rels| rels.keys());
+        let reserved = attributes
+            .map(|name| {
+                (
+                    name,
+                    "attributes",
+                    ["type", "id", "relationships", "links"].as_slice(),
+                )
+            })
+            .chain(relationships.map(|name| (name, "relationships", ["type", "id"].as_slice())))
+            .find(|(name, _, reserved)| reserved.contains(&name.as_str()));
+        match reserved {
+            Some((name, member, _)) => Err(Error::new_bad_request(&format!(
+                "'{}' is reserved and can't be used as a field name",
+                name
+            ))
+            .with_pointer(&format!("/data/{}/{}", member, name))),
+            None => Ok(()),
+        }
     }
 }
 
-impl<R> ops::Deref for JsonApi<R> {
-    type Target = R;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
+#[cfg(feature = "actixweb")]
+impl<D> From<StrictRequest<D>> for Request<D> {
+    // from keeps the members the spec allows as extensions, as the lenient Deserialize does
+    fn from(req: StrictRequest<D>) -> Self {
+        let data = req.data;
+        Request {
+            data: ResourceRequest {
+                id: data.id,
+                typ: data.typ,
+                attributes: data.attributes,
+                relationships: data.relationships,
+                extensions: members([
+                    ("lid", data.lid),
+                    ("links", data.links),
+                    ("meta", data.meta),
+                ]),
+            },
+            extensions: members([
+                ("meta", req.meta),
+                ("jsonapi", req.jsonapi),
+                ("links", req.links),
+                ("included", req.included),
+            ]),
+        }
     }
 }
 
+// members collects the members of a strict request which are there
+#[cfg(feature = "actixweb")]
+fn members<const N: usize>(members: [(&str, Option<serde_json::Value>); N]) -> Extensions {
+    let members = members.into_iter();
+    members
+        .filter_map(|(name, value)| Some((name.to_owned(), value?)))
+        .collect()
+}
+
 #[cfg(feature = "actixweb")]
 impl<R: FromRequest> FromWebRequest for JsonApi<R>
 where
     R::Attributes: DeserializeOwned,
 {
-    type Error = Error;
+    type Error = errors::Rejection;
 
     type Future = JsonApiExtractFut<R>;
 
@@ -521,81 +2046,391 @@ where
         req: &actix_web::HttpRequest,
         payload: &mut actix_web::dev::Payload,
     ) -> Self::Future {
+        let config = JsonApiConfig::of(req);
+        errors::warn_unwrapped(req);
+        // binary bodies are the encoding of the plain media type, whose parameters need no checks
+        let binary = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(encoding::Encoding::from_media_type);
+        if binary.is_none() {
+            if let Err(err) = check_content_type(req, &config) {
+                return JsonApiExtractFut::failed(req, err);
+            }
+        }
+        let length = req
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok()?.parse::<usize>().ok());
+        if let Some(length) = length.filter(|length| *length > BODY_LIMIT) {
+            return JsonApiExtractFut::failed(
+                req,
+                JsonPayloadError::OverflowKnownLength {
+                    length,
+                    limit: BODY_LIMIT,
+                }
+                .into(),
+            );
+        }
         JsonApiExtractFut {
-            fut: JsonBody::new(req, payload, None, true),
+            payload: Some(Decompress::from_headers(payload.take(), req.headers())),
+            // a known length sizes the buffer up front, so it's filled without reallocating
+            body: BytesMut::with_capacity(length.unwrap_or(8192)),
+            req: req.clone(),
+            encoding: binary.unwrap_or_default(),
+            creating: req.method() == actix_web::http::Method::POST,
+            err: None,
+            #[cfg(feature = "tracing")]
+            span: trace::extract_span(),
+            _request: PhantomData,
         }
     }
 }
 
+// check_content_type makes sure a request body is JSON, and that the JSON:API media type
+// parameters it comes with are supported
+#[cfg(feature = "actixweb")]
+pub(crate) fn check_content_type(
+    req: &actix_web::HttpRequest,
+    config: &JsonApiConfig,
+) -> Result<(), Error> {
+    match req.mime_type() {
+        Ok(Some(mime)) if mime.subtype() == mime::JSON || mime.suffix() == Some(mime::JSON) => {}
+        _ => return Err(JsonPayloadError::ContentType.into()),
+    }
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok());
+    media::negotiate_content_type(
+        content_type.unwrap_or_default(),
+        config.version,
+        &config.extensions,
+    )
+    .map(|_| ())
+}
+
+// JsonApiExtractFut buffers the request body and decodes it straight into the jsonapi Request
 #[cfg(feature = "actixweb")]
 pub struct JsonApiExtractFut<T: FromRequest> {
-    fut: JsonBody<Request<T::Attributes>>,
+    payload: Option<Decompress<actix_web::dev::Payload>>,
+    body: BytesMut,
+    // req is kept for the JsonApiConfig registered with the app, which is read from it rather
+    // than cloned for every request
+    req: actix_web::HttpRequest,
+    encoding: encoding::Encoding,
+    // creating is set for POST requests, whose ids are subject to the client id policy
+    creating: bool,
+    err: Option<Error>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+    _request: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "actixweb")]
+impl<T: FromRequest> JsonApiExtractFut<T> {
+    fn failed(req: &actix_web::HttpRequest, err: Error) -> Self {
+        JsonApiExtractFut {
+            payload: None,
+            body: BytesMut::new(),
+            req: req.clone(),
+            encoding: encoding::Encoding::Json,
+            creating: false,
+            err: Some(err),
+            #[cfg(feature = "tracing")]
+            span: trace::extract_span(),
+            _request: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl<T: FromRequest> JsonApiExtractFut<T> {
+    // decode parses the body, the parser's message redacted if errors are
+    fn decode<R: DeserializeOwned>(&mut self) -> Result<R, Error> {
+        let decoded = match self.encoding {
+            encoding::Encoding::Json => decode_request(&mut self.body),
+            #[allow(unreachable_patterns)]
+            binary => binary.decode(&self.body),
+        };
+        let config = JsonApiConfig::of(&self.req);
+        decoded.map_err(|err| redaction::redact_payload(&config, err))
+    }
 }
 
 #[cfg(feature = "actixweb")]
 impl From<JsonPayloadError> for Error {
+    // from answers bodies too large with 413, of the wrong type with 415, and which can't be
+    // parsed with 400, the parser's message in the detail
     fn from(err: JsonPayloadError) -> Error {
-        Error::new_bad_request(&err.to_string())
+        let converted = match &err {
+            JsonPayloadError::Overflow { .. } | JsonPayloadError::OverflowKnownLength { .. } => {
+                Error::new_payload_too_large(&err.to_string())
+            }
+            JsonPayloadError::ContentType => Error::new_unsupported_media_type(&err.to_string()),
+            JsonPayloadError::Deserialize(de) => invalid_body(de),
+            _ => Error::new_bad_request(&err.to_string()),
+        };
+        converted.with_cause(err)
     }
 }
 
+#[cfg(feature = "actixweb")]
+fn invalid_body(err: impl Display) -> Error {
+    let mut invalid = Error::new_bad_request("the request body isn't a valid document");
+    invalid.detail = Some(err.to_string());
+    invalid
+}
+
 #[cfg(feature = "actixweb")]
 impl<T: FromRequest> Future for JsonApiExtractFut<T>
 where
     T::Attributes: DeserializeOwned,
 {
-    type Output = Result<JsonApi<T>, Error>;
+    type Output = Result<JsonApi<T>, errors::Rejection>;
 
     fn poll(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Self::Output> {
         let this = self.get_mut();
+        #[cfg(feature = "tracing")]
+        let span = this.span.clone();
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+        let res = this.poll_extract(cx);
+        #[cfg(feature = "tracing")]
+        if let Poll::Ready(Err(err)) = &res {
+            span.record("error.status", trace::statuses(&err.0).as_str());
+        }
+        res.map_err(|errors| errors::Rejection::new(&this.req, errors.0))
+    }
+}
 
-        let res = ready!(Pin::new(&mut this.fut).poll(cx));
+#[cfg(feature = "actixweb")]
+impl<T: FromRequest> JsonApiExtractFut<T>
+where
+    T::Attributes: DeserializeOwned,
+{
+    fn poll_extract(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<JsonApi<T>, Errors>> {
+        let req = ready!(self.poll_request(cx))?;
+        Poll::Ready(T::from_request_all(req).map(JsonApi))
+    }
 
-        let res = match res {
-            Err(err) => Err(err.into()),
-            Ok(data) => Ok(Json(data)),
+    // poll_request reads the body into a request, held to the JsonApiConfig
+    fn poll_request(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Result<Request<T::Attributes>, Error>> {
+        if let Some(err) = self.err.take() {
+            return Poll::Ready(Err(err));
+        }
+        let payload = self
+            .payload
+            .as_mut()
+            .expect("JsonApiExtractFut polled after completion");
+
+        while let Some(chunk) = ready!(Pin::new(&mut *payload).poll_next(cx)) {
+            let chunk = chunk.map_err(JsonPayloadError::Payload)?;
+            if self.body.len() + chunk.len() > BODY_LIMIT {
+                return Poll::Ready(Err(JsonPayloadError::Overflow { limit: BODY_LIMIT }.into()));
+            }
+            self.body.extend_from_slice(&chunk);
+        }
+        self.payload = None;
+        let http = self.req.clone();
+        let config = JsonApiConfig::of(&http);
+        #[cfg(feature = "jsonschema")]
+        if let Some(schemas) = &config.attribute_schemas {
+            schemas.validate_request(&self.body, self.encoding)?;
+        }
+        // serde_json rather than decode_request, which may parse the buffer in place. A body which
+        // doesn't parse here is left for decode_request to report
+        if !config.version.accepts_lid() {
+            let local = self.encoding.decode::<LocalId>(&self.body);
+            if local.is_ok_and(|local| local.data.lid.is_some()) {
+                return Poll::Ready(Err(Error::new_bad_request(&format!(
+                    "lid isn't supported by JSON:API {}",
+                    config.version
+                ))
+                .with_pointer("/data/lid")));
+            }
+        }
+        let req = if config.strict {
+            if let Ok(names) = self.encoding.decode::<FieldNames>(&self.body) {
+                names.check()?;
+            }
+            let req: StrictRequest<T::Attributes> = self.decode()?;
+            req.into()
+        } else {
+            self.decode::<Request<T::Attributes>>()?
         };
+        #[cfg(feature = "tracing")]
+        {
+            self.span.record("resource.type", req.data.typ.as_str());
+            let count = req.data.relationships.as_ref().map_or(0, |rels| rels.len());
+            self.span.record("relationships.count", count);
+        }
+        if self.creating {
+            config.client_ids.check(req.data.id.as_ref())?;
+        }
+        if let Some(rules) = &config.id_rules {
+            rules.check_request(&req.data)?;
+        }
+        Poll::Ready(Ok(req))
+    }
+}
 
-        Poll::Ready(match res {
-            Err(err) => Err(err),
-            Ok(json_req) => match T::from_request(json_req.into_inner()) {
-                Ok(inner) => Ok(JsonApi(inner)),
-                Err(err) => Err(err),
-            },
-        })
+// decode_request parses a buffered request body. simd-json parses in place, so the buffer is
+// handed over mutably rather than copied
+#[cfg(all(feature = "actixweb", not(feature = "simd")))]
+fn decode_request<R: DeserializeOwned>(body: &mut [u8]) -> Result<R, Error> {
+    // tracking the path slows every decode down, so it's only done to point at what didn't parse
+    if let Ok(req) = serde_json::from_slice(body) {
+        return Ok(req);
     }
+    let mut de = serde_json::Deserializer::from_slice(body);
+    let req = serde_path_to_error::deserialize(&mut de).map_err(|err| {
+        let pointer = pointer_of(err.path());
+        let err: Error = JsonPayloadError::Deserialize(err.into_inner()).into();
+        match pointer {
+            Some(pointer) => err.with_pointer(&pointer),
+            None => err,
+        }
+    })?;
+    de.end()
+        .map_err(|err| JsonPayloadError::Deserialize(err).into())
+        .map(|_| req)
+}
+
+// pointer_of is the JSON pointer of where a request body stopped making sense, None if that's
+// the document itself
+#[cfg(all(feature = "actixweb", not(feature = "simd")))]
+fn pointer_of(path: &serde_path_to_error::Path) -> Option<String> {
+    use serde_path_to_error::Segment;
+
+    let pointer: String = path
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Seq { index } => Some(format!("/{}", index)),
+            Segment::Map { key } => Some(format!("/{}", key.replace('~', "~0").replace('/', "~1"))),
+            Segment::Enum { .. } | Segment::Unknown => None,
+        })
+        .collect();
+    (!pointer.is_empty()).then_some(pointer)
+}
+
+#[cfg(feature = "simd")]
+fn decode_request<R: DeserializeOwned>(body: &mut [u8]) -> Result<R, Error> {
+    simd_json::serde::from_slice(body).map_err(|err| invalid_body(&err).with_cause(err))
 }
 
 #[cfg(feature = "actixweb")]
 impl ResponseError for Error {
+    // status_code is the error's own status: a status policy takes the JsonApiConfig, see
+    // errors::ErrorDocuments
+    fn status_code(&self) -> StatusCode {
+        status::status_of(std::slice::from_ref(self), None)
+    }
+
+    fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
+        errors::error_document(self.status_code(), std::slice::from_ref(self))
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl ResponseError for Errors {
     fn status_code(&self) -> StatusCode {
-        (&self.status).into()
+        status::status_of(&self.0, None)
     }
 
     fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
-        HttpResponseBuilder::new(self.status_code()).json(Response::from(self.clone()))
+        errors::error_document(self.status_code(), &self.0)
     }
 }
 
+// Responder sends a document with the JSON:API media type, first filling in the links of the
+// LinkGenerator and catalog registered with JsonApiConfig (if any), translating its errors with
+// its localizer and answering them as its status policy decides, so handlers can return a
+// Response as is
 #[cfg(feature = "actixweb")]
-impl Into<HttpResponse> for Error {
-    fn into(self) -> HttpResponse {
-        HttpResponseBuilder::new(self.status_code()).json(Response::from(self))
+impl<P: serde::Serialize, I: serde::Serialize> actix_web::Responder for Response<P, I> {
+    type Body = actix_web::body::BoxBody;
+
+    fn respond_to(self, req: &actix_web::HttpRequest) -> HttpResponse {
+        #[cfg(feature = "tracing")]
+        let _span = trace::respond_span(&self).entered();
+        errors::warn_unwrapped(req);
+        let response = match links::from_request(req) {
+            Some(generator) => self.linked(generator.as_ref()),
+            None => self,
+        };
+        let mut response = match req
+            .app_data::<JsonApiConfig>()
+            .and_then(|config| config.catalog.as_ref())
+        {
+            Some(catalog) => response.described(catalog),
+            None => response,
+        };
+        if let ResponseType::Error(errors) = &mut response.primary {
+            locale::localize_request(req, errors.iter_mut());
+            let config = JsonApiConfig::of(req);
+            let redacted = std::mem::take(errors).into_iter();
+            *errors = redacted
+                .map(|err| redaction::redact_internal(&config, err))
+                .collect();
+        }
+        let status = match &response.primary {
+            ResponseType::Ok(_) => StatusCode::OK,
+            ResponseType::Error(errors) => status::request_status(req, errors),
+        };
+        let accept = req
+            .headers()
+            .get(ACCEPT)
+            .and_then(|value| value.to_str().ok());
+        let encoding = encoding::Encoding::from_accept(accept);
+        let body = match encoding {
+            encoding::Encoding::Json => response
+                .to_vec()
+                .map_err(|_| Error::new_internal_error("the response couldn't be serialized")),
+            #[allow(unreachable_patterns)]
+            binary => binary.encode(&response),
+        };
+        match body {
+            Ok(body) => HttpResponseBuilder::new(status)
+                .content_type(encoding.media_type())
+                .body(body),
+            Err(err) => err.into(),
+        }
     }
 }
 
 #[cfg(feature = "actixweb")]
-impl Into<StatusCode> for &ErrorStatus {
-    fn into(self) -> StatusCode {
-        match self {
+impl From<Error> for HttpResponse {
+    fn from(err: Error) -> HttpResponse {
+        err.error_response()
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl From<&ErrorStatus> for StatusCode {
+    fn from(status: &ErrorStatus) -> StatusCode {
+        match status {
             ErrorStatus::BadRequest => StatusCode::BAD_REQUEST,
             ErrorStatus::Unauthorized => StatusCode::UNAUTHORIZED,
             ErrorStatus::Forbidden => StatusCode::FORBIDDEN,
             ErrorStatus::NotFound => StatusCode::NOT_FOUND,
+            ErrorStatus::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
             ErrorStatus::Conflict => StatusCode::CONFLICT,
+            ErrorStatus::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+            ErrorStatus::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorStatus::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ErrorStatus::UnprocessableEntity => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorStatus::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
             ErrorStatus::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -603,21 +2438,23 @@ impl Into<StatusCode> for &ErrorStatus {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::BTreeMap;
+    use std::borrow::Cow;
     use uuid::Uuid;
 
     use crate::{
-        FromID, FromRelationships, FromRequest, Identifier, IntoResponse, Relationship,
-        RelationshipData, Request, ResourceRequest, ResourceResponse, Response,
+        Extensions, FromID, FromRelationships, FromRequest, Identifier, IntoResponse, Link,
+        RawResponse, Relationship, RelationshipData, Relationships, Request, ResourceRequest,
+        ResourceResponse, Response, ResponseType, ID,
     };
 
     // A simple request with no relationships
+    #[allow(dead_code)]
     struct SimpleRequest {
         id: Uuid,
         attributes: SimpleAttributes,
     }
 
-    #[derive(Clone)]
+    #[derive(Clone, serde_derive::Serialize)]
     struct SimpleAttributes {
         foo: String,
         bar: Option<isize>,
@@ -628,7 +2465,7 @@ mod tests {
 
         fn from_request(req: Request<Self::Attributes>) -> Result<Self, crate::Error> {
             // ensure no relationships were passed (this implicitly has a "relationships" of unit struct)
-            FromRelationships::from_relationships(req.data.relationships)?;
+            let _: () = FromRelationships::from_relationships(req.data.relationships)?;
             Ok(SimpleRequest {
                 id: FromID::from_id(req.data.id.unwrap())?,
                 attributes: req.data.attributes,
@@ -641,33 +2478,433 @@ mod tests {
         let id = Uuid::new_v4();
         let mut req = Request {
             data: ResourceRequest {
-                id: Some(id.clone().into()),
+                id: Some(id.into()),
                 typ: "simple".into(),
                 attributes: SimpleAttributes {
                     foo: "testing".into(),
                     bar: Some(123),
                 },
                 relationships: None,
+                extensions: Extensions::new(),
             },
+            extensions: Extensions::new(),
         };
         assert!(SimpleRequest::from_request(req.clone()).is_ok());
         req.data.id = Some("foobarbaz".into()); // invalid UUID format
         assert!(SimpleRequest::from_request(req.clone()).is_err());
-        req.data.id = Some(id.clone().into());
-        let mut relations = BTreeMap::new();
+        req.data.id = Some(id.into());
+        let mut relations = Relationships::new();
         relations.insert(
             "fake".to_owned(),
             RelationshipData {
-                data: Relationship::ToOne(Identifier {
+                data: Some(Relationship::ToOne(Identifier {
                     id: "test".into(),
                     typ: "fake".into(),
-                }),
+                })),
+                ..Default::default()
             },
         );
         req.data.relationships = Some(relations);
         assert!(SimpleRequest::from_request(req.clone()).is_err());
     }
 
+    // Echo hands the extracted request over as it is, once its type is checked
+    #[cfg(feature = "actixweb")]
+    struct Echo(Request<serde_json::Value>);
+
+    #[cfg(feature = "actixweb")]
+    impl FromRequest for Echo {
+        type Attributes = serde_json::Value;
+
+        fn from_request(req: Request<Self::Attributes>) -> Result<Self, crate::Error> {
+            req.data.check_type("simple")?;
+            Ok(Echo(req))
+        }
+    }
+
+    // extract_with runs the JsonApi extractor on a POST with the config, content type and body
+    #[cfg(feature = "actixweb")]
+    fn extract_with(
+        config: crate::JsonApiConfig,
+        content_type: &str,
+        body: &'static str,
+    ) -> Result<crate::JsonApi<Echo>, crate::Error> {
+        use actix_web::test::TestRequest;
+        use std::{
+            future::Future,
+            pin::Pin,
+            task::{Context, Poll, Waker},
+        };
+
+        let (req, mut payload) = TestRequest::post()
+            .app_data(config)
+            .insert_header(("content-type", content_type))
+            .set_payload(body)
+            .to_http_parts();
+        let mut fut =
+            <crate::JsonApi<Echo> as actix_web::FromRequest>::from_request(&req, &mut payload);
+        match Pin::new(&mut fut).poll(&mut Context::from_waker(Waker::noop())) {
+            // these requests are each wrong in one way
+            Poll::Ready(res) => res.map_err(|mut rejection| rejection.errors.remove(0)),
+            Poll::Pending => panic!("an in-memory payload should be ready immediately"),
+        }
+    }
+
+    #[cfg(feature = "actixweb")]
+    fn extract(
+        content_type: &str,
+        body: &'static str,
+    ) -> Result<crate::JsonApi<Echo>, crate::Error> {
+        extract_with(crate::JsonApiConfig::default(), content_type, body)
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor() {
+        let body = r#"{"data": {"type": "simple", "attributes": {"foo": "bar"}}}"#;
+        let echo = extract("application/vnd.api+json", body)
+            .unwrap()
+            .into_inner();
+        assert_eq!(echo.0.data.typ, "simple");
+        assert_eq!(echo.0.data.attributes["foo"], "bar");
+        assert!(extract("text/plain", body).is_err());
+        assert!(extract("application/json", "{}").is_err());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor_type() {
+        use crate::ErrorStatus;
+
+        let body = r#"{"data": {"type": "other", "attributes": {}}}"#;
+        let err = extract("application/json", body).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::Conflict));
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/type"));
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor_strict() {
+        use crate::JsonApiConfig;
+
+        let strict = JsonApiConfig::default().strict(true);
+        let body = r#"{"data": {"type": "simple", "attributes": {}, "meta": {}}, "meta": {}}"#;
+        assert!(extract_with(strict.clone(), "application/json", body).is_ok());
+        let body = r#"{"data": {"type": "simple", "attributes": {}, "extra": 1}}"#;
+        assert!(extract("application/json", body).is_ok());
+        assert!(extract_with(strict.clone(), "application/json", body).is_err());
+        let body = r#"{"data": {"type": "simple", "attributes": {}}, "extra": 1}"#;
+        assert!(extract_with(strict, "application/json", body).is_err());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor_lid() {
+        use crate::{ErrorStatus, JsonApiConfig, SpecVersion};
+
+        let strict = JsonApiConfig::default().strict(true);
+        let body = r#"{"data": {"type": "simple", "lid": "a", "attributes": {}}}"#;
+        assert!(extract_with(strict.clone(), "application/json", body).is_ok());
+        let strict_1_0 = strict.version(SpecVersion::V1_0);
+        assert!(extract_with(strict_1_0.clone(), "application/json", body).is_err());
+        // the version is held to whether or not the request is
+        let lenient_1_0 = JsonApiConfig::default().version(SpecVersion::V1_0);
+        let err = extract_with(lenient_1_0, "application/json", body)
+            .err()
+            .unwrap();
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/lid"));
+        // nor does 1.0 have profiles
+        let body = r#"{"data": {"type": "simple", "attributes": {}}}"#;
+        let profile = r#"application/vnd.api+json; profile="https://example.com/p""#;
+        let err = extract_with(strict_1_0, profile, body).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::UnsupportedMediaType));
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor_media_type() {
+        use crate::{ErrorStatus, JsonApiConfig};
+
+        let body = r#"{"data": {"type": "simple", "attributes": {}}}"#;
+        let profile = r#"application/vnd.api+json; profile="https://example.com/p""#;
+        assert!(extract(profile, body).is_ok());
+        let charset = "application/vnd.api+json; charset=utf-8";
+        assert!(extract(charset, body).is_err());
+        let atomic = r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic""#;
+        let err = extract(atomic, body).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::UnsupportedMediaType));
+        let with_atomic = JsonApiConfig::default().extension("https://jsonapi.org/ext/atomic");
+        assert!(extract_with(with_atomic, atomic, body).is_ok());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor_reserved_names() {
+        use crate::JsonApiConfig;
+
+        let strict = JsonApiConfig::default().strict(true);
+        let body = r#"{"data": {"type": "simple", "attributes": {"id": 1}}}"#;
+        assert!(extract("application/json", body).is_ok());
+        let err = extract_with(strict.clone(), "application/json", body)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.source.unwrap().pointer.as_deref(),
+            Some("/data/attributes/id")
+        );
+        let body =
+            r#"{"data": {"type": "simple", "attributes": {}, "relationships": {"type": {}}}}"#;
+        assert!(extract_with(strict, "application/json", body).is_err());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor_client_ids() {
+        use crate::{ClientIdPolicy, ErrorStatus, JsonApiConfig};
+
+        let body = r#"{"data": {"type": "simple", "id": "1", "attributes": {}}}"#;
+        assert!(extract("application/json", body).is_ok());
+        let forbid = JsonApiConfig::default().client_ids(ClientIdPolicy::Forbid);
+        let err = extract_with(forbid.clone(), "application/json", body)
+            .err()
+            .unwrap();
+        assert!(matches!(err.status, ErrorStatus::Forbidden));
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/id"));
+        let uuid = JsonApiConfig::default().client_ids(ClientIdPolicy::AcceptUuid);
+        assert!(extract_with(uuid.clone(), "application/json", body).is_err());
+        let body = r#"{"data": {"type": "simple", "id": "67e55044-10b1-426f-9247-bb680e5fe0c8", "attributes": {}}}"#;
+        assert!(extract_with(uuid, "application/json", body).is_ok());
+        let body = r#"{"data": {"type": "simple", "attributes": {}}}"#;
+        assert!(extract_with(forbid, "application/json", body).is_ok());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor_id_rules() {
+        use crate::{ErrorStatus, JsonApiConfig};
+
+        let rules = JsonApiConfig::default().id_rules(crate::ids::IdRules::new().max_length(2));
+        let body = r#"{"data": {"type": "simple", "id": "123", "attributes": {}}}"#;
+        let err = extract_with(rules.clone(), "application/json", body)
+            .err()
+            .unwrap();
+        assert!(matches!(err.status, ErrorStatus::BadRequest));
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/id"));
+        let body = r#"{"data": {"type": "simple", "id": "12", "attributes": {}}}"#;
+        assert!(extract_with(rules, "application/json", body).is_ok());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor_pointer() {
+        use crate::ErrorStatus;
+
+        let body = r#"{"data": {"type": "simple", "id": 12, "attributes": {}}}"#;
+        let err = extract("application/json", body).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::BadRequest));
+        assert!(err.detail.is_some());
+        // simd-json doesn't tell where parsing failed
+        #[cfg(not(feature = "simd"))]
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/id"));
+        let err = extract("application/json", "{").err().unwrap();
+        assert!(err.detail.is_some() && err.source.is_none());
+        let err = extract("application/json", r#"{"data": null} x"#)
+            .err()
+            .unwrap();
+        assert!(matches!(err.status, ErrorStatus::BadRequest));
+        let err = extract("text/plain", body).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::UnsupportedMediaType));
+        let large = String::leak(format!("\"{}\"", "a".repeat(crate::BODY_LIMIT)));
+        let err = extract("application/json", large).err().unwrap();
+        assert!(matches!(err.status, ErrorStatus::PayloadTooLarge));
+    }
+
+    #[test]
+    fn test_static_id() {
+        const ID_ONE: ID = ID::from_static("1");
+        assert!(matches!(ID_ONE.clone().0, Cow::Borrowed("1")));
+        assert!(ID_ONE == ID::from(1usize));
+        let parsed: ID = serde_json::from_str(r#""1""#).unwrap();
+        assert_eq!(parsed.as_str(), ID_ONE.as_str());
+    }
+
+    #[test]
+    fn test_integer_ids() {
+        use std::num::NonZeroU64;
+
+        assert_eq!(ID::from(-7i64).as_str(), "-7");
+        assert_eq!(ID::from(u128::MAX).as_str(), u128::MAX.to_string());
+        assert_eq!(i32::from_id("-7".into()).unwrap(), -7);
+        assert_eq!(u64::from_id(ID::from(42u64)).unwrap(), 42);
+        assert!(u32::from_id("-1".into()).is_err());
+        assert!(i32::from_id(ID::from(i64::MAX)).is_err());
+        let id = NonZeroU64::new(3).unwrap();
+        assert_eq!(NonZeroU64::from_id(ID::from(id)).unwrap(), id);
+        let err = NonZeroU64::from_id("0".into()).err().unwrap();
+        assert_eq!(err.title, "invalid value for non-zero unsigned id value: 0");
+    }
+
+    #[cfg(feature = "ulid")]
+    #[test]
+    fn test_ulid_ids() {
+        let id = ulid::Ulid::new();
+        assert_eq!(ulid::Ulid::from_id(ID::from(id)).unwrap(), id);
+        assert!(ulid::Ulid::from_id("01ARZ3NDEKTSV4RRFFQ69G5FAV".into()).is_ok());
+        assert!(ulid::Ulid::from_id("01ARZ3NDEKTSV4RRFFQ69G5FA".into()).is_err());
+    }
+
+    #[test]
+    fn test_linkage_sets() {
+        let ident = |typ: &str, id: &str| Identifier {
+            id: id.to_owned().into(),
+            typ: typ.into(),
+        };
+        let mut current = Relationship::ToMany(vec![
+            ident("tags", "2"),
+            ident("tags", "1"),
+            ident("tags", "2"),
+            ident("labels", "9"),
+        ]);
+        let requested = Relationship::ToMany(vec![ident("tags", "1"), ident("tags", "3")]);
+        assert_eq!(
+            current.difference(&requested),
+            vec![ident("tags", "2"), ident("labels", "9")]
+        );
+        assert_eq!(requested.difference(&current), vec![ident("tags", "3")]);
+        current.dedup();
+        assert_eq!(current.identifiers().len(), 3);
+
+        let one = Relationship::ToOne(ident("tags", "1"));
+        assert!(one.contains(&ident("tags", "1")));
+        assert!(one.difference(&requested).is_empty());
+
+        let mut sorted = current.identifiers().to_vec();
+        sorted.sort();
+        assert_eq!(
+            sorted,
+            vec![ident("labels", "9"), ident("tags", "1"), ident("tags", "2")]
+        );
+    }
+
+    #[test]
+    fn test_try_from_id() {
+        let id = ID::from("42");
+        assert_eq!(u64::try_from(&id).unwrap(), 42);
+        assert_eq!(i32::try_from(id.clone()).unwrap(), 42);
+        assert!(std::num::NonZeroU32::try_from(ID::from("0")).is_err());
+        assert_eq!(String::from(&id), "42");
+        assert_eq!(id.as_ref(), "42");
+        let uuid = Uuid::new_v4();
+        assert_eq!(Uuid::try_from(ID::from(uuid)).unwrap(), uuid);
+        assert!(Uuid::try_from(&id).is_err());
+    }
+
+    #[test]
+    fn test_raw_passthrough() {
+        let body = r#"{"data":[{"type":"articles","id":"1","attributes":{"z": 1, "a": [true]},"relationships":{"author":{"data":{"id":"9","type":"people"}}}}],"included":null}"#;
+        let doc: RawResponse = serde_json::from_str(body).unwrap();
+        let ResponseType::Ok(data) = &doc.primary else {
+            panic!("expected primary data");
+        };
+        assert_eq!(data[0].id.typ, "articles");
+        assert_eq!(data[0].attributes.get(), r#"{"z": 1, "a": [true]}"#);
+        // attributes are written back out exactly as they came in
+        let out = serde_json::to_string(&doc).unwrap();
+        assert!(out.contains(r#""attributes":{"z": 1, "a": [true]}"#));
+
+        let errors: RawResponse =
+            serde_json::from_str(r#"{"errors":[{"status":"404","title":"gone"}]}"#).unwrap();
+        assert!(matches!(errors.primary, ResponseType::Error(_)));
+        assert!(serde_json::from_str::<RawResponse>(r#"{"included":[]}"#).is_err());
+    }
+
+    #[test]
+    fn test_extension_members() {
+        let body = r#"{"data":[{"id":"1","type":"a","attributes":{},"vendor:rank":3}],"atomic:results":[{}],"jsonapi":{"version":"1.1"}}"#;
+        let doc: RawResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(
+            doc.jsonapi.as_ref().unwrap().version.as_deref(),
+            Some("1.1")
+        );
+        assert!(!doc.extensions.contains_key("jsonapi"));
+        let ResponseType::Ok(data) = &doc.primary else {
+            panic!("expected primary data");
+        };
+        assert_eq!(data[0].extensions["vendor:rank"], 3);
+        let out: serde_json::Value = serde_json::to_value(&doc).unwrap();
+        assert_eq!(
+            out,
+            serde_json::from_str::<serde_json::Value>(body).unwrap()
+        );
+
+        // requests keep theirs too, along with the members they don't model
+        let body = r#"{"data":{"type":"a","lid":"x","attributes":{"n":1},"vendor:rank":3},"atomic:operations":[],"meta":{"m":1}}"#;
+        let req: Request<serde_json::Value> = serde_json::from_str(body).unwrap();
+        assert_eq!(req.data.extensions["lid"], "x");
+        assert_eq!(req.data.extensions["vendor:rank"], 3);
+        assert!(req.extensions.contains_key("atomic:operations"));
+        let out: serde_json::Value = serde_json::to_value(&req).unwrap();
+        assert_eq!(
+            out,
+            serde_json::from_str::<serde_json::Value>(body).unwrap()
+        );
+        // strict mode refuses the extension members, keeping those the spec has
+        #[cfg(feature = "actixweb")]
+        {
+            assert!(serde_json::from_str::<crate::StrictRequest<serde_json::Value>>(body).is_err());
+            let body = r#"{"data":{"type":"a","lid":"x","attributes":{"n":1}},"meta":{"m":1}}"#;
+            let strict: crate::StrictRequest<serde_json::Value> =
+                serde_json::from_str(body).unwrap();
+            let req = Request::from(strict);
+            assert_eq!(req.data.extensions["lid"], "x");
+            assert_eq!(req.extensions["meta"]["m"], 1);
+        }
+    }
+
+    #[test]
+    fn test_error_status_as_str() {
+        use crate::ErrorStatus;
+
+        for status in [
+            ErrorStatus::BadRequest,
+            ErrorStatus::NotFound,
+            ErrorStatus::UnprocessableEntity,
+            ErrorStatus::InternalError,
+        ] {
+            assert_eq!(serde_json::to_value(&status).unwrap(), status.as_str());
+        }
+    }
+
+    #[test]
+    fn test_top_level_members() {
+        let body =
+            r#"{"data":[],"meta":{"total":1},"links":{"self":"/a"},"jsonapi":{"ext":["x"]}}"#;
+        let doc: RawResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(doc.meta.as_ref().unwrap()["total"], 1);
+        assert_eq!(doc.links.as_ref().unwrap()["self"], Link::Url("/a".into()));
+        assert_eq!(doc.jsonapi.as_ref().unwrap().ext, ["x"]);
+        assert!(doc.extensions.is_empty());
+
+        // members without the spec's shape are kept as they are
+        let body = r#"{"data":[],"meta":3,"jsonapi":{"vendor":true}}"#;
+        let doc: RawResponse = serde_json::from_str(body).unwrap();
+        assert!(doc.meta.is_none() && doc.jsonapi.is_none());
+        assert_eq!(
+            serde_json::to_value(&doc).unwrap(),
+            serde_json::from_str::<serde_json::Value>(body).unwrap()
+        );
+        let out = serde_json::to_value(doc.jsonapi(crate::SpecVersion::V1_0)).unwrap();
+        assert_eq!(out["jsonapi"], serde_json::json!({"version": "1.0"}));
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_relationship_order() {
+        let body = r#"{"data":[{"id":"1","type":"a","attributes":{},"relationships":{"zebra":{"data":[]},"apple":{"data":[]}}}]}"#;
+        let doc: RawResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(serde_json::to_string(&doc).unwrap(), body);
+    }
+
     struct SimpleResponse {
         id: Uuid,
         attributes: SimpleAttributes,
@@ -684,10 +2921,147 @@ mod tests {
                 },
                 attributes: self.attributes,
                 relationships: None,
+                meta: None,
+                links: None,
+                extensions: Default::default(),
             }
         }
     }
 
+    #[test]
+    fn test_links() {
+        let links: crate::Links = serde_json::from_str(
+            r#"{
+                "self": "/articles/1",
+                "related": {"href": "/articles/1/author", "title": "Author", "hreflang": "en",
+                    "describedby": "/schemas/people", "meta": {"count": 1}}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(links["self"], Link::Url("/articles/1".into()));
+        assert_eq!(links["related"], "/articles/1/author");
+        let Link::Object {
+            title,
+            hreflang,
+            describedby,
+            ..
+        } = &links["related"]
+        else {
+            panic!("expected a link object");
+        };
+        assert_eq!(title.as_deref(), Some("Author"));
+        assert_eq!(hreflang, &["en"]);
+        assert_eq!(describedby.as_deref().unwrap(), "/schemas/people");
+
+        let out = serde_json::to_value(&links).unwrap();
+        assert_eq!(out["self"], "/articles/1");
+        assert_eq!(out["related"]["hreflang"], "en");
+        assert!(out["related"].get("rel").is_none());
+        let mut link = Link::object("/x");
+        if let Link::Object { hreflang, .. } = &mut link {
+            hreflang.extend(["en".to_owned(), "de".to_owned()]);
+        }
+        let out = serde_json::to_value(link);
+        assert_eq!(
+            out.unwrap(),
+            serde_json::json!({"href": "/x", "hreflang": ["en", "de"]})
+        );
+    }
+
+    #[test]
+    fn test_paginated_relationship() {
+        use crate::pagination::{
+            PageNumberPagination, PageParams, PageResult, PageSize, PaginationStrategy,
+        };
+
+        let strategy = PageNumberPagination {
+            size: PageSize::new(2),
+        };
+        let page = strategy.parse(&PageParams::default()).unwrap();
+        let result = PageResult {
+            items: 2,
+            total: Some(5000),
+            ..Default::default()
+        };
+        let base = "/articles/1/relationships/comments";
+        let paginated = strategy.paginate(&page, &result, base);
+        let linkage = vec![
+            Identifier {
+                id: "1".into(),
+                typ: "comments".into(),
+            },
+            Identifier {
+                id: "2".into(),
+                typ: "comments".into(),
+            },
+        ];
+        let mut related = crate::Links::new();
+        related.insert("related".into(), "/articles/1/comments".into());
+        let rel = RelationshipData::paginated(paginated.clone(), Some(linkage)).with_links(related);
+        let out = serde_json::to_value(&rel).unwrap();
+        assert_eq!(out["data"][1]["id"], "2");
+        assert_eq!(out["meta"]["total"], 5000);
+        assert_eq!(out["links"]["related"], "/articles/1/comments");
+        assert_eq!(
+            out["links"]["next"],
+            "/articles/1/relationships/comments?page[number]=2&page[size]=2"
+        );
+
+        // links alone are a valid relationship object, but not one a request can use
+        let rel = RelationshipData::paginated(paginated, None);
+        let out = serde_json::to_value(&rel).unwrap();
+        assert!(out.get("data").is_none());
+        assert!(!rel.is_empty());
+        let parsed: RelationshipData = serde_json::from_value(out).unwrap();
+        assert!(parsed.into_data().is_err());
+    }
+
+    #[test]
+    fn test_bulk_construction() {
+        let resource = |foo: &str| SimpleResponse {
+            id: Uuid::new_v4(),
+            attributes: SimpleAttributes {
+                foo: foo.into(),
+                bar: None,
+            },
+        };
+        let response: Response<_, SimpleAttributes> = Response::with_capacity(2, 3)
+            .push(resource("a"))
+            .push(resource("b"))
+            .include_many(vec![resource("c"), resource("d"), resource("e")]);
+        let ResponseType::Ok(data) = &response.primary else {
+            panic!("expected primary data");
+        };
+        assert_eq!(data.len(), 2);
+        assert_eq!(response.included.as_ref().unwrap().capacity(), 3);
+
+        let response = Response::from_parts(vec![resource("a")], vec![resource("b")]);
+        assert_eq!(response.included.unwrap()[0].attributes.foo, "b");
+
+        let mut page = crate::pagination::Paginated::default();
+        page.links
+            .insert("next".into(), "/simple?page[number]=2".into());
+        page.meta.insert("total".into(), 3.into());
+        let response: Response<_, SimpleAttributes> = Response::with_capacity(1, 0)
+            .push(resource("a"))
+            .paginated(page);
+        let out = serde_json::to_value(&response).unwrap();
+        assert_eq!(out["links"]["next"], "/simple?page[number]=2");
+        assert_eq!(out["meta"]["total"], 3);
+
+        let counts = crate::pagination::CollectionMeta {
+            total: Some(3),
+            page_count: Some(2),
+            ..Default::default()
+        };
+        let response: Response<_, SimpleAttributes> = Response::with_capacity(1, 0)
+            .push(resource("a"))
+            .with_collection_meta(counts, &Default::default());
+        let out = serde_json::to_value(&response).unwrap();
+        assert_eq!(out["meta"]["pageCount"], 2);
+        assert!(out["meta"].get("pageSize").is_none());
+    }
+
     #[test]
     fn test_simple_response() {
         let attrs = SimpleAttributes {
@@ -703,6 +3077,146 @@ mod tests {
         // finish is essentially a more readable way to provided types for responses
         // with no included resources. There is likely a better way to do this but for
         // now this is the approach we're taking.
-        Response::from(response).finish();
+        let response = Response::from(response)
+            .finish()
+            .jsonapi(crate::SpecVersion::V1_0);
+        let doc = serde_json::to_value(&response).unwrap();
+        assert_eq!(doc["jsonapi"]["version"], "1.0");
+        assert_eq!(crate::validate(&doc), []);
+        match response.primary {
+            ResponseType::Ok(data) => assert!(!data.spilled()),
+            ResponseType::Error(_) => panic!("expected primary data"),
+        }
+    }
+
+    #[test]
+    fn test_into_result() {
+        let body = r#"{"data": [{"type": "simples", "id": "1", "attributes": {"foo": "a"}}],
+            "included": [{"type": "others", "id": "2", "attributes": null}]}"#;
+        let response: Response<serde_json::Value, ()> = serde_json::from_str(body).unwrap();
+        let (data, included) = response.into_result().unwrap();
+        assert_eq!(data[0].attributes["foo"], "a");
+        assert_eq!(included.unwrap()[0].id.typ, "others");
+
+        let body = r#"{"errors": [{"status": "404", "title": "not found"}]}"#;
+        let response: Response<serde_json::Value, ()> = serde_json::from_str(body).unwrap();
+        let errors = response.into_result().err().unwrap();
+        assert_eq!(errors[0].title, "not found");
+    }
+
+    #[test]
+    fn test_from_result() {
+        use crate::JsonApiResult;
+
+        let find = |id: Option<Uuid>| -> JsonApiResult<SimpleResponse> {
+            let id = id.ok_or_else(|| crate::Error::new_not_found("no such simple"))?;
+            Ok(SimpleResponse {
+                id,
+                attributes: SimpleAttributes {
+                    foo: "foo".into(),
+                    bar: None,
+                },
+            })
+        };
+        let response: Response<_, ()> = Response::from(find(Some(Uuid::new_v4())));
+        assert!(matches!(response.primary, ResponseType::Ok(data) if data.len() == 1));
+        let response: Response<_, ()> = Response::from(find(None));
+        let doc = serde_json::to_value(&response).unwrap();
+        assert_eq!(doc["errors"][0]["status"], "404");
+
+        let list: JsonApiResult<Vec<SimpleResponse>> = Ok(Vec::new());
+        let response: Response<_, ()> = list.into();
+        assert!(matches!(response.primary, ResponseType::Ok(data) if data.is_empty()));
+    }
+
+    #[test]
+    fn test_error_response() {
+        #[derive(serde_derive::Serialize)]
+        struct Included {
+            name: String,
+        }
+
+        fn handler(found: bool) -> Response<SimpleAttributes, Included> {
+            if !found {
+                return crate::Error::new_not_found("no such simple").into();
+            }
+            Response::from(SimpleResponse {
+                id: Uuid::new_v4(),
+                attributes: SimpleAttributes {
+                    foo: "foo".into(),
+                    bar: None,
+                },
+            })
+        }
+        let doc = serde_json::to_value(handler(false)).unwrap();
+        assert_eq!(doc["errors"][0]["title"], "no such simple");
+        assert!(doc.get("data").is_none());
+        assert!(matches!(handler(true).primary, ResponseType::Ok(_)));
+    }
+
+    #[test]
+    fn test_create_and_update_requests() {
+        use crate::{ClientIdPolicy, CreateRequest, UpdateRequest};
+
+        let parse =
+            |body: &str| -> Request<serde_json::Value> { serde_json::from_str(body).unwrap() };
+        let anonymous = r#"{"data": {"type": "simples", "attributes": {"foo": "a"}}}"#;
+        let identified = r#"{"data": {"type": "simples", "id": "7", "attributes": {"foo": "a"}}}"#;
+
+        let create = CreateRequest::from_request(parse(anonymous)).unwrap();
+        assert!(create.id.is_none());
+        assert!(create.check_id(ClientIdPolicy::Forbid).is_ok());
+        let create = CreateRequest::from_request(parse(identified)).unwrap();
+        assert!(create.check_id(ClientIdPolicy::Forbid).is_err());
+        assert!(create.check_id(ClientIdPolicy::Accept).is_ok());
+
+        let err = UpdateRequest::from_request(parse(anonymous)).err().unwrap();
+        assert_eq!(err.source.unwrap().pointer.unwrap(), "/data");
+        let update = UpdateRequest::from_request(parse(identified)).unwrap();
+        assert_eq!(update.attributes["foo"], "a");
+        assert!(update.check_id("7").is_ok());
+        let err = update.check_id("8").unwrap_err();
+        assert!(matches!(err.status, crate::ErrorStatus::Conflict));
+    }
+
+    #[test]
+    fn test_too_many_requests() {
+        use std::time::Duration;
+
+        let err = crate::Error::new_too_many_requests(Duration::from_millis(1500));
+        assert_eq!(err.retry_after(), Some(2));
+        let doc = serde_json::to_value(crate::Response::<(), ()>::from(err.clone())).unwrap();
+        assert_eq!(doc["errors"][0]["status"], "429");
+        assert_eq!(doc["errors"][0]["meta"]["retryAfter"], 2);
+        assert_eq!(crate::Error::new_conflict("a").retry_after(), None);
+
+        #[cfg(feature = "actixweb")]
+        {
+            use actix_web::ResponseError;
+
+            let res = err.error_response();
+            assert_eq!(res.status(), 429);
+            assert_eq!(res.headers().get("retry-after").unwrap(), "2");
+            let res = actix_web::HttpResponse::from(crate::Error::new_conflict("a"));
+            assert!(res.headers().get("retry-after").is_none());
+        }
+    }
+
+    #[test]
+    fn test_error_cause() {
+        use std::error::Error as _;
+
+        let io = std::io::Error::other("disk full");
+        let err = crate::Error::new_internal_error("the article couldn't be saved").with_cause(io);
+        assert_eq!(err.source().unwrap().to_string(), "disk full");
+        assert_eq!(
+            err,
+            crate::Error::new_internal_error("the article couldn't be saved")
+        );
+        let doc = serde_json::to_value(crate::Response::<(), ()>::from(err.clone())).unwrap();
+        assert!(doc["errors"][0].get("cause").is_none());
+
+        let err = <Uuid as FromID>::from_id(ID::from("x")).err().unwrap();
+        assert!(err.source().is_some());
     }
 }
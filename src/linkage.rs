@@ -0,0 +1,405 @@
+// Requests to relationship endpoints, e.g. /articles/1/relationships/tags. Their documents hold
+// nothing but resource linkage: for a to-one relationship an identifier or null, which PATCH sets
+// or clears; for a to-many a list of identifiers, which PATCH replaces the relationship with, POST
+// adds to it and DELETE removes from it.
+#[cfg(feature = "actixweb")]
+use actix_web::{http::Method, web::JsonBody, FromRequest as FromWebRequest};
+#[cfg(feature = "actixweb")]
+use futures_core::ready;
+use serde_derive::{Deserialize, Serialize};
+use std::{collections::HashSet, hash::Hash};
+#[cfg(feature = "actixweb")]
+use std::{future::Future, marker::PhantomData, pin::Pin, task::Poll};
+
+use crate::{Error, FromID, Maybe, Meta, Relationship};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RelationshipRequest {
+    #[serde(default, skip_serializing_if = "Maybe::is_absent")]
+    pub data: Maybe<Relationship>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Meta>,
+}
+
+// RelationshipOp is what a request does to the relationship, decided by its method
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationshipOp {
+    Replace,
+    Add,
+    Remove,
+}
+
+#[cfg(feature = "actixweb")]
+impl RelationshipOp {
+    // from_method maps PATCH, POST and DELETE to their operations, None for other methods
+    pub fn from_method(method: &Method) -> Option<RelationshipOp> {
+        match *method {
+            Method::PATCH => Some(RelationshipOp::Replace),
+            Method::POST => Some(RelationshipOp::Add),
+            Method::DELETE => Some(RelationshipOp::Remove),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToOneUpdate<T> {
+    Set(T),
+    Clear,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToManyUpdate<T> {
+    Replace(Vec<T>),
+    Add(Vec<T>),
+    Remove(Vec<T>),
+}
+
+// ToManyDiff is what an update does to a to-many relationship: the members it adds, those it
+// removes and those it leaves in place. Each member is listed once, in the order it was first met
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToManyDiff<T> {
+    pub added: Vec<T>,
+    pub removed: Vec<T>,
+    pub unchanged: Vec<T>,
+}
+
+impl<T> ToManyDiff<T> {
+    pub fn is_noop(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl<T: Eq + Hash + Clone> ToManyUpdate<T> {
+    // diff applies the update to the relationship's current members. Adding a member already in
+    // it or removing one that isn't changes nothing, as the spec has it
+    pub fn diff(&self, existing: &[T]) -> ToManyDiff<T> {
+        let (requested, op) = match self {
+            ToManyUpdate::Replace(ids) => (ids, RelationshipOp::Replace),
+            ToManyUpdate::Add(ids) => (ids, RelationshipOp::Add),
+            ToManyUpdate::Remove(ids) => (ids, RelationshipOp::Remove),
+        };
+        diff(existing, requested, op)
+    }
+}
+
+// diff compares the current members of a to-many relationship with those of a request doing op,
+// e.g. for linkage taken straight from the request's identifiers
+pub fn diff<T: Eq + Hash + Clone>(
+    existing: &[T],
+    requested: &[T],
+    op: RelationshipOp,
+) -> ToManyDiff<T> {
+    let current: HashSet<&T> = existing.iter().collect();
+    let wanted: HashSet<&T> = requested.iter().collect();
+    let mut seen = HashSet::new();
+    let mut diff = ToManyDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        unchanged: Vec::new(),
+    };
+    for id in existing.iter().filter(|id| seen.insert(*id)) {
+        let removed = match op {
+            RelationshipOp::Replace => !wanted.contains(id),
+            RelationshipOp::Add => false,
+            RelationshipOp::Remove => wanted.contains(id),
+        };
+        if removed {
+            diff.removed.push(id.clone());
+        } else {
+            diff.unchanged.push(id.clone());
+        }
+    }
+    if op != RelationshipOp::Remove {
+        diff.added = requested
+            .iter()
+            .filter(|id| !current.contains(id) && seen.insert(*id))
+            .cloned()
+            .collect();
+    }
+    diff
+}
+
+// FromRelationshipRequest is implemented by the updates of a relationship endpoint. name is the
+// relationship the request was sent to, which matters to types covering several relationships:
+// the FromRelationshipRequest derive makes an enum of updates dispatch on it, e.g.
+//
+//     #[derive(FromRelationshipRequest)]
+//     enum ArticleRelationship {
+//         Author(ToOneUpdate<usize>),
+//         Tags(ToManyUpdate<usize>),
+//     }
+pub trait FromRelationshipRequest
+where
+    Self: Sized,
+{
+    fn from_relationship_request(
+        name: &str,
+        op: RelationshipOp,
+        req: RelationshipRequest,
+    ) -> Result<Self, Error>;
+}
+
+fn missing_data() -> Error {
+    Error::new_bad_request("relationship requests need resource linkage in data")
+        .with_pointer("/data")
+}
+
+impl<I: FromID> FromRelationshipRequest for ToOneUpdate<I> {
+    fn from_relationship_request(
+        name: &str,
+        op: RelationshipOp,
+        req: RelationshipRequest,
+    ) -> Result<Self, Error> {
+        if op != RelationshipOp::Replace {
+            // the spec has servers refuse unsupported relationship updates with 403
+            return Err(Error::new_forbidden(&format!(
+                "to-one relationship '{}' can only be replaced",
+                name
+            )));
+        }
+        match req.data {
+            Maybe::Absent => Err(missing_data()),
+            Maybe::Null => Ok(ToOneUpdate::Clear),
+            Maybe::Value(Relationship::ToOne(identifier)) => I::from_id(identifier.id)
+                .map(ToOneUpdate::Set)
+                .map_err(|err| err.with_pointer("/data/id")),
+            Maybe::Value(Relationship::ToMany(_)) => Err(Error::new_bad_request(&format!(
+                "to-one relationship '{}' takes a single resource identifier",
+                name
+            ))
+            .with_pointer("/data")),
+        }
+    }
+}
+
+impl<I: FromID> FromRelationshipRequest for ToManyUpdate<I> {
+    fn from_relationship_request(
+        name: &str,
+        op: RelationshipOp,
+        req: RelationshipRequest,
+    ) -> Result<Self, Error> {
+        let identifiers = match req.data {
+            Maybe::Absent => return Err(missing_data()),
+            Maybe::Value(Relationship::ToMany(identifiers)) => identifiers,
+            Maybe::Null | Maybe::Value(Relationship::ToOne(_)) => {
+                return Err(Error::new_bad_request(&format!(
+                    "to-many relationship '{}' takes a list of resource identifiers",
+                    name
+                ))
+                .with_pointer("/data"))
+            }
+        };
+        let ids = identifiers
+            .into_iter()
+            .enumerate()
+            .map(|(i, identifier)| {
+                I::from_id(identifier.id)
+                    .map_err(|err| err.with_pointer(&format!("/data/{}/id", i)))
+            })
+            .collect::<Result<Vec<I>, Error>>()?;
+        Ok(match op {
+            RelationshipOp::Replace => ToManyUpdate::Replace(ids),
+            RelationshipOp::Add => ToManyUpdate::Add(ids),
+            RelationshipOp::Remove => ToManyUpdate::Remove(ids),
+        })
+    }
+}
+
+// JsonApiRelationship extracts the update of a relationship endpoint. The relationship's name is
+// the last segment of the request's path, and the operation comes from its method
+#[cfg(feature = "actixweb")]
+pub struct JsonApiRelationship<R>(pub R);
+
+#[cfg(feature = "actixweb")]
+impl<R> JsonApiRelationship<R> {
+    pub fn into_inner(self) -> R {
+        self.0
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl<R: FromRelationshipRequest> FromWebRequest for JsonApiRelationship<R> {
+    type Error = Error;
+
+    type Future = RelationshipExtractFut<R>;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        payload: &mut actix_web::dev::Payload,
+    ) -> Self::Future {
+        let config = req
+            .app_data::<crate::JsonApiConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let name = req
+            .path()
+            .rsplit('/')
+            .find(|segment| !segment.is_empty())
+            .unwrap_or_default()
+            .to_owned();
+        let op = RelationshipOp::from_method(req.method()).ok_or_else(|| {
+            Error::new_bad_request("relationships are updated with PATCH, POST or DELETE")
+        });
+        let body = op.and_then(|op| {
+            crate::check_content_type(req, &config)?;
+            let body = JsonBody::new(req, payload, None, true).limit(crate::BODY_LIMIT);
+            Ok((op, body))
+        });
+        RelationshipExtractFut {
+            body: body.map_err(Some),
+            name,
+            _update: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "actixweb")]
+pub struct RelationshipExtractFut<R> {
+    body: Result<(RelationshipOp, JsonBody<RelationshipRequest>), Option<Error>>,
+    name: String,
+    _update: PhantomData<fn() -> R>,
+}
+
+#[cfg(feature = "actixweb")]
+impl<R: FromRelationshipRequest> Future for RelationshipExtractFut<R> {
+    type Output = Result<JsonApiRelationship<R>, Error>;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        let this = self.get_mut();
+        match &mut this.body {
+            Ok((op, body)) => {
+                let req = ready!(Pin::new(body).poll(cx))?;
+                Poll::Ready(
+                    R::from_relationship_request(&this.name, *op, req).map(JsonApiRelationship),
+                )
+            }
+            Err(err) => Poll::Ready(Err(err
+                .take()
+                .expect("RelationshipExtractFut polled after completion"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        diff, FromRelationshipRequest, RelationshipOp, RelationshipRequest, ToManyDiff,
+        ToManyUpdate, ToOneUpdate,
+    };
+    use crate::{ErrorStatus, Identifier};
+
+    fn request(body: serde_json::Value) -> RelationshipRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn test_to_one() {
+        let update = ToOneUpdate::<usize>::from_relationship_request(
+            "author",
+            RelationshipOp::Replace,
+            request(json!({"data": {"type": "people", "id": "9"}})),
+        );
+        assert_eq!(update.unwrap(), ToOneUpdate::Set(9));
+        let update = ToOneUpdate::<usize>::from_relationship_request(
+            "author",
+            RelationshipOp::Replace,
+            request(json!({"data": null})),
+        );
+        assert_eq!(update.unwrap(), ToOneUpdate::Clear);
+        let err = ToOneUpdate::<usize>::from_relationship_request(
+            "author",
+            RelationshipOp::Replace,
+            request(json!({})),
+        )
+        .unwrap_err();
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data"));
+        let err = ToOneUpdate::<usize>::from_relationship_request(
+            "author",
+            RelationshipOp::Add,
+            request(json!({"data": null})),
+        )
+        .unwrap_err();
+        assert_eq!(err.status, ErrorStatus::Forbidden);
+    }
+
+    #[test]
+    fn test_to_many() {
+        let tags = json!({"data": [{"type": "tags", "id": "1"}, {"type": "tags", "id": "2"}]});
+        let update = ToManyUpdate::<usize>::from_relationship_request(
+            "tags",
+            RelationshipOp::Remove,
+            request(tags),
+        );
+        assert_eq!(update.unwrap(), ToManyUpdate::Remove(vec![1, 2]));
+        let err = ToManyUpdate::<usize>::from_relationship_request(
+            "tags",
+            RelationshipOp::Add,
+            request(json!({"data": [{"type": "tags", "id": "1"}, {"type": "tags", "id": "x"}]})),
+        )
+        .unwrap_err();
+        assert_eq!(err.source.unwrap().pointer.as_deref(), Some("/data/1/id"));
+        let err = ToManyUpdate::<usize>::from_relationship_request(
+            "tags",
+            RelationshipOp::Replace,
+            request(json!({"data": null})),
+        );
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_diff() {
+        let existing = [1, 2, 3, 2];
+        assert_eq!(
+            ToManyUpdate::Replace(vec![3, 4, 4]).diff(&existing),
+            ToManyDiff {
+                added: vec![4],
+                removed: vec![1, 2],
+                unchanged: vec![3],
+            }
+        );
+        let add = ToManyUpdate::Add(vec![2, 5]).diff(&existing);
+        assert_eq!((add.added, add.removed), (vec![5], vec![]));
+        assert_eq!(add.unchanged, vec![1, 2, 3]);
+        let remove = ToManyUpdate::Remove(vec![2, 9]).diff(&existing);
+        assert_eq!(
+            (remove.added, remove.removed, remove.unchanged),
+            (vec![], vec![2], vec![1, 3])
+        );
+        assert!(ToManyUpdate::Add(vec![1]).diff(&existing).is_noop());
+
+        let tag = |id: &str| Identifier {
+            id: id.to_owned().into(),
+            typ: "tags".into(),
+        };
+        let diff = diff(&[tag("1")], &[tag("2")], RelationshipOp::Replace);
+        assert_eq!((diff.added, diff.removed), (vec![tag("2")], vec![tag("1")]));
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor() {
+        use actix_web::{test, FromRequest};
+
+        use super::JsonApiRelationship;
+
+        let (req, mut payload) = test::TestRequest::post()
+            .uri("/articles/1/relationships/tags")
+            .insert_header(("content-type", crate::media::JSONAPI_MEDIA_TYPE))
+            .set_payload(json!({"data": [{"type": "tags", "id": "3"}]}).to_string())
+            .to_http_parts();
+        let update = actix_web::rt::System::new()
+            .block_on(JsonApiRelationship::<ToManyUpdate<usize>>::from_request(
+                &req,
+                &mut payload,
+            ))
+            .ok()
+            .unwrap();
+        assert_eq!(update.into_inner(), ToManyUpdate::Add(vec![3]));
+    }
+}
@@ -0,0 +1,705 @@
+// Link generation. Rather than formatting URLs in every handler, a LinkGenerator knows where each
+// resource type lives, and documents ask it for the links they're missing (see Response::linked).
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::{Link, ID};
+
+pub trait LinkGenerator {
+    // base_url is put in front of every path, e.g. "https://api.example.com/v1"
+    fn base_url(&self) -> &str;
+
+    // resource_path is the path of a single resource, None if it has no URL of its own
+    fn resource_path(&self, typ: &str, id: &ID) -> Option<String>;
+
+    // related_path is the path of the resources a relationship points at
+    fn related_path(&self, typ: &str, id: &ID, relationship: &str) -> Option<String> {
+        Some(format!("{}/{}", self.resource_path(typ, id)?, relationship))
+    }
+
+    // relationship_path is the path of the relationship itself, for managing its linkage
+    fn relationship_path(&self, typ: &str, id: &ID, relationship: &str) -> Option<String> {
+        Some(format!(
+            "{}/relationships/{}",
+            self.resource_path(typ, id)?,
+            relationship
+        ))
+    }
+
+    fn self_link(&self, typ: &str, id: &ID) -> Option<Link> {
+        Some(self.link(&self.resource_path(typ, id)?))
+    }
+
+    fn related_link(&self, typ: &str, id: &ID, relationship: &str) -> Option<Link> {
+        Some(self.link(&self.related_path(typ, id, relationship)?))
+    }
+
+    fn relationship_link(&self, typ: &str, id: &ID, relationship: &str) -> Option<Link> {
+        Some(self.link(&self.relationship_path(typ, id, relationship)?))
+    }
+
+    fn link(&self, path: &str) -> Link {
+        Link::Url(format!("{}{}", self.base_url().trim_end_matches('/'), path))
+    }
+}
+
+// SharedLinkGenerator is how a generator is registered with JsonApiConfig and handed around
+pub type SharedLinkGenerator = Arc<dyn LinkGenerator + Send + Sync>;
+
+impl<G: LinkGenerator + ?Sized> LinkGenerator for Arc<G> {
+    fn base_url(&self) -> &str {
+        (**self).base_url()
+    }
+
+    fn resource_path(&self, typ: &str, id: &ID) -> Option<String> {
+        (**self).resource_path(typ, id)
+    }
+
+    fn related_path(&self, typ: &str, id: &ID, relationship: &str) -> Option<String> {
+        (**self).related_path(typ, id, relationship)
+    }
+
+    fn relationship_path(&self, typ: &str, id: &ID, relationship: &str) -> Option<String> {
+        (**self).relationship_path(typ, id, relationship)
+    }
+
+    fn self_link(&self, typ: &str, id: &ID) -> Option<Link> {
+        (**self).self_link(typ, id)
+    }
+
+    fn related_link(&self, typ: &str, id: &ID, relationship: &str) -> Option<Link> {
+        (**self).related_link(typ, id, relationship)
+    }
+
+    fn relationship_link(&self, typ: &str, id: &ID, relationship: &str) -> Option<Link> {
+        (**self).relationship_link(typ, id, relationship)
+    }
+
+    fn link(&self, path: &str) -> Link {
+        (**self).link(path)
+    }
+}
+
+// from_request gives the generator for a request: links from the named routes registered with
+// the JsonApiConfig app data, falling back to its generator (based on the request's origin if
+// links are host aware). None if neither is registered
+#[cfg(feature = "actixweb")]
+pub fn from_request(req: &actix_web::HttpRequest) -> Option<Box<dyn LinkGenerator>> {
+    let config = req.app_data::<crate::JsonApiConfig>()?;
+    let mut generator = config.link_generator().cloned();
+    if config.host_aware_links {
+        generator = generator.map(|generator| {
+            let base_url = format!("{}{}", request_origin(req), generator.base_url());
+            Arc::new(Rebased::new(&base_url, generator)) as SharedLinkGenerator
+        });
+    }
+    match &config.route_names {
+        Some(names) => Some(Box::new(UrlFor {
+            req: req.clone(),
+            names: names.clone(),
+            fallback: generator,
+        })),
+        None => Some(Box::new(generator?)),
+    }
+}
+
+// RouteNames names the actix routes of resource types, so links are built by
+// HttpRequest::url_for_map from the routes the app really has rather than templates repeating
+// them. Route patterns can use the {id}, {type} and {relationship} segments, e.g. a related route
+// "/articles/{id}/{relationship}"
+#[cfg(feature = "actixweb")]
+#[derive(Debug, Clone, Default)]
+pub struct RouteNames {
+    resources: BTreeMap<String, String>,
+    related: BTreeMap<String, String>,
+    relationships: BTreeMap<String, String>,
+}
+
+#[cfg(feature = "actixweb")]
+impl RouteNames {
+    pub fn new() -> RouteNames {
+        RouteNames::default()
+    }
+
+    pub fn resource(mut self, typ: &str, name: &str) -> Self {
+        self.resources.insert(typ.to_owned(), name.to_owned());
+        self
+    }
+
+    pub fn related(mut self, typ: &str, name: &str) -> Self {
+        self.related.insert(typ.to_owned(), name.to_owned());
+        self
+    }
+
+    pub fn relationship(mut self, typ: &str, name: &str) -> Self {
+        self.relationships.insert(typ.to_owned(), name.to_owned());
+        self
+    }
+}
+
+// UrlFor generates links from named routes for one request. url_for_map gives whole URLs, so
+// its paths are URLs too, and links of types without a route come from the fallback
+#[cfg(feature = "actixweb")]
+struct UrlFor {
+    req: actix_web::HttpRequest,
+    names: Arc<RouteNames>,
+    fallback: Option<SharedLinkGenerator>,
+}
+
+#[cfg(feature = "actixweb")]
+impl UrlFor {
+    fn url_for(&self, name: &str, typ: &str, id: &ID, relationship: &str) -> Option<String> {
+        let mut elements = std::collections::HashMap::new();
+        elements.insert("type", typ);
+        elements.insert("id", &id.0);
+        elements.insert("relationship", relationship);
+        Some(self.req.url_for_map(name, &elements).ok()?.into())
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl LinkGenerator for UrlFor {
+    fn base_url(&self) -> &str {
+        ""
+    }
+
+    fn resource_path(&self, typ: &str, id: &ID) -> Option<String> {
+        match self.names.resources.get(typ) {
+            Some(name) => self.url_for(name, typ, id, ""),
+            None => self.fallback.as_ref()?.resource_path(typ, id),
+        }
+    }
+
+    fn related_path(&self, typ: &str, id: &ID, relationship: &str) -> Option<String> {
+        match self.names.related.get(typ) {
+            Some(name) => self.url_for(name, typ, id, relationship),
+            None => self.fallback.as_ref()?.related_path(typ, id, relationship),
+        }
+    }
+
+    fn relationship_path(&self, typ: &str, id: &ID, relationship: &str) -> Option<String> {
+        match self.names.relationships.get(typ) {
+            Some(name) => self.url_for(name, typ, id, relationship),
+            None => self
+                .fallback
+                .as_ref()?
+                .relationship_path(typ, id, relationship),
+        }
+    }
+
+    fn self_link(&self, typ: &str, id: &ID) -> Option<Link> {
+        match self.names.resources.get(typ) {
+            Some(_) => Some(Link::Url(self.resource_path(typ, id)?)),
+            None => self.fallback.as_ref()?.self_link(typ, id),
+        }
+    }
+
+    fn related_link(&self, typ: &str, id: &ID, relationship: &str) -> Option<Link> {
+        match self.names.related.get(typ) {
+            Some(_) => Some(Link::Url(self.related_path(typ, id, relationship)?)),
+            None => self.fallback.as_ref()?.related_link(typ, id, relationship),
+        }
+    }
+
+    fn relationship_link(&self, typ: &str, id: &ID, relationship: &str) -> Option<Link> {
+        match self.names.relationships.get(typ) {
+            Some(_) => Some(Link::Url(self.relationship_path(typ, id, relationship)?)),
+            None => self
+                .fallback
+                .as_ref()?
+                .relationship_link(typ, id, relationship),
+        }
+    }
+}
+
+// request_origin is the external URL the request was made to, up to the path: the scheme and
+// host from the Forwarded or X-Forwarded-Proto and X-Forwarded-Host headers (falling back to Host
+// and the connection), plus the path a proxy mounts the API under from X-Forwarded-Prefix
+#[cfg(feature = "actixweb")]
+pub fn request_origin(req: &actix_web::HttpRequest) -> String {
+    let info = req.connection_info();
+    let prefix = req
+        .headers()
+        .get("x-forwarded-prefix")
+        .and_then(|prefix| prefix.to_str().ok())
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    format!("{}://{}{}", info.scheme(), info.host(), prefix)
+}
+
+// Rebased generates the links of another generator under a different base URL
+pub struct Rebased {
+    base_url: String,
+    generator: SharedLinkGenerator,
+}
+
+impl Rebased {
+    pub fn new(base_url: &str, generator: SharedLinkGenerator) -> Rebased {
+        Rebased {
+            base_url: base_url.to_owned(),
+            generator,
+        }
+    }
+}
+
+impl LinkGenerator for Rebased {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn resource_path(&self, typ: &str, id: &ID) -> Option<String> {
+        self.generator.resource_path(typ, id)
+    }
+
+    fn related_path(&self, typ: &str, id: &ID, relationship: &str) -> Option<String> {
+        self.generator.related_path(typ, id, relationship)
+    }
+
+    fn relationship_path(&self, typ: &str, id: &ID, relationship: &str) -> Option<String> {
+        self.generator.relationship_path(typ, id, relationship)
+    }
+}
+
+// RouteLinks generates links from URI templates per resource type, which are expanded with the
+// resource's `type` and `id`, the `relationship` for related links, and any variables set on the
+// generator. Types without a route are taken to live at `/{type}/{id}`
+#[derive(Debug, Clone, Default)]
+pub struct RouteLinks {
+    base_url: String,
+    // a type mapped to None is never linked
+    routes: BTreeMap<String, Option<UriTemplate>>,
+    related: BTreeMap<(String, String), UriTemplate>,
+    vars: BTreeMap<String, String>,
+}
+
+impl RouteLinks {
+    pub fn new(base_url: &str) -> RouteLinks {
+        RouteLinks {
+            base_url: base_url.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    // route sets the template of a type's resources, e.g. "/articles/{id}"
+    pub fn route(mut self, typ: &str, template: &str) -> Self {
+        self.routes
+            .insert(typ.to_owned(), Some(UriTemplate::new(template)));
+        self
+    }
+
+    // related_route sets the template of the related link of one relationship, e.g.
+    // "/articles/{id}/comments{?page[number]}"
+    pub fn related_route(mut self, typ: &str, relationship: &str, template: &str) -> Self {
+        self.related.insert(
+            (typ.to_owned(), relationship.to_owned()),
+            UriTemplate::new(template),
+        );
+        self
+    }
+
+    // var sets a variable for every template, e.g. an API version
+    pub fn var(mut self, name: &str, value: &str) -> Self {
+        self.vars.insert(name.to_owned(), value.to_owned());
+        self
+    }
+
+    fn expand(&self, template: &UriTemplate, typ: &str, id: &ID, relationship: &str) -> String {
+        template.expand(|name| match name {
+            "type" => Some(typ.to_owned()),
+            "id" => Some(id.0.to_string()),
+            "relationship" if !relationship.is_empty() => Some(relationship.to_owned()),
+            _ => self.vars.get(name).cloned(),
+        })
+    }
+
+    // unlinked leaves resources of a type without generated links, e.g. ones not exposed on
+    // their own
+    pub fn unlinked(mut self, typ: &str) -> Self {
+        self.routes.insert(typ.to_owned(), None);
+        self
+    }
+}
+
+impl LinkGenerator for RouteLinks {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn resource_path(&self, typ: &str, id: &ID) -> Option<String> {
+        Some(match self.routes.get(typ) {
+            Some(template) => self.expand(template.as_ref()?, typ, id, ""),
+            None => format!("/{}/{}", typ, id),
+        })
+    }
+
+    fn related_path(&self, typ: &str, id: &ID, relationship: &str) -> Option<String> {
+        let key = (typ.to_owned(), relationship.to_owned());
+        match self.related.get(&key) {
+            Some(template) => Some(self.expand(template, typ, id, relationship)),
+            None => Some(format!("{}/{}", self.resource_path(typ, id)?, relationship)),
+        }
+    }
+}
+
+// UriTemplate is an RFC 6570 URI template, up to level 3 (all the operators, without the prefix
+// and explode modifiers). Undefined variables expand to nothing, and as the RFC allows,
+// malformed expressions are left in the output as they are rather than failing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UriTemplate(Vec<TemplatePart>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Literal(String),
+    Expression { op: Option<char>, vars: Vec<String> },
+}
+
+impl UriTemplate {
+    pub fn new(template: &str) -> UriTemplate {
+        let mut parts = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else {
+                break;
+            };
+            let expr = &rest[start + 1..start + len];
+            let op = expr.chars().next().filter(|c| "+#./;?&".contains(*c));
+            let names = &expr[op.map_or(0, char::len_utf8)..];
+            if names.is_empty() || names.split(',').any(str::is_empty) {
+                parts.push(TemplatePart::Literal(rest[..start + len + 1].to_owned()));
+            } else {
+                parts.push(TemplatePart::Literal(rest[..start].to_owned()));
+                parts.push(TemplatePart::Expression {
+                    op,
+                    vars: names.split(',').map(str::to_owned).collect(),
+                });
+            }
+            rest = &rest[start + len + 1..];
+        }
+        parts.push(TemplatePart::Literal(rest.to_owned()));
+        UriTemplate(parts)
+    }
+
+    // expand fills in the template with the variables `vars` knows of
+    pub fn expand(&self, vars: impl Fn(&str) -> Option<String>) -> String {
+        let mut out = String::new();
+        for part in &self.0 {
+            match part {
+                TemplatePart::Literal(literal) => out.push_str(literal),
+                TemplatePart::Expression { op, vars: names } => {
+                    // first, separator, named, if empty, reserved allowed: the table of RFC 6570
+                    // appendix A
+                    let (first, sep, named, empty, reserved) = match op {
+                        None => ("", ",", false, "", false),
+                        Some('+') => ("", ",", false, "", true),
+                        Some('#') => ("#", ",", false, "", true),
+                        Some('.') => (".", ".", false, "", false),
+                        Some('/') => ("/", "/", false, "", false),
+                        Some(';') => (";", ";", true, "", false),
+                        Some('?') => ("?", "&", true, "=", false),
+                        _ => ("&", "&", true, "=", false),
+                    };
+                    let mut defined = names
+                        .iter()
+                        .filter_map(|name| Some((name, vars(name)?)))
+                        .peekable();
+                    if defined.peek().is_some() {
+                        out.push_str(first);
+                    }
+                    for (i, (name, value)) in defined.enumerate() {
+                        if i > 0 {
+                            out.push_str(sep);
+                        }
+                        if named {
+                            out.push_str(name);
+                            out.push_str(if value.is_empty() { empty } else { "=" });
+                        }
+                        pct_encode(&mut out, &value, reserved);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+// pct_encode percent-encodes everything but unreserved characters, and reserved ones (and
+// existing escapes) too if they're allowed
+fn pct_encode(out: &mut String, value: &str, reserved: bool) {
+    let bytes = value.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        let keep = b.is_ascii_alphanumeric()
+            || b"-._~".contains(&b)
+            || reserved
+                && (b":/?#[]@!$&'()*+,;=".contains(&b)
+                    || b == b'%'
+                        && bytes
+                            .get(i + 1..i + 3)
+                            .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)));
+        if keep {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smallvec::smallvec;
+
+    use super::{LinkGenerator, RouteLinks, UriTemplate};
+    use crate::{
+        Identifier, Relationship, Relationships, ResourceResponse, Response, ResponseType, ID,
+    };
+
+    #[test]
+    fn test_route_links() {
+        let links = RouteLinks::new("https://api.example.com/").route("people", "/authors/{id}");
+        let id = ID::from_static("1");
+        assert_eq!(
+            links.self_link("articles", &id).unwrap(),
+            "https://api.example.com/articles/1"
+        );
+        assert_eq!(
+            links.related_link("people", &id, "books").unwrap(),
+            "https://api.example.com/authors/1/books"
+        );
+        assert_eq!(
+            links.relationship_link("articles", &id, "author").unwrap(),
+            "https://api.example.com/articles/1/relationships/author"
+        );
+    }
+
+    #[test]
+    fn test_uri_template() {
+        let vars = |name: &str| match name {
+            "id" => Some("a b".to_owned()),
+            "path" => Some("/x/y".to_owned()),
+            "page[number]" => Some("2".to_owned()),
+            "empty" => Some(String::new()),
+            _ => None,
+        };
+        let expand = |template: &str| UriTemplate::new(template).expand(vars);
+        assert_eq!(
+            expand("/articles/{id}/comments{?page[number],page[size]}"),
+            "/articles/a%20b/comments?page[number]=2"
+        );
+        assert_eq!(expand("{+path}{/id}{#id}"), "/x/y/a%20b#a%20b");
+        assert_eq!(expand("/x{?missing}{&empty}{;empty}"), "/x&empty=;empty");
+        assert_eq!(expand("{.id}{path}"), ".a%20b%2Fx%2Fy");
+        assert_eq!(expand("/{unclosed"), "/{unclosed");
+        assert_eq!(expand("/{}/{a,}"), "/{}/{a,}");
+
+        let links = RouteLinks::new("")
+            .var("version", "v2")
+            .route("articles", "/{version}/articles/{id}")
+            .related_route("articles", "comments", "/comments{?article}");
+        let id = ID::from_static("1");
+        assert_eq!(
+            links.resource_path("articles", &id).unwrap(),
+            "/v2/articles/1"
+        );
+        assert_eq!(
+            links.related_path("articles", &id, "comments").unwrap(),
+            "/comments"
+        );
+        assert_eq!(
+            links.related_path("articles", &id, "author").unwrap(),
+            "/v2/articles/1/author"
+        );
+    }
+
+    #[test]
+    fn test_linked() {
+        let links = RouteLinks::new("https://api.example.com");
+        let mut rels = Relationships::new();
+        rels.insert(
+            "author".into(),
+            Relationship::ToOne(Identifier {
+                id: "9".into(),
+                typ: "people".into(),
+            })
+            .into(),
+        );
+        let resource = ResourceResponse {
+            id: Identifier {
+                id: "1".into(),
+                typ: "articles".into(),
+            },
+            attributes: (),
+            relationships: Some(rels),
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        };
+        let response: Response<(), ()> = Response {
+            primary: ResponseType::Ok(smallvec![resource]),
+            included: None,
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Default::default(),
+        };
+        let out = serde_json::to_value(response.linked(&links)).unwrap();
+        assert_eq!(
+            out["data"][0]["links"]["self"],
+            "https://api.example.com/articles/1"
+        );
+        assert_eq!(
+            out["data"][0]["relationships"]["author"]["links"]["related"],
+            "https://api.example.com/articles/1/author"
+        );
+        assert_eq!(
+            out["data"][0]["relationships"]["author"]["links"]["self"],
+            "https://api.example.com/articles/1/relationships/author"
+        );
+        assert_eq!(out["data"][0]["relationships"]["author"]["data"]["id"], "9");
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_responder() {
+        use actix_web::{body::MessageBody, test::TestRequest, Responder};
+
+        let resource = |typ: &str| ResourceResponse {
+            id: Identifier {
+                id: "1".into(),
+                typ: typ.into(),
+            },
+            attributes: (),
+            relationships: None,
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        };
+        let response: Response<(), ()> = Response {
+            primary: ResponseType::Ok(smallvec![resource("articles")]),
+            included: Some(vec![resource("tags")]),
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Default::default(),
+        };
+        let config = crate::JsonApiConfig::default()
+            .links(RouteLinks::new("http://localhost").unlinked("tags"));
+        let req = TestRequest::get().app_data(config).to_http_request();
+        let res = response.respond_to(&req);
+        assert_eq!(
+            res.headers().get("content-type").unwrap(),
+            crate::media::JSONAPI_MEDIA_TYPE
+        );
+        let body = res.into_body().try_into_bytes().unwrap();
+        let out: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            out["data"][0]["links"]["self"],
+            "http://localhost/articles/1"
+        );
+        assert!(out["included"][0].get("links").is_none());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_url_for() {
+        use super::RouteNames;
+        use actix_web::{test, web, App, HttpRequest};
+
+        async fn article(req: HttpRequest) -> Response<(), ()> {
+            let mut rels = Relationships::new();
+            rels.insert(
+                "author".into(),
+                Relationship::ToOne(Identifier {
+                    id: "9".into(),
+                    typ: "people".into(),
+                })
+                .into(),
+            );
+            let resource = ResourceResponse {
+                id: Identifier {
+                    id: req.match_info()["id"].into(),
+                    typ: "articles".into(),
+                },
+                attributes: (),
+                relationships: Some(rels),
+                meta: None,
+                links: None,
+                extensions: Default::default(),
+            };
+            Response {
+                primary: ResponseType::Ok(smallvec![resource]),
+                included: None,
+                meta: None,
+                links: None,
+                jsonapi: None,
+                extensions: Default::default(),
+            }
+        }
+
+        let config = crate::JsonApiConfig::default()
+            .links(RouteLinks::new("https://fallback"))
+            .route_names(
+                RouteNames::new()
+                    .resource("articles", "article")
+                    .related("articles", "article_related"),
+            );
+        let out: serde_json::Value = actix_web::rt::System::new().block_on(async {
+            let app = test::init_service(
+                App::new()
+                    .app_data(config)
+                    .service(
+                        web::resource("/api/articles/{id}")
+                            .name("article")
+                            .to(article),
+                    )
+                    .service(
+                        web::resource("/api/articles/{id}/{relationship}")
+                            .name("article_related")
+                            .to(article),
+                    ),
+            )
+            .await;
+            let req = test::TestRequest::get()
+                .uri("/api/articles/1")
+                .insert_header(("host", "api.example.com"))
+                .to_request();
+            test::call_and_read_body_json(&app, req).await
+        });
+        let links = &out["data"][0]["links"];
+        assert_eq!(links["self"], "http://api.example.com/api/articles/1");
+        let links = &out["data"][0]["relationships"]["author"]["links"];
+        assert_eq!(
+            links["related"],
+            "http://api.example.com/api/articles/1/author"
+        );
+        assert_eq!(
+            links["self"],
+            "https://fallback/articles/1/relationships/author"
+        );
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_host_aware() {
+        use super::{from_request, request_origin};
+        use actix_web::test::TestRequest;
+
+        let req = TestRequest::get()
+            .insert_header(("host", "internal:8080"))
+            .to_http_request();
+        assert_eq!(request_origin(&req), "http://internal:8080");
+
+        let config = crate::JsonApiConfig::default()
+            .links(RouteLinks::new("/v1"))
+            .host_aware_links(true);
+        let req = TestRequest::get()
+            .app_data(config)
+            .insert_header(("host", "internal:8080"))
+            .insert_header(("x-forwarded-host", "api.example.com"))
+            .insert_header(("x-forwarded-proto", "https"))
+            .insert_header(("x-forwarded-prefix", "/public/"))
+            .to_http_request();
+        let links = from_request(&req).unwrap();
+        assert_eq!(
+            links.self_link("articles", &ID::from_static("1")).unwrap(),
+            "https://api.example.com/public/v1/articles/1"
+        );
+    }
+}
@@ -0,0 +1,352 @@
+// Structural checks of a JSON:API document against the spec, for testing our own responses and
+// vetting documents received from upstream services. Only the shape of the document is checked,
+// not whether it makes sense for a particular endpoint.
+use serde_json::{Map, Value};
+use std::{collections::BTreeSet, fmt::Display};
+
+use crate::SpecVersion;
+
+// Violation is a single spec rule a document breaks, located by a JSON pointer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(f, "{}: {}", pointer, self.message)
+    }
+}
+
+const TOP_LEVEL_MEMBERS: &[&str] = &["data", "errors", "meta", "jsonapi", "links", "included"];
+const RESOURCE_MEMBERS: &[&str] = &[
+    "id",
+    "lid",
+    "type",
+    "attributes",
+    "relationships",
+    "links",
+    "meta",
+];
+const IDENTIFIER_MEMBERS: &[&str] = &["id", "lid", "type", "meta"];
+const RELATIONSHIP_MEMBERS: &[&str] = &["data", "links", "meta"];
+const ERROR_MEMBERS: &[&str] = &[
+    "id", "links", "status", "code", "title", "detail", "source", "meta",
+];
+
+// validate checks a whole document and returns every violation found, none for a valid document
+pub fn validate(doc: &Value) -> Vec<Violation> {
+    validate_with(doc, SpecVersion::default())
+}
+
+// validate_with checks a document against a particular version of the spec. 1.0 documents
+// can't use local ids or extension members
+pub fn validate_with(doc: &Value, version: SpecVersion) -> Vec<Violation> {
+    let mut lint = Lint {
+        version,
+        violations: Vec::new(),
+        seen: BTreeSet::new(),
+    };
+    lint.document(doc);
+    lint.violations
+}
+
+struct Lint {
+    version: SpecVersion,
+    violations: Vec<Violation>,
+    // (type, id) of every resource object seen, to catch duplicates
+    seen: BTreeSet<(String, String)>,
+}
+
+impl Lint {
+    fn violation(&mut self, pointer: &str, message: impl Into<String>) {
+        self.violations.push(Violation {
+            pointer: pointer.to_owned(),
+            message: message.into(),
+        });
+    }
+
+    // object reports a violation unless value is an object, which it returns
+    fn object<'v>(&mut self, value: &'v Value, pointer: &str) -> Option<&'v Map<String, Value>> {
+        let obj = value.as_object();
+        if obj.is_none() {
+            self.violation(pointer, "must be an object");
+        }
+        obj
+    }
+
+    // members reports members which aren't in `allowed`. Since 1.1, extension members (whose
+    // names contain a colon) are allowed anywhere
+    fn members(&mut self, obj: &Map<String, Value>, pointer: &str, allowed: &[&str]) {
+        for name in obj.keys() {
+            let allowed = match name.as_str() {
+                "lid" => allowed.contains(&"lid") && self.version.accepts_lid(),
+                name if name.contains(':') => self.version >= SpecVersion::V1_1,
+                name => allowed.contains(&name),
+            };
+            if !allowed {
+                self.violation(pointer, format!("unexpected member '{}'", name));
+            }
+        }
+    }
+
+    fn string_member(&mut self, obj: &Map<String, Value>, pointer: &str, name: &str) {
+        if let Some(value) = obj.get(name) {
+            if !value.is_string() {
+                self.violation(&format!("{}/{}", pointer, name), "must be a string");
+            }
+        }
+    }
+
+    fn document(&mut self, doc: &Value) {
+        let Some(doc) = self.object(doc, "") else {
+            return;
+        };
+        self.members(doc, "", TOP_LEVEL_MEMBERS);
+        let (data, errors) = (doc.get("data"), doc.get("errors"));
+        if data.is_none() && errors.is_none() && !doc.contains_key("meta") {
+            self.violation(
+                "",
+                "a document must contain at least one of data, errors or meta",
+            );
+        }
+        if data.is_some() && errors.is_some() {
+            self.violation("", "data and errors must not coexist");
+        }
+        if doc.contains_key("included") && data.is_none() {
+            self.violation("/included", "included is only allowed alongside data");
+        }
+        match data {
+            None | Some(Value::Null) => {}
+            Some(Value::Array(resources)) => {
+                for (i, resource) in resources.iter().enumerate() {
+                    self.resource(resource, &format!("/data/{}", i));
+                }
+            }
+            Some(resource) => self.resource(resource, "/data"),
+        }
+        if let Some(included) = doc.get("included") {
+            match included.as_array() {
+                Some(resources) => {
+                    for (i, resource) in resources.iter().enumerate() {
+                        self.resource(resource, &format!("/included/{}", i));
+                    }
+                }
+                None => self.violation("/included", "must be an array of resource objects"),
+            }
+        }
+        if let Some(errors) = errors {
+            match errors.as_array() {
+                Some(errors) => {
+                    for (i, error) in errors.iter().enumerate() {
+                        self.error(error, &format!("/errors/{}", i));
+                    }
+                }
+                None => self.violation("/errors", "must be an array of error objects"),
+            }
+        }
+        if let Some(jsonapi) = doc.get("jsonapi") {
+            if let Some(jsonapi) = self.object(jsonapi, "/jsonapi") {
+                self.string_member(jsonapi, "/jsonapi", "version");
+            }
+        }
+        self.common(doc, "");
+    }
+
+    // common checks the links and meta members, which most objects may have
+    fn common(&mut self, obj: &Map<String, Value>, pointer: &str) {
+        if let Some(meta) = obj.get("meta") {
+            self.object(meta, &format!("{}/meta", pointer));
+        }
+        if let Some(links) = obj.get("links") {
+            let pointer = format!("{}/links", pointer);
+            if let Some(links) = self.object(links, &pointer) {
+                for (name, link) in links {
+                    match link {
+                        Value::Null | Value::String(_) => {}
+                        Value::Object(link) if link.get("href").is_some_and(Value::is_string) => {}
+                        _ => self.violation(
+                            &format!("{}/{}", pointer, name),
+                            "a link must be null, a string or an object with an href",
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    fn resource(&mut self, resource: &Value, pointer: &str) {
+        let Some(obj) = self.object(resource, pointer) else {
+            return;
+        };
+        self.members(obj, pointer, RESOURCE_MEMBERS);
+        self.identity(obj, pointer);
+        let mut fields = BTreeSet::new();
+        if let Some(attributes) = obj.get("attributes") {
+            let pointer = format!("{}/attributes", pointer);
+            if let Some(attributes) = self.object(attributes, &pointer) {
+                for name in attributes.keys() {
+                    self.field_name(name, &pointer);
+                    fields.insert(name);
+                }
+            }
+        }
+        if let Some(relationships) = obj.get("relationships") {
+            let pointer = format!("{}/relationships", pointer);
+            if let Some(relationships) = self.object(relationships, &pointer) {
+                for (name, relationship) in relationships {
+                    self.field_name(name, &pointer);
+                    if fields.contains(name) {
+                        self.violation(
+                            &pointer,
+                            format!("'{}' is both an attribute and a relationship", name),
+                        );
+                    }
+                    self.relationship(relationship, &format!("{}/{}", pointer, name));
+                }
+            }
+        }
+        self.common(obj, pointer);
+        if let (Some(Value::String(typ)), Some(Value::String(id))) =
+            (obj.get("type"), obj.get("id"))
+        {
+            if !self.seen.insert((typ.clone(), id.clone())) {
+                self.violation(
+                    pointer,
+                    format!("resource {}/{} appears more than once", typ, id),
+                );
+            }
+        }
+    }
+
+    // identity checks the type and id (or lid) members shared by resources and identifiers
+    fn identity(&mut self, obj: &Map<String, Value>, pointer: &str) {
+        match obj.get("type") {
+            Some(Value::String(_)) => {}
+            Some(_) => self.violation(&format!("{}/type", pointer), "must be a string"),
+            None => self.violation(pointer, "missing type"),
+        }
+        let has_lid = obj.contains_key("lid") && self.version.accepts_lid();
+        if !obj.contains_key("id") && !has_lid {
+            self.violation(pointer, "missing id");
+        }
+        self.string_member(obj, pointer, "id");
+        self.string_member(obj, pointer, "lid");
+    }
+
+    fn field_name(&mut self, name: &str, pointer: &str) {
+        if name == "id" || name == "type" {
+            self.violation(pointer, format!("'{}' can't be used as a field name", name));
+        }
+    }
+
+    fn relationship(&mut self, relationship: &Value, pointer: &str) {
+        let Some(obj) = self.object(relationship, pointer) else {
+            return;
+        };
+        self.members(obj, pointer, RELATIONSHIP_MEMBERS);
+        if !RELATIONSHIP_MEMBERS
+            .iter()
+            .any(|name| obj.contains_key(*name))
+        {
+            self.violation(pointer, "a relationship must contain links, data or meta");
+        }
+        match obj.get("data") {
+            None | Some(Value::Null) => {}
+            Some(Value::Array(identifiers)) => {
+                for (i, identifier) in identifiers.iter().enumerate() {
+                    self.identifier(identifier, &format!("{}/data/{}", pointer, i));
+                }
+            }
+            Some(identifier) => self.identifier(identifier, &format!("{}/data", pointer)),
+        }
+        self.common(obj, pointer);
+    }
+
+    fn identifier(&mut self, identifier: &Value, pointer: &str) {
+        if let Some(obj) = self.object(identifier, pointer) {
+            self.members(obj, pointer, IDENTIFIER_MEMBERS);
+            self.identity(obj, pointer);
+        }
+    }
+
+    fn error(&mut self, error: &Value, pointer: &str) {
+        let Some(obj) = self.object(error, pointer) else {
+            return;
+        };
+        self.members(obj, pointer, ERROR_MEMBERS);
+        for name in ["id", "status", "code", "title", "detail"] {
+            self.string_member(obj, pointer, name);
+        }
+        if let Some(source) = obj.get("source") {
+            self.object(source, &format!("{}/source", pointer));
+        }
+        self.common(obj, pointer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{validate, validate_with};
+    use crate::SpecVersion;
+
+    #[test]
+    fn test_validate() {
+        let valid = json!({
+            "data": [{"type": "articles", "id": "1", "attributes": {"title": "a"},
+                "relationships": {"author": {"data": {"type": "people", "id": "9"}}}}],
+            "included": [{"type": "people", "id": "9", "attributes": {}}],
+            "jsonapi": {"version": "1.1"},
+            "atomic:results": []
+        });
+        assert!(validate(&valid).is_empty());
+
+        let invalid = json!({
+            "data": [{"id": 1, "attributes": {"type": "x", "author": 1},
+                "relationships": {"author": {"data": {"id": "9"}}}}],
+            "errors": [],
+            "included": [{"type": "people", "id": "9"}, {"type": "people", "id": "9"}],
+            "extra": true
+        });
+        let pointers: Vec<String> = validate(&invalid)
+            .into_iter()
+            .map(|violation| violation.to_string())
+            .collect();
+        assert_eq!(
+            pointers,
+            [
+                "/: unexpected member 'extra'",
+                "/: data and errors must not coexist",
+                "/data/0: missing type",
+                "/data/0/id: must be a string",
+                "/data/0/attributes: 'type' can't be used as a field name",
+                "/data/0/relationships: 'author' is both an attribute and a relationship",
+                "/data/0/relationships/author/data: missing type",
+                "/included/1: resource people/9 appears more than once",
+            ]
+        );
+
+        let local = json!({"data": {"type": "articles", "lid": "a", "x:y": 1}});
+        assert!(validate(&local).is_empty());
+        let violations: Vec<String> = validate_with(&local, SpecVersion::V1_0)
+            .into_iter()
+            .map(|violation| violation.to_string())
+            .collect();
+        assert_eq!(
+            violations,
+            [
+                "/data: unexpected member 'lid'",
+                "/data: unexpected member 'x:y'",
+                "/data: missing id",
+            ]
+        );
+    }
+}
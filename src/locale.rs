@@ -0,0 +1,160 @@
+// Localized error text. An ErrorLocalizer registered with JsonApiConfig translates the title (and
+// detail) of errors by their code into the language a client prefers, going by its
+// Accept-Language header, wherever an error is answered with the config at hand, see
+// errors::ErrorDocuments.
+#[cfg(feature = "actixweb")]
+use actix_web::{http::header::ACCEPT_LANGUAGE, HttpRequest};
+use std::collections::HashMap;
+
+use crate::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalizedText {
+    pub title: String,
+    pub detail: Option<String>,
+}
+
+pub trait ErrorLocalizer: Send + Sync {
+    // localize is the text of errors with code in a locale, a lowercase language tag (e.g. "de-ch"
+    // or "de"), None if there's no translation
+    fn localize(&self, code: &str, locale: &str) -> Option<LocalizedText>;
+}
+
+// Translations is a localizer of texts registered up front
+#[derive(Debug, Clone, Default)]
+pub struct Translations {
+    texts: HashMap<(String, String), LocalizedText>,
+}
+
+impl Translations {
+    pub fn new() -> Translations {
+        Translations::default()
+    }
+
+    pub fn text(mut self, code: &str, locale: &str, title: &str, detail: Option<&str>) -> Self {
+        let text = LocalizedText {
+            title: title.to_owned(),
+            detail: detail.map(str::to_owned),
+        };
+        self.texts
+            .insert((code.to_owned(), locale.to_ascii_lowercase()), text);
+        self
+    }
+}
+
+impl ErrorLocalizer for Translations {
+    fn localize(&self, code: &str, locale: &str) -> Option<LocalizedText> {
+        self.texts
+            .get(&(code.to_owned(), locale.to_owned()))
+            .cloned()
+    }
+}
+
+// locales are the languages of an Accept-Language header, most preferred first. A tag with a
+// region is followed by its language, unless the client ranks that itself
+pub fn locales(accept_language: &str) -> Vec<String> {
+    let mut ranked: Vec<(f32, String)> = accept_language
+        .split(',')
+        .filter_map(|entry| {
+            let tag = entry.split(';').next()?.trim().to_ascii_lowercase();
+            let weight = crate::encoding::weight(entry);
+            (weight > 0.0 && !tag.is_empty() && tag != "*").then_some((weight, tag))
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut locales: Vec<String> = Vec::with_capacity(ranked.len());
+    for (_, tag) in &ranked {
+        let language = tag.split('-').next().unwrap_or_default();
+        for locale in [tag.as_str(), language] {
+            let ranked_later = locale != tag && ranked.iter().any(|(_, other)| other == locale);
+            if !ranked_later && !locales.iter().any(|seen| seen == locale) {
+                locales.push(locale.to_owned());
+            }
+        }
+    }
+    locales
+}
+
+// localize translates an error into the first locale there's a translation for, false if there's
+// none (or the error has no code). Details without a translation are kept
+pub fn localize(localizer: &dyn ErrorLocalizer, err: &mut Error, accept_language: &str) -> bool {
+    let Some(code) = err.code.as_deref() else {
+        return false;
+    };
+    let Some(text) = locales(accept_language)
+        .iter()
+        .find_map(|locale| localizer.localize(code, locale))
+    else {
+        return false;
+    };
+    err.title = text.title;
+    if text.detail.is_some() {
+        err.detail = text.detail;
+    }
+    true
+}
+
+// localize_request translates errors into the locale of the request, with the localizer
+// registered with JsonApiConfig
+#[cfg(feature = "actixweb")]
+pub(crate) fn localize_request<'a>(
+    req: &HttpRequest,
+    errors: impl IntoIterator<Item = &'a mut Error>,
+) -> bool {
+    let localizer = req
+        .app_data::<crate::JsonApiConfig>()
+        .and_then(|config| config.localizer.as_deref());
+    let accept_language = req
+        .headers()
+        .get(ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok());
+    let (Some(localizer), Some(accept_language)) = (localizer, accept_language) else {
+        return false;
+    };
+    let mut localized = false;
+    for err in errors {
+        localized |= localize(localizer, err, accept_language);
+    }
+    localized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{locales, localize, Translations};
+    use crate::Error;
+
+    fn translations() -> Translations {
+        Translations::new()
+            .text("Not Found", "de", "nicht gefunden", None)
+            .text(
+                "Not Found",
+                "fr-CA",
+                "introuvable",
+                Some("aucune ressource"),
+            )
+    }
+
+    #[test]
+    fn test_locales() {
+        assert_eq!(locales("de-CH, fr;q=0.5, *;q=0.1"), ["de-ch", "de", "fr"]);
+        assert_eq!(locales("en;q=0.2, en-GB, x;q=0"), ["en-gb", "en"]);
+        assert!(locales("").is_empty());
+    }
+
+    #[test]
+    fn test_localize() {
+        let mut err = Error::new_not_found("no such article");
+        err.detail = Some("article 5".into());
+        assert!(!localize(&translations(), &mut err.clone(), "it"));
+        assert!(localize(&translations(), &mut err, "it, de-AT;q=0.8"));
+        assert_eq!(err.title, "nicht gefunden");
+        assert_eq!(err.detail.as_deref(), Some("article 5"));
+        assert!(localize(&translations(), &mut err, "fr-ca"));
+        assert_eq!(err.detail.as_deref(), Some("aucune ressource"));
+        assert!(!localize(
+            &translations(),
+            &mut Error::new_conflict("a"),
+            "de"
+        ));
+    }
+}
@@ -0,0 +1,248 @@
+// Negotiation of the JSON:API media type and its `ext` and `profile` parameters. Extensions change
+// the meaning of a document, so requests using one the server doesn't support are refused (415
+// for the body, 406 for the response); profiles don't, so unknown ones are passed on and the
+// handler decides which to apply.
+#[cfg(feature = "actixweb")]
+use actix_web::{
+    http::header::{HeaderName, ACCEPT, CONTENT_TYPE},
+    FromRequest as FromWebRequest,
+};
+use std::fmt::Display;
+
+use crate::{Error, SpecVersion};
+
+pub const JSONAPI_MEDIA_TYPE: &str = "application/vnd.api+json";
+
+// MediaType is the JSON:API media type with the extensions and profiles it's modified by
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MediaType {
+    pub ext: Vec<String>,
+    pub profile: Vec<String>,
+}
+
+impl MediaType {
+    // parse reads a single media type, e.g. a Content-Type header. It's None for anything but the
+    // JSON:API media type, or if that has a parameter the spec version doesn't allow
+    pub fn parse(value: &str, version: SpecVersion) -> Option<MediaType> {
+        let mut params = split_quoted(value, ';');
+        if !params
+            .next()?
+            .trim()
+            .eq_ignore_ascii_case(JSONAPI_MEDIA_TYPE)
+        {
+            return None;
+        }
+        let mut media = MediaType::default();
+        for param in params {
+            let (name, value) = param.split_once('=')?;
+            let name = name.trim().to_ascii_lowercase();
+            if !version.accepts_media_type_param(&name) {
+                return None;
+            }
+            let value = value.trim().trim_matches('"');
+            let uris = value.split_whitespace().map(str::to_owned);
+            match name.as_str() {
+                "ext" => media.ext.extend(uris),
+                _ => media.profile.extend(uris),
+            }
+        }
+        Some(media)
+    }
+
+    pub fn has_ext(&self, uri: &str) -> bool {
+        self.ext.iter().any(|ext| ext == uri)
+    }
+
+    pub fn has_profile(&self, uri: &str) -> bool {
+        self.profile.iter().any(|profile| profile == uri)
+    }
+}
+
+// Display gives the media type as a header value, with ext and profile quoted as the spec requires
+impl Display for MediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(JSONAPI_MEDIA_TYPE)?;
+        if !self.ext.is_empty() {
+            write!(f, "; ext=\"{}\"", self.ext.join(" "))?;
+        }
+        if !self.profile.is_empty() {
+            write!(f, "; profile=\"{}\"", self.profile.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+// split_quoted splits on a separator outside of double quotes, as ext and profile values are
+// quoted URI lists
+fn split_quoted(value: &str, sep: char) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    value.split(move |c: char| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == sep && !quoted
+    })
+}
+
+// negotiate_content_type checks the Content-Type of a request body. Plain JSON is let through
+// without extensions; the JSON:API media type must only carry parameters the version allows
+// and extensions the server supports
+pub fn negotiate_content_type(
+    value: &str,
+    version: SpecVersion,
+    extensions: &[String],
+) -> Result<MediaType, Error> {
+    let essence = value.split(';').next().unwrap_or_default().trim();
+    if !essence.eq_ignore_ascii_case(JSONAPI_MEDIA_TYPE) {
+        return Ok(MediaType::default());
+    }
+    let media = MediaType::parse(value, version).ok_or_else(|| {
+        Error::new_unsupported_media_type(&format!(
+            "media type parameters other than ext and profile aren't supported by JSON:API {}",
+            version
+        ))
+    })?;
+    if let Some(ext) = media.ext.iter().find(|ext| !extensions.contains(ext)) {
+        return Err(Error::new_unsupported_media_type(&format!(
+            "extension {} isn't supported",
+            ext
+        )));
+    }
+    Ok(media)
+}
+
+// negotiate_accept picks the media type of the response from an Accept header. JSON:API entries
+// with other parameters, or asking for an extension the server doesn't support, are passed over;
+// if that leaves none the request fails with 406. Without any JSON:API entry (or header) the
+// plain media type is used
+pub fn negotiate_accept(
+    value: Option<&str>,
+    version: SpecVersion,
+    extensions: &[String],
+) -> Result<MediaType, Error> {
+    let mut candidates = 0;
+    let mut best: Option<(f32, MediaType)> = None;
+    for entry in value.into_iter().flat_map(|value| split_quoted(value, ',')) {
+        let essence = entry.split(';').next().unwrap_or_default().trim();
+        if !essence.eq_ignore_ascii_case(JSONAPI_MEDIA_TYPE) {
+            continue;
+        }
+        candidates += 1;
+        // q is an Accept parameter rather than a media type one, so it's taken off before parsing
+        let mut weight = 1.0;
+        let media_type = split_quoted(entry, ';')
+            .filter(|param| match param.trim().strip_prefix("q=") {
+                Some(q) => {
+                    weight = q.trim().parse().unwrap_or(0.0);
+                    false
+                }
+                None => true,
+            })
+            .collect::<Vec<_>>()
+            .join(";");
+        let Some(media) = MediaType::parse(&media_type, version) else {
+            continue;
+        };
+        if weight <= 0.0 || media.ext.iter().any(|ext| !extensions.contains(ext)) {
+            continue;
+        }
+        if best.as_ref().is_none_or(|(best, _)| weight > *best) {
+            best = Some((weight, media));
+        }
+    }
+    match best {
+        Some((_, media)) => Ok(media),
+        None if candidates == 0 => Ok(MediaType::default()),
+        None => Err(Error::new_not_acceptable(
+            "none of the accepted JSON:API media types can be served",
+        )),
+    }
+}
+
+// Negotiated extracts the outcome of negotiation for a handler: the extensions and profiles the
+// request body uses, and the ones the client accepts in the response. Unsupported extensions fail
+// the request with 415 or 406
+#[cfg(feature = "actixweb")]
+#[derive(Debug, Clone, Default)]
+pub struct Negotiated {
+    pub request: MediaType,
+    pub response: MediaType,
+}
+
+#[cfg(feature = "actixweb")]
+impl FromWebRequest for Negotiated {
+    type Error = Error;
+
+    type Future = std::future::Ready<Result<Negotiated, Error>>;
+
+    fn from_request(req: &actix_web::HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        let config = req
+            .app_data::<crate::JsonApiConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let header = |name: HeaderName| {
+            req.headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let negotiated = || {
+            let request = match header(CONTENT_TYPE) {
+                Some(value) => negotiate_content_type(value, config.version, &config.extensions)?,
+                None => MediaType::default(),
+            };
+            let response = negotiate_accept(header(ACCEPT), config.version, &config.extensions)?;
+            Ok(Negotiated { request, response })
+        };
+        std::future::ready(negotiated())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{negotiate_accept, negotiate_content_type, MediaType};
+    use crate::{ErrorStatus, SpecVersion};
+
+    const ATOMIC: &str = "https://jsonapi.org/ext/atomic";
+
+    #[test]
+    fn test_media_type() {
+        let value = r#"application/vnd.api+json; ext="https://jsonapi.org/ext/atomic https://example.com/x"; profile="https://example.com/p""#;
+        let media = MediaType::parse(value, SpecVersion::V1_1).unwrap();
+        assert!(media.has_ext(ATOMIC));
+        assert!(media.has_profile("https://example.com/p"));
+        assert_eq!(media.to_string(), value);
+        assert!(MediaType::parse(value, SpecVersion::V1_0).is_none());
+        assert!(MediaType::parse("application/json", SpecVersion::V1_1).is_none());
+        assert!(
+            MediaType::parse("application/vnd.api+json; charset=utf-8", SpecVersion::V1_1)
+                .is_none()
+        );
+        assert_eq!(MediaType::default().to_string(), "application/vnd.api+json");
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let supported = [ATOMIC.to_owned()];
+        let atomic = format!(r#"application/vnd.api+json; ext="{}""#, ATOMIC);
+        let other = r#"application/vnd.api+json; ext="https://example.com/x""#;
+
+        let media = negotiate_content_type(&atomic, SpecVersion::V1_1, &supported).unwrap();
+        assert!(media.has_ext(ATOMIC));
+        let err = negotiate_content_type(other, SpecVersion::V1_1, &supported).unwrap_err();
+        assert!(matches!(err.status, ErrorStatus::UnsupportedMediaType));
+        assert!(negotiate_content_type("application/json", SpecVersion::V1_1, &[]).is_ok());
+
+        assert_eq!(
+            negotiate_accept(None, SpecVersion::V1_1, &[]).unwrap(),
+            MediaType::default()
+        );
+        let accept = format!("{}, {};q=0.5", other, atomic);
+        let media = negotiate_accept(Some(&accept), SpecVersion::V1_1, &supported).unwrap();
+        assert!(media.has_ext(ATOMIC));
+        let err = negotiate_accept(Some(other), SpecVersion::V1_1, &supported).unwrap_err();
+        assert!(matches!(err.status, ErrorStatus::NotAcceptable));
+        let accept = "application/vnd.api+json; charset=utf-8, text/html";
+        assert!(negotiate_accept(Some(accept), SpecVersion::V1_1, &[]).is_err());
+        assert!(negotiate_accept(Some("*/*"), SpecVersion::V1_1, &[]).is_ok());
+    }
+}
@@ -0,0 +1,311 @@
+// OpenAPI schemas of documents, for utoipa. Request<D> and Response<P, I> describe the whole
+// JSON:API envelope around the schema of their attributes, rather than just the attributes, and
+// document_schema! names the document of a resource type for use in `#[utoipa::path]`. Schemas
+// are built inline, so a document is described in full wherever it's used.
+use std::borrow::Cow;
+pub use utoipa;
+use utoipa::{
+    openapi::{
+        schema::{
+            AdditionalProperties, ArrayBuilder, ObjectBuilder, OneOfBuilder, Schema, SchemaType,
+            Type,
+        },
+        RefOr,
+    },
+    PartialSchema, ToSchema,
+};
+
+use crate::{Error, Identifier, Request, ResourceRequest, ResourceResponse, Response};
+
+type Schemas = Vec<(String, RefOr<Schema>)>;
+
+fn string() -> ObjectBuilder {
+    ObjectBuilder::new().schema_type(Type::String)
+}
+
+fn array(items: impl Into<RefOr<Schema>>) -> RefOr<Schema> {
+    ArrayBuilder::new().items(items.into()).into()
+}
+
+// free_form is an object with any members, as meta is
+fn free_form() -> RefOr<Schema> {
+    ObjectBuilder::new()
+        .additional_properties(Some(AdditionalProperties::FreeForm(true)))
+        .into()
+}
+
+fn identifier_schema(typ: Option<&str>, id_required: bool) -> ObjectBuilder {
+    let typ = match typ {
+        Some(typ) => string().enum_values(Some([typ])),
+        None => string(),
+    };
+    let identifier = ObjectBuilder::new()
+        .property("type", typ)
+        .required("type")
+        .property("id", string());
+    if id_required {
+        identifier.required("id")
+    } else {
+        identifier
+    }
+}
+
+// links_schema is the links object, each link a URL or a link object
+pub fn links_schema() -> RefOr<Schema> {
+    let link = OneOfBuilder::new().item(string()).item(
+        ObjectBuilder::new()
+            .property("href", string())
+            .required("href")
+            .property("rel", string())
+            .property("title", string())
+            .property("type", string())
+            .property("meta", free_form()),
+    );
+    ObjectBuilder::new()
+        .additional_properties(Some(RefOr::from(link)))
+        .into()
+}
+
+// relationships_schema is the relationships object of a resource, whatever its relationships
+pub fn relationships_schema() -> RefOr<Schema> {
+    let linkage = OneOfBuilder::new()
+        .item(
+            ObjectBuilder::new()
+                .schema_type(SchemaType::from_iter([Type::Object, Type::Null]))
+                .property("type", string())
+                .property("id", string()),
+        )
+        .item(array(identifier_schema(None, true)));
+    let relationship = ObjectBuilder::new()
+        .property("data", linkage)
+        .property("links", links_schema())
+        .property("meta", free_form());
+    ObjectBuilder::new()
+        .additional_properties(Some(RefOr::from(relationship)))
+        .into()
+}
+
+// resource_schema is a resource object with the given attributes. typ restricts its type member
+// to that type, and requests creating a resource may leave out the id
+pub fn resource_schema(
+    typ: Option<&str>,
+    attributes: RefOr<Schema>,
+    id_required: bool,
+) -> RefOr<Schema> {
+    identifier_schema(typ, id_required)
+        .property("attributes", attributes)
+        .property("relationships", relationships_schema())
+        .property("links", links_schema())
+        .property("meta", free_form())
+        .into()
+}
+
+pub fn error_schema() -> RefOr<Schema> {
+    let source = ObjectBuilder::new()
+        .property("pointer", string())
+        .property("parameter", string());
+    ObjectBuilder::new()
+        .property("id", string())
+        .property("status", string())
+        .required("status")
+        .property("code", string())
+        .property("title", string())
+        .required("title")
+        .property("detail", string())
+        .property("source", source)
+        .property("meta", free_form())
+        .into()
+}
+
+// document_schema is a document with the given primary data, which is either that or errors.
+// included describes the resources which may be included, if any
+pub fn document_schema(data: RefOr<Schema>, included: Option<RefOr<Schema>>) -> RefOr<Schema> {
+    let mut document = ObjectBuilder::new()
+        .property("data", data)
+        .property("errors", array(error_schema()))
+        .property("meta", free_form())
+        .property("links", links_schema())
+        .property("jsonapi", free_form());
+    if let Some(included) = included {
+        document = document.property("included", array(included));
+    }
+    document.into()
+}
+
+// document_schema! declares a named schema of the document of a resource type, which restricts
+// the resource's type member and (unlike Response) describes its data as a single resource, e.g.
+// `document_schema!(ArticleDocument, "articles", ArticleAttributes)` for use as
+// `body = ArticleDocument` in `#[utoipa::path]`
+#[macro_export]
+macro_rules! document_schema {
+    ($name:ident, $typ:literal, $attributes:ty) => {
+        pub struct $name;
+
+        impl $crate::openapi::utoipa::PartialSchema for $name {
+            fn schema() -> $crate::openapi::utoipa::openapi::RefOr<
+                $crate::openapi::utoipa::openapi::schema::Schema,
+            > {
+                let attributes = <$attributes as $crate::openapi::utoipa::PartialSchema>::schema();
+                $crate::openapi::document_schema(
+                    $crate::openapi::resource_schema(Some($typ), attributes, true),
+                    None,
+                )
+            }
+        }
+
+        impl $crate::openapi::utoipa::ToSchema for $name {
+            fn schemas(
+                schemas: &mut Vec<(
+                    String,
+                    $crate::openapi::utoipa::openapi::RefOr<
+                        $crate::openapi::utoipa::openapi::schema::Schema,
+                    >,
+                )>,
+            ) {
+                <$attributes as $crate::openapi::utoipa::ToSchema>::schemas(schemas)
+            }
+        }
+    };
+}
+
+impl PartialSchema for Identifier {
+    fn schema() -> RefOr<Schema> {
+        identifier_schema(None, true).into()
+    }
+}
+
+impl ToSchema for Identifier {}
+
+impl PartialSchema for Error {
+    fn schema() -> RefOr<Schema> {
+        error_schema()
+    }
+}
+
+impl ToSchema for Error {}
+
+impl<D: ToSchema> PartialSchema for ResourceRequest<D> {
+    fn schema() -> RefOr<Schema> {
+        resource_schema(None, D::schema(), false)
+    }
+}
+
+impl<D: ToSchema> ToSchema for ResourceRequest<D> {
+    fn name() -> Cow<'static, str> {
+        Cow::Owned(format!("{}RequestResource", D::name()))
+    }
+
+    fn schemas(schemas: &mut Schemas) {
+        D::schemas(schemas)
+    }
+}
+
+impl<D: ToSchema> PartialSchema for Request<D> {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .property("data", ResourceRequest::<D>::schema())
+            .required("data")
+            .into()
+    }
+}
+
+impl<D: ToSchema> ToSchema for Request<D> {
+    fn name() -> Cow<'static, str> {
+        Cow::Owned(format!("{}Request", D::name()))
+    }
+
+    fn schemas(schemas: &mut Schemas) {
+        D::schemas(schemas)
+    }
+}
+
+impl<D: ToSchema> PartialSchema for ResourceResponse<D> {
+    fn schema() -> RefOr<Schema> {
+        resource_schema(None, D::schema(), true)
+    }
+}
+
+impl<D: ToSchema> ToSchema for ResourceResponse<D> {
+    fn name() -> Cow<'static, str> {
+        Cow::Owned(format!("{}Resource", D::name()))
+    }
+
+    fn schemas(schemas: &mut Schemas) {
+        D::schemas(schemas)
+    }
+}
+
+impl<P: ToSchema, I: ToSchema> PartialSchema for Response<P, I> {
+    fn schema() -> RefOr<Schema> {
+        document_schema(
+            array(ResourceResponse::<P>::schema()),
+            Some(ResourceResponse::<I>::schema()),
+        )
+    }
+}
+
+impl<P: ToSchema, I: ToSchema> ToSchema for Response<P, I> {
+    fn name() -> Cow<'static, str> {
+        Cow::Owned(format!("{}Response", P::name()))
+    }
+
+    fn schemas(schemas: &mut Schemas) {
+        P::schemas(schemas);
+        I::schemas(schemas);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utoipa::{
+        openapi::schema::{ObjectBuilder, Type},
+        PartialSchema, ToSchema,
+    };
+
+    use crate::{Request, Response};
+
+    struct ArticleAttributes;
+
+    impl PartialSchema for ArticleAttributes {
+        fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+            ObjectBuilder::new()
+                .property("title", ObjectBuilder::new().schema_type(Type::String))
+                .into()
+        }
+    }
+
+    impl ToSchema for ArticleAttributes {}
+
+    crate::document_schema!(ArticleDocument, "articles", ArticleAttributes);
+
+    #[test]
+    fn test_schemas() {
+        assert_eq!(
+            Request::<ArticleAttributes>::name(),
+            "ArticleAttributesRequest"
+        );
+        let schema = serde_json::to_value(Request::<ArticleAttributes>::schema()).unwrap();
+        let resource = &schema["properties"]["data"];
+        assert_eq!(resource["required"], serde_json::json!(["type"]));
+        assert_eq!(
+            resource["properties"]["attributes"]["properties"]["title"]["type"],
+            "string"
+        );
+
+        let schema = serde_json::to_value(Response::<ArticleAttributes, ()>::schema()).unwrap();
+        assert_eq!(schema["properties"]["data"]["type"], "array");
+        assert_eq!(
+            schema["properties"]["errors"]["items"]["required"],
+            serde_json::json!(["status", "title"])
+        );
+
+        let schema = serde_json::to_value(ArticleDocument::schema()).unwrap();
+        let resource = &schema["properties"]["data"];
+        assert_eq!(
+            resource["properties"]["type"]["enum"],
+            serde_json::json!(["articles"])
+        );
+        assert_eq!(resource["required"], serde_json::json!(["type", "id"]));
+        assert_eq!(ArticleDocument::name(), "ArticleDocument");
+    }
+}
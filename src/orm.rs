@@ -0,0 +1,334 @@
+// sea-orm integration. Models become resources through ModelResource: the primary key is the id,
+// the other serialized fields the attributes, and belongs-to relations give relationship linkage
+// from their foreign keys. ListSelect applies the filter, sort and page parameters of a list
+// request to a query, through a ColumnMap (see ColumnMap::from_entity for one covering a table).
+use sea_orm::{
+    ColumnTrait, ColumnType, EntityTrait, IdenStatic, Iterable, ModelTrait, PrimaryKeyToColumn,
+    QueryFilter, QuerySelect, RelationDef,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    filter::{FilterSpec, Operator},
+    pagination::{NumberedPage, OffsetPage},
+    sort::SortSpec,
+    sql::{ColumnKind, ColumnMap},
+    Error, Identifier, IntoResponse, Relationship, RelationshipData, Relationships,
+    ResourceResponse, Response,
+};
+
+// ModelResource makes a model a resource of type TYPE. A composite primary key makes an id of its
+// values joined with commas
+pub trait ModelResource: ModelTrait + Serialize {
+    const TYPE: &'static str;
+
+    // relationships lists the relations sent as relationships. Belongs-to relations have their
+    // linkage filled in; the others are left to the link generator
+    fn relationships() -> Vec<ModelRelationship> {
+        Vec::new()
+    }
+}
+
+pub struct ModelRelationship {
+    name: String,
+    typ: String,
+    def: RelationDef,
+}
+
+impl ModelRelationship {
+    // new names a relation of the model, whose related resources are of type typ, e.g.
+    // `ModelRelationship::new("author", "people", Relation::Author.def())`
+    pub fn new(name: &str, typ: &str, def: RelationDef) -> ModelRelationship {
+        ModelRelationship {
+            name: name.to_owned(),
+            typ: typ.to_owned(),
+            def,
+        }
+    }
+}
+
+// AsResource wraps a model to push it into a Response
+pub struct AsResource<M>(pub M);
+
+impl<M: ModelResource> IntoResponse for AsResource<M> {
+    type Attributes = Value;
+
+    fn into_response(self) -> ResourceResponse<Value> {
+        let model = self.0;
+        let keys: Vec<String> = <M::Entity as EntityTrait>::PrimaryKey::iter()
+            .map(|key| key.into_column().as_str().to_owned())
+            .collect();
+        let id = keys
+            .iter()
+            .filter_map(|key| column_id(&model, key))
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut relationships = Relationships::new();
+        let mut foreign_keys = Vec::new();
+        for rel in M::relationships() {
+            let data = if rel.def.is_owner {
+                None
+            } else {
+                let columns: Vec<String> = rel
+                    .def
+                    .from_col
+                    .into_iter()
+                    .map(|col| col.to_string())
+                    .collect();
+                let id = columns
+                    .iter()
+                    .map(|col| column_id(&model, col))
+                    .collect::<Option<Vec<_>>>();
+                foreign_keys.extend(columns);
+                id.map(|id| {
+                    Relationship::ToOne(Identifier {
+                        id: id.join(",").into(),
+                        typ: rel.typ,
+                    })
+                })
+            };
+            relationships.insert(
+                rel.name,
+                RelationshipData {
+                    data,
+                    ..Default::default()
+                },
+            );
+        }
+        let mut attributes = serde_json::to_value(&model).unwrap_or_default();
+        if let Some(fields) = attributes.as_object_mut() {
+            for key in keys.iter().chain(&foreign_keys) {
+                fields.remove(key);
+            }
+        }
+        ResourceResponse {
+            id: Identifier {
+                id: id.into(),
+                typ: M::TYPE.into(),
+            },
+            attributes,
+            relationships: (!relationships.is_empty()).then_some(relationships),
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        }
+    }
+}
+
+// to_response makes a response of a list of models
+pub fn to_response<M: ModelResource>(models: Vec<M>) -> Response<Value, ()> {
+    models
+        .into_iter()
+        .fold(Response::with_capacity(0, 0), |res, model| {
+            res.push(AsResource(model))
+        })
+}
+
+// column_id gives the value of a model's column as an id, None if it's null or not made of text,
+// numbers or uuids
+fn column_id<M: ModelTrait>(model: &M, name: &str) -> Option<String> {
+    use sea_orm::Value::*;
+
+    let column = <M::Entity as EntityTrait>::Column::iter().find(|col| col.as_str() == name)?;
+    match model.get(column) {
+        TinyInt(v) => v.map(|v| v.to_string()),
+        SmallInt(v) => v.map(|v| v.to_string()),
+        Int(v) => v.map(|v| v.to_string()),
+        BigInt(v) => v.map(|v| v.to_string()),
+        TinyUnsigned(v) => v.map(|v| v.to_string()),
+        SmallUnsigned(v) => v.map(|v| v.to_string()),
+        Unsigned(v) => v.map(|v| v.to_string()),
+        BigUnsigned(v) => v.map(|v| v.to_string()),
+        String(v) => v.map(|v| *v),
+        Char(v) => v.map(|v| v.to_string()),
+        Uuid(v) => v.map(|v| v.to_string()),
+        _ => None,
+    }
+}
+
+impl ColumnMap {
+    // from_entity maps every column of a table to the field of the same name, with the
+    // operators that make sense for its type. Columns of other than text, number and boolean types
+    // are left out
+    pub fn from_entity<E: EntityTrait>() -> ColumnMap {
+        E::Column::iter().fold(ColumnMap::new(), |columns, column| {
+            let (kind, operators): (ColumnKind, &[Operator]) = match column.def().get_column_type()
+            {
+                ColumnType::Char(_) | ColumnType::String(_) | ColumnType::Text => (
+                    ColumnKind::Text,
+                    &[Operator::Eq, Operator::Ne, Operator::In, Operator::Contains],
+                ),
+                ColumnType::TinyInteger
+                | ColumnType::SmallInteger
+                | ColumnType::Integer
+                | ColumnType::BigInteger
+                | ColumnType::TinyUnsigned
+                | ColumnType::SmallUnsigned
+                | ColumnType::Unsigned
+                | ColumnType::BigUnsigned => (ColumnKind::Int, ORDERED),
+                ColumnType::Float | ColumnType::Double => (ColumnKind::Float, ORDERED),
+                ColumnType::Boolean => (ColumnKind::Bool, &[Operator::Eq, Operator::Ne]),
+                _ => return columns,
+            };
+            columns.column(column.as_str(), column.as_str(), kind, operators)
+        })
+    }
+}
+
+const ORDERED: &[Operator] = &[
+    Operator::Eq,
+    Operator::Ne,
+    Operator::Lt,
+    Operator::Lte,
+    Operator::Gt,
+    Operator::Gte,
+    Operator::In,
+];
+
+// ListSelect narrows down a query to what a list request asks for, e.g.
+// `Entity::find().filtered(&columns, &filter)?.sorted(&columns, &sort)?.numbered_page(&page)`
+pub trait ListSelect: Sized {
+    fn filtered(self, columns: &ColumnMap, spec: &FilterSpec) -> Result<Self, Error>;
+
+    fn sorted(self, columns: &ColumnMap, spec: &SortSpec) -> Result<Self, Error>;
+
+    fn offset_page(self, page: &OffsetPage) -> Self;
+
+    fn numbered_page(self, page: &NumberedPage) -> Self;
+}
+
+impl<S: QueryFilter + QuerySelect> ListSelect for S {
+    fn filtered(self, columns: &ColumnMap, spec: &FilterSpec) -> Result<Self, Error> {
+        Ok(self.filter(columns.condition(spec)?))
+    }
+
+    fn sorted(mut self, columns: &ColumnMap, spec: &SortSpec) -> Result<Self, Error> {
+        columns.order_by(spec, QuerySelect::query(&mut self))?;
+        Ok(self)
+    }
+
+    fn offset_page(self, page: &OffsetPage) -> Self {
+        self.offset(page.offset as u64).limit(page.limit as u64)
+    }
+
+    fn numbered_page(self, page: &NumberedPage) -> Self {
+        self.offset(page.offset() as u64).limit(page.size as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{DbBackend, EntityTrait, QueryTrait, RelationTrait};
+    use serde_json::json;
+
+    use super::{to_response, ListSelect, ModelRelationship, ModelResource};
+    use crate::{filter::FilterSpec, pagination::NumberedPage, sort::SortSpec, sql::ColumnMap};
+
+    mod article {
+        use sea_orm::entity::prelude::*;
+        use serde_derive::Serialize;
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize)]
+        #[sea_orm(table_name = "articles")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+            pub title: String,
+            pub likes: i64,
+            pub author_id: Option<i32>,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {
+            #[sea_orm(
+                belongs_to = "super::person::Entity",
+                from = "Column::AuthorId",
+                to = "super::person::Column::Id"
+            )]
+            Author,
+        }
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    mod person {
+        use sea_orm::entity::prelude::*;
+
+        #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+        #[sea_orm(table_name = "people")]
+        pub struct Model {
+            #[sea_orm(primary_key)]
+            pub id: i32,
+        }
+
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+        pub enum Relation {}
+
+        impl ActiveModelBehavior for ActiveModel {}
+    }
+
+    impl ModelResource for article::Model {
+        const TYPE: &'static str = "articles";
+
+        fn relationships() -> Vec<ModelRelationship> {
+            vec![ModelRelationship::new(
+                "author",
+                "people",
+                article::Relation::Author.def(),
+            )]
+        }
+    }
+
+    #[test]
+    fn test_model_resource() {
+        let articles = vec![
+            article::Model {
+                id: 1,
+                title: "a".into(),
+                likes: 2,
+                author_id: Some(9),
+            },
+            article::Model {
+                id: 2,
+                title: "b".into(),
+                likes: 0,
+                author_id: None,
+            },
+        ];
+        let out = serde_json::to_value(to_response(articles)).unwrap();
+        assert_eq!(
+            out["data"][0],
+            json!({
+                "type": "articles",
+                "id": "1",
+                "attributes": {"title": "a", "likes": 2},
+                "relationships": {"author": {"data": {"type": "people", "id": "9"}}}
+            })
+        );
+        assert_eq!(out["data"][1]["relationships"]["author"], json!({}));
+    }
+
+    #[test]
+    fn test_list_select() {
+        let columns = ColumnMap::from_entity::<article::Entity>();
+        let query = "filter[title][contains]=x&filter[likes][gte]=3&sort=-likes";
+        let select = article::Entity::find()
+            .filtered(&columns, &FilterSpec::from_query(query).unwrap())
+            .unwrap()
+            .sorted(&columns, &SortSpec::from_query(query).unwrap())
+            .unwrap()
+            .numbered_page(&NumberedPage {
+                number: 2,
+                size: 10,
+            });
+        assert_eq!(
+            select.build(DbBackend::Postgres).to_string(),
+            r#"SELECT "articles"."id", "articles"."title", "articles"."likes", "articles"."author_id" FROM "articles" WHERE "title" LIKE '%x%' ESCAPE E'\\' AND "likes" >= 3 ORDER BY "likes" DESC LIMIT 10 OFFSET 10"#
+        );
+        let not_allowed = FilterSpec::from_query("filter[likes][contains]=3").unwrap();
+        assert!(article::Entity::find()
+            .filtered(&columns, &not_allowed)
+            .is_err());
+    }
+}
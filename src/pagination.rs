@@ -0,0 +1,609 @@
+// Pagination of collections through the `page` family of query parameters. The spec leaves the
+// strategy up to the server, so it's a trait with the usual strategies built in. Links are worked
+// out from the page that was asked for and what it turned out to hold, so handlers only fetch.
+use std::collections::BTreeMap;
+
+use crate::{Error, Link, Links, Meta};
+
+// PageParams holds the page[...] query parameters of a request, keyed by the name in brackets
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageParams(pub BTreeMap<String, String>);
+
+impl PageParams {
+    pub fn from_query(query: &str) -> PageParams {
+        PageParams(
+            form_urlencoded::parse(query.as_bytes())
+                .filter_map(|(name, value)| {
+                    let name = name.strip_prefix("page[")?.strip_suffix(']')?;
+                    Some((name.to_owned(), value.into_owned()))
+                })
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(String::as_str)
+    }
+
+    // number parses a numeric parameter, which must be at least `min`
+    pub fn number(&self, name: &str, min: usize) -> Result<Option<usize>, Error> {
+        let Some(value) = self.get(name) else {
+            return Ok(None);
+        };
+        match value.parse() {
+            Ok(number) if number >= min => Ok(Some(number)),
+            _ => Err(Error::new_bad_request(&format!(
+                "page[{}] must be a whole number of at least {}",
+                name, min
+            ))
+            .with_parameter(&format!("page[{}]", name))),
+        }
+    }
+}
+
+// PageSize sets the page size used when a request doesn't ask for one, and the largest it may ask
+// for. Larger sizes are refused with a 400, or brought down to the maximum if `clamp` is set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageSize {
+    pub default: usize,
+    pub max: Option<usize>,
+    pub clamp: bool,
+}
+
+impl PageSize {
+    pub fn new(default: usize) -> PageSize {
+        PageSize {
+            default,
+            max: None,
+            clamp: false,
+        }
+    }
+
+    pub fn max(mut self, max: usize) -> PageSize {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn clamp(mut self, clamp: bool) -> PageSize {
+        self.clamp = clamp;
+        self
+    }
+
+    // resolve gives the effective size for a request's size parameter
+    pub fn resolve(&self, params: &PageParams, name: &str) -> Result<usize, Error> {
+        let size = params.number(name, 1)?.unwrap_or(self.default);
+        match self.max {
+            Some(max) if size > max && self.clamp => Ok(max),
+            Some(max) if size > max => Err(Error::new_bad_request(&format!(
+                "page[{}] must be at most {}",
+                name, max
+            ))
+            .with_parameter(&format!("page[{}]", name))),
+            _ => Ok(size),
+        }
+    }
+}
+
+// PageSizes holds the page size settings of particular resource types, and those of the rest
+#[derive(Debug, Clone)]
+pub struct PageSizes {
+    pub global: PageSize,
+    pub types: BTreeMap<String, PageSize>,
+}
+
+impl PageSizes {
+    pub fn new(global: PageSize) -> PageSizes {
+        PageSizes {
+            global,
+            types: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_type(mut self, typ: &str, size: PageSize) -> PageSizes {
+        self.types.insert(typ.to_owned(), size);
+        self
+    }
+
+    pub fn for_type(&self, typ: &str) -> PageSize {
+        self.types.get(typ).copied().unwrap_or(self.global)
+    }
+}
+
+// PageResult describes the page a handler fetched. The total is optional, as counting can be
+// expensive; without it a full page is taken to mean there may be another one
+#[derive(Debug, Clone, Default)]
+pub struct PageResult {
+    pub items: usize,
+    pub total: Option<usize>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+// Paginated holds the top-level links and meta of a page, see Response::paginated
+#[derive(Debug, Clone, Default)]
+pub struct Paginated {
+    pub links: Links,
+    pub meta: Meta,
+}
+
+// CollectionMeta is the counts a list endpoint reports in the top-level meta, see
+// Response::with_collection_meta. Counts that aren't known are left out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollectionMeta {
+    pub total: Option<usize>,
+    pub page_count: Option<usize>,
+    pub page_size: Option<usize>,
+    pub page_number: Option<usize>,
+}
+
+impl CollectionMeta {
+    // numbered describes a page of a numbered collection, working out the page count from the total
+    pub fn numbered(page: &NumberedPage, total: Option<usize>) -> CollectionMeta {
+        CollectionMeta {
+            total,
+            page_count: total.map(|total| total.div_ceil(page.size).max(1)),
+            page_size: Some(page.size),
+            page_number: Some(page.number),
+        }
+    }
+
+    pub fn to_meta(&self, keys: &CollectionMetaKeys) -> Meta {
+        [
+            (&keys.total, self.total),
+            (&keys.page_count, self.page_count),
+            (&keys.page_size, self.page_size),
+            (&keys.page_number, self.page_number),
+        ]
+        .into_iter()
+        .filter_map(|(key, count)| Some((key.clone(), count?.into())))
+        .collect()
+    }
+}
+
+// CollectionMetaKeys names the members CollectionMeta is written to, for APIs with their own
+// conventions (total_count, say)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollectionMetaKeys {
+    pub total: String,
+    pub page_count: String,
+    pub page_size: String,
+    pub page_number: String,
+}
+
+impl Default for CollectionMetaKeys {
+    fn default() -> Self {
+        CollectionMetaKeys {
+            total: "total".into(),
+            page_count: "pageCount".into(),
+            page_size: "pageSize".into(),
+            page_number: "pageNumber".into(),
+        }
+    }
+}
+
+pub trait PaginationStrategy {
+    type Page;
+
+    // parse reads the page requested, falling back to the first page
+    fn parse(&self, params: &PageParams) -> Result<Self::Page, Error>;
+
+    // paginate builds the self, first, prev, next and last links (as far as they're known) from
+    // the collection's URL, which may carry other query parameters already
+    fn paginate(&self, page: &Self::Page, result: &PageResult, base: &str) -> Paginated;
+}
+
+// page_link appends page parameters to a collection URL
+fn page_link(base: &str, params: &[(&str, String)]) -> Link {
+    let mut link = base.to_owned();
+    let mut sep = if base.contains('?') { '&' } else { '?' };
+    for (name, value) in params {
+        link.push(sep);
+        link.push_str(&format!("page[{}]=", name));
+        link.extend(form_urlencoded::byte_serialize(value.as_bytes()));
+        sep = '&';
+    }
+    Link::Url(link)
+}
+
+// page_meta gives the size the page was fetched with, and the size of the collection if known
+fn page_meta(size: usize, total: Option<usize>) -> Meta {
+    CollectionMeta {
+        total,
+        page_size: Some(size),
+        ..Default::default()
+    }
+    .to_meta(&CollectionMetaKeys::default())
+}
+
+// OffsetPagination pages with page[offset] and page[limit]
+#[derive(Debug, Clone, Copy)]
+pub struct OffsetPagination {
+    pub limit: PageSize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OffsetPage {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl PaginationStrategy for OffsetPagination {
+    type Page = OffsetPage;
+
+    fn parse(&self, params: &PageParams) -> Result<OffsetPage, Error> {
+        Ok(OffsetPage {
+            offset: params.number("offset", 0)?.unwrap_or(0),
+            limit: self.limit.resolve(params, "limit")?,
+        })
+    }
+
+    fn paginate(&self, page: &OffsetPage, result: &PageResult, base: &str) -> Paginated {
+        let link = |offset: usize| {
+            page_link(
+                base,
+                &[
+                    ("offset", offset.to_string()),
+                    ("limit", page.limit.to_string()),
+                ],
+            )
+        };
+        let mut links = Links::new();
+        links.insert("self".into(), link(page.offset));
+        links.insert("first".into(), link(0));
+        if page.offset > 0 {
+            links.insert("prev".into(), link(page.offset.saturating_sub(page.limit)));
+        }
+        let next = page.offset + page.limit;
+        if result
+            .total
+            .map_or(result.items >= page.limit, |total| next < total)
+        {
+            links.insert("next".into(), link(next));
+        }
+        if let Some(total) = result.total {
+            let last = total.saturating_sub(1) / page.limit * page.limit;
+            links.insert("last".into(), link(last));
+        }
+        Paginated {
+            links,
+            meta: page_meta(page.limit, result.total),
+        }
+    }
+}
+
+// PageNumberPagination pages with page[number], counting from 1, and page[size]
+#[derive(Debug, Clone, Copy)]
+pub struct PageNumberPagination {
+    pub size: PageSize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberedPage {
+    pub number: usize,
+    pub size: usize,
+}
+
+impl NumberedPage {
+    // offset is the position of the page's first item in the collection
+    pub fn offset(&self) -> usize {
+        (self.number - 1) * self.size
+    }
+}
+
+impl PaginationStrategy for PageNumberPagination {
+    type Page = NumberedPage;
+
+    fn parse(&self, params: &PageParams) -> Result<NumberedPage, Error> {
+        Ok(NumberedPage {
+            number: params.number("number", 1)?.unwrap_or(1),
+            size: self.size.resolve(params, "size")?,
+        })
+    }
+
+    fn paginate(&self, page: &NumberedPage, result: &PageResult, base: &str) -> Paginated {
+        let link = |number: usize| {
+            page_link(
+                base,
+                &[
+                    ("number", number.to_string()),
+                    ("size", page.size.to_string()),
+                ],
+            )
+        };
+        let mut links = Links::new();
+        links.insert("self".into(), link(page.number));
+        links.insert("first".into(), link(1));
+        if page.number > 1 {
+            links.insert("prev".into(), link(page.number - 1));
+        }
+        let more = match result.total {
+            Some(total) => page.number * page.size < total,
+            None => result.items >= page.size,
+        };
+        if more {
+            links.insert("next".into(), link(page.number + 1));
+        }
+        if let Some(total) = result.total {
+            links.insert("last".into(), link(total.div_ceil(page.size).max(1)));
+        }
+        Paginated {
+            links,
+            meta: page_meta(page.size, result.total),
+        }
+    }
+}
+
+// CursorPagination pages with page[size] and the opaque page[after] and page[before] cursors,
+// as in the cursor pagination profile. The handler hands back the cursors of the neighbouring
+// pages in the PageResult
+#[derive(Debug, Clone, Copy)]
+pub struct CursorPagination {
+    pub size: PageSize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorPage {
+    pub size: usize,
+    pub after: Option<String>,
+    pub before: Option<String>,
+}
+
+// decode_after and decode_before read the cursors of a page made with a CursorCodec, errors
+// pointing at the parameter the cursor came from
+#[cfg(feature = "cursor")]
+impl CursorPage {
+    pub fn decode_after<T: serde::de::DeserializeOwned>(
+        &self,
+        codec: &crate::cursor::CursorCodec,
+    ) -> Result<Option<T>, Error> {
+        decode_cursor(codec, self.after.as_deref(), "page[after]")
+    }
+
+    pub fn decode_before<T: serde::de::DeserializeOwned>(
+        &self,
+        codec: &crate::cursor::CursorCodec,
+    ) -> Result<Option<T>, Error> {
+        decode_cursor(codec, self.before.as_deref(), "page[before]")
+    }
+}
+
+#[cfg(feature = "cursor")]
+fn decode_cursor<T: serde::de::DeserializeOwned>(
+    codec: &crate::cursor::CursorCodec,
+    cursor: Option<&str>,
+    parameter: &str,
+) -> Result<Option<T>, Error> {
+    cursor
+        .map(|cursor| {
+            codec
+                .decode(cursor)
+                .map_err(|err| err.with_parameter(parameter))
+        })
+        .transpose()
+}
+
+impl PaginationStrategy for CursorPagination {
+    type Page = CursorPage;
+
+    fn parse(&self, params: &PageParams) -> Result<CursorPage, Error> {
+        Ok(CursorPage {
+            size: self.size.resolve(params, "size")?,
+            after: params.get("after").map(str::to_owned),
+            before: params.get("before").map(str::to_owned),
+        })
+    }
+
+    fn paginate(&self, page: &CursorPage, result: &PageResult, base: &str) -> Paginated {
+        let size = ("size", page.size.to_string());
+        let mut current = vec![size.clone()];
+        if let Some(after) = &page.after {
+            current.push(("after", after.clone()));
+        }
+        if let Some(before) = &page.before {
+            current.push(("before", before.clone()));
+        }
+        let mut links = Links::new();
+        links.insert("self".into(), page_link(base, &current));
+        links.insert("first".into(), page_link(base, &current[..1]));
+        if let Some(prev) = &result.prev_cursor {
+            let params = [size.clone(), ("before", prev.clone())];
+            links.insert("prev".into(), page_link(base, &params));
+        }
+        if let Some(next) = &result.next_cursor {
+            links.insert(
+                "next".into(),
+                page_link(base, &[size, ("after", next.clone())]),
+            );
+        }
+        Paginated {
+            links,
+            meta: page_meta(page.size, result.total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CollectionMeta, CollectionMetaKeys, CursorPagination, NumberedPage, OffsetPage,
+        OffsetPagination, PageNumberPagination, PageParams, PageResult, PageSize, PageSizes,
+        PaginationStrategy,
+    };
+
+    #[test]
+    fn test_offset() {
+        let strategy = OffsetPagination {
+            limit: PageSize::new(10).max(50),
+        };
+        let params = PageParams::from_query("sort=-created&page%5Boffset%5D=20&page[limit]=10");
+        let page = strategy.parse(&params).unwrap();
+        assert_eq!(
+            page,
+            OffsetPage {
+                offset: 20,
+                limit: 10
+            }
+        );
+        let result = PageResult {
+            items: 10,
+            total: Some(35),
+            ..Default::default()
+        };
+        let paginated = strategy.paginate(&page, &result, "/articles?sort=-created");
+        assert_eq!(
+            paginated.links["next"],
+            "/articles?sort=-created&page[offset]=30&page[limit]=10"
+        );
+        assert_eq!(
+            paginated.links["prev"],
+            "/articles?sort=-created&page[offset]=10&page[limit]=10"
+        );
+        assert_eq!(
+            paginated.links["last"],
+            "/articles?sort=-created&page[offset]=30&page[limit]=10"
+        );
+        assert_eq!(paginated.meta["total"], 35);
+        assert_eq!(paginated.meta["pageSize"], 10);
+
+        let err = strategy
+            .parse(&PageParams::from_query("page[limit]=0"))
+            .unwrap_err();
+        assert_eq!(
+            err.source.unwrap().parameter.as_deref(),
+            Some("page[limit]")
+        );
+        let first = strategy.parse(&PageParams::default()).unwrap();
+        assert_eq!(
+            first,
+            OffsetPage {
+                offset: 0,
+                limit: 10
+            }
+        );
+    }
+
+    #[test]
+    fn test_page_size() {
+        let sizes = PageSizes::new(PageSize::new(20).max(100))
+            .with_type("logs", PageSize::new(100).max(1000).clamp(true));
+        let params = PageParams::from_query("page[size]=500");
+        let err = sizes
+            .for_type("articles")
+            .resolve(&params, "size")
+            .unwrap_err();
+        assert_eq!(err.source.unwrap().parameter.as_deref(), Some("page[size]"));
+        assert_eq!(
+            sizes.for_type("logs").resolve(&params, "size").unwrap(),
+            500
+        );
+        let params = PageParams::from_query("page[size]=5000");
+        assert_eq!(
+            sizes.for_type("logs").resolve(&params, "size").unwrap(),
+            1000
+        );
+        let params = PageParams::default();
+        assert_eq!(
+            sizes.for_type("articles").resolve(&params, "size").unwrap(),
+            20
+        );
+    }
+
+    #[test]
+    fn test_collection_meta() {
+        let page = NumberedPage {
+            number: 2,
+            size: 10,
+        };
+        let meta = CollectionMeta::numbered(&page, Some(31)).to_meta(&Default::default());
+        assert_eq!(meta["pageCount"], 4);
+        assert_eq!(meta["pageNumber"], 2);
+        assert_eq!(meta["total"], 31);
+        let keys = CollectionMetaKeys {
+            total: "total_count".into(),
+            ..Default::default()
+        };
+        let meta = CollectionMeta::numbered(&page, None).to_meta(&keys);
+        assert_eq!(meta.len(), 2);
+        assert!(!meta.contains_key("pageCount"));
+        let meta = CollectionMeta {
+            total: Some(0),
+            ..Default::default()
+        }
+        .to_meta(&keys);
+        assert_eq!(meta["total_count"], 0);
+    }
+
+    #[test]
+    fn test_page_number() {
+        let strategy = PageNumberPagination {
+            size: PageSize::new(2),
+        };
+        let page = strategy
+            .parse(&PageParams::from_query("page[number]=1"))
+            .unwrap();
+        assert_eq!(page.offset(), 0);
+        let result = PageResult {
+            items: 2,
+            ..Default::default()
+        };
+        let paginated = strategy.paginate(&page, &result, "/articles");
+        assert_eq!(
+            paginated.links["next"],
+            "/articles?page[number]=2&page[size]=2"
+        );
+        assert!(!paginated.links.contains_key("prev"));
+        assert!(!paginated.links.contains_key("last"));
+        assert!(strategy
+            .parse(&PageParams::from_query("page[number]=0"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_cursor() {
+        let strategy = CursorPagination {
+            size: PageSize::new(5),
+        };
+        let page = strategy
+            .parse(&PageParams::from_query("page[after]=abc%3D"))
+            .unwrap();
+        assert_eq!(page.after.as_deref(), Some("abc="));
+        let result = PageResult {
+            items: 5,
+            next_cursor: Some("def=".into()),
+            ..Default::default()
+        };
+        let paginated = strategy.paginate(&page, &result, "/articles");
+        assert_eq!(
+            paginated.links["self"],
+            "/articles?page[size]=5&page[after]=abc%3D"
+        );
+        assert_eq!(
+            paginated.links["next"],
+            "/articles?page[size]=5&page[after]=def%3D"
+        );
+        assert!(!paginated.links.contains_key("prev"));
+    }
+
+    #[cfg(feature = "cursor")]
+    #[test]
+    fn test_signed_cursor() {
+        let codec = crate::cursor::CursorCodec::signed(b"secret");
+        let query = format!("page[after]={}", codec.encode(&("2024-01-01", 7)));
+        let strategy = CursorPagination {
+            size: PageSize::new(5),
+        };
+        let page = strategy.parse(&PageParams::from_query(&query)).unwrap();
+        let after: Option<(String, u32)> = page.decode_after(&codec).unwrap();
+        assert_eq!(after, Some(("2024-01-01".into(), 7)));
+        assert_eq!(page.decode_before::<(String, u32)>(&codec).unwrap(), None);
+
+        let page = strategy
+            .parse(&PageParams::from_query("page[after]=e30.AAAA"))
+            .unwrap();
+        let err = page.decode_after::<(String, u32)>(&codec).unwrap_err();
+        assert_eq!(
+            err.source.unwrap().parameter.as_deref(),
+            Some("page[after]")
+        );
+    }
+}
@@ -0,0 +1,162 @@
+// Partial updates. A PATCH request leaves members it doesn't mention unchanged and clears those
+// it sets to null, so its fields have three states rather than the two of an Option: see Maybe.
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::Error;
+
+// Maybe is a member of an update: Absent leaves it alone, Null clears it and Value sets it.
+// Attribute fields need `#[serde(default)]` to be Absent when missing (and
+// `skip_serializing_if = "Maybe::is_absent"` to be left out again); relationship fields of the
+// FromRelationships derive work as they are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Maybe<T> {
+    #[default]
+    Absent,
+    Null,
+    Value(T),
+}
+
+impl<T> Maybe<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Maybe::Absent)
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Maybe::Null)
+    }
+
+    pub fn is_value(&self) -> bool {
+        matches!(self, Maybe::Value(_))
+    }
+
+    pub fn as_ref(&self) -> Maybe<&T> {
+        match self {
+            Maybe::Absent => Maybe::Absent,
+            Maybe::Null => Maybe::Null,
+            Maybe::Value(value) => Maybe::Value(value),
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Maybe<U> {
+        match self {
+            Maybe::Absent => Maybe::Absent,
+            Maybe::Null => Maybe::Null,
+            Maybe::Value(value) => Maybe::Value(f(value)),
+        }
+    }
+
+    // value is the new value, if there is one
+    pub fn value(self) -> Option<T> {
+        match self {
+            Maybe::Value(value) => Some(value),
+            Maybe::Absent | Maybe::Null => None,
+        }
+    }
+
+    // into_option is None for Absent, and otherwise the new value of an optional field
+    pub fn into_option(self) -> Option<Option<T>> {
+        match self {
+            Maybe::Absent => None,
+            Maybe::Null => Some(None),
+            Maybe::Value(value) => Some(Some(value)),
+        }
+    }
+}
+
+// an Option is always present, None being null
+impl<T> From<Option<T>> for Maybe<T> {
+    fn from(option: Option<T>) -> Self {
+        match option {
+            Some(value) => Maybe::Value(value),
+            None => Maybe::Null,
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Maybe<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Maybe::Value(value) => serializer.serialize_some(value),
+            Maybe::Absent | Maybe::Null => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Maybe<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::<T>::deserialize(deserializer).map(Maybe::from)
+    }
+}
+
+// Apply is implemented by update types (see the JsonApiPatch derive) for the structs they update:
+// the attributes they were derived from, and any listed with `#[jsonapi(apply_to(Model))]`
+pub trait Apply<T> {
+    // apply sets the fields the update has a value for and clears those it has null for,
+    // returning the names of the fields it changed
+    fn apply(self, target: &mut T) -> Vec<&'static str>;
+}
+
+// PatchAttributes are the attributes of an update as they arrive, before the JsonApiPatch
+// derive takes them apart. Updates can leave attributes out altogether
+pub type PatchAttributes = Option<serde_json::Map<String, Value>>;
+
+// take_attribute removes an attribute from those of an update. Null is refused (with a 400
+// pointing at the attribute) for attributes which can't be cleared
+pub fn take_attribute<T: DeserializeOwned>(
+    attributes: &mut serde_json::Map<String, Value>,
+    name: &str,
+    nullable: bool,
+) -> Result<Maybe<T>, Error> {
+    let pointer = || format!("/data/attributes/{}", name);
+    let value = match attributes.remove(name) {
+        None => return Ok(Maybe::Absent),
+        Some(Value::Null) if !nullable => {
+            return Err(
+                Error::new_bad_request(&format!("attribute '{}' can't be null", name))
+                    .with_pointer(&pointer()),
+            )
+        }
+        Some(value) => value,
+    };
+    Option::<T>::deserialize(value)
+        .map(Maybe::from)
+        .map_err(|err| {
+            Error::new_bad_request(&format!("invalid value for attribute '{}': {}", name, err))
+                .with_pointer(&pointer())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_derive::{Deserialize, Serialize};
+    use serde_json::json;
+
+    use super::Maybe;
+
+    #[derive(Serialize, Deserialize)]
+    struct ArticleUpdate {
+        #[serde(default, skip_serializing_if = "Maybe::is_absent")]
+        title: Maybe<String>,
+        #[serde(default, skip_serializing_if = "Maybe::is_absent")]
+        summary: Maybe<String>,
+        #[serde(default, skip_serializing_if = "Maybe::is_absent")]
+        rating: Maybe<u8>,
+    }
+
+    #[test]
+    fn test_maybe() {
+        let update: ArticleUpdate =
+            serde_json::from_value(json!({"title": "new", "summary": null})).unwrap();
+        assert_eq!(update.title, Maybe::Value("new".to_owned()));
+        assert_eq!(update.summary, Maybe::Null);
+        assert_eq!(update.rating, Maybe::Absent);
+        assert_eq!(update.summary.clone().into_option(), Some(None));
+        assert_eq!(update.rating.into_option(), None);
+        assert_eq!(
+            serde_json::to_value(&update).unwrap(),
+            json!({"title": "new", "summary": null})
+        );
+        assert!(serde_json::from_value::<ArticleUpdate>(json!({"rating": "x"})).is_err());
+    }
+}
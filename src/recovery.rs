@@ -0,0 +1,431 @@
+// Error documents for failures handlers don't answer themselves. Wrapping an app in Recover (or a
+// tower service in RecoverLayer) turns a handler panic into a 500 error document rather than
+// actix's plain-text 500 or a dropped connection. With actix, errors which aren't JSON:API
+// Errors (a failed Path extractor, say) become documents too. Errors reported as 500s carry a
+// correlation id, the request's x-request-id if it sent a usable one, which is also sent back in
+// that header.
+#[cfg(feature = "actixweb")]
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::{
+        header::{HeaderName, HeaderValue},
+        StatusCode,
+    },
+    HttpResponse, ResponseError,
+};
+use std::{
+    future::Future,
+    panic::{catch_unwind, AssertUnwindSafe},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::Error;
+#[cfg(feature = "actixweb")]
+use crate::ErrorStatus;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+// correlation_id is the id a failure is reported under: the one the client (or a proxy in front
+// of the app) sent, if it's short and printable, else a new one
+fn correlation_id(sent: Option<&[u8]>) -> String {
+    match sent.and_then(|id| std::str::from_utf8(id).ok()) {
+        Some(id)
+            if !id.is_empty() && id.len() <= 128 && id.bytes().all(|b| b.is_ascii_graphic()) =>
+        {
+            id.to_owned()
+        }
+        _ => crate::redaction::new_id(),
+    }
+}
+
+// internal_error is what a failure is reported as, saying nothing of what went wrong
+fn internal_error(id: &str) -> Error {
+    Error::new_internal_error("internal server error").with_id(id)
+}
+
+// poll_caught polls a future, None if it panics. The future isn't polled again after a panic, so
+// whatever state it was left in doesn't matter
+fn poll_caught<F: Future>(fut: Pin<&mut F>, cx: &mut Context<'_>) -> Option<Poll<F::Output>> {
+    catch_unwind(AssertUnwindSafe(|| fut.poll(cx))).ok()
+}
+
+// Recover is the actix middleware, e.g. `App::new().wrap(Recover)`
+#[cfg(feature = "actixweb")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Recover;
+
+#[cfg(feature = "actixweb")]
+impl<S, B> Transform<S, ServiceRequest> for Recover
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = RecoverService<S>;
+    type InitError = ();
+    type Future = std::future::Ready<Result<RecoverService<S>, ()>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        std::future::ready(Ok(RecoverService { service }))
+    }
+}
+
+#[cfg(feature = "actixweb")]
+pub struct RecoverService<S> {
+    service: S,
+}
+
+#[cfg(feature = "actixweb")]
+impl<S, B> Service<ServiceRequest> for RecoverService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    B: MessageBody,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = RecoverFut<S::Future>;
+
+    actix_web::dev::forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let sent = req.headers().get(REQUEST_ID_HEADER);
+        let id = correlation_id(sent.map(HeaderValue::as_bytes));
+        // handlers may start running (and panic) before their future is polled
+        let fut = catch_unwind(AssertUnwindSafe(|| self.service.call(req)))
+            .ok()
+            .map(Box::pin);
+        RecoverFut { fut, id }
+    }
+}
+
+#[cfg(feature = "actixweb")]
+pub struct RecoverFut<F> {
+    fut: Option<Pin<Box<F>>>,
+    id: String,
+}
+
+#[cfg(feature = "actixweb")]
+impl<F, B> Future for RecoverFut<F>
+where
+    F: Future<Output = Result<ServiceResponse<B>, actix_web::Error>>,
+    B: MessageBody,
+{
+    type Output = Result<ServiceResponse<EitherBody<B>>, actix_web::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let outcome = match this.fut.as_mut().map(|fut| poll_caught(fut.as_mut(), cx)) {
+            Some(Some(Poll::Pending)) => return Poll::Pending,
+            Some(Some(Poll::Ready(outcome))) => Some(outcome),
+            _ => None,
+        };
+        this.fut = None;
+        let err = match outcome {
+            Some(Ok(res)) => {
+                // handlers answer errors of their extractors themselves
+                let err = match res.response().error() {
+                    Some(err) if !is_document(err) => from_actix(err),
+                    _ => return Poll::Ready(Ok(res.map_into_left_body())),
+                };
+                let err = Recovered(err.unwrap_or_else(|| internal_error(&this.id)));
+                let (http, _) = res.into_parts();
+                let res = ServiceResponse::new(http, err.error_response());
+                return Poll::Ready(Ok(res.map_into_right_body()));
+            }
+            Some(Err(err)) if is_document(&err) => return Poll::Ready(Err(err)),
+            Some(Err(err)) => from_actix(&err),
+            None => None,
+        };
+        let err = err.unwrap_or_else(|| internal_error(&this.id));
+        Poll::Ready(Err(Recovered(err).into()))
+    }
+}
+
+// is_document is whether an error is answered with an error document of its own
+#[cfg(feature = "actixweb")]
+fn is_document(err: &actix_web::Error) -> bool {
+    err.as_error::<Error>().is_some()
+        || err.as_error::<crate::Errors>().is_some()
+        || err.as_error::<crate::errors::Rejection>().is_some()
+}
+
+// Recovered is answered as the error document it holds, with its correlation id (if it has one)
+// in the x-request-id header
+#[cfg(feature = "actixweb")]
+#[derive(Debug)]
+struct Recovered(Error);
+
+#[cfg(feature = "actixweb")]
+impl std::fmt::Display for Recovered {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl ResponseError for Recovered {
+    fn status_code(&self) -> StatusCode {
+        self.0.status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut res = self.0.error_response();
+        if let Some(id) = self
+            .0
+            .id
+            .as_deref()
+            .and_then(|id| HeaderValue::from_str(id).ok())
+        {
+            res.headers_mut()
+                .insert(HeaderName::from_static(REQUEST_ID_HEADER), id);
+        }
+        res
+    }
+}
+
+// from_actix makes an error document of an actix error with a client error status the document
+// can carry, None for the others, which are reported as internal errors
+#[cfg(feature = "actixweb")]
+fn from_actix(err: &actix_web::Error) -> Option<Error> {
+    let status = match err.as_response_error().status_code() {
+        StatusCode::BAD_REQUEST => ErrorStatus::BadRequest,
+        StatusCode::UNAUTHORIZED => ErrorStatus::Unauthorized,
+        StatusCode::FORBIDDEN => ErrorStatus::Forbidden,
+        StatusCode::NOT_FOUND => ErrorStatus::NotFound,
+        StatusCode::NOT_ACCEPTABLE => ErrorStatus::NotAcceptable,
+        StatusCode::CONFLICT => ErrorStatus::Conflict,
+        StatusCode::PRECONDITION_FAILED => ErrorStatus::PreconditionFailed,
+        StatusCode::PAYLOAD_TOO_LARGE => ErrorStatus::PayloadTooLarge,
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => ErrorStatus::UnsupportedMediaType,
+        StatusCode::UNPROCESSABLE_ENTITY => ErrorStatus::UnprocessableEntity,
+        StatusCode::TOO_MANY_REQUESTS => ErrorStatus::TooManyRequests,
+        _ => return None,
+    };
+    let reason = StatusCode::from(&status)
+        .canonical_reason()
+        .unwrap_or_default();
+    Some(Error {
+        id: None,
+        status,
+        code: Some(reason.to_owned()),
+        title: reason.to_owned(),
+        detail: Some(err.to_string()),
+        source: None,
+        meta: None,
+        cause: None,
+    })
+}
+
+// RecoverLayer is the tower layer, for services whose response bodies can be made of a string
+// (as axum's and http-body-util's Full can). Errors of the service are left alone, only panics
+// are caught
+#[cfg(feature = "tower")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecoverLayer;
+
+#[cfg(feature = "tower")]
+impl<S> tower_layer::Layer<S> for RecoverLayer {
+    type Service = RecoverTower<S>;
+
+    fn layer(&self, inner: S) -> RecoverTower<S> {
+        RecoverTower { inner }
+    }
+}
+
+#[cfg(feature = "tower")]
+#[derive(Debug, Clone)]
+pub struct RecoverTower<S> {
+    inner: S,
+}
+
+#[cfg(feature = "tower")]
+impl<S, ReqBody, ResBody> tower_service::Service<http::Request<ReqBody>> for RecoverTower<S>
+where
+    S: tower_service::Service<http::Request<ReqBody>, Response = http::Response<ResBody>>,
+    ResBody: From<String>,
+{
+    type Response = http::Response<ResBody>;
+    type Error = S::Error;
+    type Future = RecoverTowerFut<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        let sent = req.headers().get(REQUEST_ID_HEADER);
+        let id = correlation_id(sent.map(http::HeaderValue::as_bytes));
+        let fut = catch_unwind(AssertUnwindSafe(|| self.inner.call(req)))
+            .ok()
+            .map(Box::pin);
+        RecoverTowerFut { fut, id }
+    }
+}
+
+#[cfg(feature = "tower")]
+pub struct RecoverTowerFut<F> {
+    fut: Option<Pin<Box<F>>>,
+    id: String,
+}
+
+#[cfg(feature = "tower")]
+impl<F, B, E> Future for RecoverTowerFut<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+    B: From<String>,
+{
+    type Output = Result<http::Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        match this.fut.as_mut().map(|fut| poll_caught(fut.as_mut(), cx)) {
+            Some(Some(poll)) => return poll,
+            _ => this.fut = None,
+        }
+        let doc = crate::Response::<(), ()>::from(internal_error(&this.id));
+        let mut res = http::Response::new(B::from(serde_json::to_string(&doc).unwrap_or_default()));
+        *res.status_mut() = http::StatusCode::INTERNAL_SERVER_ERROR;
+        res.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static(crate::media::JSONAPI_MEDIA_TYPE),
+        );
+        if let Ok(id) = http::HeaderValue::from_str(&this.id) {
+            res.headers_mut().insert(REQUEST_ID_HEADER, id);
+        }
+        Poll::Ready(Ok(res))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::correlation_id;
+
+    #[test]
+    fn test_correlation_id() {
+        assert_eq!(correlation_id(Some(b"abc-1")), "abc-1");
+        for sent in [None, Some(&b""[..]), Some(b"a b"), Some(&[b'a'; 129][..])] {
+            let id = correlation_id(sent);
+            assert!(!id.is_empty() && id.len() <= 128);
+        }
+        assert_ne!(correlation_id(None), correlation_id(None));
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_recover() {
+        use actix_web::{body::to_bytes, test, web, App, HttpResponse};
+
+        use crate::{Error, Response};
+
+        async fn panics() -> HttpResponse {
+            panic!("the handler panicked")
+        }
+
+        async fn fails() -> Result<HttpResponse, Error> {
+            Err(Error::new_conflict("taken"))
+        }
+
+        async fn by_id(id: web::Path<u32>) -> HttpResponse {
+            HttpResponse::Ok().body(id.to_string())
+        }
+
+        actix_web::rt::System::new().block_on(async {
+            let app = test::init_service(
+                App::new()
+                    .wrap(super::Recover)
+                    .route("/panics", web::get().to(panics))
+                    .route("/fails", web::get().to(fails))
+                    .route("/items/{id}", web::get().to(by_id)),
+            )
+            .await;
+            // as the server does, answering errors with their error responses
+            let call = |req| async {
+                match test::try_call_service(&app, req).await {
+                    Ok(res) => res.into_parts().1.map_into_boxed_body(),
+                    Err(err) => err.error_response(),
+                }
+            };
+
+            let req = test::TestRequest::get()
+                .uri("/panics")
+                .insert_header(("x-request-id", "req-7"))
+                .to_request();
+            let res = call(req).await;
+            assert_eq!(res.status(), 500);
+            assert_eq!(res.headers().get("x-request-id").unwrap(), "req-7");
+            let doc: Response<(), ()> =
+                serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+            let err = doc.into_result().err().unwrap().remove(0);
+            assert_eq!(err.id.as_deref(), Some("req-7"));
+            assert_eq!(err.title, "internal server error");
+
+            let req = test::TestRequest::get().uri("/fails").to_request();
+            let res = call(req).await;
+            assert_eq!(res.status(), 409);
+
+            let req = test::TestRequest::get().uri("/items/x").to_request();
+            let res = call(req).await;
+            assert_eq!(res.status(), 404);
+            let doc: Response<(), ()> =
+                serde_json::from_slice(&to_bytes(res.into_body()).await.unwrap()).unwrap();
+            let err = doc.into_result().err().unwrap().remove(0);
+            assert_eq!(err.title, "Not Found");
+            assert!(err.detail.is_some());
+
+            let req = test::TestRequest::get().uri("/items/3").to_request();
+            let res = call(req).await;
+            assert_eq!(to_bytes(res.into_body()).await.unwrap(), "3");
+        });
+    }
+
+    #[cfg(feature = "tower")]
+    #[test]
+    fn test_recover_layer() {
+        use std::{
+            future::{ready, Future, Ready},
+            pin::pin,
+            task::{Context, Poll, Waker},
+        };
+        use tower_layer::Layer;
+        use tower_service::Service;
+
+        struct Panics;
+
+        impl Service<http::Request<()>> for Panics {
+            type Response = http::Response<String>;
+            type Error = std::convert::Infallible;
+            type Future = Ready<Result<http::Response<String>, Self::Error>>;
+
+            fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+                Poll::Ready(Ok(()))
+            }
+
+            fn call(&mut self, req: http::Request<()>) -> Self::Future {
+                if req.uri() == "/panics" {
+                    panic!("the service panicked")
+                }
+                ready(Ok(http::Response::new("ok".to_owned())))
+            }
+        }
+
+        let mut service = super::RecoverLayer.layer(Panics);
+        let mut call = |uri: &str| {
+            let fut = pin!(service.call(http::Request::get(uri).body(()).unwrap()));
+            match fut.poll(&mut Context::from_waker(Waker::noop())) {
+                Poll::Ready(Ok(res)) => res,
+                _ => panic!("the service should be ready"),
+            }
+        };
+        let res = call("/panics");
+        assert_eq!(res.status(), 500);
+        let doc: serde_json::Value = serde_json::from_str(res.body()).unwrap();
+        assert_eq!(
+            doc["errors"][0]["id"].as_str(),
+            res.headers()["x-request-id"].to_str().ok()
+        );
+        assert_eq!(call("/").into_body(), "ok");
+    }
+}
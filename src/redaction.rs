@@ -0,0 +1,102 @@
+// Redaction of error text meant for developers rather than clients: what went wrong inside the
+// server (internal errors, whose titles tend to carry database or serialization messages) and why
+// a request body couldn't be parsed (the parser's messages). It's off by default, for development;
+// with JsonApiConfig::redacted(true), e.g. in release builds, such errors are answered with a
+// generic message and a correlation id, and the original is handed to the config's reporter so
+// it can still be found in the logs. As with the config's status policy, errors handlers return
+// are only redacted in errors::ErrorDocuments.
+use crate::Error;
+
+// Reporter is what redacted errors are reported to, with the correlation id they're answered with
+pub type Reporter = dyn Fn(&Error) + Send + Sync;
+
+// redact replaces the title and detail of an error with message, keeping its status, code and
+// source, and reports the original to reporter
+pub fn redact(mut err: Error, message: &str, reporter: Option<&Reporter>) -> Error {
+    if err.title == message && err.detail.is_none() {
+        return err;
+    }
+    let id = err.id.get_or_insert_with(new_id).clone();
+    if let Some(report) = reporter {
+        report(&err);
+    }
+    Error {
+        id: Some(id),
+        title: message.to_owned(),
+        detail: None,
+        ..err
+    }
+}
+
+// redact_internal redacts internal errors if the config says so, leaving the others as they are
+#[cfg(feature = "actixweb")]
+pub(crate) fn redact_internal(config: &crate::JsonApiConfig, err: Error) -> Error {
+    if !config.redacted || err.status != crate::ErrorStatus::InternalError {
+        return err;
+    }
+    redact(err, "internal server error", config.reporter.as_deref())
+}
+
+// redact_payload redacts an error about a request body that couldn't be parsed if the config
+// says so
+#[cfg(feature = "actixweb")]
+pub(crate) fn redact_payload(config: &crate::JsonApiConfig, err: Error) -> Error {
+    if !config.redacted {
+        return err;
+    }
+    redact(
+        err,
+        "the request body isn't a valid document",
+        config.reporter.as_deref(),
+    )
+}
+
+// new_id makes a correlation id
+#[cfg(feature = "server")]
+pub(crate) fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+#[cfg(not(feature = "server"))]
+pub(crate) fn new_id() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos());
+    format!("{:x}-{:x}", nanos, NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::redact;
+    use crate::{Error, ErrorStatus};
+
+    #[test]
+    fn test_redact() {
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let report = {
+            let reported = reported.clone();
+            move |err: &Error| reported.lock().unwrap().push(err.clone())
+        };
+        let err = Error::new_internal_error("connection to db-3 refused").with_pointer("/data");
+        let redacted_err = redact(err.clone(), "oops", Some(&report));
+        assert_eq!(redacted_err.title, "oops");
+        assert_eq!(redacted_err.status, ErrorStatus::InternalError);
+        assert_eq!(redacted_err.source, err.source);
+        assert!(redacted_err.id.is_some());
+        // the original is reported with the id it's answered with
+        let original = reported.lock().unwrap().remove(0);
+        assert_eq!(original.title, "connection to db-3 refused");
+        assert_eq!(original.id, redacted_err.id);
+
+        let kept = redact(err.with_id("req-1"), "oops", None);
+        assert_eq!(kept.id.as_deref(), Some("req-1"));
+        assert_eq!(redact(kept.clone(), "oops", Some(&report)), kept);
+        assert!(reported.lock().unwrap().is_empty());
+    }
+}
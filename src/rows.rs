@@ -0,0 +1,329 @@
+// Resources hydrated straight from sqlx rows. A RowMapping says which column holds the id, which
+// ones the attributes and which the ids of related resources, and RowLoader uses one to fetch the
+// resources a response includes by id, a batch per query rather than a query per resource.
+use serde_json::{Map, Value};
+use sqlx::{Arguments, ColumnIndex, Database, Decode, Encode, Executor, IntoArguments, Row, Type};
+
+use crate::{
+    Error, Identifier, Relationship, RelationshipData, Relationships, ResourceResponse, ID,
+};
+
+// ColumnType is what a column is decoded as. Int columns are read as 64 bit integers, so with
+// databases which tell the two apart (e.g. Postgres) narrower ones need a cast in the query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Text,
+    Int,
+    Float,
+    Bool,
+}
+
+// DecodeColumn decodes a column of a row into JSON, null if the column is. It's implemented for
+// the rows of any database which can decode text, 64 bit integers, floats and booleans
+pub trait DecodeColumn: Row {
+    fn decode_column(&self, column: &str, typ: ColumnType) -> Result<Value, sqlx::Error>;
+}
+
+impl<R: Row> DecodeColumn for R
+where
+    for<'a> &'a str: ColumnIndex<R>,
+    for<'r> String: Decode<'r, R::Database> + Type<R::Database>,
+    for<'r> i64: Decode<'r, R::Database> + Type<R::Database>,
+    for<'r> f64: Decode<'r, R::Database> + Type<R::Database>,
+    for<'r> bool: Decode<'r, R::Database> + Type<R::Database>,
+{
+    fn decode_column(&self, column: &str, typ: ColumnType) -> Result<Value, sqlx::Error> {
+        Ok(match typ {
+            ColumnType::Text => self.try_get::<Option<String>, _>(column)?.into(),
+            ColumnType::Int => self.try_get::<Option<i64>, _>(column)?.into(),
+            ColumnType::Float => self.try_get::<Option<f64>, _>(column)?.into(),
+            ColumnType::Bool => self.try_get::<Option<bool>, _>(column)?.into(),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Column {
+    name: String,
+    typ: ColumnType,
+}
+
+#[derive(Debug, Clone)]
+struct RelationshipColumn {
+    name: String,
+    typ: String,
+    column: Column,
+}
+
+// RowMapping maps the columns of a row to a resource of a type
+#[derive(Debug, Clone)]
+pub struct RowMapping {
+    typ: String,
+    id: Column,
+    attributes: Vec<(String, Column)>,
+    relationships: Vec<RelationshipColumn>,
+}
+
+impl RowMapping {
+    pub fn new(typ: &str, id_column: &str, id_type: ColumnType) -> RowMapping {
+        RowMapping {
+            typ: typ.to_owned(),
+            id: Column {
+                name: id_column.to_owned(),
+                typ: id_type,
+            },
+            attributes: Vec::new(),
+            relationships: Vec::new(),
+        }
+    }
+
+    // attribute maps an attribute to the column holding it
+    pub fn attribute(mut self, field: &str, column: &str, typ: ColumnType) -> Self {
+        self.attributes.push((
+            field.to_owned(),
+            Column {
+                name: column.to_owned(),
+                typ,
+            },
+        ));
+        self
+    }
+
+    // relationship maps a to-one relationship to the column holding the id of the related
+    // resource, which is of type `related`. A null id leaves the relationship without data
+    pub fn relationship(
+        mut self,
+        name: &str,
+        related: &str,
+        column: &str,
+        typ: ColumnType,
+    ) -> Self {
+        self.relationships.push(RelationshipColumn {
+            name: name.to_owned(),
+            typ: related.to_owned(),
+            column: Column {
+                name: column.to_owned(),
+                typ,
+            },
+        });
+        self
+    }
+
+    // resource hydrates the resource a row holds
+    pub fn resource<R: DecodeColumn>(&self, row: &R) -> Result<ResourceResponse<Value>, Error> {
+        let id = column_id(row, &self.id)?
+            .ok_or_else(|| Error::new_internal_error(&format!("a {} row has no id", self.typ)))?;
+        let mut attributes = Map::new();
+        for (field, column) in &self.attributes {
+            attributes.insert(field.clone(), decode(row, column)?);
+        }
+        let mut relationships = Relationships::new();
+        for rel in &self.relationships {
+            let data = column_id(row, &rel.column)?.map(|id| {
+                Relationship::ToOne(Identifier {
+                    id,
+                    typ: rel.typ.clone(),
+                })
+            });
+            relationships.insert(
+                rel.name.clone(),
+                RelationshipData {
+                    data,
+                    ..Default::default()
+                },
+            );
+        }
+        Ok(ResourceResponse {
+            id: Identifier {
+                id,
+                typ: self.typ.clone(),
+            },
+            attributes: Value::Object(attributes),
+            relationships: (!relationships.is_empty()).then_some(relationships),
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        })
+    }
+
+    pub fn resources<R: DecodeColumn>(
+        &self,
+        rows: &[R],
+    ) -> Result<Vec<ResourceResponse<Value>>, Error> {
+        rows.iter().map(|row| self.resource(row)).collect()
+    }
+}
+
+fn decode<R: DecodeColumn>(row: &R, column: &Column) -> Result<Value, Error> {
+    row.decode_column(&column.name, column.typ).map_err(|err| {
+        Error::new_internal_error(&format!(
+            "column '{}' couldn't be read: {}",
+            column.name, err
+        ))
+    })
+}
+
+fn column_id<R: DecodeColumn>(row: &R, column: &Column) -> Result<Option<ID>, Error> {
+    Ok(match decode(row, column)? {
+        Value::Null => None,
+        Value::String(id) => Some(id.into()),
+        id => Some(id.to_string().into()),
+    })
+}
+
+// related_ids collects the ids of the resources a to-one relationship of the given resources
+// points at, each once, e.g. to load them with a RowLoader
+pub fn related_ids<D>(resources: &[ResourceResponse<D>], relationship: &str) -> Vec<ID> {
+    let mut ids: Vec<ID> = Vec::new();
+    let linked = resources
+        .iter()
+        .filter_map(|res| res.relationships.as_ref()?.get(relationship)?.data.as_ref());
+    for data in linked {
+        if let Relationship::ToOne(identifier) = data {
+            if !ids.contains(&identifier.id) {
+                ids.push(identifier.id.clone());
+            }
+        }
+    }
+    ids
+}
+
+// RowLoader loads resources by id with a query of the form `<select> WHERE <id column> IN (...)`,
+// the ids bound as parameters, batch_size of them at a time
+#[derive(Debug, Clone)]
+pub struct RowLoader {
+    mapping: RowMapping,
+    select: String,
+    batch_size: usize,
+}
+
+impl RowLoader {
+    // new loads with a query selecting the mapped columns, up to where the WHERE clause would
+    // go, e.g. "SELECT id, name FROM people"
+    pub fn new(mapping: RowMapping, select: &str) -> RowLoader {
+        RowLoader {
+            mapping,
+            select: select.to_owned(),
+            batch_size: 500,
+        }
+    }
+
+    // batch_size caps the ids bound to a single query, which databases limit
+    pub fn batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    pub async fn load<'c, DB, E>(
+        &self,
+        executor: E,
+        ids: &[ID],
+    ) -> Result<Vec<ResourceResponse<Value>>, Error>
+    where
+        DB: Database,
+        DB::Row: DecodeColumn,
+        E: Executor<'c, Database = DB> + Copy,
+        for<'q> String: Encode<'q, DB> + Type<DB>,
+        for<'q> i64: Encode<'q, DB> + Type<DB>,
+        for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    {
+        // ids which can't be in an integer column are left out rather than failing the query
+        let ids: Vec<&ID> = ids
+            .iter()
+            .filter(|id| self.mapping.id.typ != ColumnType::Int || id.0.parse::<i64>().is_ok())
+            .collect();
+        let mut resources = Vec::with_capacity(ids.len());
+        for batch in ids.chunks(self.batch_size) {
+            let mut sql = format!("{} WHERE {} IN (", self.select, self.mapping.id.name);
+            let mut args = DB::Arguments::default();
+            for (i, id) in batch.iter().enumerate() {
+                if i > 0 {
+                    sql.push_str(", ");
+                }
+                args.format_placeholder(&mut sql)
+                    .expect("writing to a String can't fail");
+                let added = match self.mapping.id.typ {
+                    ColumnType::Int => args.add(id.0.parse::<i64>().unwrap_or_default()),
+                    _ => args.add(id.0.to_string()),
+                };
+                added.map_err(|err| {
+                    Error::new_internal_error(&format!("id '{}' couldn't be bound: {}", id, err))
+                })?;
+            }
+            sql.push(')');
+            let rows = sqlx::query_with(&sql, args)
+                .fetch_all(executor)
+                .await
+                .map_err(|err| {
+                    Error::new_internal_error(&format!(
+                        "{} couldn't be loaded: {}",
+                        self.mapping.typ, err
+                    ))
+                })?;
+            resources.extend(self.mapping.resources(&rows)?);
+        }
+        Ok(resources)
+    }
+}
+
+// actix's runtime is tokio, which the sqlite pool runs on
+#[cfg(all(test, feature = "actixweb"))]
+mod tests {
+    use serde_json::json;
+    use sqlx::sqlite::SqlitePool;
+
+    use super::{related_ids, ColumnType, RowLoader, RowMapping};
+    use crate::Response;
+
+    #[test]
+    fn test_rows() {
+        actix_web::rt::System::new().block_on(async {
+            let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+            for statement in [
+                "CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT NOT NULL)",
+                "CREATE TABLE articles (id INTEGER PRIMARY KEY, title TEXT, author_id INTEGER)",
+                "INSERT INTO people VALUES (1, 'ann'), (2, 'bob')",
+                "INSERT INTO articles VALUES (1, 'a', 2), (2, 'b', NULL), (3, 'c', 2)",
+            ] {
+                sqlx::query(statement).execute(&pool).await.unwrap();
+            }
+
+            let articles = RowMapping::new("articles", "id", ColumnType::Int)
+                .attribute("title", "title", ColumnType::Text)
+                .relationship("author", "people", "author_id", ColumnType::Int);
+            let rows = sqlx::query("SELECT id, title, author_id FROM articles ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+            let resources = articles.resources(&rows).unwrap();
+            let ids = related_ids(&resources, "author");
+            assert_eq!(ids, vec!["2".into()]);
+
+            let people = RowLoader::new(
+                RowMapping::new("people", "id", ColumnType::Int).attribute(
+                    "name",
+                    "name",
+                    ColumnType::Text,
+                ),
+                "SELECT id, name FROM people",
+            )
+            .batch_size(1);
+            let included = people.load(&pool, &ids).await.unwrap();
+            let out = serde_json::to_value(Response::from_parts(resources, included)).unwrap();
+            assert_eq!(
+                out["data"][0],
+                json!({
+                    "type": "articles",
+                    "id": "1",
+                    "attributes": {"title": "a"},
+                    "relationships": {"author": {"data": {"type": "people", "id": "2"}}}
+                })
+            );
+            assert_eq!(out["data"][1]["relationships"]["author"], json!({}));
+            assert_eq!(
+                out["included"],
+                json!([{"type": "people", "id": "2", "attributes": {"name": "bob"}}])
+            );
+        });
+    }
+}
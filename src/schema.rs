@@ -0,0 +1,406 @@
+// JSON Schemas of documents, for schemars. The document types are generic over their attributes
+// (or resources), whose schemas are taken from their own JsonSchema implementations, so schemas of
+// e.g. Request<ArticleAttributes> describe the whole envelope. Extension members aren't
+// described, but are allowed wherever the spec allows them.
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use std::borrow::Cow;
+
+use crate::{
+    document::{Document, GenericResource},
+    Error, ErrorSource, ErrorStatus, Identifier, Link, Relationship, RelationshipData, Request,
+    ResourceRequest, ResourceResponse, Response, ID,
+};
+
+// generic_name is the name of a generic type's schema, as schemars' derive would make it
+fn generic_name(name: &str, params: &[Cow<'static, str>]) -> Cow<'static, str> {
+    Cow::Owned(format!("{}_for_{}", name, params.join("_and_")))
+}
+
+fn meta() -> Schema {
+    json_schema!({"type": "object"})
+}
+
+fn links(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "object",
+        "additionalProperties": generator.subschema_for::<Link>(),
+    })
+}
+
+fn relationships(generator: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "object",
+        "additionalProperties": generator.subschema_for::<RelationshipData>(),
+    })
+}
+
+fn resource_object(
+    generator: &mut SchemaGenerator,
+    attributes: Schema,
+    required: &[&str],
+) -> Schema {
+    json_schema!({
+        "type": "object",
+        "properties": {
+            "type": {"type": "string"},
+            "id": generator.subschema_for::<ID>(),
+            "attributes": attributes,
+            "relationships": relationships(generator),
+            "meta": meta(),
+            "links": links(generator),
+        },
+        "required": required,
+    })
+}
+
+impl JsonSchema for ID {
+    fn schema_name() -> Cow<'static, str> {
+        "ID".into()
+    }
+
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({"type": "string"})
+    }
+}
+
+impl JsonSchema for Identifier {
+    fn schema_name() -> Cow<'static, str> {
+        "Identifier".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "type": {"type": "string"},
+                "id": generator.subschema_for::<ID>(),
+                "meta": meta(),
+            },
+            "required": ["type", "id"],
+        })
+    }
+}
+
+impl JsonSchema for Link {
+    fn schema_name() -> Cow<'static, str> {
+        "Link".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let hreflang = json_schema!({
+            "anyOf": [
+                {"type": "string"},
+                {"type": "array", "items": {"type": "string"}},
+            ],
+        });
+        json_schema!({
+            "anyOf": [
+                {"type": "string"},
+                {
+                    "type": "object",
+                    "properties": {
+                        "href": {"type": "string"},
+                        "rel": {"type": "string"},
+                        "describedby": generator.subschema_for::<Link>(),
+                        "title": {"type": "string"},
+                        "type": {"type": "string"},
+                        "hreflang": hreflang,
+                        "meta": meta(),
+                    },
+                    "required": ["href"],
+                },
+            ],
+        })
+    }
+}
+
+impl JsonSchema for Relationship {
+    fn schema_name() -> Cow<'static, str> {
+        "Relationship".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let identifier = generator.subschema_for::<Identifier>();
+        json_schema!({
+            "anyOf": [
+                identifier.clone(),
+                {"type": "array", "items": identifier},
+            ],
+        })
+    }
+}
+
+impl JsonSchema for RelationshipData {
+    fn schema_name() -> Cow<'static, str> {
+        "RelationshipData".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "data": {
+                    "anyOf": [generator.subschema_for::<Relationship>(), {"type": "null"}],
+                },
+                "links": links(generator),
+                "meta": meta(),
+            },
+        })
+    }
+}
+
+impl JsonSchema for ErrorStatus {
+    fn schema_name() -> Cow<'static, str> {
+        "ErrorStatus".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let statuses = [
+            ErrorStatus::BadRequest,
+            ErrorStatus::Unauthorized,
+            ErrorStatus::Forbidden,
+            ErrorStatus::NotFound,
+            ErrorStatus::NotAcceptable,
+            ErrorStatus::Conflict,
+            ErrorStatus::PreconditionFailed,
+            ErrorStatus::PayloadTooLarge,
+            ErrorStatus::UnsupportedMediaType,
+            ErrorStatus::UnprocessableEntity,
+            ErrorStatus::TooManyRequests,
+            ErrorStatus::InternalError,
+        ]
+        .map(|status| serde_json::to_value(status).expect("statuses are strings"));
+        json_schema!({"type": "string", "enum": statuses})
+    }
+}
+
+impl JsonSchema for ErrorSource {
+    fn schema_name() -> Cow<'static, str> {
+        "ErrorSource".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "pointer": {"type": "string"},
+                "parameter": {"type": "string"},
+            },
+        })
+    }
+}
+
+impl JsonSchema for Error {
+    fn schema_name() -> Cow<'static, str> {
+        "Error".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "id": {"type": "string"},
+                "status": generator.subschema_for::<ErrorStatus>(),
+                "code": {"type": "string"},
+                "title": {"type": "string"},
+                "detail": {"type": "string"},
+                "source": generator.subschema_for::<ErrorSource>(),
+                "meta": meta(),
+            },
+            "required": ["status", "title"],
+        })
+    }
+}
+
+impl<D: JsonSchema> JsonSchema for ResourceRequest<D> {
+    fn schema_name() -> Cow<'static, str> {
+        generic_name("ResourceRequest", &[D::schema_name()])
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Cow::Owned(format!("jsonapi::ResourceRequest<{}>", D::schema_id()))
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let attributes = generator.subschema_for::<D>();
+        resource_object(generator, attributes, &["type"])
+    }
+}
+
+impl<D: JsonSchema> JsonSchema for Request<D> {
+    fn schema_name() -> Cow<'static, str> {
+        generic_name("Request", &[D::schema_name()])
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Cow::Owned(format!("jsonapi::Request<{}>", D::schema_id()))
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {"data": generator.subschema_for::<ResourceRequest<D>>()},
+            "required": ["data"],
+        })
+    }
+}
+
+impl<D: JsonSchema> JsonSchema for ResourceResponse<D> {
+    fn schema_name() -> Cow<'static, str> {
+        generic_name("ResourceResponse", &[D::schema_name()])
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Cow::Owned(format!("jsonapi::ResourceResponse<{}>", D::schema_id()))
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let attributes = generator.subschema_for::<D>();
+        resource_object(generator, attributes, &["type", "id", "attributes"])
+    }
+}
+
+// a Response is a document with either data (and maybe included resources) or errors
+impl<P: JsonSchema, I: JsonSchema> JsonSchema for Response<P, I> {
+    fn schema_name() -> Cow<'static, str> {
+        generic_name("Response", &[P::schema_name(), I::schema_name()])
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Cow::Owned(format!(
+            "jsonapi::Response<{}, {}>",
+            P::schema_id(),
+            I::schema_id()
+        ))
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "data": {"type": "array", "items": generator.subschema_for::<ResourceResponse<P>>()},
+                "errors": {"type": "array", "items": generator.subschema_for::<Error>()},
+                "included": {"type": "array", "items": generator.subschema_for::<ResourceResponse<I>>()},
+                "meta": meta(),
+                "links": links(generator),
+                "jsonapi": {"type": "object"},
+            },
+            "oneOf": [{"required": ["data"]}, {"required": ["errors"]}],
+        })
+    }
+}
+
+impl JsonSchema for GenericResource {
+    fn schema_name() -> Cow<'static, str> {
+        "GenericResource".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let mut schema = resource_object(generator, json_schema!({"type": "object"}), &["type"]);
+        if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+            properties.insert("lid".into(), serde_json::json!({"type": "string"}));
+        }
+        schema
+    }
+}
+
+// a Document's data is null, a single resource or a list of them
+impl<D: JsonSchema, I: JsonSchema> JsonSchema for Document<D, I> {
+    fn schema_name() -> Cow<'static, str> {
+        generic_name("Document", &[D::schema_name(), I::schema_name()])
+    }
+
+    fn schema_id() -> Cow<'static, str> {
+        Cow::Owned(format!(
+            "jsonapi::document::Document<{}, {}>",
+            D::schema_id(),
+            I::schema_id()
+        ))
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        let resource = generator.subschema_for::<D>();
+        json_schema!({
+            "type": "object",
+            "properties": {
+                "data": {
+                    "anyOf": [
+                        {"type": "null"},
+                        resource.clone(),
+                        {"type": "array", "items": resource},
+                    ],
+                },
+                "errors": {"type": "array", "items": generator.subschema_for::<Error>()},
+                "included": {"type": "array", "items": generator.subschema_for::<I>()},
+                "meta": meta(),
+                "links": links(generator),
+                "jsonapi": {"type": "object"},
+            },
+            "anyOf": [
+                {"required": ["data"]},
+                {"required": ["errors"]},
+                {"required": ["meta"]},
+            ],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{schema_for, JsonSchema};
+    use serde_json::json;
+
+    use crate::{document::GenericDocument, Request, Response};
+
+    struct ArticleAttributes;
+
+    impl JsonSchema for ArticleAttributes {
+        fn schema_name() -> std::borrow::Cow<'static, str> {
+            "ArticleAttributes".into()
+        }
+
+        fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+            schemars::json_schema!({
+                "type": "object",
+                "properties": {"title": {"type": "string"}},
+                "required": ["title"],
+            })
+        }
+    }
+
+    #[test]
+    fn test_schemas() {
+        let schema = serde_json::to_value(schema_for!(Request<ArticleAttributes>)).unwrap();
+        assert_eq!(schema["title"], "Request_for_ArticleAttributes");
+        let resource = &schema["$defs"]["ResourceRequest_for_ArticleAttributes"];
+        assert_eq!(resource["required"], json!(["type"]));
+        assert_eq!(
+            resource["properties"]["attributes"]["$ref"],
+            "#/$defs/ArticleAttributes"
+        );
+
+        let schema = serde_json::to_value(schema_for!(Response<ArticleAttributes, ()>)).unwrap();
+        assert_eq!(
+            schema["$defs"]["Error"]["properties"]["status"]["$ref"],
+            "#/$defs/ErrorStatus"
+        );
+        assert!(schema["$defs"]["ErrorStatus"]["enum"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("412")));
+
+        let schema = serde_json::to_value(schema_for!(GenericDocument)).unwrap();
+        assert_eq!(
+            schema["properties"]["data"]["anyOf"][0],
+            json!({"type": "null"})
+        );
+        assert_eq!(
+            schema["$defs"]["GenericResource"]["properties"]["lid"],
+            json!({"type": "string"})
+        );
+    }
+}
@@ -0,0 +1,137 @@
+// Sorting through the `sort` query parameter: a comma separated list of fields, each sorted in
+// ascending order unless prefixed with a minus.
+use std::cmp::Ordering;
+
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortField {
+    pub name: String,
+    pub direction: Direction,
+}
+
+// SortSpec is the parsed sort parameter, fields in order of precedence
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SortSpec(pub Vec<SortField>);
+
+impl SortSpec {
+    // parse reads the value of a sort parameter
+    pub fn parse(value: &str) -> Result<SortSpec, Error> {
+        value
+            .split(',')
+            .map(|field| {
+                let (name, direction) = match field.strip_prefix('-') {
+                    Some(name) => (name, Direction::Desc),
+                    None => (field, Direction::Asc),
+                };
+                if name.is_empty() {
+                    return Err(
+                        Error::new_bad_request("sort fields can't be empty").with_parameter("sort")
+                    );
+                }
+                Ok(SortField {
+                    name: name.to_owned(),
+                    direction,
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(SortSpec)
+    }
+
+    // from_query reads the sort parameter of a query string, if there is one
+    pub fn from_query(query: &str) -> Result<SortSpec, Error> {
+        match form_urlencoded::parse(query.as_bytes()).find(|(name, _)| name == "sort") {
+            Some((_, value)) => SortSpec::parse(&value),
+            None => Ok(SortSpec::default()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    // apply sorts items in memory. Fields the type can't be sorted by are refused with a 400,
+    // as the spec requires, before anything is sorted
+    pub fn apply<T: Sortable>(&self, items: &mut [T]) -> Result<(), Error> {
+        let comparators = self
+            .0
+            .iter()
+            .map(|field| match T::comparator(&field.name) {
+                Some(compare) => Ok((compare, field.direction)),
+                None => Err(
+                    Error::new_bad_request(&format!("can't sort by '{}'", field.name))
+                        .with_parameter("sort"),
+                ),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // sort_by is stable, so items which compare equal on every field keep their order
+        items.sort_by(|a, b| {
+            comparators
+                .iter()
+                .map(|(compare, direction)| match direction {
+                    Direction::Asc => compare(a, b),
+                    Direction::Desc => compare(b, a),
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+        Ok(())
+    }
+}
+
+// Sortable maps the sort field names of a type to comparators, for sorting collections held in
+// memory (caches, small datasets) rather than by the database
+pub trait Sortable {
+    // comparator gives the ascending order of a field, or None if it can't be sorted by
+    fn comparator(field: &str) -> Option<fn(&Self, &Self) -> Ordering>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::{Direction, SortSpec, Sortable};
+
+    #[derive(Debug, PartialEq)]
+    struct Article {
+        title: &'static str,
+        likes: usize,
+    }
+
+    impl Sortable for Article {
+        fn comparator(field: &str) -> Option<fn(&Self, &Self) -> Ordering> {
+            match field {
+                "title" => Some(|a, b| a.title.cmp(b.title)),
+                "likes" => Some(|a, b| a.likes.cmp(&b.likes)),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        let spec = SortSpec::from_query("page[size]=2&sort=-likes,title").unwrap();
+        assert_eq!(spec.0[0].direction, Direction::Desc);
+        let article = |title, likes| Article { title, likes };
+        let mut articles = vec![article("b", 1), article("c", 5), article("a", 1)];
+        spec.apply(&mut articles).unwrap();
+        assert_eq!(
+            articles,
+            [article("c", 5), article("a", 1), article("b", 1)]
+        );
+
+        let err = SortSpec::parse("author")
+            .unwrap()
+            .apply(&mut articles)
+            .unwrap_err();
+        assert_eq!(err.source.unwrap().parameter.as_deref(), Some("sort"));
+        assert!(SortSpec::parse("title,").is_err());
+        assert!(SortSpec::from_query("").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+// How error documents map to HTTP statuses. By default a document is answered with the status of
+// its first error; a StatusPolicy registered with JsonApiConfig can decide otherwise, e.g.
+// answering documents of mixed errors with 400. It's consulted wherever errors are answered with
+// the config at hand, see errors::ErrorDocuments.
+use actix_web::{http::StatusCode, HttpRequest};
+
+use crate::Error;
+
+pub trait StatusPolicy: Send + Sync {
+    // status is the status a document of errors (never empty) is answered with, None leaving it
+    // to the default
+    fn status(&self, errors: &[Error]) -> Option<StatusCode>;
+}
+
+// closures are policies, e.g. one making errors with an "upstream" code 502s
+impl<F> StatusPolicy for F
+where
+    F: Fn(&[Error]) -> Option<StatusCode> + Send + Sync,
+{
+    fn status(&self, errors: &[Error]) -> Option<StatusCode> {
+        self(errors)
+    }
+}
+
+// MostGeneral answers documents of errors with different statuses with the most generally
+// applicable one, as the spec recommends: 400 if they're all client errors, else 500
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MostGeneral;
+
+impl StatusPolicy for MostGeneral {
+    fn status(&self, errors: &[Error]) -> Option<StatusCode> {
+        let first = errors.first()?;
+        if errors.iter().all(|err| err.status == first.status) {
+            return None;
+        }
+        let client = errors
+            .iter()
+            .all(|err| StatusCode::from(&err.status).is_client_error());
+        Some(if client {
+            StatusCode::BAD_REQUEST
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+    }
+}
+
+// status_of is the status errors are answered with under a policy
+pub fn status_of(errors: &[Error], policy: Option<&dyn StatusPolicy>) -> StatusCode {
+    let default = errors
+        .first()
+        .map_or(StatusCode::INTERNAL_SERVER_ERROR, |err| {
+            (&err.status).into()
+        });
+    policy
+        .and_then(|policy| policy.status(errors))
+        .unwrap_or(default)
+}
+
+// request_status is the status errors are answered with under the policy registered with
+// JsonApiConfig
+pub(crate) fn request_status(req: &HttpRequest, errors: &[Error]) -> StatusCode {
+    let policy = req
+        .app_data::<crate::JsonApiConfig>()
+        .and_then(|config| config.status_policy.as_deref());
+    status_of(errors, policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, ResponseError};
+
+    use super::{status_of, MostGeneral, StatusPolicy};
+    use crate::Error;
+
+    #[test]
+    fn test_status_policy() {
+        let not_found = Error::new_not_found("a");
+        let forbidden = Error::new_forbidden("b");
+        assert_eq!(
+            MostGeneral.status(&[not_found.clone(), forbidden.clone()]),
+            Some(StatusCode::BAD_REQUEST)
+        );
+        assert_eq!(
+            MostGeneral.status(&[not_found.clone(), Error::new_internal_error("c")]),
+            Some(StatusCode::INTERNAL_SERVER_ERROR)
+        );
+        assert_eq!(
+            MostGeneral.status(&[not_found.clone(), not_found.clone()]),
+            None
+        );
+
+        let mut upstream = Error::new_internal_error("upstream failed");
+        upstream.code = Some("upstream".into());
+        let policy = |errors: &[Error]| {
+            if errors
+                .iter()
+                .any(|err| err.code.as_deref() == Some("upstream"))
+            {
+                Some(StatusCode::BAD_GATEWAY)
+            } else {
+                MostGeneral.status(errors)
+            }
+        };
+        assert_eq!(
+            status_of(std::slice::from_ref(&upstream), Some(&policy)),
+            StatusCode::BAD_GATEWAY
+        );
+        assert_eq!(
+            status_of(std::slice::from_ref(&not_found), Some(&policy)),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            status_of(&[not_found.clone(), forbidden.clone()], Some(&policy)),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status_of(&[not_found, forbidden], None),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(status_of(&[], None), StatusCode::INTERNAL_SERVER_ERROR);
+        // without a request, errors are answered with their own status
+        assert_eq!(upstream.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_actix() {
+        use actix_web::{test, web, App, HttpResponse};
+
+        use crate::{JsonApi, JsonApiConfig, Request, Response};
+
+        async fn mixed() -> Response<(), ()> {
+            vec![Error::new_not_found("a"), Error::new_forbidden("b")].into()
+        }
+
+        async fn create(_: JsonApi<Request<serde_json::Value>>) -> HttpResponse {
+            HttpResponse::Created().finish()
+        }
+
+        // documents handlers respond with and rejections of the extractor are held to the policy
+        // without any middleware
+        let teapot = |_: &[Error]| Some(StatusCode::IM_A_TEAPOT);
+        actix_web::rt::System::new().block_on(async {
+            let app = test::init_service(
+                App::new()
+                    .app_data(JsonApiConfig::default().status_policy(teapot))
+                    .route("/mixed", web::get().to(mixed))
+                    .route("/articles", web::post().to(create)),
+            )
+            .await;
+            let req = test::TestRequest::get().uri("/mixed").to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::IM_A_TEAPOT);
+            let req = test::TestRequest::post()
+                .uri("/articles")
+                .insert_header(("content-type", "text/plain"))
+                .set_payload("{}")
+                .to_request();
+            let res = test::call_service(&app, req).await;
+            assert_eq!(res.status(), StatusCode::IM_A_TEAPOT);
+        });
+    }
+}
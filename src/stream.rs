@@ -0,0 +1,535 @@
+// Serialization of collection documents straight from an iterator of resources. Each resource
+// is converted and written as the `data` array is serialized, instead of first building the
+// Vec<ResourceResponse> a Response holds, so peak memory doesn't grow with the collection.
+// ResourceStream does the same for resources coming from a Stream, e.g. a database cursor.
+// NdjsonExport writes the resources as NDJSON instead, for exports too large for a single document.
+#[cfg(feature = "actixweb")]
+use actix_web::{web::Bytes, HttpResponse};
+#[cfg(feature = "actixweb")]
+use futures_core::Stream;
+use serde::ser::{Error as _, Serialize, SerializeMap, SerializeSeq, Serializer};
+use std::{cell::Cell, io};
+#[cfg(feature = "actixweb")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{IntoResponse, Meta};
+
+// StreamedResponse is a document whose primary data is produced by an iterator. As it drains
+// the iterator it can only be serialized once; a second attempt is a serialization error.
+pub struct StreamedResponse<I> {
+    resources: Cell<Option<I>>,
+}
+
+impl<I> StreamedResponse<I>
+where
+    I: Iterator,
+    I::Item: IntoResponse,
+{
+    pub fn new<R>(resources: R) -> Self
+    where
+        R: IntoIterator<IntoIter = I>,
+    {
+        StreamedResponse {
+            resources: Cell::new(Some(resources.into_iter())),
+        }
+    }
+}
+
+impl<I> Serialize for StreamedResponse<I>
+where
+    I: Iterator,
+    I::Item: IntoResponse,
+    <I::Item as IntoResponse>::Attributes: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let resources = self
+            .resources
+            .take()
+            .ok_or_else(|| S::Error::custom("streamed response was already serialized"))?;
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry("data", &Data(Cell::new(Some(resources))))?;
+        map.end()
+    }
+}
+
+impl<I> StreamedResponse<I>
+where
+    I: Iterator,
+    I::Item: IntoResponse,
+    <I::Item as IntoResponse>::Attributes: Serialize,
+{
+    pub fn write_to<W: io::Write>(&self, writer: W) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, self)
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl<I> StreamedResponse<I>
+where
+    I: Iterator + Unpin + 'static,
+    I::Item: IntoResponse,
+    <I::Item as IntoResponse>::Attributes: Serialize,
+{
+    // into_http_response sends the document as a chunked body, serializing resources as the
+    // client reads them instead of buffering the whole document first
+    pub fn into_http_response(self) -> HttpResponse {
+        HttpResponse::Ok()
+            .content_type(crate::media::JSONAPI_MEDIA_TYPE)
+            .streaming(Chunks {
+                resources: self.resources.into_inner().map(Iter),
+                opened: false,
+                first: true,
+            })
+    }
+}
+
+// ResourceStream is a document whose primary data is produced by a stream. It can only be sent
+// as a chunked body, each chunk holding the resources the stream had ready
+#[cfg(feature = "actixweb")]
+pub struct ResourceStream<S> {
+    resources: S,
+}
+
+#[cfg(feature = "actixweb")]
+impl<S> ResourceStream<S>
+where
+    S: Stream + 'static,
+    S::Item: IntoResponse,
+    <S::Item as IntoResponse>::Attributes: Serialize,
+{
+    pub fn new(resources: S) -> Self {
+        ResourceStream { resources }
+    }
+
+    pub fn into_http_response(self) -> HttpResponse {
+        HttpResponse::Ok()
+            .content_type(crate::media::JSONAPI_MEDIA_TYPE)
+            .streaming(Chunks {
+                resources: Some(Box::pin(self.resources)),
+                opened: false,
+                first: true,
+            })
+    }
+}
+
+// Iter is an iterator as a stream which is always ready
+#[cfg(feature = "actixweb")]
+struct Iter<I>(I);
+
+#[cfg(feature = "actixweb")]
+impl<I: Iterator + Unpin> Stream for Iter<I> {
+    type Item = I::Item;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        Poll::Ready(self.get_mut().0.next())
+    }
+}
+
+// roughly how much serialized output is collected into each chunk of the body
+#[cfg(feature = "actixweb")]
+const CHUNK_SIZE: usize = 8 * 1024;
+
+#[cfg(feature = "actixweb")]
+struct Chunks<S> {
+    resources: Option<S>,
+    opened: bool,
+    first: bool,
+}
+
+#[cfg(feature = "actixweb")]
+impl<S> Stream for Chunks<S>
+where
+    S: Stream + Unpin,
+    S::Item: IntoResponse,
+    <S::Item as IntoResponse>::Attributes: Serialize,
+{
+    type Item = Result<Bytes, serde_json::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Some(resources) = this.resources.as_mut() else {
+            return Poll::Ready(None);
+        };
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+        if !this.opened {
+            chunk.extend_from_slice(br#"{"data":["#);
+            this.opened = true;
+        }
+        while chunk.len() < CHUNK_SIZE {
+            let resource = match Pin::new(&mut *resources).poll_next(cx) {
+                Poll::Ready(Some(resource)) => resource,
+                Poll::Ready(None) => {
+                    chunk.extend_from_slice(b"]}");
+                    this.resources = None;
+                    break;
+                }
+                Poll::Pending if chunk.is_empty() => return Poll::Pending,
+                // what's serialized so far is sent rather than held back until there's more
+                Poll::Pending => break,
+            };
+            if !this.first {
+                chunk.push(b',');
+            }
+            this.first = false;
+            if let Err(err) = serde_json::to_writer(&mut chunk, &resource.into_response()) {
+                this.resources = None;
+                return Poll::Ready(Some(Err(err)));
+            }
+        }
+        Poll::Ready(Some(Ok(Bytes::from(chunk))))
+    }
+}
+
+struct Data<I>(Cell<Option<I>>);
+
+impl<I> Serialize for Data<I>
+where
+    I: Iterator,
+    I::Item: IntoResponse,
+    <I::Item as IntoResponse>::Attributes: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let resources = self
+            .0
+            .take()
+            .ok_or_else(|| S::Error::custom("streamed response was already serialized"))?;
+        let mut seq = serializer.serialize_seq(None)?;
+        for resource in resources {
+            seq.serialize_element(&resource.into_response())?;
+        }
+        seq.end()
+    }
+}
+
+pub const NDJSON_MEDIA_TYPE: &str = "application/x-ndjson";
+
+// NdjsonExport writes a collection as NDJSON: a resource object per line, after a header line
+// holding the header meta (if any) and before a footer line with the footer meta and the count of
+// resources, which tells a complete export from one cut short, e.g.
+//
+//     {"meta":{"exported":"2024-01-01"}}
+//     {"type":"articles","id":"1","attributes":{...}}
+//     {"meta":{"count":1}}
+pub struct NdjsonExport<I> {
+    resources: I,
+    header: Option<Meta>,
+    footer: Meta,
+}
+
+impl<I> NdjsonExport<I>
+where
+    I: Iterator,
+    I::Item: IntoResponse,
+    <I::Item as IntoResponse>::Attributes: Serialize,
+{
+    pub fn new<R>(resources: R) -> Self
+    where
+        R: IntoIterator<IntoIter = I>,
+    {
+        NdjsonExport {
+            resources: resources.into_iter(),
+            header: None,
+            footer: Meta::new(),
+        }
+    }
+
+    pub fn header(mut self, meta: Meta) -> Self {
+        self.header = Some(meta);
+        self
+    }
+
+    // footer sets the meta of the footer line, whose count member is always the resources written
+    pub fn footer(mut self, meta: Meta) -> Self {
+        self.footer = meta;
+        self
+    }
+
+    pub fn write_to<W: io::Write>(self, mut writer: W) -> serde_json::Result<()> {
+        if let Some(header) = &self.header {
+            write_meta(&mut writer, header)?;
+        }
+        let mut count = 0;
+        for resource in self.resources {
+            serde_json::to_writer(&mut writer, &resource.into_response())?;
+            writer.write_all(b"\n").map_err(serde_json::Error::io)?;
+            count += 1;
+        }
+        let mut footer = self.footer;
+        footer.insert("count".into(), count.into());
+        write_meta(&mut writer, &footer)
+    }
+}
+
+fn write_meta<W: io::Write>(mut writer: W, meta: &Meta) -> serde_json::Result<()> {
+    #[derive(serde_derive::Serialize)]
+    struct MetaLine<'a> {
+        meta: &'a Meta,
+    }
+
+    serde_json::to_writer(&mut writer, &MetaLine { meta })?;
+    writer.write_all(b"\n").map_err(serde_json::Error::io)
+}
+
+#[cfg(feature = "actixweb")]
+impl<I> NdjsonExport<I>
+where
+    I: Iterator + Unpin + 'static,
+    I::Item: IntoResponse,
+    <I::Item as IntoResponse>::Attributes: Serialize,
+{
+    // into_http_response streams the export as a chunked body, like StreamedResponse does
+    pub fn into_http_response(self) -> HttpResponse {
+        HttpResponse::Ok()
+            .content_type(NDJSON_MEDIA_TYPE)
+            .streaming(NdjsonChunks {
+                header: self.header,
+                resources: Some(self.resources),
+                footer: Some(self.footer),
+                count: 0,
+            })
+    }
+}
+
+#[cfg(feature = "actixweb")]
+struct NdjsonChunks<I> {
+    header: Option<Meta>,
+    resources: Option<I>,
+    footer: Option<Meta>,
+    count: usize,
+}
+
+#[cfg(feature = "actixweb")]
+impl<I> NdjsonChunks<I>
+where
+    I: Iterator,
+    I::Item: IntoResponse,
+    <I::Item as IntoResponse>::Attributes: Serialize,
+{
+    // fill writes lines into the chunk until it's full, true once that includes the footer
+    fn fill(&mut self, chunk: &mut Vec<u8>, footer: &mut Meta) -> serde_json::Result<bool> {
+        if let Some(header) = self.header.take() {
+            write_meta(&mut *chunk, &header)?;
+        }
+        while chunk.len() < CHUNK_SIZE {
+            let Some(resource) = self.resources.as_mut().and_then(Iterator::next) else {
+                self.resources = None;
+                footer.insert("count".into(), self.count.into());
+                return write_meta(&mut *chunk, footer).map(|_| true);
+            };
+            serde_json::to_writer(&mut *chunk, &resource.into_response())?;
+            chunk.push(b'\n');
+            self.count += 1;
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(feature = "actixweb")]
+impl<I> Stream for NdjsonChunks<I>
+where
+    I: Iterator + Unpin,
+    I::Item: IntoResponse,
+    <I::Item as IntoResponse>::Attributes: Serialize,
+{
+    type Item = Result<Bytes, serde_json::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let Some(mut footer) = this.footer.take() else {
+            return Poll::Ready(None);
+        };
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+        let written = this.fill(&mut chunk, &mut footer);
+        match written {
+            Ok(true) => {}
+            Ok(false) => this.footer = Some(footer),
+            Err(err) => return Poll::Ready(Some(Err(err))),
+        }
+        Poll::Ready(Some(Ok(Bytes::from(chunk))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_derive::Serialize;
+
+    use super::{NdjsonExport, StreamedResponse};
+    use crate::{Identifier, IntoResponse, ResourceResponse, Response};
+
+    #[derive(Serialize)]
+    struct Attributes {
+        n: usize,
+    }
+
+    struct Number(usize);
+
+    impl IntoResponse for Number {
+        type Attributes = Attributes;
+
+        fn into_response(self) -> ResourceResponse<Self::Attributes> {
+            ResourceResponse {
+                id: Identifier {
+                    id: self.0.into(),
+                    typ: "numbers".into(),
+                },
+                attributes: Attributes { n: self.0 },
+                relationships: None,
+                meta: None,
+                links: None,
+                extensions: Default::default(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_streamed_response() {
+        let streamed = StreamedResponse::new((0..100).map(Number));
+        let buffered = Response::<_, Option<()>>::from((0..100).map(Number).collect::<Vec<_>>());
+        let streamed_value = serde_json::to_value(&streamed).unwrap();
+        let buffered_value = serde_json::to_value(&buffered).unwrap();
+        assert_eq!(streamed_value["data"], buffered_value["data"]);
+        // the iterator has been drained
+        assert!(serde_json::to_value(&streamed).is_err());
+
+        let mut written = Vec::new();
+        buffered.write_to(&mut written).unwrap();
+        assert_eq!(written, buffered.to_vec().unwrap());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_chunked_body() {
+        use futures_core::Stream;
+        use std::{
+            pin::Pin,
+            task::{Context, Poll, Waker},
+        };
+
+        let mut chunks = super::Chunks {
+            resources: Some(super::Iter((0..2000).map(Number))),
+            opened: false,
+            first: true,
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut body = Vec::new();
+        let mut count = 0;
+        while let Poll::Ready(Some(chunk)) = Pin::new(&mut chunks).poll_next(&mut cx) {
+            body.extend_from_slice(&chunk.unwrap());
+            count += 1;
+        }
+        assert!(count > 1);
+        let buffered = Response::<_, Option<()>>::from((0..2000).map(Number).collect::<Vec<_>>());
+        let doc: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            doc["data"],
+            serde_json::to_value(&buffered).unwrap()["data"]
+        );
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_resource_stream() {
+        use actix_web::body::{to_bytes, MessageBody};
+        use futures_core::Stream;
+        use std::{
+            pin::Pin,
+            task::{Context, Poll},
+        };
+
+        use super::ResourceStream;
+
+        // Numbers is a stream which is pending every other poll
+        struct Numbers {
+            next: usize,
+            ready: bool,
+        }
+
+        impl Stream for Numbers {
+            type Item = Number;
+
+            fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Number>> {
+                let this = self.get_mut();
+                this.ready = !this.ready;
+                if !this.ready {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                this.next += 1;
+                Poll::Ready((this.next <= 3).then_some(Number(this.next)))
+            }
+        }
+
+        let res = ResourceStream::new(Numbers {
+            next: 0,
+            ready: false,
+        })
+        .into_http_response();
+        assert_eq!(
+            res.headers().get("content-type").unwrap(),
+            crate::media::JSONAPI_MEDIA_TYPE
+        );
+        let body = actix_web::rt::System::new()
+            .block_on(async { to_bytes(res.into_body().boxed()).await })
+            .ok()
+            .unwrap();
+        let doc: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let buffered = Response::<_, Option<()>>::from((1..4).map(Number).collect::<Vec<_>>());
+        assert_eq!(
+            doc["data"],
+            serde_json::to_value(&buffered).unwrap()["data"]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_export() {
+        let mut header = crate::Meta::new();
+        header.insert("exported".into(), "today".into());
+        let mut written = Vec::new();
+        NdjsonExport::new((0..3).map(Number))
+            .header(header)
+            .write_to(&mut written)
+            .unwrap();
+        let lines: Vec<serde_json::Value> = written
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], serde_json::json!({"meta": {"exported": "today"}}));
+        assert_eq!(lines[3]["attributes"]["n"], 2);
+        assert_eq!(lines[4], serde_json::json!({"meta": {"count": 3}}));
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_ndjson_chunks() {
+        use futures_core::Stream;
+        use std::{
+            pin::Pin,
+            task::{Context, Poll, Waker},
+        };
+
+        let mut chunks = super::NdjsonChunks {
+            header: None,
+            resources: Some((0..2000).map(Number)),
+            footer: Some(crate::Meta::new()),
+            count: 0,
+        };
+        let mut cx = Context::from_waker(Waker::noop());
+        let mut body = Vec::new();
+        let mut count = 0;
+        while let Poll::Ready(Some(chunk)) = Pin::new(&mut chunks).poll_next(&mut cx) {
+            body.extend_from_slice(&chunk.unwrap());
+            count += 1;
+        }
+        assert!(count > 1);
+        let mut written = Vec::new();
+        NdjsonExport::new((0..2000).map(Number))
+            .write_to(&mut written)
+            .unwrap();
+        assert_eq!(body, written);
+    }
+}
@@ -0,0 +1,137 @@
+// The timestamps profile: resources carry when they were created and last updated as RFC 3339
+// strings in `created_at` and `updated_at` members of their meta, and documents following it
+// list PROFILE in `jsonapi.profile`. Timestamps can also be flattened into attributes
+// (`#[serde(flatten)]`) by services which expose them there.
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{Meta, ResourceResponse, Response};
+
+// PROFILE identifies the profile, it isn't meant to be dereferenced
+pub const PROFILE: &str = "https://github.com/qmuloadmin/jsonapi/profiles/timestamps";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Timestamps {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+impl Timestamps {
+    pub fn new(created_at: &str, updated_at: &str) -> Timestamps {
+        Timestamps {
+            created_at: Some(created_at.to_owned()),
+            updated_at: Some(updated_at.to_owned()),
+        }
+    }
+
+    pub fn to_meta(&self) -> Meta {
+        [
+            ("created_at", &self.created_at),
+            ("updated_at", &self.updated_at),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.to_owned(), value.clone()?.into())))
+        .collect()
+    }
+}
+
+// Timestamped is implemented by resources which know their timestamps, see Response::timestamped
+pub trait Timestamped {
+    fn timestamps(&self) -> Timestamps;
+}
+
+impl<D> ResourceResponse<D> {
+    // with_timestamps adds timestamps to the resource's meta
+    pub fn with_timestamps(&mut self, timestamps: &Timestamps) {
+        let meta = timestamps.to_meta();
+        if !meta.is_empty() {
+            self.meta.get_or_insert_with(Meta::new).extend(meta);
+        }
+    }
+}
+
+impl<P: Timestamped, I> Response<P, I> {
+    // timestamped adds the timestamps of the primary resources to their meta, and announces the
+    // profile
+    pub fn timestamped(mut self) -> Self {
+        if let crate::ResponseType::Ok(data) = &mut self.primary {
+            for res in data.iter_mut() {
+                let timestamps = res.attributes.timestamps();
+                res.with_timestamps(&timestamps);
+            }
+        }
+        self.profile(PROFILE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_derive::Serialize;
+    use smallvec::smallvec;
+
+    use super::{Timestamped, Timestamps, PROFILE};
+    use crate::{Identifier, ResourceResponse, Response, ResponseType, SpecVersion};
+
+    #[derive(Serialize)]
+    struct Article {
+        title: &'static str,
+        #[serde(skip)]
+        created: &'static str,
+    }
+
+    impl Timestamped for Article {
+        fn timestamps(&self) -> Timestamps {
+            Timestamps {
+                created_at: Some(self.created.into()),
+                updated_at: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let resource = ResourceResponse {
+            id: Identifier {
+                id: "1".into(),
+                typ: "articles".into(),
+            },
+            attributes: Article {
+                title: "a",
+                created: "2024-01-01T00:00:00Z",
+            },
+            relationships: None,
+            meta: None,
+            links: None,
+            extensions: Default::default(),
+        };
+        let response: Response<Article, ()> = Response {
+            primary: ResponseType::Ok(smallvec![resource]),
+            included: None,
+            meta: None,
+            links: None,
+            jsonapi: None,
+            extensions: Default::default(),
+        };
+        let out = serde_json::to_value(response.jsonapi(SpecVersion::V1_1).timestamped()).unwrap();
+        assert_eq!(out["data"][0]["meta"]["created_at"], "2024-01-01T00:00:00Z");
+        assert!(out["data"][0]["meta"].get("updated_at").is_none());
+        assert_eq!(
+            out["jsonapi"],
+            serde_json::json!({"version": "1.1", "profile": [PROFILE]})
+        );
+
+        #[derive(Serialize)]
+        struct Attributes {
+            title: &'static str,
+            #[serde(flatten)]
+            timestamps: Timestamps,
+        }
+        let attributes = Attributes {
+            title: "a",
+            timestamps: Timestamps::new("2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z"),
+        };
+        let out = serde_json::to_value(attributes).unwrap();
+        assert_eq!(out["updated_at"], "2024-01-02T00:00:00Z");
+    }
+}
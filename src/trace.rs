@@ -0,0 +1,259 @@
+// Spans for the tracing feature, so the time spent extracting requests, including resources and
+// serializing responses shows up in traces. They're at debug level, named jsonapi.*, and carry
+// the resource type, how many resources there are and the statuses of errors.
+use tracing::{debug_span, field::Empty, Span};
+
+use crate::{Error, Response, ResponseType};
+
+// extract_span covers the JsonApi extractor, from reading the body to FromRequest
+#[cfg(feature = "actixweb")]
+pub(crate) fn extract_span() -> Span {
+    debug_span!(
+        "jsonapi.extract",
+        resource.r#type = Empty,
+        relationships.count = Empty,
+        error.status = Empty,
+    )
+}
+
+pub(crate) fn include_span(count: usize) -> Span {
+    debug_span!("jsonapi.include", included.count = count)
+}
+
+// respond_span covers answering with a document under actix: linking, localizing and encoding it
+#[cfg(feature = "actixweb")]
+pub(crate) fn respond_span<P, I>(response: &Response<P, I>) -> Span {
+    let span = debug_span!(
+        "jsonapi.respond",
+        resource.r#type = Empty,
+        data.count = Empty,
+        included.count = Empty,
+        error.statuses = Empty,
+    );
+    record_document(&span, response);
+    span
+}
+
+// serialize_span covers writing a document as JSON
+pub(crate) fn serialize_span<P, I>(response: &Response<P, I>) -> Span {
+    let span = debug_span!(
+        "jsonapi.serialize",
+        resource.r#type = Empty,
+        data.count = Empty,
+        included.count = Empty,
+        error.statuses = Empty,
+    );
+    record_document(&span, response);
+    span
+}
+
+fn record_document<P, I>(span: &Span, response: &Response<P, I>) {
+    if span.is_disabled() {
+        return;
+    }
+    match &response.primary {
+        ResponseType::Ok(data) => {
+            if let Some(first) = data.first() {
+                span.record("resource.type", first.id.typ.as_str());
+            }
+            span.record("data.count", data.len());
+        }
+        ResponseType::Error(errors) => {
+            span.record("error.statuses", statuses(errors).as_str());
+        }
+    }
+    span.record(
+        "included.count",
+        response.included.as_ref().map_or(0, Vec::len),
+    );
+}
+
+// statuses lists the statuses of errors, e.g. "404,409"
+pub(crate) fn statuses(errors: &[Error]) -> String {
+    let statuses: Vec<&str> = errors.iter().map(|err| err.status.as_str()).collect();
+    statuses.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    use super::statuses;
+    use crate::{Error, Extensions, Identifier, ResourceResponse, Response};
+
+    // SpanFields is the name of a span and its fields, as recorded
+    type SpanFields = (&'static str, BTreeMap<String, String>);
+
+    // Spans is a subscriber keeping the fields of the spans opened while it's the default
+    #[derive(Clone, Default)]
+    struct Spans(Arc<Mutex<Vec<SpanFields>>>);
+
+    impl Spans {
+        // fields are those of the last span named name
+        fn fields(&self, name: &str) -> BTreeMap<String, String> {
+            let spans = self.0.lock().unwrap();
+            let span = spans.iter().rev().find(|(span, _)| *span == name);
+            span.map(|(_, fields)| fields.clone()).unwrap_or_default()
+        }
+    }
+
+    struct Fields<'a>(&'a mut BTreeMap<String, String>);
+
+    impl Visit for Fields<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_owned(), value.to_owned());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_owned(), format!("{:?}", value));
+        }
+    }
+
+    impl Subscriber for Spans {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut spans = self.0.lock().unwrap();
+            let mut fields = BTreeMap::new();
+            span.record(&mut Fields(&mut fields));
+            spans.push((span.metadata().name(), fields));
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut spans = self.0.lock().unwrap();
+            let (_, fields) = &mut spans[span.into_u64() as usize - 1];
+            values.record(&mut Fields(fields));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    fn resource(typ: &str, id: &str) -> ResourceResponse<()> {
+        ResourceResponse {
+            id: Identifier {
+                id: id.into(),
+                typ: typ.into(),
+            },
+            attributes: (),
+            relationships: None,
+            meta: None,
+            links: None,
+            extensions: Extensions::new(),
+        }
+    }
+
+    #[test]
+    fn test_statuses() {
+        let errors = [Error::new_not_found("a"), Error::new_conflict("b")];
+        assert_eq!(statuses(&errors), "404,409");
+        assert_eq!(statuses(&[]), "");
+    }
+
+    #[test]
+    fn test_spans() {
+        let spans = Spans::default();
+        tracing::subscriber::with_default(spans.clone(), || {
+            let response = Response::with_capacity(2, 3)
+                .push(resource("articles", "1"))
+                .push(resource("articles", "2"))
+                .include(resource("people", "9"))
+                .include_many(vec![resource("comments", "5"), resource("comments", "12")]);
+            response.to_vec().unwrap();
+            let errors: Response<(), ()> =
+                vec![Error::new_not_found("a"), Error::new_conflict("b")].into();
+            errors.to_vec().unwrap();
+        });
+        assert_eq!(spans.fields("jsonapi.include")["included.count"], "2");
+        let spans = spans.0.lock().unwrap();
+        let serialized: Vec<_> = spans
+            .iter()
+            .filter(|(name, _)| *name == "jsonapi.serialize")
+            .map(|(_, fields)| fields)
+            .collect();
+        let fields = serialized[0];
+        assert_eq!(fields["resource.type"], "articles");
+        assert_eq!(fields["data.count"], "2");
+        assert_eq!(fields["included.count"], "3");
+        assert!(!fields.contains_key("error.statuses"));
+        let fields = serialized[1];
+        assert_eq!(fields["error.statuses"], "404,409");
+        assert_eq!(fields["included.count"], "0");
+        assert!(!fields.contains_key("resource.type"));
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_actix_spans() {
+        use actix_web::{test, web, App, HttpResponse};
+
+        use crate::{JsonApi, Request};
+
+        async fn create(_: JsonApi<Request<serde_json::Value>>) -> HttpResponse {
+            HttpResponse::Created().finish()
+        }
+
+        async fn list() -> Response<(), ()> {
+            Response::with_capacity(1, 1)
+                .push(resource("articles", "1"))
+                .include(resource("people", "9"))
+        }
+
+        let spans = Spans::default();
+        tracing::subscriber::with_default(spans.clone(), || {
+            actix_web::rt::System::new().block_on(async {
+                let app = test::init_service(
+                    App::new()
+                        .route("/articles", web::post().to(create))
+                        .route("/articles", web::get().to(list)),
+                )
+                .await;
+                let body = r#"{"data": {"type": "articles", "attributes": {},
+                    "relationships": {"author": {"data": {"type": "people", "id": "9"}}}}}"#;
+                let req = test::TestRequest::post()
+                    .uri("/articles")
+                    .insert_header(("content-type", "application/vnd.api+json"))
+                    .set_payload(body)
+                    .to_request();
+                test::call_service(&app, req).await;
+                let fields = spans.fields("jsonapi.extract");
+                assert_eq!(fields["resource.type"], "articles");
+                assert_eq!(fields["relationships.count"], "1");
+                assert!(!fields.contains_key("error.status"));
+
+                let req = test::TestRequest::post()
+                    .uri("/articles")
+                    .insert_header(("content-type", "text/plain"))
+                    .set_payload(body)
+                    .to_request();
+                test::call_service(&app, req).await;
+                let fields = spans.fields("jsonapi.extract");
+                assert_eq!(fields["error.status"], "415");
+                assert!(!fields.contains_key("resource.type"));
+
+                let req = test::TestRequest::get().uri("/articles").to_request();
+                test::call_service(&app, req).await;
+                let fields = spans.fields("jsonapi.respond");
+                assert_eq!(fields["resource.type"], "articles");
+                assert_eq!(fields["data.count"], "1");
+                assert_eq!(fields["included.count"], "1");
+            });
+        });
+    }
+}
@@ -0,0 +1,176 @@
+// Validation of attributes against JSON Schemas registered per resource type, for attributes whose
+// shape serde can't enforce (e.g. a serde_json::Value). Registered with JsonApiConfig, the JsonApi
+// extractor checks a request's attributes before they're decoded and FromRequest runs, answering
+// violations with 422 pointing at the offending attribute.
+use jsonschema::{ValidationError, Validator};
+#[cfg(feature = "actixweb")]
+use serde_derive::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::Error;
+
+#[derive(Default)]
+pub struct AttributeSchemas {
+    validators: HashMap<String, Validator>,
+}
+
+impl AttributeSchemas {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // register sets the schema the attributes of typ are held to, failing if it isn't a valid
+    // schema
+    pub fn register(mut self, typ: &str, schema: &Value) -> Result<Self, ValidationError<'static>> {
+        let validator = jsonschema::validator_for(schema)?;
+        self.validators.insert(typ.to_owned(), validator);
+        Ok(self)
+    }
+
+    // validate checks the attributes of a resource of type typ, with an error per violation.
+    // Types without a schema are always valid
+    pub fn validate(&self, typ: &str, attributes: &Value) -> Result<(), Vec<Error>> {
+        let validator = match self.validators.get(typ) {
+            Some(validator) => validator,
+            None => return Ok(()),
+        };
+        let errors: Vec<Error> = validator
+            .iter_errors(attributes)
+            .map(|violation| {
+                Error::new_unprocessable_entity(&violation.to_string()).with_pointer(&format!(
+                    "/data/attributes{}",
+                    violation.instance_path.as_str()
+                ))
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // validate_request checks the attributes of a request body, reporting its first violation.
+    // Bodies which aren't requests are left for the extractor to refuse
+    #[cfg(feature = "actixweb")]
+    pub(crate) fn validate_request(
+        &self,
+        body: &[u8],
+        encoding: crate::encoding::Encoding,
+    ) -> Result<(), Error> {
+        let req = match encoding.decode::<TypedAttributes>(body) {
+            Ok(req) => req,
+            Err(_) => return Ok(()),
+        };
+        match req.data.attributes {
+            Some(attributes) => self
+                .validate(&req.data.typ, &attributes)
+                .map_err(|mut errors| errors.remove(0)),
+            None => Ok(()),
+        }
+    }
+}
+
+// TypedAttributes reads only the type and attributes of a request
+#[cfg(feature = "actixweb")]
+#[derive(Deserialize)]
+struct TypedAttributes {
+    data: ResourceAttributes,
+}
+
+#[cfg(feature = "actixweb")]
+#[derive(Deserialize)]
+struct ResourceAttributes {
+    #[serde(rename = "type")]
+    typ: String,
+    attributes: Option<Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::AttributeSchemas;
+    use crate::ErrorStatus;
+
+    fn schemas() -> AttributeSchemas {
+        AttributeSchemas::new()
+            .register(
+                "articles",
+                &json!({
+                    "type": "object",
+                    "properties": {
+                        "title": {"type": "string"},
+                        "tags": {"type": "array", "items": {"type": "string"}}
+                    },
+                    "required": ["title"]
+                }),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_validate() {
+        let schemas = schemas();
+        assert!(schemas
+            .validate("articles", &json!({"title": "a", "tags": ["b"]}))
+            .is_ok());
+        assert!(schemas.validate("people", &json!({"title": 1})).is_ok());
+        let errors = schemas
+            .validate("articles", &json!({"title": 1, "tags": ["b", 2]}))
+            .unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .all(|err| err.status == ErrorStatus::UnprocessableEntity));
+        let mut pointers: Vec<String> = errors
+            .into_iter()
+            .filter_map(|err| err.source?.pointer)
+            .collect();
+        pointers.sort();
+        assert_eq!(
+            pointers,
+            ["/data/attributes/tags/1", "/data/attributes/title"]
+        );
+        assert!(AttributeSchemas::new()
+            .register("articles", &json!({"type": 1}))
+            .is_err());
+    }
+
+    #[cfg(feature = "actixweb")]
+    #[test]
+    fn test_extractor() {
+        use actix_web::{test, FromRequest};
+        use std::sync::Arc;
+
+        use crate::{JsonApi, JsonApiConfig, Request};
+
+        let config = JsonApiConfig::default().attribute_schemas(Arc::new(schemas()));
+        let extract = |body: serde_json::Value| {
+            let (req, mut payload) = test::TestRequest::post()
+                .app_data(config.clone())
+                .insert_header(("content-type", crate::media::JSONAPI_MEDIA_TYPE))
+                .set_payload(body.to_string())
+                .to_http_parts();
+            actix_web::rt::System::new()
+                .block_on(JsonApi::<Request<serde_json::Value>>::from_request(
+                    &req,
+                    &mut payload,
+                ))
+                .map_err(|mut rejection| rejection.errors.remove(0))
+        };
+        let err = extract(json!({"data": {"type": "articles", "attributes": {"tags": []}}}))
+            .err()
+            .unwrap();
+        assert_eq!(err.status, ErrorStatus::UnprocessableEntity);
+        assert_eq!(
+            err.source.unwrap().pointer.as_deref(),
+            Some("/data/attributes")
+        );
+        let req = extract(json!({"data": {"type": "articles", "attributes": {"title": "a"}}}))
+            .ok()
+            .unwrap();
+        assert_eq!(req.into_inner().data.attributes["title"], "a");
+    }
+}
@@ -0,0 +1,135 @@
+// Warnings are non-fatal problems reported alongside a successful document, e.g. a filter which
+// was ignored or an included resource which couldn't be loaded. They're error objects without a
+// status, listed under `warnings` in the top-level meta, so clients which don't look for them
+// aren't bothered.
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{Error, ErrorSource, Meta, Response};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Warning {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<ErrorSource>,
+}
+
+impl Warning {
+    pub fn new(title: &str) -> Warning {
+        Warning {
+            code: None,
+            title: title.to_owned(),
+            detail: None,
+            source: None,
+        }
+    }
+
+    pub fn with_code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_owned());
+        self
+    }
+
+    pub fn with_detail(mut self, detail: &str) -> Self {
+        self.detail = Some(detail.to_owned());
+        self
+    }
+
+    // with_parameter names the query parameter the warning is about, e.g. "filter[color]"
+    pub fn with_parameter(mut self, parameter: &str) -> Self {
+        self.source
+            .get_or_insert_with(ErrorSource::default)
+            .parameter = Some(parameter.into());
+        self
+    }
+}
+
+// an error can be downgraded to a warning, e.g. one about a part of a request that could be
+// served without
+impl From<Error> for Warning {
+    fn from(err: Error) -> Warning {
+        Warning {
+            code: err.code,
+            title: err.title,
+            detail: err.detail,
+            source: err.source,
+        }
+    }
+}
+
+impl<P, I> Response<P, I> {
+    // warning adds a warning to the document's top-level meta. A warnings member which isn't a
+    // list (set by hand) becomes the first entry of one, rather than the warning being dropped
+    pub fn warning(mut self, warning: Warning) -> Self {
+        let warning = serde_json::to_value(warning).expect("warnings are always valid JSON");
+        let warnings = self
+            .meta
+            .get_or_insert_with(Meta::new)
+            .entry("warnings".into())
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+        match warnings {
+            serde_json::Value::Array(warnings) => warnings.push(warning),
+            other => *other = serde_json::Value::Array(vec![other.take(), warning]),
+        }
+        self
+    }
+
+    // warnings reads the warnings of a document, skipping any which aren't warning objects
+    pub fn warnings(&self) -> Vec<Warning> {
+        let warnings = self
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.get("warnings"))
+            .and_then(|warnings| warnings.as_array());
+        warnings
+            .into_iter()
+            .flatten()
+            .filter_map(|warning| serde_json::from_value(warning.clone()).ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Warning;
+    use crate::{Error, Meta, Response};
+
+    #[test]
+    fn test_warnings() {
+        let response = Response::<(), ()>::with_capacity(0, 0)
+            .warning(Warning::new("unknown filter ignored").with_parameter("filter[color]"))
+            .warning(Error::new_not_found("author 3 couldn't be included").into());
+        let out = serde_json::to_value(&response).unwrap();
+        let warnings = out["meta"]["warnings"].as_array().unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0]["source"]["parameter"], "filter[color]");
+        assert_eq!(warnings[1]["code"], "Not Found");
+        assert!(warnings[1].get("status").is_none());
+
+        let parsed: Response<(), ()> = serde_json::from_value(out).unwrap();
+        assert_eq!(parsed.warnings(), response.warnings());
+        assert_eq!(parsed.warnings()[0].title, "unknown filter ignored");
+        assert!(Response::<(), ()>::with_capacity(0, 0)
+            .warnings()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_warnings_not_a_list() {
+        let mut response = Response::<(), ()>::with_capacity(0, 0);
+        response.meta = Some(Meta::from([("warnings".to_owned(), "legacy".into())]));
+        let response = response.warning(Warning::new("unknown filter ignored"));
+        let out = serde_json::to_value(&response).unwrap();
+        assert_eq!(out["meta"]["warnings"][0], "legacy");
+        assert_eq!(
+            out["meta"]["warnings"][1]["title"],
+            "unknown filter ignored"
+        );
+        assert_eq!(
+            response.warnings(),
+            [Warning::new("unknown filter ignored")]
+        );
+    }
+}